@@ -0,0 +1,117 @@
+// host benchmarks of the verification primitives: cargo bench --features signing
+//
+// these time the native build, not the program. on-chain a compute unit is roughly one
+// executed SBF instruction, so the numbers here only carry over as relative changes,
+// and only for changes that do less work: fewer multiplications or reductions in the
+// NTT, fewer permutations in the sponge, fewer bits read while decoding. a change that
+// wins on the host through the cache, SIMD or the branch predictor usually buys nothing
+// on the SBF VM. rough CU for each benchmark are in FALCON_512_PERFORMANCE_PROFILE
+// (falcon::performance), e.g. about 35k for the NTTs; after a change here looks
+// faster, confirm it with cargo test --features bench-tests test_measured_compute_units
+//
+// inputs come from the checked-in fixtures (src/fixtures.rs), the same ones the tests
+// verify, so every benchmark runs on a real key and signature
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::hint::black_box;
+
+use solana_falcon_vault::falcon::internals::decompress_signature;
+use solana_falcon_vault::falcon::{
+    keccak_f1600, ntt_forward, ntt_forward_precomputed, ntt_inverse, ntt_inverse_precomputed,
+    verify_falcon_signature_with_context, Polynomial, Shake256, FALCON_512_N, FALCON_512_NONCE_SIZE, Q,
+};
+use solana_falcon_vault::fixtures::fixture_keypairs;
+use solana_falcon_vault::messages::VAULT_SIGNING_CONTEXT;
+
+// a fixed polynomial with coefficients spread over [0, q)
+fn coefficients() -> [u32; FALCON_512_N] {
+    let mut coeffs = [0u32; FALCON_512_N];
+    for (i, coeff) in coeffs.iter_mut().enumerate() {
+        *coeff = (i as u32 * 24593 + 7) % Q;
+    }
+    coeffs
+}
+
+fn bench_keccak(c: &mut Criterion) {
+    let mut state = [0u64; 25];
+    c.bench_function("keccak_f1600", |b| b.iter(|| keccak_f1600(black_box(&mut state))));
+
+    // hash_to_point absorbs nonce + an 80-byte transfer message and squeezes at least 1 KB
+    let input = [0xA5u8; 1024];
+    let mut group = c.benchmark_group("shake256");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.bench_function("absorb_1k", |b| {
+        b.iter(|| {
+            let mut hasher = Shake256::new();
+            hasher.update(black_box(&input));
+            hasher
+        })
+    });
+    group.bench_function("squeeze_1k", |b| {
+        b.iter_batched(
+            || {
+                let mut hasher = Shake256::new();
+                hasher.update(&input[..88]);
+                hasher.finalize_xof()
+            },
+            |mut reader| {
+                let mut out = [0u8; 1024];
+                reader.read(&mut out);
+                out
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_ntt(c: &mut Criterion) {
+    let input = coefficients();
+    let mut group = c.benchmark_group("ntt");
+    group.bench_function("forward", |b| {
+        b.iter_batched_ref(|| input, ntt_forward, BatchSize::SmallInput)
+    });
+    group.bench_function("inverse", |b| {
+        b.iter_batched_ref(|| input, ntt_inverse, BatchSize::SmallInput)
+    });
+    group.bench_function("forward_precomputed", |b| {
+        b.iter_batched_ref(|| input, ntt_forward_precomputed, BatchSize::SmallInput)
+    });
+    group.bench_function("inverse_precomputed", |b| {
+        b.iter_batched_ref(|| input, ntt_inverse_precomputed, BatchSize::SmallInput)
+    });
+
+    let mut values = [0u16; FALCON_512_N];
+    for (value, &coeff) in values.iter_mut().zip(input.iter()) {
+        *value = coeff as u16;
+    }
+    let other = Polynomial::from_u16_array(&values);
+    group.bench_function("pointwise_multiply", |b| {
+        b.iter_batched_ref(
+            || Polynomial::from_u16_array(&values),
+            |poly| poly.mul_assign_pointwise(black_box(&other)),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let keypair = &fixture_keypairs()[0];
+    let transfer = &keypair.transfers[0];
+    let message = transfer.message();
+    let verify = |signature: &[u8], message: &[u8]| {
+        verify_falcon_signature_with_context(&keypair.public_key, signature, VAULT_SIGNING_CONTEXT, &[message])
+    };
+    assert_eq!(verify(&transfer.signature, &message), Ok(()));
+
+    c.bench_function("decompress_signature", |b| {
+        b.iter(|| decompress_signature(black_box(&transfer.signature[1 + FALCON_512_NONCE_SIZE..])))
+    });
+    c.bench_function("verify_falcon_signature_with_context", |b| {
+        b.iter(|| verify(black_box(&transfer.signature), black_box(&message)))
+    });
+}
+
+criterion_group!(benches, bench_keccak, bench_ntt, bench_verify);
+criterion_main!(benches);
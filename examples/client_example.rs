@@ -1,168 +1,83 @@
-use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
-    pubkey::Pubkey,
-    signature::Keypair,
-    signer::Signer,
-};
+// a vault's whole life from the client side: open it, transfer out of it and close it,
+// with instructions from the client module and signatures from the off-chain signer,
+// run through mollusk against the program in target/deploy. it exits non-zero if the
+// program turns any of them down, so it can run in CI
+//
+//   cargo build-sbf && cargo run --example client_example --features bench-tests,signing
 
-const FALCON_512_PUBLIC_KEY_SIZE: usize = 897;
-const FALCON_512_SIGNATURE_SIZE: usize = 666;
+use mollusk_svm::{program::keyed_account_for_system_program, result::Check, Mollusk};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use solana_falcon_vault::client::{
+    close_message, close_vault_ix, derive_vault_address, open_vault_ix, transfer_ix, transfer_message,
+    vault_close_nonce, vault_transfer_nonce, VAULT_SIGNING_CONTEXT,
+};
+use solana_falcon_vault::falcon::FalconKeyPair;
+use solana_falcon_vault::measure::PROGRAM_PATH;
 
 fn main() {
-    
-    let falcon_public_key = generate_mock_falcon_public_key();
-    let falcon_private_key = generate_mock_falcon_private_key();
-    
-    let program_id = Pubkey::new_unique(); // Your program ID
-    let pubkey_hash = hash_falcon_public_key(&falcon_public_key);
-    let (vault_pda, bump) = Pubkey::find_program_address(
-        &[&pubkey_hash],
-        &program_id,
-    );
-    
+    // the deployed program's id: the vault PDA is derived from it
+    let program_id = Pubkey::new_from_array(solana_falcon_vault::ID);
+    let mollusk = Mollusk::new(&program_id, PROGRAM_PATH);
+    let system = keyed_account_for_system_program();
+
+    // keep the private key off-chain, only the public key goes into the vault
+    let mut rng = rand::rng();
+    let falcon_keypair = FalconKeyPair::generate(&mut rng);
+    let (vault_pda, bump) = derive_vault_address(&program_id, &falcon_keypair.public_key);
     println!("Vault PDA: {}", vault_pda);
-    
 
-    let payer = Keypair::new();
-    let open_vault_ix = create_open_vault_instruction(
-        &program_id,
-        &payer.pubkey(),
-        &vault_pda,
-        &falcon_public_key,
-        bump,
-    );
-    
-    let recipient = Keypair::new();
-    let transfer_amount = 100_000_000; // 0.1 SOL
-    
-    let mut transfer_message = vec![0u8; 48];
-    transfer_message[0..8].copy_from_slice(&transfer_amount.to_le_bytes());
-    transfer_message[8..40].copy_from_slice(&recipient.pubkey().to_bytes());
-    transfer_message[40..48].copy_from_slice(&[0u8; 8]); // nonce placeholder
-    
-    let transfer_signature = sign_with_falcon(&falcon_private_key, &transfer_message);
-    
-    let transfer_ix = create_transfer_instruction(
-        &program_id,
-        &vault_pda,
-        &recipient.pubkey(),
-        transfer_amount,
-        &transfer_signature,
-        bump,
-    );
-    
-    let refund_account = Keypair::new();
-    
-    let mut close_message = vec![0u8; 43];
-    close_message[0..11].copy_from_slice(b"CLOSE_VAULT");
-    close_message[11..43].copy_from_slice(&refund_account.pubkey().to_bytes());
-    
-    let close_signature = sign_with_falcon(&falcon_private_key, &close_message);
-    
-    let close_ix = create_close_vault_instruction(
-        &program_id,
-        &vault_pda,
-        &refund_account.pubkey(),
-        &close_signature,
-        bump,
+    let payer = Pubkey::new_unique();
+    let open = mollusk.process_and_validate_instruction(
+        &open_vault_ix(&program_id, &payer, &vault_pda, &falcon_keypair.public_key, bump, None),
+        &[
+            (payer, Account::new(10_000_000_000, 0, &system.0)),
+            (vault_pda, Account::default()),
+            system.clone(),
+        ],
+        &[Check::success()],
     );
-    
-    println!("Example instructions created successfully!");
-}
-
-
-fn generate_mock_falcon_public_key() -> [u8; FALCON_512_PUBLIC_KEY_SIZE] {
-    let mut key = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
-    key[0..32].copy_from_slice(&[1u8; 32]);
-    key
-}
+    println!("OpenVault: {} CU", open.compute_units_consumed);
 
-fn generate_mock_falcon_private_key() -> Vec<u8> {
-    vec![2u8; 2048]
-}
+    // anyone can fund the vault with a plain system transfer to its address
+    let vault_account = carry_over(open.get_account(&vault_pda).unwrap(), 1_000_000_000);
 
-fn hash_falcon_public_key(public_key: &[u8; FALCON_512_PUBLIC_KEY_SIZE]) -> [u8; 32] {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    
-    let mut hasher = DefaultHasher::new();
-    public_key.hash(&mut hasher);
-    let hash_value = hasher.finish();
-    
-    let mut result = [0u8; 32];
-    result[0..8].copy_from_slice(&hash_value.to_le_bytes());
-    result
-}
+    // on a cluster, send this with client::transfer_ix_with_budget: verification needs
+    // most of the default compute budget
+    let recipient = Pubkey::new_unique();
+    let transfer_amount = 100_000_000; // 0.1 SOL
+    // a vault's nonce starts at one derived from the slot it's opened in and moves on with
+    // each transfer; on a cluster, read it from the vault account with client::vault_transfer_nonce
+    let nonce = vault_transfer_nonce(&vault_account.data).unwrap();
+    // every vault signature is made under VAULT_SIGNING_CONTEXT
+    let message = transfer_message(transfer_amount, &recipient, nonce, &vault_pda);
+    let transfer_signature = falcon_keypair.private_key.sign_with_context(VAULT_SIGNING_CONTEXT, &message, &mut rng);
+    let transfer = mollusk.process_and_validate_instruction(
+        &transfer_ix(&program_id, &vault_pda, &recipient, transfer_amount, &transfer_signature.bytes, bump),
+        &[(vault_pda, vault_account), (recipient, Account::default()), system],
+        &[Check::success()],
+    );
+    assert_eq!(transfer.get_account(&recipient).unwrap().lamports, transfer_amount);
+    println!("TransferFromVault: {} CU", transfer.compute_units_consumed);
 
-fn sign_with_falcon(_private_key: &[u8], _message: &[u8]) -> [u8; FALCON_512_SIGNATURE_SIZE] {
-    let mut signature = [0u8; FALCON_512_SIGNATURE_SIZE];
-    signature[0] = 0xFF; 
-    signature
-}
+    // closing refunds whatever is left
+    let vault_account = carry_over(transfer.get_account(&vault_pda).unwrap(), 0);
+    let refund = Pubkey::new_unique();
+    // a close signs the vault's current nonce too, so it can't be replayed on a reopened vault
+    let nonce = vault_close_nonce(&vault_account.data).unwrap();
+    let message = close_message(&refund, nonce, &vault_pda);
+    let close_signature = falcon_keypair.private_key.sign_with_context(VAULT_SIGNING_CONTEXT, &message, &mut rng);
+    let close = mollusk.process_and_validate_instruction(
+        &close_vault_ix(&program_id, &vault_pda, &refund, &close_signature.bytes, bump),
+        &[(vault_pda, vault_account.clone()), (refund, Account::default())],
+        &[Check::success()],
+    );
+    assert_eq!(close.get_account(&refund).unwrap().lamports, vault_account.lamports);
+    println!("CloseVault: {} CU", close.compute_units_consumed);
 
-fn create_open_vault_instruction(
-    program_id: &Pubkey,
-    payer: &Pubkey,
-    vault_pda: &Pubkey,
-    falcon_public_key: &[u8; FALCON_512_PUBLIC_KEY_SIZE],
-    bump: u8,
-) -> Instruction {
-    let mut data = vec![0u8]; 
-    data.extend_from_slice(falcon_public_key);
-    data.push(bump);
-    
-    Instruction::new_with_bytes(
-        *program_id,
-        &data,
-        vec![
-            AccountMeta::new(*payer, true),
-            AccountMeta::new(*vault_pda, false),
-            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
-        ],
-    )
+    println!("Opened, used and closed the vault");
 }
 
-fn create_transfer_instruction(
-    program_id: &Pubkey,
-    vault_pda: &Pubkey,
-    recipient: &Pubkey,
-    amount: u64,
-    signature: &[u8; FALCON_512_SIGNATURE_SIZE],
-    bump: u8,
-) -> Instruction {
-    let mut data = vec![1u8]; 
-    data.extend_from_slice(signature);
-    data.extend_from_slice(&amount.to_le_bytes());
-    data.push(bump);
-    
-    Instruction::new_with_bytes(
-        *program_id,
-        &data,
-        vec![
-            AccountMeta::new(*vault_pda, false),
-            AccountMeta::new(*recipient, false),
-            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
-        ],
-    )
+// an account as one instruction left it, plus deposit lamports, for the next one
+fn carry_over(account: &Account, deposit: u64) -> Account {
+    Account { lamports: account.lamports + deposit, ..account.clone() }
 }
-
-fn create_close_vault_instruction(
-    program_id: &Pubkey,
-    vault_pda: &Pubkey,
-    refund: &Pubkey,
-    signature: &[u8; FALCON_512_SIGNATURE_SIZE],
-    bump: u8,
-) -> Instruction {
-    let mut data = vec![2u8]; 
-    data.extend_from_slice(signature);
-    data.push(bump);
-    
-    Instruction::new_with_bytes(
-        *program_id,
-        &data,
-        vec![
-            AccountMeta::new(*vault_pda, false),
-            AccountMeta::new(*refund, false),
-        ],
-    )
-} 
\ No newline at end of file
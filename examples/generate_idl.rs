@@ -0,0 +1,15 @@
+// writes the program's interface description to idl/solana_falcon_vault.json, or to
+// the path given; a unit test fails until the committed copy is regenerated
+//
+//   cargo run --example generate_idl --features client
+
+use solana_falcon_vault::idl::idl_json;
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| "idl/solana_falcon_vault.json".into());
+    if let Some(dir) = std::path::Path::new(&path).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).expect("creating the IDL directory");
+    }
+    std::fs::write(&path, idl_json()).expect("writing the IDL");
+    println!("Wrote {}", path);
+}
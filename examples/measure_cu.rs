@@ -0,0 +1,10 @@
+// prints the measured compute units of the vault instructions next to the estimates
+// in falcon::performance, for updating those constants
+//
+//   cargo build-sbf && cargo run --example measure_cu --features bench-tests
+
+use solana_falcon_vault::measure::{measure_compute_units, print_measurements};
+
+fn main() {
+    print_measurements(&measure_compute_units());
+}
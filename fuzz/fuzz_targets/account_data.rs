@@ -0,0 +1,9 @@
+#![no_main]
+
+// vault data, stored h_ntt and exported SHAKE256 states
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    solana_falcon_vault::fuzz::account_data(data);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+// the compressed s2 payload of a signature
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    solana_falcon_vault::fuzz::decompress_signature(data);
+});
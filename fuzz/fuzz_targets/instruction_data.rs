@@ -0,0 +1,9 @@
+#![no_main]
+
+// instruction data through every deserializer
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    solana_falcon_vault::fuzz::instruction_data(data);
+});
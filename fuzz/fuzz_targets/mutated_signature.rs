@@ -0,0 +1,9 @@
+#![no_main]
+
+// fixture signatures and keys with bytes flipped, see src/fuzz.rs for the input layout
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    solana_falcon_vault::fuzz::mutated_signature(data);
+});
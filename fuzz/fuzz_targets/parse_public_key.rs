@@ -0,0 +1,9 @@
+#![no_main]
+
+// public keys, reducing and strict
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    solana_falcon_vault::fuzz::parse_public_key(data);
+});
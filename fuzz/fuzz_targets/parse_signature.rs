@@ -0,0 +1,9 @@
+#![no_main]
+
+// signature headers, nonces and payloads
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    solana_falcon_vault::fuzz::parse_signature(data);
+});
@@ -0,0 +1,148 @@
+// test-only global allocator that catches heap use in code meant to run without one.
+// the program build has no allocator configured, so a format! or a Vec that slips into
+// the verification path would only show up as a failure on-chain; here it fails a test.
+// allocating inside forbid_heap is what "panics": the allocator can't unwind itself
+// (panicking from alloc aborts the whole test binary), so it records the size, serves
+// the request and forbid_heap panics as soon as the closure returns
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct HeapGuard;
+
+thread_local! {
+    // set while a forbid_heap closure runs on this thread
+    static FORBIDDEN: Cell<bool> = const { Cell::new(false) };
+    // size of the first allocation made while forbidden
+    static CAUGHT: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+fn record(layout: &Layout) {
+    // try_with, since threads still allocate while their thread locals are torn down
+    let _ = FORBIDDEN.try_with(|forbidden| {
+        if forbidden.replace(false) {
+            CAUGHT.set(Some(layout.size()));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for HeapGuard {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(&layout);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        record(&layout);
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(&layout);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: HeapGuard = HeapGuard;
+
+// run f and panic if it touched the heap; only this thread is watched, so the other
+// tests running in parallel don't trip it
+pub(crate) fn forbid_heap<T>(f: impl FnOnce() -> T) -> T {
+    CAUGHT.set(None);
+    FORBIDDEN.set(true);
+    let result = f();
+    FORBIDDEN.set(false);
+    if let Some(size) = CAUGHT.take() {
+        panic!("heap allocation of {} bytes on a path that must not allocate", size);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::falcon::internals::hash_to_point;
+    use crate::falcon::verify::tests::{signed_fixture, FIXTURE_NONCE};
+    use crate::falcon::*;
+
+    #[test]
+    #[should_panic(expected = "heap allocation")]
+    fn test_forbid_heap_catches_allocations() {
+        forbid_heap(|| std::hint::black_box(vec![0u8; 16]).len());
+    }
+
+    #[test]
+    fn test_verification_never_allocates() {
+        // fixtures and messages are built before the guard goes up
+        let (public_key, signature) = signed_fixture(b"no heap", &FIXTURE_NONCE);
+        let mut tampered = signature;
+        tampered[100] ^= 0x01;
+
+        forbid_heap(|| {
+            assert_eq!(verify_falcon_signature(&public_key, &signature, b"no heap"), Ok(()));
+            assert!(verify_falcon_signature(&public_key, &tampered, b"no heap").is_err());
+            assert!(verify_falcon_signature(&public_key, &signature, b"other").is_err());
+            assert_eq!(verify_falcon_signature_ct(&public_key, &signature, b"no heap"), Ok(()));
+            assert_eq!(
+                verify_falcon_signature_parts(&public_key, &signature, &[b"no ", b"heap"]),
+                Ok(())
+            );
+
+            // both NTT table choices and early termination
+            let config = OptimizationConfig { use_lookup_tables: true, ..OptimizationConfig::default() };
+            assert_eq!(
+                verify_falcon_signature_with_config(&public_key, &signature, b"no heap", &config),
+                Ok(())
+            );
+            let config = OptimizationConfig { use_lookup_tables: false, ..OptimizationConfig::default() };
+            assert_eq!(
+                verify_falcon_signature_with_config(&public_key, &signature, b"no heap", &config),
+                Ok(())
+            );
+        });
+    }
+
+    #[test]
+    fn test_building_blocks_never_allocate() {
+        forbid_heap(|| {
+            let c = hash_to_point(b"no heap", &FIXTURE_NONCE);
+            assert!(c.iter().all(|&coeff| (coeff as u32) < Q));
+
+            let mut coeffs = [0u32; FALCON_512_N];
+            for (coeff, &value) in coeffs.iter_mut().zip(c.iter()) {
+                *coeff = value as u32;
+            }
+            let original = coeffs;
+            ntt_forward(&mut coeffs);
+            ntt_inverse(&mut coeffs);
+            ntt_forward_precomputed(&mut coeffs);
+            ntt_inverse_precomputed(&mut coeffs);
+            assert_eq!(coeffs, original);
+
+            let mut poly = Polynomial::from_u16_array(&c);
+            poly.ntt_in_place();
+            poly.mul_assign_pointwise(&Polynomial::from_u16_array(&c));
+            poly.intt_in_place();
+            assert!(poly.squared_norm() > 0);
+        });
+    }
+
+    // the types verification keeps on the stack, against the limits the docs give:
+    // 2 KB per polynomial and 3 KB of VerifyScratch, under the 4 KB SBF stack frame
+    #[test]
+    fn test_stack_types_within_limits() {
+        use core::mem::size_of;
+
+        assert_eq!(size_of::<Polynomial>(), 2048);
+        assert_eq!(size_of::<VerifyScratch>(), 3 * 1024);
+
+        // what the instructions hold next to it
+        assert!(size_of::<FalconPublicKey>() + size_of::<FalconSignature>() < 2048);
+        assert!(size_of::<Shake256>() <= 512);
+    }
+}
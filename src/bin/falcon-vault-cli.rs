@@ -0,0 +1,12 @@
+// command-line client for the vault program, see src/cli for the commands and the
+// keystore format. build with: cargo build --release --features cli --bin falcon-vault-cli
+
+use clap::Parser;
+use solana_falcon_vault::cli::{run, Cli};
+
+fn main() {
+    if let Err(error) = run(Cli::parse()) {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,143 @@
+// decoding a vault account for `falcon-vault-cli inspect`, from the RPC or from the
+// JSON `solana account <ADDRESS> --output json` writes
+
+use std::fmt;
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use super::CliResult;
+use crate::client::derive_vault_address_with_authority;
+use crate::encoding::{hex_len, to_hex};
+use crate::falcon::FalconPublicKey;
+use crate::instructions::{
+    load_close_authority, load_public_key, load_transfer_nonce, VAULT_VERSION, VAULT_VERSION_CLOSE_AUTHORITY,
+    VAULT_VERSION_NONCE, VAULT_VERSION_NONCE_CLOSE_AUTHORITY,
+};
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+// what a vault account holds. the layout has no limits, a transfer is bounded only by
+// the balance
+#[derive(Clone)]
+pub struct VaultInfo {
+    pub address: Pubkey,
+    pub lamports: u64,
+    pub version: u8,
+    pub public_key: FalconPublicKey,
+    pub close_authority: Option<Pubkey>,
+    // the nonce the next transfer signs, None for a v1 or v2 vault, which the program
+    // only lets close
+    pub nonce: Option<u64>,
+    // Some when address is the vault PDA of public_key and close_authority, which
+    // every instruction checks
+    pub bump: Option<u8>,
+}
+
+impl VaultInfo {
+    // fails for an account the program doesn't own or whose data isn't a known layout
+    pub fn decode(program_id: &Pubkey, address: &Pubkey, account: &Account) -> CliResult<Self> {
+        if account.owner != *program_id {
+            return Err(format!("{} is owned by {}, not the vault program {}", address, account.owner, program_id).into());
+        }
+        let version = match account.data.first() {
+            Some(
+                &version @ (VAULT_VERSION
+                | VAULT_VERSION_CLOSE_AUTHORITY
+                | VAULT_VERSION_NONCE
+                | VAULT_VERSION_NONCE_CLOSE_AUTHORITY),
+            ) => version,
+            Some(version) => return Err(format!("{} has unknown vault layout version {}", address, version).into()),
+            None => return Err(format!("{} has no data, it isn't a vault", address).into()),
+        };
+        let not_a_vault = |_| format!("{} holds {} bytes, not a version {} vault", address, account.data.len(), version);
+        let public_key = load_public_key(&account.data).map_err(not_a_vault)?;
+        let close_authority = load_close_authority(&account.data).map_err(not_a_vault)?.map(Pubkey::new_from_array);
+        let has_nonce = matches!(version, VAULT_VERSION_NONCE | VAULT_VERSION_NONCE_CLOSE_AUTHORITY);
+        let nonce = match has_nonce {
+            true => Some(load_transfer_nonce(&account.data).map_err(not_a_vault)?.value()),
+            false => None,
+        };
+
+        let (derived, bump) = derive_vault_address_with_authority(program_id, &public_key, close_authority.as_ref());
+        Ok(Self {
+            address: *address,
+            lamports: account.lamports,
+            version,
+            public_key,
+            close_authority,
+            nonce,
+            bump: (derived == *address).then_some(bump),
+        })
+    }
+
+    pub fn key_hash(&self) -> [u8; 32] {
+        self.public_key.hash()
+    }
+}
+
+impl fmt::Display for VaultInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut hash = [0u8; hex_len(32)];
+        let hash = to_hex(&self.key_hash(), &mut hash).map_err(|_| fmt::Error)?;
+        let sol = (self.lamports / LAMPORTS_PER_SOL, self.lamports % LAMPORTS_PER_SOL);
+
+        writeln!(f, "Vault:           {}", self.address)?;
+        writeln!(f, "Balance:         {} lamports ({}.{:09} SOL)", self.lamports, sol.0, sol.1)?;
+        writeln!(f, "Layout version:  {}", self.version)?;
+        writeln!(f, "Key hash:        {}", hash)?;
+        let seeds = if self.close_authority.is_some() { "the key and close authority" } else { "the key" };
+        match self.bump {
+            Some(bump) => writeln!(f, "Address:         vault PDA of {}, bump {}", seeds, bump)?,
+            None => writeln!(f, "Address:         NOT the vault PDA of {}", seeds)?,
+        }
+        match self.close_authority {
+            Some(authority) => writeln!(f, "Close authority: {}", authority)?,
+            None => writeln!(f, "Close authority: none")?,
+        }
+        match self.nonce {
+            Some(nonce) => write!(f, "Transfer nonce:  {}", nonce),
+            None => write!(f, "Transfer nonce:  none (transfers rejected, close the vault)"),
+        }
+    }
+}
+
+// the parts of `solana account --output json` inspect reads, the rest is ignored
+#[derive(Deserialize)]
+struct AccountDump {
+    pubkey: String,
+    account: DumpedAccount,
+}
+
+#[derive(Deserialize)]
+struct DumpedAccount {
+    lamports: u64,
+    // [data, encoding]
+    data: (String, String),
+    owner: String,
+}
+
+// the address and account in a `solana account --output json` dump
+pub fn parse_account_dump(json: &str) -> CliResult<(Pubkey, Account)> {
+    let dump: AccountDump = serde_json::from_str(json)?;
+    let (data, encoding) = dump.account.data;
+    if encoding != "base64" {
+        return Err(format!("account data is {}, expected base64", encoding).into());
+    }
+
+    let account = Account {
+        lamports: dump.account.lamports,
+        data: STANDARD.decode(data)?,
+        owner: dump.account.owner.parse()?,
+        executable: false,
+        rent_epoch: 0,
+    };
+    Ok((dump.pubkey.parse()?, account))
+}
+
+pub fn read_account_file(path: &Path) -> CliResult<(Pubkey, Account)> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_account_dump(&json).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
@@ -0,0 +1,202 @@
+// encrypted keystore for a Falcon-512 key pair, one JSON file per key:
+//
+//   {
+//     "version": 1,
+//     "public_key": "<base64, 897 bytes>",
+//     "kdf": { "name": "argon2id", "salt": "<base64, 16 bytes>", "m_cost": 19456, "t_cost": 2, "p_cost": 1 },
+//     "cipher": { "name": "aes-256-gcm", "nonce": "<base64, 12 bytes>" },
+//     "ciphertext": "<base64, 48 bytes>"
+//   }
+//
+// the secret is the 32-byte seed the key pair is generated from (see keypair_from_seed),
+// so the private key itself is never serialized. argon2id turns the passphrase and salt
+// into the AES-256-GCM key, and the public key is the associated data, so a file with
+// its public key swapped for another fails to decrypt. the public key is in the clear
+// so open, balance and inspect work without the passphrase
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::CliResult;
+use crate::falcon::{FalconKeyPair, FalconPublicKey};
+
+pub const KEYSTORE_VERSION: u8 = 1;
+pub const KEYSTORE_KDF: &str = "argon2id";
+pub const KEYSTORE_CIPHER: &str = "aes-256-gcm";
+
+pub const SEED_SIZE: usize = 32;
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+
+pub type Seed = Zeroizing<[u8; SEED_SIZE]>;
+
+// argon2id cost parameters, stored in the file so they can be raised for new keystores
+// without breaking old ones. the default is argon2's own (19 MiB, 2 passes, 1 lane)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self { m_cost: Params::DEFAULT_M_COST, t_cost: Params::DEFAULT_T_COST, p_cost: Params::DEFAULT_P_COST }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Kdf {
+    pub name: String,
+    pub salt: String,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cipher {
+    pub name: String,
+    pub nonce: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u8,
+    pub public_key: FalconPublicKey,
+    pub kdf: Kdf,
+    pub cipher: Cipher,
+    pub ciphertext: String,
+}
+
+// a fresh seed from the operating system's rng
+pub fn generate_seed() -> Seed {
+    let mut seed = Zeroizing::new([0u8; SEED_SIZE]);
+    rand::rng().fill_bytes(&mut *seed);
+    seed
+}
+
+// the key pair a seed stands for. ChaCha20 is fixed here rather than StdRng, whose
+// algorithm may change between rand releases and would then give a different key
+pub fn keypair_from_seed(seed: &[u8; SEED_SIZE]) -> FalconKeyPair {
+    FalconKeyPair::generate(&mut ChaCha20Rng::from_seed(*seed))
+}
+
+impl Keystore {
+    // seal seed under passphrase, with a random salt and nonce
+    pub fn encrypt(
+        seed: &[u8; SEED_SIZE],
+        public_key: &FalconPublicKey,
+        passphrase: &str,
+        params: KdfParams,
+    ) -> CliResult<Self> {
+        let mut salt = [0u8; SALT_SIZE];
+        let mut nonce = [0u8; NONCE_SIZE];
+        rand::rng().fill_bytes(&mut salt);
+        rand::rng().fill_bytes(&mut nonce);
+
+        let key = cipher_key(passphrase, &salt, params)?;
+        let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&*key))
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: seed, aad: public_key.as_bytes() })
+            .map_err(|_| "keystore encryption failed")?;
+
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            public_key: *public_key,
+            kdf: Kdf {
+                name: KEYSTORE_KDF.into(),
+                salt: STANDARD.encode(salt),
+                m_cost: params.m_cost,
+                t_cost: params.t_cost,
+                p_cost: params.p_cost,
+            },
+            cipher: Cipher { name: KEYSTORE_CIPHER.into(), nonce: STANDARD.encode(nonce) },
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    // the seed, or an error for a wrong passphrase or a tampered file
+    pub fn decrypt_seed(&self, passphrase: &str) -> CliResult<Seed> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(format!("unsupported keystore version {}", self.version).into());
+        }
+        if self.kdf.name != KEYSTORE_KDF || self.cipher.name != KEYSTORE_CIPHER {
+            return Err(format!("unsupported keystore scheme {} / {}", self.kdf.name, self.cipher.name).into());
+        }
+        let salt = STANDARD.decode(&self.kdf.salt)?;
+        let nonce = STANDARD.decode(&self.cipher.nonce)?;
+        if nonce.len() != NONCE_SIZE {
+            return Err(format!("keystore nonce is {} bytes, expected {}", nonce.len(), NONCE_SIZE).into());
+        }
+        let ciphertext = STANDARD.decode(&self.ciphertext)?;
+
+        let params = KdfParams { m_cost: self.kdf.m_cost, t_cost: self.kdf.t_cost, p_cost: self.kdf.p_cost };
+        let key = cipher_key(passphrase, &salt, params)?;
+        let plaintext = Zeroizing::new(
+            Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&*key))
+                .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: self.public_key.as_bytes() })
+                .map_err(|_| "wrong passphrase or corrupted keystore")?,
+        );
+
+        let mut seed = Zeroizing::new([0u8; SEED_SIZE]);
+        if plaintext.len() != SEED_SIZE {
+            return Err("corrupted keystore".into());
+        }
+        seed.copy_from_slice(&plaintext);
+        Ok(seed)
+    }
+
+    // the key pair, checked against the public key the file names
+    pub fn unlock(&self, passphrase: &str) -> CliResult<FalconKeyPair> {
+        let keypair = keypair_from_seed(&*self.decrypt_seed(passphrase)?);
+        if keypair.public_key != self.public_key {
+            return Err("keystore seed doesn't generate its public key".into());
+        }
+        Ok(keypair)
+    }
+
+    pub fn read(path: &Path) -> CliResult<Self> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?)
+    }
+
+    // writes a new file readable only by its owner; an existing one is only replaced
+    // with overwrite, so keygen can't silently destroy a key
+    pub fn write(&self, path: &Path, overwrite: bool) -> CliResult<()> {
+        let mut options = OpenOptions::new();
+        options.write(true);
+        if overwrite {
+            options.create(true).truncate(true);
+        } else {
+            options.create_new(true);
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options.open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+        file.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+// the AES-256 key for passphrase and salt
+fn cipher_key(passphrase: &str, salt: &[u8], params: KdfParams) -> CliResult<Zeroizing<[u8; 32]>> {
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| format!("invalid keystore kdf parameters: {}", e))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut *key)
+        .map_err(|e| format!("keystore kdf failed: {}", e))?;
+    Ok(key)
+}
@@ -0,0 +1,251 @@
+// falcon-vault-cli: Falcon key generation into an encrypted keystore, vault operations
+// against an RPC endpoint and decoding of vault accounts. host only (feature "cli");
+// src/bin/falcon-vault-cli.rs just parses the arguments and calls run, everything
+// else lives here so the integration tests in tests/cli.rs can reach it
+
+pub mod inspect;
+pub use inspect::*;
+
+pub mod keystore;
+pub use keystore::*;
+
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand};
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+use zeroize::Zeroizing;
+
+use crate::client::{
+    close_message, close_vault_ix, derive_vault_address, derive_vault_address_with_authority, fits_in_transaction,
+    open_vault_ix, transfer_ix_with_budget, transfer_message, vault_close_nonce, vault_transfer_nonce,
+    VAULT_SIGNING_CONTEXT,
+};
+
+pub type CliResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+// the keystore passphrase is read from here if set, otherwise prompted for
+pub const PASSPHRASE_ENV: &str = "FALCON_VAULT_PASSPHRASE";
+
+pub const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+
+#[derive(Debug, Parser)]
+#[command(name = "falcon-vault-cli", version, about = "Falcon-512 vault operations")]
+pub struct Cli {
+    #[arg(long, short = 'u', global = true, default_value = DEFAULT_RPC_URL, help = "JSON RPC endpoint")]
+    pub url: String,
+
+    #[arg(long, global = true, default_value_t = Pubkey::new_from_array(crate::ID), help = "vault program id")]
+    pub program_id: Pubkey,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Solana keypair file paying fees and rent [default: ~/.config/solana/id.json]"
+    )]
+    pub fee_payer: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    #[command(about = "generate a Falcon-512 key pair into a new encrypted keystore")]
+    Keygen {
+        #[arg(long, short = 'k', help = "keystore file to create")]
+        keypair: PathBuf,
+        #[arg(long, help = "replace the keystore if it already exists")]
+        force: bool,
+    },
+
+    #[command(about = "open the vault of a keystore's key")]
+    Open {
+        #[arg(long, short = 'k', help = "keystore file")]
+        keypair: PathBuf,
+        #[arg(long, help = "account that may close the vault without a Falcon signature once it's empty")]
+        close_authority: Option<Pubkey>,
+    },
+
+    #[command(about = "print a vault's balance")]
+    Balance {
+        #[command(flatten)]
+        vault: VaultArg,
+    },
+
+    #[command(about = "transfer lamports out of a keystore's vault")]
+    Transfer {
+        #[arg(long, short = 'k', help = "keystore file")]
+        keypair: PathBuf,
+        #[arg(long, help = "recipient address")]
+        to: Pubkey,
+        #[arg(long)]
+        lamports: u64,
+        #[arg(long, help = "compute unit limit for the transfer [default: client::TRANSFER_COMPUTE_UNIT_LIMIT]")]
+        compute_unit_limit: Option<u32>,
+        #[arg(long, help = CLOSE_AUTHORITY_HELP)]
+        close_authority: Option<Pubkey>,
+    },
+
+    #[command(about = "close a keystore's vault, refunding its whole balance")]
+    Close {
+        #[arg(long, short = 'k', help = "keystore file")]
+        keypair: PathBuf,
+        #[arg(long, help = "address receiving the balance")]
+        refund: Pubkey,
+        #[arg(long, help = CLOSE_AUTHORITY_HELP)]
+        close_authority: Option<Pubkey>,
+    },
+
+    #[command(about = "decode a vault account")]
+    Inspect {
+        #[command(flatten)]
+        vault: VaultArg,
+        #[arg(long, conflicts_with_all = ["vault", "keypair"], help = "read the account from `solana account --output json` instead")]
+        account_file: Option<PathBuf>,
+    },
+}
+
+// the close authority is a seed of the vault address, so naming a vault opened with one
+// by its keystore takes the authority too
+const CLOSE_AUTHORITY_HELP: &str = "close authority the vault was opened with, if any";
+
+// a vault named by its address or by the keystore whose key it holds
+#[derive(Debug, PartialEq, Eq, Args)]
+pub struct VaultArg {
+    #[arg(long, conflicts_with = "keypair", help = "vault address")]
+    pub vault: Option<Pubkey>,
+    #[arg(long, short = 'k', help = "keystore whose vault to use")]
+    pub keypair: Option<PathBuf>,
+    #[arg(long, requires = "keypair", conflicts_with = "vault", help = CLOSE_AUTHORITY_HELP)]
+    pub close_authority: Option<Pubkey>,
+}
+
+pub fn run(cli: Cli) -> CliResult<()> {
+    let program_id = &cli.program_id;
+    match &cli.command {
+        Command::Keygen { keypair, force } => {
+            // the file is checked first, so a taken path fails before the passphrase prompt
+            if keypair.exists() && !force {
+                return Err(format!("{} already exists, pass --force to replace it", keypair.display()).into());
+            }
+            let passphrase = passphrase(true)?;
+            let seed = generate_seed();
+            let public_key = keypair_from_seed(&seed).public_key;
+            Keystore::encrypt(&seed, &public_key, &passphrase, KdfParams::default())?.write(keypair, *force)?;
+
+            println!("Wrote {}", keypair.display());
+            println!("Vault: {}", derive_vault_address(program_id, &public_key).0);
+        }
+
+        Command::Open { keypair, close_authority } => {
+            let public_key = Keystore::read(keypair)?.public_key;
+            let (vault, bump) = derive_vault_address_with_authority(program_id, &public_key, close_authority.as_ref());
+            let fee_payer = fee_payer(&cli)?;
+            let ix = open_vault_ix(program_id, &fee_payer.pubkey(), &vault, &public_key, bump, close_authority.as_ref());
+            send(&cli, &fee_payer, &[ix])?;
+            println!("Opened vault {}", vault);
+        }
+
+        Command::Balance { vault } => {
+            let vault = vault_address(program_id, vault)?;
+            let lamports = rpc(&cli).get_balance(&vault)?;
+            println!("{} lamports", lamports);
+        }
+
+        Command::Transfer { keypair, to, lamports, compute_unit_limit, close_authority } => {
+            let keypair = Keystore::read(keypair)?.unlock(&passphrase(false)?)?;
+            let (vault, bump) =
+                derive_vault_address_with_authority(program_id, &keypair.public_key, close_authority.as_ref());
+            // the vault's current nonce is what the program checks the signature against
+            let vault_data = rpc(&cli).get_account_data(&vault)?;
+            let nonce = vault_transfer_nonce(&vault_data).ok_or_else(|| format!("{} is not a vault", vault))?;
+            let message = transfer_message(*lamports, to, nonce, &vault);
+            let signature = keypair.private_key.sign_with_context(VAULT_SIGNING_CONTEXT, &message, &mut rand::rng());
+            let ixs = transfer_ix_with_budget(program_id, &vault, to, *lamports, &signature.bytes, bump, *compute_unit_limit);
+            send(&cli, &fee_payer(&cli)?, &ixs)?;
+            println!("Transferred {} lamports from {} to {}", lamports, vault, to);
+        }
+
+        Command::Close { keypair, refund, close_authority } => {
+            let keypair = Keystore::read(keypair)?.unlock(&passphrase(false)?)?;
+            let (vault, bump) =
+                derive_vault_address_with_authority(program_id, &keypair.public_key, close_authority.as_ref());
+            // a close signs the vault's current nonce, like a transfer
+            let vault_data = rpc(&cli).get_account_data(&vault)?;
+            let nonce = vault_close_nonce(&vault_data).ok_or_else(|| format!("{} is not a vault", vault))?;
+            let message = close_message(refund, nonce, &vault);
+            let signature = keypair.private_key.sign_with_context(VAULT_SIGNING_CONTEXT, &message, &mut rand::rng());
+            let ix = close_vault_ix(program_id, &vault, refund, &signature.bytes, bump);
+            send(&cli, &fee_payer(&cli)?, &[ix])?;
+            println!("Closed vault {}, balance refunded to {}", vault, refund);
+        }
+
+        Command::Inspect { vault, account_file } => {
+            let (address, account) = match account_file {
+                Some(path) => read_account_file(path)?,
+                None => {
+                    let address = vault_address(program_id, vault)?;
+                    (address, rpc(&cli).get_account(&address)?)
+                }
+            };
+            println!("{}", VaultInfo::decode(program_id, &address, &account)?);
+        }
+    }
+    Ok(())
+}
+
+fn rpc(cli: &Cli) -> RpcClient {
+    RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed())
+}
+
+fn send(cli: &Cli, fee_payer: &Keypair, instructions: &[Instruction]) -> CliResult<()> {
+    // fails here, naming the size, rather than with the RPC's error for an oversized packet
+    fits_in_transaction(instructions, 1)?;
+    let rpc = rpc(cli);
+    let blockhash = rpc.get_latest_blockhash()?;
+    let transaction =
+        Transaction::new_signed_with_payer(instructions, Some(&fee_payer.pubkey()), &[fee_payer], blockhash);
+    println!("Signature: {}", rpc.send_and_confirm_transaction(&transaction)?);
+    Ok(())
+}
+
+fn fee_payer(cli: &Cli) -> CliResult<Keypair> {
+    let path = match &cli.fee_payer {
+        Some(path) => path.clone(),
+        None => {
+            let home = std::env::var_os("HOME").ok_or("HOME isn't set, pass --fee-payer")?;
+            Path::new(&home).join(".config/solana/id.json")
+        }
+    };
+    read_keypair_file(&path).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+// the address a VaultArg names; the keystore's public key is in the clear, so no passphrase
+pub fn vault_address(program_id: &Pubkey, vault: &VaultArg) -> CliResult<Pubkey> {
+    match (&vault.vault, &vault.keypair) {
+        (Some(address), _) => Ok(*address),
+        (None, Some(keypair)) => {
+            let public_key = Keystore::read(keypair)?.public_key;
+            Ok(derive_vault_address_with_authority(program_id, &public_key, vault.close_authority.as_ref()).0)
+        }
+        (None, None) => Err("name the vault with --vault or --keypair".into()),
+    }
+}
+
+fn passphrase(confirm: bool) -> CliResult<Zeroizing<String>> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+    let passphrase = Zeroizing::new(rpassword::prompt_password("Keystore passphrase: ")?);
+    if confirm && *passphrase != *Zeroizing::new(rpassword::prompt_password("Repeat passphrase: ")?) {
+        return Err("passphrases don't match".into());
+    }
+    Ok(passphrase)
+}
@@ -0,0 +1,544 @@
+// off-chain instruction builders for the vault program
+// only built for clients (feature "client") and tests, the on-chain program never uses it
+// the instruction data layouts here are the ones the processors' deserialize functions
+// read, and the signed messages come from the messages module the processors verify with
+
+use solana_compute_budget_interface::ComputeBudgetInstruction;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+};
+
+use crate::falcon::{FalconPublicKey, FALCON_512_SIGNATURE_SIZE, TOTAL_ESTIMATED_COMPUTE_UNITS};
+use crate::instructions::{
+    load_close_nonce, load_transfer_nonce, VaultInstructions, VERIFY_ONLY_ACCEPTED, VERIFY_ONLY_REJECTED,
+    VERIFY_ONLY_REJECTED_SIZE,
+};
+
+// the messages a vault key signs, re-exported so clients need only this module
+pub use crate::messages::{
+    build_close_message, build_close_message_with_balance, build_transfer_message, CLOSE_MESSAGE_SIZE,
+    LEGACY_CLOSE_NONCE, TRANSFER_MESSAGE_SIZE, VAULT_SIGNING_CONTEXT,
+};
+
+// compute unit limit requested ahead of a transfer: the verification estimate
+// plus 25% headroom for account loading, the PDA check and estimate drift
+pub const TRANSFER_COMPUTE_UNIT_LIMIT: u32 =
+    (TOTAL_ESTIMATED_COMPUTE_UNITS + TOTAL_ESTIMATED_COMPUTE_UNITS / 4) as u32;
+
+fn system_program() -> Pubkey {
+    Pubkey::new_from_array(pinocchio_system::ID)
+}
+
+// the vault address and bump for a key: the PDA of the key's SHA256 hash, which is
+// what OpenVault creates and every instruction checks the vault account against
+pub fn derive_vault_address(program_id: &Pubkey, public_key: &FalconPublicKey) -> (Pubkey, u8) {
+    derive_vault_address_with_authority(program_id, public_key, None)
+}
+
+// the vault address and bump for a key opened with close_authority, which is a seed
+// of the address after the key hash; None is derive_vault_address
+pub fn derive_vault_address_with_authority(
+    program_id: &Pubkey,
+    public_key: &FalconPublicKey,
+    close_authority: Option<&Pubkey>,
+) -> (Pubkey, u8) {
+    let authority_seed = close_authority.map_or(&[][..], |authority| authority.as_ref());
+    Pubkey::find_program_address(&[&public_key.hash(), authority_seed], program_id)
+}
+
+// the message to sign for transfer_ix out of vault, nonce from vault_transfer_nonce,
+// under VAULT_SIGNING_CONTEXT
+pub fn transfer_message(amount: u64, recipient: &Pubkey, nonce: u64, vault: &Pubkey) -> [u8; TRANSFER_MESSAGE_SIZE] {
+    build_transfer_message(amount, &recipient.to_bytes(), nonce, &vault.to_bytes())
+}
+
+// the nonce the next transfer out of a vault signs, read from its account data. None if
+// the data isn't a vault, or is a v1 or v2 vault, which has no nonce and can't transfer
+pub fn vault_transfer_nonce(vault_data: &[u8]) -> Option<u64> {
+    load_transfer_nonce(vault_data).ok().map(|nonce| nonce.value())
+}
+
+// the nonce a close of a vault signs, read from its account data: the next transfer's
+// nonce, or LEGACY_CLOSE_NONCE for a v1 or v2 vault. None if the data isn't a vault
+pub fn vault_close_nonce(vault_data: &[u8]) -> Option<u64> {
+    load_close_nonce(vault_data).ok().map(|nonce| nonce.value())
+}
+
+// the message to sign for close_vault_ix of vault, nonce from vault_close_nonce,
+// under VAULT_SIGNING_CONTEXT
+pub fn close_message(refund: &Pubkey, nonce: u64, vault: &Pubkey) -> [u8; CLOSE_MESSAGE_SIZE] {
+    build_close_message(&refund.to_bytes(), nonce, &vault.to_bytes())
+}
+
+// build an OpenVault instruction, vault and bump from derive_vault_address_with_authority
+// with the same close_authority
+// data: [discriminator(1), falcon_pubkey(897), bump(1)], then close_authority(32) if any
+pub fn open_vault_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    vault: &Pubkey,
+    public_key: &FalconPublicKey,
+    bump: u8,
+    close_authority: Option<&Pubkey>,
+) -> Instruction {
+    let mut data = vec![VaultInstructions::OpenVault as u8];
+    data.extend_from_slice(public_key.as_bytes());
+    data.push(bump);
+    if let Some(close_authority) = close_authority {
+        data.extend_from_slice(close_authority.as_ref());
+    }
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(system_program(), false),
+        ],
+    )
+}
+
+// build a BatchOpenVault instruction opening a vault for each key, with the vault
+// addresses in the order of the keys
+// data: [discriminator(1)] then [falcon_pubkey(897), bump(1)] per key
+pub fn batch_open_vault_ix(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    public_keys: &[FalconPublicKey],
+) -> (Instruction, Vec<Pubkey>) {
+    let mut data = vec![VaultInstructions::BatchOpenVault as u8];
+    let mut accounts = vec![AccountMeta::new(*payer, true), AccountMeta::new_readonly(system_program(), false)];
+    let mut vaults = Vec::with_capacity(public_keys.len());
+    for public_key in public_keys {
+        let (vault, bump) = derive_vault_address(program_id, public_key);
+        data.extend_from_slice(public_key.as_bytes());
+        data.push(bump);
+        accounts.push(AccountMeta::new(vault, false));
+        vaults.push(vault);
+    }
+    (Instruction::new_with_bytes(*program_id, &data, accounts), vaults)
+}
+
+// build a TransferFromVault instruction
+// data: [discriminator(1), signature(666), amount(8), bump(1)]
+pub fn transfer_ix(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    signature: &[u8; FALCON_512_SIGNATURE_SIZE],
+    bump: u8,
+) -> Instruction {
+    let mut data = vec![VaultInstructions::TransferFromVault as u8];
+    data.extend_from_slice(signature);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(system_program(), false),
+        ],
+    )
+}
+
+// a transfer preceded by a compute budget request, since verification alone
+// leaves little room under the default per-instruction limit
+// compute_unit_limit overrides TRANSFER_COMPUTE_UNIT_LIMIT when set
+pub fn transfer_ix_with_budget(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    signature: &[u8; FALCON_512_SIGNATURE_SIZE],
+    bump: u8,
+    compute_unit_limit: Option<u32>,
+) -> Vec<Instruction> {
+    vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(
+            compute_unit_limit.unwrap_or(TRANSFER_COMPUTE_UNIT_LIMIT),
+        ),
+        transfer_ix(program_id, vault, recipient, amount, signature, bump),
+    ]
+}
+
+// build a VerifyOnly instruction, the dry run of the same transfer_ix
+// data: [discriminator(1), signature(666), amount(8), bump(1), recipient(32)]; a dry run
+// of a pre-hashed transfer appends TRANSFER_MESSAGE_PREHASHED like the transfer does
+pub fn verify_only_ix(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    signature: &[u8; FALCON_512_SIGNATURE_SIZE],
+    bump: u8,
+) -> Instruction {
+    let mut data = vec![VaultInstructions::VerifyOnly as u8];
+    data.extend_from_slice(signature);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(bump);
+    data.extend_from_slice(recipient.as_ref());
+
+    Instruction::new_with_bytes(*program_id, &data, vec![AccountMeta::new_readonly(*vault, false)])
+}
+
+// the outcome a VerifyOnly reported in its return data: Ok if the transfer would go
+// through, otherwise the error code it would fail with. None if the data is neither
+pub fn verify_only_result(return_data: &[u8]) -> Option<Result<(), u64>> {
+    match return_data {
+        [VERIFY_ONLY_ACCEPTED] => Some(Ok(())),
+        [VERIFY_ONLY_REJECTED, error @ ..] if return_data.len() == VERIFY_ONLY_REJECTED_SIZE => {
+            Some(Err(u64::from_le_bytes(error.try_into().ok()?)))
+        }
+        _ => None,
+    }
+}
+
+// build a SelfTest instruction, no accounts and no data beyond the discriminator
+pub fn self_test_ix(program_id: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(*program_id, &[VaultInstructions::SelfTest as u8], vec![])
+}
+
+// build a CloseVault instruction signed over close_message(refund, nonce, vault)
+// data: [discriminator(1), signature(666), bump(1)]
+pub fn close_vault_ix(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    refund: &Pubkey,
+    signature: &[u8; FALCON_512_SIGNATURE_SIZE],
+    bump: u8,
+) -> Instruction {
+    let mut data = vec![VaultInstructions::CloseVault as u8];
+    data.extend_from_slice(signature);
+    data.push(bump);
+
+    Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![AccountMeta::new(*vault, false), AccountMeta::new(*refund, false)],
+    )
+}
+
+// build a CloseVault instruction signed over build_close_message_with_balance, which
+// only goes through while the vault balance is within [min_balance, max_balance]
+// data: [discriminator(1), signature(666), bump(1), min_balance(8), max_balance(8)]
+pub fn close_vault_with_balance_ix(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    refund: &Pubkey,
+    signature: &[u8; FALCON_512_SIGNATURE_SIZE],
+    bump: u8,
+    min_balance: u64,
+    max_balance: u64,
+) -> Instruction {
+    let mut ix = close_vault_ix(program_id, vault, refund, signature, bump);
+    ix.data.extend_from_slice(&min_balance.to_le_bytes());
+    ix.data.extend_from_slice(&max_balance.to_le_bytes());
+    ix
+}
+
+// build a CloseVault instruction for a vault opened with a close authority, which
+// signs the transaction instead of the Falcon key
+// data: [discriminator(1), bump(1)]
+pub fn close_vault_with_authority_ix(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    refund: &Pubkey,
+    close_authority: &Pubkey,
+    bump: u8,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &[VaultInstructions::CloseVault as u8, bump],
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*refund, false),
+            AccountMeta::new_readonly(*close_authority, true),
+        ],
+    )
+}
+
+// a transaction over the packet limit, by overflow bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionTooLarge {
+    pub size: usize,
+    pub overflow: usize,
+}
+
+impl core::fmt::Display for TransactionTooLarge {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "transaction is {} bytes, {} over the {}-byte packet limit",
+            self.size, self.overflow, PACKET_DATA_SIZE
+        )
+    }
+}
+
+impl std::error::Error for TransactionTooLarge {}
+
+// bytes of a compact-u16 length prefix
+const fn compact_len_size(len: usize) -> usize {
+    match len {
+        0..0x80 => 1,
+        0x80..0x4000 => 2,
+        _ => 3,
+    }
+}
+
+// serialized size of a legacy transaction carrying ixs: the signatures, the message
+// header, the deduplicated account keys (program ids included), the blockhash and each
+// instruction's key indices and data. num_signers counts every signature the
+// transaction needs; signers beyond those marked in ixs, like a fee payer no
+// instruction names, are extra account keys
+pub fn estimate_transaction_size(ixs: &[Instruction], num_signers: usize) -> usize {
+    // each key once, a signer if any instruction marks it as one
+    let mut keys: Vec<(&Pubkey, bool)> = Vec::new();
+    for ix in ixs {
+        let metas = ix.accounts.iter().map(|meta| (&meta.pubkey, meta.is_signer));
+        for (key, signer) in core::iter::once((&ix.program_id, false)).chain(metas) {
+            match keys.iter_mut().find(|(known, _)| *known == key) {
+                Some(known) => known.1 |= signer,
+                None => keys.push((key, signer)),
+            }
+        }
+    }
+    let ix_signers = keys.iter().filter(|(_, signer)| *signer).count();
+    let signatures = num_signers.max(ix_signers);
+    let num_keys = keys.len() + signatures - ix_signers;
+
+    let instructions: usize = ixs
+        .iter()
+        .map(|ix| {
+            let accounts = compact_len_size(ix.accounts.len()) + ix.accounts.len();
+            1 + accounts + compact_len_size(ix.data.len()) + ix.data.len()
+        })
+        .sum();
+
+    compact_len_size(signatures)
+        + 64 * signatures
+        + 3
+        + compact_len_size(num_keys)
+        + 32 * num_keys
+        + 32
+        + compact_len_size(ixs.len())
+        + instructions
+}
+
+// whether ixs fit in one transaction, see estimate_transaction_size. worth checking
+// before sending: an oversized transaction otherwise fails at the RPC with an error
+// that doesn't say why
+pub fn fits_in_transaction(ixs: &[Instruction], num_signers: usize) -> Result<(), TransactionTooLarge> {
+    let size = estimate_transaction_size(ixs, num_signers);
+    match size.checked_sub(PACKET_DATA_SIZE) {
+        Some(overflow) if overflow > 0 => Err(TransactionTooLarge { size, overflow }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_ix_with_budget() {
+        let program_id = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let signature = [0x59u8; FALCON_512_SIGNATURE_SIZE];
+
+        let ixs = transfer_ix_with_budget(&program_id, &vault, &recipient, 42, &signature, 254, None);
+
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0].program_id, solana_compute_budget_interface::id());
+        assert_eq!(ixs[0], ComputeBudgetInstruction::set_compute_unit_limit(TRANSFER_COMPUTE_UNIT_LIMIT));
+        assert!(TRANSFER_COMPUTE_UNIT_LIMIT as u64 > TOTAL_ESTIMATED_COMPUTE_UNITS);
+
+        assert_eq!(ixs[1], transfer_ix(&program_id, &vault, &recipient, 42, &signature, 254));
+        assert_eq!(ixs[1].data.len(), 1 + FALCON_512_SIGNATURE_SIZE + 8 + 1);
+        assert_eq!(ixs[1].data[0], 1);
+
+        let ixs = transfer_ix_with_budget(&program_id, &vault, &recipient, 42, &signature, 254, Some(300_000));
+        assert_eq!(ixs[0], ComputeBudgetInstruction::set_compute_unit_limit(300_000));
+    }
+
+    // the address derive_vault_address gives is the one check_vault_address accepts,
+    // i.e. where OpenVault creates the vault, with and without a close authority
+    #[test]
+    fn test_derive_vault_address_matches_program() {
+        let program_id = Pubkey::new_from_array(crate::ID);
+        let authority = Pubkey::new_unique().to_bytes();
+        for keypair in crate::fixtures::fixture_keypairs() {
+            let public_key = FalconPublicKey::new(keypair.public_key);
+            for close_authority in [None, Some(&authority)] {
+                let (vault, bump) = derive_vault_address_with_authority(
+                    &program_id,
+                    &public_key,
+                    close_authority.map(|authority| Pubkey::new_from_array(*authority)).as_ref(),
+                );
+                let expected = solana_nostd_sha256::hashv(&[
+                    public_key.hash().as_ref(),
+                    crate::instructions::close_authority_seed(close_authority),
+                    &[bump],
+                    crate::ID.as_ref(),
+                    b"ProgramDerivedAddress",
+                ]);
+                assert_eq!(vault.to_bytes(), expected);
+            }
+
+            // a vault without an authority is the PDA of the key hash alone, as before
+            // authorities were seeds, and naming one moves the vault elsewhere
+            let plain = derive_vault_address(&program_id, &public_key);
+            assert_eq!(plain, Pubkey::find_program_address(&[&public_key.hash()], &program_id));
+            let with_authority =
+                derive_vault_address_with_authority(&program_id, &public_key, Some(&Pubkey::new_from_array(authority)));
+            assert_ne!(plain.0, with_authority.0);
+        }
+    }
+
+    #[test]
+    fn test_verify_only_result() {
+        assert_eq!(verify_only_result(&[VERIFY_ONLY_ACCEPTED]), Some(Ok(())));
+        let mut rejected = vec![VERIFY_ONLY_REJECTED];
+        rejected.extend_from_slice(&18u64.to_le_bytes());
+        assert_eq!(verify_only_result(&rejected), Some(Err(18)));
+
+        // the bare rejection byte the program wrote before it carried the error, or anything else
+        for other in [&[VERIFY_ONLY_REJECTED][..], &[], &[2], &rejected[..8], &[VERIFY_ONLY_ACCEPTED, 0]] {
+            assert_eq!(verify_only_result(other), None);
+        }
+    }
+
+    // every builder's data goes through the processor's own deserialize, so changing a
+    // layout on one side only fails here
+    #[test]
+    fn test_builders_match_processor_layouts() {
+        use crate::instructions::{CloseVault, OpenVault, TransferFromVault, VerifyOnly};
+
+        let program_id = Pubkey::new_from_array(crate::ID);
+        let (payer, recipient, refund) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let public_key = FalconPublicKey::new(crate::fixtures::fixture_keypairs()[0].public_key);
+        let (vault, bump) = derive_vault_address(&program_id, &public_key);
+        let signature = [0x59u8; FALCON_512_SIGNATURE_SIZE];
+
+        let discriminator = |ix: &Instruction| VaultInstructions::try_from(&ix.data[0]).map(|kind| kind as u8);
+
+        for close_authority in [None, Some(&refund)] {
+            let ix = open_vault_ix(&program_id, &payer, &vault, &public_key, bump, close_authority);
+            assert_eq!(discriminator(&ix), Ok(VaultInstructions::OpenVault as u8));
+            assert!(OpenVault::deserialize(&ix.data[1..]).is_ok());
+            assert_eq!(ix.accounts[1].pubkey, vault);
+        }
+
+        let ix = transfer_ix(&program_id, &vault, &recipient, 42, &signature, bump);
+        assert_eq!(discriminator(&ix), Ok(VaultInstructions::TransferFromVault as u8));
+        assert_eq!(TransferFromVault::deserialize(&ix.data[1..]).map(|transfer| transfer.amount()), Ok(42));
+
+        let ix = verify_only_ix(&program_id, &vault, &recipient, 42, &signature, bump);
+        assert_eq!(discriminator(&ix), Ok(VaultInstructions::VerifyOnly as u8));
+        assert!(VerifyOnly::deserialize(&ix.data[1..]).is_ok());
+
+        for ix in [
+            close_vault_ix(&program_id, &vault, &refund, &signature, bump),
+            close_vault_with_balance_ix(&program_id, &vault, &refund, &signature, bump, 1, 2),
+            close_vault_with_authority_ix(&program_id, &vault, &refund, &payer, bump),
+        ] {
+            assert_eq!(discriminator(&ix), Ok(VaultInstructions::CloseVault as u8));
+            assert!(CloseVault::deserialize(&ix.data[1..]).is_ok());
+        }
+
+        let keys = [public_key, FalconPublicKey::new([0x09; 897])];
+        let (ix, vaults) = batch_open_vault_ix(&program_id, &payer, &keys);
+        assert_eq!(discriminator(&ix), Ok(VaultInstructions::BatchOpenVault as u8));
+        assert_eq!(ix.data.len(), 1 + 2 * (897 + 1));
+        assert_eq!(vaults, [vault, derive_vault_address(&program_id, &keys[1]).0]);
+    }
+
+    // the fixture signatures, which the program accepts, are over exactly these messages
+    #[test]
+    fn test_messages_match_fixture_signatures() {
+        use crate::falcon::FalconSignature;
+
+        for keypair in crate::fixtures::fixture_keypairs() {
+            let public_key = FalconPublicKey::new(keypair.public_key);
+            for transfer in &keypair.transfers {
+                let recipient = Pubkey::new_from_array(transfer.recipient);
+                let vault = Pubkey::new_from_array(transfer.vault);
+                let message = transfer_message(transfer.amount, &recipient, transfer.nonce, &vault);
+                let signature = FalconSignature::new(transfer.signature);
+                assert_eq!(signature.verify_with_context(&public_key, VAULT_SIGNING_CONTEXT, &[&message]), Ok(()));
+            }
+            for close in &keypair.closes {
+                let (refund, vault) = (Pubkey::new_from_array(close.refund), Pubkey::new_from_array(close.vault));
+                let message = close_message(&refund, close.nonce, &vault);
+                let signature = FalconSignature::new(close.signature);
+                assert_eq!(signature.verify_with_context(&public_key, VAULT_SIGNING_CONTEXT, &[&message]), Ok(()));
+            }
+        }
+    }
+
+    // the serialized size of the transaction solana-sdk would build, fee payer first
+    fn serialized_size(ixs: &[Instruction], payer: &Pubkey) -> usize {
+        let transaction = solana_sdk::transaction::Transaction::new_with_payer(ixs, Some(payer));
+        bincode::serialize(&transaction).unwrap().len()
+    }
+
+    // each builder's transaction with a separate fee payer, at the size solana-sdk gives it
+    #[test]
+    fn test_transaction_sizes() {
+        let program_id = Pubkey::new_from_array(crate::ID);
+        let (fee_payer, recipient, refund) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let public_key = FalconPublicKey::new(crate::fixtures::fixture_keypairs()[0].public_key);
+        let (vault, bump) = derive_vault_address(&program_id, &public_key);
+        let signature = [0x59u8; FALCON_512_SIGNATURE_SIZE];
+
+        let cases: [(Vec<Instruction>, usize, usize); 7] = [
+            (vec![open_vault_ix(&program_id, &fee_payer, &vault, &public_key, bump, None)], 1, 1136),
+            (vec![open_vault_ix(&program_id, &fee_payer, &vault, &public_key, bump, Some(&refund))], 1, 1168),
+            (vec![transfer_ix(&program_id, &vault, &recipient, 42, &signature, bump)], 1, 945),
+            (transfer_ix_with_budget(&program_id, &vault, &recipient, 42, &signature, bump, None), 1, 985),
+            (vec![verify_only_ix(&program_id, &vault, &recipient, 42, &signature, bump)], 1, 911),
+            (vec![close_vault_ix(&program_id, &vault, &refund, &signature, bump)], 1, 904),
+            (vec![close_vault_with_authority_ix(&program_id, &vault, &refund, &fee_payer, bump)], 1, 238),
+        ];
+        for (ixs, num_signers, size) in cases {
+            assert_eq!(estimate_transaction_size(&ixs, num_signers), size);
+            assert_eq!(serialized_size(&ixs, &fee_payer), size);
+            assert_eq!(fits_in_transaction(&ixs, num_signers), Ok(()));
+        }
+
+        // a close authority that isn't the fee payer is a second signature
+        let ixs = [close_vault_with_authority_ix(&program_id, &vault, &refund, &recipient, bump)];
+        assert_eq!(estimate_transaction_size(&ixs, 2), serialized_size(&ixs, &fee_payer));
+    }
+
+    #[test]
+    fn test_transaction_over_the_packet_limit() {
+        let program_id = Pubkey::new_from_array(crate::ID);
+        let (fee_payer, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let public_key = FalconPublicKey::new(crate::fixtures::fixture_keypairs()[0].public_key);
+        let (vault, bump) = derive_vault_address(&program_id, &public_key);
+        let signature = [0x59u8; FALCON_512_SIGNATURE_SIZE];
+
+        // opening a vault and spending from it in one go, with a compute budget
+        let mut ixs = transfer_ix_with_budget(&program_id, &vault, &recipient, 42, &signature, bump, None);
+        ixs.push(open_vault_ix(&program_id, &fee_payer, &vault, &public_key, bump, None));
+        let size = serialized_size(&ixs, &fee_payer);
+        assert_eq!(estimate_transaction_size(&ixs, 1), size);
+        let error = fits_in_transaction(&ixs, 1).unwrap_err();
+        assert_eq!(error, TransactionTooLarge { size, overflow: size - PACKET_DATA_SIZE });
+        assert!(error.to_string().contains("over the 1232-byte packet limit"));
+
+        // two batch entries alone are over it
+        let (ix, _) = batch_open_vault_ix(&program_id, &fee_payer, &[public_key, FalconPublicKey::new([0x09; 897])]);
+        let ixs = [ix];
+        assert_eq!(estimate_transaction_size(&ixs, 1), serialized_size(&ixs, &fee_payer));
+        assert!(fits_in_transaction(&ixs, 1).is_err());
+    }
+}
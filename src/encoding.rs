@@ -0,0 +1,115 @@
+// human readable encodings of short byte strings (public key hashes, nonces, pubkeys)
+// for msg! logs and return data. no_std and allocation free: everything is written into
+// a buffer the caller provides, and the written part comes back as a &str.
+// for the base64 encodings of whole keys and signatures see falcon::encoding
+
+use pinocchio::program_error::ProgramError;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// characters to_hex writes for len bytes
+pub const fn hex_len(len: usize) -> usize {
+    2 * len
+}
+
+// characters to_base58 writes at most for len bytes: log(256) / log(58) < 1.37 per byte,
+// rounded up. 44 for a 32-byte pubkey or hash
+pub const fn base58_max_len(len: usize) -> usize {
+    (len * 137).div_ceil(100)
+}
+
+// lowercase hex of bytes; out must hold at least hex_len(bytes.len())
+pub fn to_hex<'a>(bytes: &[u8], out: &'a mut [u8]) -> Result<&'a str, ProgramError> {
+    let out = out.get_mut(..hex_len(bytes.len())).ok_or(ProgramError::InvalidArgument)?;
+    for (pair, &byte) in out.chunks_exact_mut(2).zip(bytes) {
+        pair[0] = HEX_DIGITS[(byte >> 4) as usize];
+        pair[1] = HEX_DIGITS[(byte & 0x0F) as usize];
+    }
+    core::str::from_utf8(out).map_err(|_| ProgramError::InvalidArgument)
+}
+
+// bitcoin-alphabet base58, the encoding Solana uses for addresses; out must hold the
+// encoding, which base58_max_len(bytes.len()) always does. the digits are built in out
+// itself by repeated division, so there is no scratch buffer
+pub fn to_base58<'a>(bytes: &[u8], out: &'a mut [u8]) -> Result<&'a str, ProgramError> {
+    // every leading zero byte is a leading '1'
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    let capacity = out.len().checked_sub(zeros).ok_or(ProgramError::InvalidArgument)?;
+
+    // base58 digits of the rest, least significant first
+    let mut len = 0;
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in out[..len].iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            if len == capacity {
+                return Err(ProgramError::InvalidArgument);
+            }
+            out[len] = (carry % 58) as u8;
+            len += 1;
+            carry /= 58;
+        }
+    }
+
+    out[..len].reverse();
+    out.copy_within(..len, zeros);
+    out[..zeros].fill(b'1');
+    for digit in out[zeros..zeros + len].iter_mut() {
+        *digit = BASE58_ALPHABET[*digit as usize];
+    }
+    core::str::from_utf8(&out[..zeros + len]).map_err(|_| ProgramError::InvalidArgument)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sha256("abc")
+    const DIGEST: [u8; 32] = [
+        0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+        0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+    ];
+
+    #[test]
+    fn test_to_hex() {
+        let mut out = [0u8; hex_len(32)];
+        assert_eq!(to_hex(&[], &mut out), Ok(""));
+        assert_eq!(to_hex(&[0x00], &mut out), Ok("00"));
+        assert_eq!(to_hex(&[0xAB], &mut out), Ok("ab"));
+        assert_eq!(
+            to_hex(&DIGEST, &mut out),
+            Ok("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(to_hex(&DIGEST, &mut out[1..]), Err(ProgramError::InvalidArgument));
+
+        // a nonce as it's laid out in the transfer message
+        assert_eq!(to_hex(&7u64.to_le_bytes(), &mut out), Ok("0700000000000000"));
+    }
+
+    #[test]
+    fn test_to_base58() {
+        let mut out = [0u8; base58_max_len(32)];
+        assert_eq!(base58_max_len(32), 44);
+        assert_eq!(to_base58(&[], &mut out), Ok(""));
+        assert_eq!(to_base58(&[0x00], &mut out), Ok("1"));
+        assert_eq!(to_base58(&[0x39], &mut out), Ok("z"));
+        assert_eq!(to_base58(&[0xFF], &mut out), Ok("5Q"));
+        assert_eq!(to_base58(&[0x00, 0x00, 0x01], &mut out), Ok("112"));
+        assert_eq!(to_base58(b"hello world", &mut out), Ok("StV1DL6CwTryKyV"));
+
+        // the system program id and the largest 32-byte value
+        assert_eq!(to_base58(&[0; 32], &mut out), Ok("11111111111111111111111111111111"));
+        assert_eq!(to_base58(&[0xFF; 32], &mut out), Ok("JEKNVnkbo3jma5nREBBJCDoXFVeKkD56V3xKrvRmWxFG"));
+        assert_eq!(to_base58(&crate::ID, &mut out), Ok("4s4MccFCMGeVkurFFhRYoeNqJR86Si8FGQhcYR6jAGit"));
+        assert_eq!(to_base58(&DIGEST, &mut out), Ok("DYu3G8aGTMBW1WrTw76zxQJQU4DHLw9MLyy7peG4LKkY"));
+
+        assert_eq!(to_base58(&[0xFF; 32], &mut out[1..]), Err(ProgramError::InvalidArgument));
+        assert_eq!(to_base58(&[0; 4], &mut out[..3]), Err(ProgramError::InvalidArgument));
+    }
+}
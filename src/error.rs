@@ -0,0 +1,39 @@
+#[cfg(feature = "program")]
+use pinocchio::program_error::ProgramError;
+
+// custom errors returned by the vault program
+// surfaced to clients as ProgramError::Custom(code)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VaultError {
+    ArithmeticOverflow = 0,
+    BadPublicKeyHeader = 1,
+    PublicKeyCoefficientOutOfRange = 2,
+    NormBoundExceeded = 3,
+    WeakNonce = 4,
+    InvalidSignature = 5,
+    BadSignatureHeader = 6,
+    DecompressionOutOfBounds = 7,
+    CoefficientTooLarge = 8,
+    NonCanonicalEncoding = 9,
+    RecipientNotRentExempt = 10,
+    UnsupportedVaultVersion = 11,
+    LogNMismatch = 12,
+    InvalidCloseAuthority = 13,
+    InvalidSystemProgram = 14,
+    TrailingSignatureBits = 15,
+    DuplicateVaultKey = 16,
+    VaultBalanceOutOfRange = 17,
+    ZeroAmount = 18,
+    MessageTooLong = 19,
+    SelfTestFailed = 20,
+    NonceExhausted = 21,
+    IncompleteSignature = 22,
+    VaultNotEmpty = 23,
+}
+
+#[cfg(feature = "program")]
+impl From<VaultError> for ProgramError {
+    fn from(error: VaultError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
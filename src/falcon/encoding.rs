@@ -0,0 +1,258 @@
+// off-chain encodings of FalconPublicKey and FalconSignature
+// serde (feature "serde"): base64 strings for human readable formats like JSON,
+// raw bytes for binary ones like bincode. borsh (feature "borsh"): the bytes as-is,
+// with no length prefix, same as a borsh [u8; N]. Display / FromStr use base64
+// decoding always checks the exact length, a truncated key or signature is an error
+
+#[cfg(feature = "serde")]
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use super::signature::{FalconPublicKey, FalconSignature};
+use super::verify::{FALCON_512_PUBLIC_KEY_SIZE, FALCON_512_SIGNATURE_SIZE};
+
+// why a string or byte buffer couldn't be turned into a key or signature
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodingError {
+    InvalidBase64,
+    InvalidLength { expected: usize, actual: usize },
+}
+
+impl core::fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EncodingError::InvalidBase64 => write!(f, "invalid base64"),
+            EncodingError::InvalidLength { expected, actual } => {
+                write!(f, "expected {} bytes, got {}", expected, actual)
+            }
+        }
+    }
+}
+
+fn bytes_to_array<const LEN: usize>(bytes: &[u8]) -> Result<[u8; LEN], EncodingError> {
+    bytes
+        .try_into()
+        .map_err(|_| EncodingError::InvalidLength { expected: LEN, actual: bytes.len() })
+}
+
+#[cfg(feature = "serde")]
+fn base64_to_array<const LEN: usize>(encoded: &str) -> Result<[u8; LEN], EncodingError> {
+    let bytes = STANDARD.decode(encoded).map_err(|_| EncodingError::InvalidBase64)?;
+    bytes_to_array(&bytes)
+}
+
+// the same impls for both fixed-size types
+macro_rules! impl_encodings {
+    ($type:ident, $size:expr, $expecting:literal) => {
+        impl TryFrom<&[u8]> for $type {
+            type Error = EncodingError;
+
+            fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+                Ok(Self { bytes: bytes_to_array::<$size>(bytes)? })
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl core::fmt::Display for $type {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(&STANDARD.encode(self.bytes))
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl core::str::FromStr for $type {
+            type Err = EncodingError;
+
+            fn from_str(encoded: &str) -> Result<Self, Self::Err> {
+                Ok(Self { bytes: base64_to_array::<$size>(encoded)? })
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $type {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() {
+                    serializer.serialize_str(&STANDARD.encode(self.bytes))
+                } else {
+                    serializer.serialize_bytes(&self.bytes)
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $type {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = $type;
+
+                    fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                        f.write_str($expecting)
+                    }
+
+                    fn visit_str<E: serde::de::Error>(self, encoded: &str) -> Result<Self::Value, E> {
+                        encoded.parse().map_err(E::custom)
+                    }
+
+                    fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+                        $type::try_from(bytes).map_err(|_| E::invalid_length(bytes.len(), &self))
+                    }
+
+                    // formats without a native byte type hand over a sequence of u8
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                        let mut bytes = [0u8; $size];
+                        for (i, byte) in bytes.iter_mut().enumerate() {
+                            *byte = seq
+                                .next_element()?
+                                .ok_or_else(|| serde::de::Error::invalid_length(i, &self))?;
+                        }
+                        if seq.next_element::<u8>()?.is_some() {
+                            return Err(serde::de::Error::invalid_length($size + 1, &self));
+                        }
+                        Ok($type { bytes })
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_str(Visitor)
+                } else {
+                    deserializer.deserialize_bytes(Visitor)
+                }
+            }
+        }
+
+        #[cfg(feature = "borsh")]
+        impl borsh::BorshSerialize for $type {
+            fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+                writer.write_all(&self.bytes)
+            }
+        }
+
+        #[cfg(feature = "borsh")]
+        impl borsh::BorshDeserialize for $type {
+            fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+                let mut bytes = [0u8; $size];
+                reader.read_exact(&mut bytes)?;
+                Ok(Self { bytes })
+            }
+        }
+    };
+}
+
+impl_encodings!(FalconPublicKey, FALCON_512_PUBLIC_KEY_SIZE, "an 897-byte Falcon-512 public key");
+impl_encodings!(FalconSignature, FALCON_512_SIGNATURE_SIZE, "a 666-byte Falcon-512 signature");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_public_key() -> FalconPublicKey {
+        let mut bytes = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (i * 7) as u8;
+        }
+        FalconPublicKey::new(bytes)
+    }
+
+    fn sample_signature() -> FalconSignature {
+        let mut bytes = [0u8; FALCON_512_SIGNATURE_SIZE];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (i * 13) as u8;
+        }
+        FalconSignature::new(bytes)
+    }
+
+    #[test]
+    fn test_try_from_slice_length() {
+        let key = sample_public_key();
+        assert_eq!(FalconPublicKey::try_from(&key.bytes[..]).unwrap().bytes, key.bytes);
+        assert_eq!(
+            FalconPublicKey::try_from(&key.bytes[..896]).err(),
+            Some(EncodingError::InvalidLength { expected: 897, actual: 896 })
+        );
+        let signature = sample_signature();
+        assert_eq!(FalconSignature::try_from(&signature.bytes[..]).unwrap().bytes, signature.bytes);
+        assert!(FalconSignature::try_from(&[0u8; 667][..]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_base64_display_roundtrip() {
+        let signature = sample_signature();
+        let encoded = signature.to_string();
+        assert_eq!(encoded.parse::<FalconSignature>().unwrap().bytes, signature.bytes);
+
+        let truncated = STANDARD.encode(&signature.bytes[..600]);
+        assert_eq!(
+            truncated.parse::<FalconSignature>().err(),
+            Some(EncodingError::InvalidLength { expected: 666, actual: 600 })
+        );
+        assert_eq!("not base64!".parse::<FalconPublicKey>().err(), Some(EncodingError::InvalidBase64));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_roundtrip() {
+        let key = sample_public_key();
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(json, format!("\"{}\"", key));
+        assert_eq!(serde_json::from_str::<FalconPublicKey>(&json).unwrap().bytes, key.bytes);
+
+        let signature = sample_signature();
+        let json = serde_json::to_string(&signature).unwrap();
+        assert_eq!(serde_json::from_str::<FalconSignature>(&json).unwrap().bytes, signature.bytes);
+
+        let truncated = format!("\"{}\"", STANDARD.encode(&signature.bytes[..665]));
+        assert!(serde_json::from_str::<FalconSignature>(&truncated).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_bincode_roundtrip() {
+        let signature = sample_signature();
+        let encoded = bincode::serialize(&signature).unwrap();
+        // u64 length prefix and the raw bytes
+        assert_eq!(encoded.len(), 8 + FALCON_512_SIGNATURE_SIZE);
+        assert_eq!(bincode::deserialize::<FalconSignature>(&encoded).unwrap().bytes, signature.bytes);
+
+        let key = sample_public_key();
+        let encoded = bincode::serialize(&key).unwrap();
+        assert_eq!(bincode::deserialize::<FalconPublicKey>(&encoded).unwrap().bytes, key.bytes);
+
+        // a valid buffer of the wrong length
+        let short = bincode::serialize(&serde_bytes_of(&key.bytes[..800])).unwrap();
+        assert!(bincode::deserialize::<FalconPublicKey>(&short).is_err());
+        assert!(bincode::deserialize::<FalconPublicKey>(&encoded[..500]).is_err());
+    }
+
+    // a byte slice serialized as serde bytes, the way bincode sees the real types
+    #[cfg(feature = "serde")]
+    fn serde_bytes_of(bytes: &[u8]) -> impl serde::Serialize + '_ {
+        struct Bytes<'a>(&'a [u8]);
+        impl serde::Serialize for Bytes<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_bytes(self.0)
+            }
+        }
+        Bytes(bytes)
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_borsh_roundtrip() {
+        let key = sample_public_key();
+        let encoded = borsh::to_vec(&key).unwrap();
+        assert_eq!(encoded, key.bytes);
+        assert_eq!(borsh::from_slice::<FalconPublicKey>(&encoded).unwrap().bytes, key.bytes);
+
+        let signature = sample_signature();
+        let encoded = borsh::to_vec(&signature).unwrap();
+        assert_eq!(borsh::from_slice::<FalconSignature>(&encoded).unwrap().bytes, signature.bytes);
+
+        assert!(borsh::from_slice::<FalconSignature>(&encoded[..665]).is_err());
+        // trailing bytes are rejected by from_slice too
+        let mut long = encoded.clone();
+        long.push(0);
+        assert!(borsh::from_slice::<FalconSignature>(&long).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+use crate::error::VaultError;
+
+// what the falcon module fails with. it doesn't name pinocchio's ProgramError, so the
+// module also builds for wasm32 without the program (see the wasm feature); with the
+// program feature each variant converts to the ProgramError it always was, so the
+// instructions and their error codes are unchanged
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FalconError {
+    // ProgramError::Custom with the VaultError code
+    Vault(VaultError),
+    // a key, stored h_ntt or exported SHAKE256 state of the wrong size or encoding
+    InvalidAccountData,
+    // a signature of the wrong size for its degree
+    InvalidInstructionData,
+    // an empty key list, a context or an input over its limit
+    InvalidArgument,
+}
+
+impl From<VaultError> for FalconError {
+    fn from(error: VaultError) -> Self {
+        FalconError::Vault(error)
+    }
+}
+
+#[cfg(feature = "program")]
+impl From<FalconError> for pinocchio::program_error::ProgramError {
+    fn from(error: FalconError) -> Self {
+        use pinocchio::program_error::ProgramError;
+        match error {
+            FalconError::Vault(error) => error.into(),
+            FalconError::InvalidAccountData => ProgramError::InvalidAccountData,
+            FalconError::InvalidInstructionData => ProgramError::InvalidInstructionData,
+            FalconError::InvalidArgument => ProgramError::InvalidArgument,
+        }
+    }
+}
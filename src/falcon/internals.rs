@@ -0,0 +1,107 @@
+// intermediate steps of Falcon-512 verification, exposed for test harnesses,
+// fuzzers and client-side pre-validation
+// WARNING: these are building blocks, not a verifier. passing any of them on its own
+// says nothing about whether a signature is valid -- use verify_falcon_signature for that
+// everything here takes and returns plain arrays so the internal polynomial types stay private
+
+use super::FalconError;
+
+use super::ntt::sum_x4;
+use super::verify::{self, FALCON_512_N, FALCON_512_NONCE_SIZE};
+
+// hash a message and the signature nonce to a polynomial c with coefficients in [0, q)
+// SHAKE256(nonce || message), rejection sampled 16 bits at a time
+pub fn hash_to_point(message: &[u8], nonce: &[u8; FALCON_512_NONCE_SIZE]) -> [u16; FALCON_512_N] {
+    verify::hash_to_point(&[message], nonce).as_u16_array()
+}
+
+// hash_to_point with a context absorbed ahead of the nonce, see FALCON_RAW_DOMAIN
+// an empty context gives the same point as hash_to_point
+pub fn hash_to_point_with_context(
+    context: &[u8],
+    message: &[u8],
+    nonce: &[u8; FALCON_512_NONCE_SIZE],
+) -> Result<[u16; FALCON_512_N], FalconError> {
+    if context.len() > verify::FALCON_MAX_CONTEXT_SIZE {
+        return Err(FalconError::InvalidArgument);
+    }
+    Ok(verify::hash_to_point_with_context(context, &[message], nonce).as_u16_array())
+}
+
+// decode the compressed s2 payload (the signature bytes after header and nonce)
+// fails if the payload runs out or a coefficient is out of range; no norm check is applied
+pub fn decompress_signature(compressed: &[u8]) -> Result<[i16; FALCON_512_N], FalconError> {
+    verify::decompress_signature(compressed, false)
+}
+
+// ||s1||^2 + ||s2||^2 over signed coefficients
+// a signature is accepted when this is below FALCON_512_SIG_BOUND
+pub fn squared_norm<const N: usize>(s1: &[i16; N], s2: &[i16; N]) -> u64 {
+    let square = |&x: &i16| (x as i64 * x as i64) as u64;
+    sum_x4(s1, square) + sum_x4(s2, square)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::falcon::verify::tests::{compress_coefficients, FIXTURE_NONCE};
+    use crate::falcon::verify::FALCON_512_Q;
+
+    #[test]
+    fn test_hash_to_point_deterministic_and_reduced() {
+        let c = hash_to_point(b"message", &FIXTURE_NONCE);
+        assert_eq!(c, hash_to_point(b"message", &FIXTURE_NONCE));
+        assert!(c.iter().all(|&x| x < FALCON_512_Q));
+
+        // both the message and the nonce feed the hash
+        assert_ne!(c, hash_to_point(b"messagf", &FIXTURE_NONCE));
+        assert_ne!(c, hash_to_point(b"message", &[0x5B; FALCON_512_NONCE_SIZE]));
+    }
+
+    #[test]
+    fn test_hash_to_point_with_context() {
+        let plain = hash_to_point(b"message", &FIXTURE_NONCE);
+        assert_eq!(plain[..4], [9599, 7811, 6049, 11820]);
+        assert_eq!(hash_to_point_with_context(b"", b"message", &FIXTURE_NONCE), Ok(plain));
+
+        // cSHAKE256(nonce || message, "FALCON-VAULT", 0 || "vault"), checked against an
+        // independent cSHAKE256 that reproduces the SP 800-185 samples
+        let bound = hash_to_point_with_context(b"vault", b"message", &FIXTURE_NONCE).unwrap();
+        assert_eq!(bound[..4], [5028, 10443, 11569, 834]);
+        assert_ne!(bound, hash_to_point_with_context(b"vaulu", b"message", &FIXTURE_NONCE).unwrap());
+
+        assert_eq!(
+            hash_to_point_with_context(&[0; 256], b"message", &FIXTURE_NONCE),
+            Err(FalconError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_decompress_signature() {
+        let mut s2 = [0i16; FALCON_512_N];
+        s2[0] = 2047;
+        s2[1] = -2047;
+        s2[511] = -1;
+        assert_eq!(decompress_signature(&compress_coefficients(&s2)), Ok(s2));
+
+        // a payload too short for 512 coefficients is rejected
+        assert!(decompress_signature(&[0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_squared_norm() {
+        let zero = [0i16; FALCON_512_N];
+        assert_eq!(squared_norm(&zero, &zero), 0);
+
+        let mut s1 = [0i16; FALCON_512_N];
+        let mut s2 = [0i16; FALCON_512_N];
+        s1[0] = 3;
+        s1[7] = -4;
+        s2[100] = -12;
+        assert_eq!(squared_norm(&s1, &s2), 9 + 16 + 144);
+
+        // the extreme case fits comfortably in u64
+        let max = [i16::MIN; FALCON_512_N];
+        assert_eq!(squared_norm(&max, &max), 2 * 512 * (1u64 << 30));
+    }
+}
@@ -1,5 +1,7 @@
-// SHAKE256 implementation for Falcon-512 verification
-// Keccak-f[1600] permutation for no_std environments
+// SHAKE256 implementation for Falcon-512 verification, plus SHAKE128, cSHAKE256,
+// SHA3-256, SHA3-512 and Ethereum's Keccak-256 on the same sponge. Keccak-f[1600] permutation for no_std environments
+
+use super::FalconError;
 
 // Keccak state size in 64-bit words
 const STATE_SIZE: usize = 25;
@@ -7,14 +9,24 @@ const STATE_SIZE: usize = 25;
 // SHAKE256 rate in bytes (1600 - 256*2) / 8 = 136
 const SHAKE256_RATE: usize = 136;
 
+// SHAKE128 rate in bytes (1600 - 128*2) / 8 = 168
+const SHAKE128_RATE: usize = 168;
+
+// SHA3 rates in bytes, (1600 - 2 * output bits) / 8
+const SHA3_256_RATE: usize = 136;
+const SHA3_512_RATE: usize = 72;
+
+// Keccak-256 has SHA3-256's rate, only the padding differs
+const KECCAK256_RATE: usize = 136;
+
 // round constants for Keccak-f[1600]
 const ROUND_CONSTANTS: [u64; 24] = [
     0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
     0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
-    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x8000000000008003,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
     0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
     0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
-    0x8000000000000000, 0x8000000080008082, 0x800000000000808a, 0x8000000080000000,
 ];
 
 // rotation offsets for rho step
@@ -22,34 +34,86 @@ const RHO_OFFSETS: [u32; 24] = [
     1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
 ];
 
-// SHAKE256 hasher state
-pub struct Shake256 {
+// lane index (5y + x) the t-th step of the combined rho/pi walk writes, starting after
+// lane 1 = (1, 0) and moving (x, y) -> (y, 2x + 3y mod 5); RHO_OFFSETS[t] goes with it
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+// layout version of an exported SHAKE state, bumped if the encoding changes
+pub const SHAKE_STATE_VERSION: u8 = 1;
+
+// [version(1), phase(1), position as little-endian u16 (2), rate / 8 (1), zero (3), lanes (200)]
+// position is the bytes waiting in the current block while absorbing, the bytes of the
+// current block not yet read while squeezing
+pub const SHAKE_STATE_BYTES: usize = 8 + 8 * STATE_SIZE;
+
+const PHASE_ABSORBING: u8 = 0;
+const PHASE_SQUEEZING: u8 = 1;
+
+fn export_lanes(state: &[u64; STATE_SIZE], phase: u8, position: usize, rate: usize) -> [u8; SHAKE_STATE_BYTES] {
+    let mut bytes = [0u8; SHAKE_STATE_BYTES];
+    bytes[0] = SHAKE_STATE_VERSION;
+    bytes[1] = phase;
+    bytes[2..4].copy_from_slice(&(position as u16).to_le_bytes());
+    bytes[4] = (rate / 8) as u8;
+    for (chunk, lane) in bytes[8..].chunks_exact_mut(8).zip(state.iter()) {
+        chunk.copy_from_slice(&lane.to_le_bytes());
+    }
+    bytes
+}
+
+// the lanes and position of an exported state, after checking everything around them;
+// the state is likely read back from account data, so none of it is trusted
+fn import_lanes(
+    bytes: &[u8],
+    phase: u8,
+    max_position: usize,
+    rate: usize,
+) -> Result<([u64; STATE_SIZE], usize), FalconError> {
+    let Ok(bytes) = <&[u8; SHAKE_STATE_BYTES]>::try_from(bytes) else {
+        return Err(FalconError::InvalidAccountData);
+    };
+    let position = u16::from_le_bytes([bytes[2], bytes[3]]) as usize;
+    if bytes[0] != SHAKE_STATE_VERSION
+        || bytes[1] != phase
+        || position > max_position
+        || bytes[4] as usize != rate / 8
+        || bytes[5..8] != [0; 3]
+    {
+        return Err(FalconError::InvalidAccountData);
+    }
+
+    let mut state = [0u64; STATE_SIZE];
+    for (lane, chunk) in state.iter_mut().zip(bytes[8..].chunks_exact(8)) {
+        *lane = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    Ok((state, position))
+}
+
+// absorbing half of a Keccak sponge with the given rate in bytes,
+// shared by SHAKE256 and the fixed-output SHA3 hashes
+#[derive(Clone)]
+struct Sponge<const RATE: usize> {
     state: [u64; STATE_SIZE],
-    buffer: [u8; SHAKE256_RATE],
+    buffer: [u8; RATE],
     buffer_len: usize,
-    absorbed: bool,
 }
 
-impl Shake256 {
-    // this creates a new SHAKE256 hasher
-    pub fn new() -> Self {
+impl<const RATE: usize> Sponge<RATE> {
+    const fn new() -> Self {
         Self {
             state: [0u64; STATE_SIZE],
-            buffer: [0u8; SHAKE256_RATE],
+            buffer: [0u8; RATE],
             buffer_len: 0,
-            absorbed: false,
         }
     }
 
     // absorb input data
-    pub fn update(&mut self, data: &[u8]) {
-        if self.absorbed {
-            panic!("Cannot update after finalization");
-        }
-
+    fn update(&mut self, data: &[u8]) {
         let mut offset = 0;
         while offset < data.len() {
-            let take = core::cmp::min(SHAKE256_RATE - self.buffer_len, data.len() - offset);
+            let take = core::cmp::min(RATE - self.buffer_len, data.len() - offset);
             
             self.buffer[self.buffer_len..self.buffer_len + take]
                 .copy_from_slice(&data[offset..offset + take]);
@@ -57,37 +121,59 @@ impl Shake256 {
             self.buffer_len += take;
             offset += take;
 
-            if self.buffer_len == SHAKE256_RATE {
+            if self.buffer_len == RATE {
                 self.absorb_block();
                 self.buffer_len = 0;
             }
         }
     }
 
-    // finalize absorption and return a reader for squeezing
-    pub fn finalize_xof(mut self) -> Shake256Reader {
-        // SHAKE256 padding: append 0x1f and pad to rate
-        self.buffer[self.buffer_len] = 0x1f;
-        for i in self.buffer_len + 1..SHAKE256_RATE {
+    // the pending bytes are XORed into the exported lanes where absorb_block would put
+    // them, so 200 bytes hold everything; import leaves those buffer bytes zero, which
+    // absorbs to the same state
+    fn export(&self, phase: u8) -> [u8; SHAKE_STATE_BYTES] {
+        let mut state = self.state;
+        for (i, &byte) in self.buffer[..self.buffer_len].iter().enumerate() {
+            state[i / 8] ^= (byte as u64) << (i % 8 * 8);
+        }
+        export_lanes(&state, phase, self.buffer_len, RATE)
+    }
+
+    fn import(bytes: &[u8], phase: u8) -> Result<Self, FalconError> {
+        // a full block is absorbed as soon as it fills, so RATE itself never appears
+        let (state, buffer_len) = import_lanes(bytes, phase, RATE - 1, RATE)?;
+        Ok(Self { state, buffer: [0u8; RATE], buffer_len })
+    }
+
+    // zero-fill the rest of the current block and absorb it, so what follows starts
+    // on a block boundary; nothing to do when already aligned
+    fn pad_to_block(&mut self) {
+        if self.buffer_len > 0 {
+            self.buffer[self.buffer_len..].fill(0);
+            self.absorb_block();
+            self.buffer_len = 0;
+        }
+    }
+
+    // pad with the domain byte (0x1f for SHAKE, 0x04 for cSHAKE, 0x06 for SHA3, 0x01 for
+    // the original Keccak) and the final 0x80,
+    // absorb the last block and return the state ready for squeezing
+    fn finish(mut self, domain: u8) -> [u64; STATE_SIZE] {
+        self.buffer[self.buffer_len] = domain;
+        for i in self.buffer_len + 1..RATE {
             self.buffer[i] = 0;
         }
         // setting the last bit for domain separation
-        self.buffer[SHAKE256_RATE - 1] |= 0x80;
-        
-        self.absorb_block();
-        self.absorbed = true;
+        self.buffer[RATE - 1] |= 0x80;
 
-        Shake256Reader {
-            state: self.state,
-            buffer: [0u8; SHAKE256_RATE],
-            buffer_len: 0,
-        }
+        self.absorb_block();
+        self.state
     }
 
     // absorb a rate-sized block into the state
     fn absorb_block(&mut self) {
         // XOR buffer into state (little-endian interpretation - 64 bits at a time)
-        for i in 0..SHAKE256_RATE / 8 {
+        for i in 0..RATE / 8 {
             let mut lane = 0u64;
             for j in 0..8 {
                 lane |= (self.buffer[i * 8 + j] as u64) << (j * 8);
@@ -100,130 +186,414 @@ impl Shake256 {
     }
 }
 
-// reader for squeezing output from SHAKE256
-pub struct Shake256Reader {
+// SHAKE256 hasher state
+// finalize_xof consumes the hasher, so absorbing after finalization doesn't compile
+// instead of failing at runtime:
+/// ```compile_fail
+/// use solana_falcon_vault::falcon::Shake256;
+///
+/// let mut hasher = Shake256::new();
+/// let _reader = hasher.finalize_xof();
+/// hasher.update(b"too late");
+/// ```
+#[derive(Clone)]
+pub struct Shake256 {
+    sponge: Sponge<SHAKE256_RATE>,
+}
+
+impl Shake256 {
+    // this creates a new SHAKE256 hasher
+    pub const fn new() -> Self {
+        Self { sponge: Sponge::new() }
+    }
+
+    // absorb input data
+    pub fn update(&mut self, data: &[u8]) {
+        self.sponge.update(data);
+    }
+
+    // finalize absorption and return a reader for squeezing
+    #[must_use]
+    pub fn finalize_xof(self) -> Shake256Reader {
+        XofReader::new(self.sponge.finish(0x1f))
+    }
+
+    // the hasher mid-absorb as bytes, to be picked up by import_state in a later
+    // instruction, e.g. hash_to_point split across two transactions
+    pub fn export_state(&self) -> [u8; SHAKE_STATE_BYTES] {
+        self.sponge.export(PHASE_ABSORBING)
+    }
+
+    // resume a hasher from export_state; anything else, including an exported reader,
+    // is InvalidAccountData
+    pub fn import_state(bytes: &[u8]) -> Result<Self, FalconError> {
+        Ok(Self { sponge: Sponge::import(bytes, PHASE_ABSORBING)? })
+    }
+
+    // the first N bytes of SHAKE256(data), see shake256_array for several parts
+    pub fn one_shot<const N: usize>(data: &[u8]) -> [u8; N] {
+        shake256_array(&[data])
+    }
+}
+
+impl Default for Shake256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SHAKE256 of the parts absorbed back to back, filling out
+pub fn shake256(parts: &[&[u8]], out: &mut [u8]) {
+    let mut hasher = Shake256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize_xof().read(out);
+}
+
+// shake256 for a fixed output size
+pub fn shake256_array<const N: usize>(parts: &[&[u8]]) -> [u8; N] {
+    let mut out = [0u8; N];
+    shake256(parts, &mut out);
+    out
+}
+
+#[cfg(feature = "digest-traits")]
+impl digest::Update for Shake256 {
+    fn update(&mut self, data: &[u8]) {
+        Shake256::update(self, data);
+    }
+}
+
+#[cfg(feature = "digest-traits")]
+impl digest::ExtendableOutput for Shake256 {
+    type Reader = Shake256Reader;
+
+    fn finalize_xof(self) -> Shake256Reader {
+        Shake256::finalize_xof(self)
+    }
+}
+
+#[cfg(feature = "digest-traits")]
+impl digest::XofReader for Shake256Reader {
+    fn read(&mut self, buffer: &mut [u8]) {
+        XofReader::read(self, buffer);
+    }
+}
+
+// SHAKE128 hasher, the same interface as Shake256 at rate 168
+pub struct Shake128 {
+    sponge: Sponge<SHAKE128_RATE>,
+}
+
+impl Shake128 {
+    pub const fn new() -> Self {
+        Self { sponge: Sponge::new() }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sponge.update(data);
+    }
+
+    #[must_use]
+    pub fn finalize_xof(self) -> Shake128Reader {
+        XofReader::new(self.sponge.finish(0x1f))
+    }
+}
+
+impl Default for Shake128 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// cSHAKE256 (NIST SP 800-185): SHAKE256 with a function name and a customization string
+// absorbed ahead of the input as bytepad(encode_string(N) || encode_string(S), 136).
+// outputs for different (N, S) are unrelated, which gives domain separation without
+// prefixing the message itself. with both empty it is plain SHAKE256, as the standard says
+pub struct CShake256 {
+    sponge: Sponge<SHAKE256_RATE>,
+    // false when N and S are both empty
+    customized: bool,
+}
+
+impl CShake256 {
+    pub fn new(function_name: &[u8], customization: &[u8]) -> Self {
+        let mut sponge = Sponge::new();
+        let customized = !function_name.is_empty() || !customization.is_empty();
+        if customized {
+            absorb_left_encode(&mut sponge, SHAKE256_RATE as u64);
+            for string in [function_name, customization] {
+                absorb_left_encode(&mut sponge, string.len() as u64 * 8);
+                sponge.update(string);
+            }
+            sponge.pad_to_block();
+        }
+        Self { sponge, customized }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sponge.update(data);
+    }
+
+    #[must_use]
+    pub fn finalize_xof(self) -> Shake256Reader {
+        XofReader::new(self.sponge.finish(if self.customized { 0x04 } else { 0x1f }))
+    }
+}
+
+// left_encode from SP 800-185: the byte count, then the value big-endian in as few
+// bytes as possible (at least one)
+fn absorb_left_encode<const RATE: usize>(sponge: &mut Sponge<RATE>, value: u64) {
+    let bytes = value.to_be_bytes();
+    let skip = (value.leading_zeros() as usize / 8).min(7);
+    sponge.update(&[(8 - skip) as u8]);
+    sponge.update(&bytes[skip..]);
+}
+
+// the first LEN bytes of the state, all a fixed-output hash needs since LEN < rate
+fn state_prefix<const LEN: usize>(state: &[u64; STATE_SIZE]) -> [u8; LEN] {
+    let mut output = [0u8; LEN];
+    for (chunk, lane) in output.chunks_mut(8).zip(state.iter()) {
+        chunk.copy_from_slice(&lane.to_le_bytes()[..chunk.len()]);
+    }
+    output
+}
+
+// SHA3-256 (FIPS 202), rate (1600 - 256*2) / 8 = 136
+pub struct Sha3_256 {
+    sponge: Sponge<SHA3_256_RATE>,
+}
+
+impl Sha3_256 {
+    pub const fn new() -> Self {
+        Self { sponge: Sponge::new() }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sponge.update(data);
+    }
+
+    #[must_use]
+    pub fn finalize(self) -> [u8; 32] {
+        state_prefix(&self.sponge.finish(0x06))
+    }
+}
+
+impl Default for Sha3_256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SHA3-512 (FIPS 202), rate (1600 - 512*2) / 8 = 72
+pub struct Sha3_512 {
+    sponge: Sponge<SHA3_512_RATE>,
+}
+
+impl Sha3_512 {
+    pub const fn new() -> Self {
+        Self { sponge: Sponge::new() }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sponge.update(data);
+    }
+
+    #[must_use]
+    pub fn finalize(self) -> [u8; 64] {
+        state_prefix(&self.sponge.finish(0x06))
+    }
+}
+
+impl Default for Sha3_512 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Keccak-256 as Ethereum uses it: the Keccak submission's 0x01 padding rather than
+// SHA3's 0x06, so it gives different digests than Sha3_256 for the same input.
+// for Ethereum addresses and Ethereum-hashed payloads
+pub struct Keccak256 {
+    sponge: Sponge<KECCAK256_RATE>,
+}
+
+impl Keccak256 {
+    pub const fn new() -> Self {
+        Self { sponge: Sponge::new() }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.sponge.update(data);
+    }
+
+    #[must_use]
+    pub fn finalize(self) -> [u8; 32] {
+        state_prefix(&self.sponge.finish(0x01))
+    }
+
+    // one-shot digest of data
+    pub fn hash(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Self::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+}
+
+impl Default for Keccak256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// reader for squeezing output from SHAKE128, SHAKE256 or cSHAKE256
+// output is copied straight out of the state lanes into the caller's slice, there is no
+// block buffer in between. the state is only permuted when the next block is needed,
+// so the lanes and the cursor are all there is to export
+#[derive(Clone)]
+pub struct XofReader<const RATE: usize> {
     state: [u64; STATE_SIZE],
-    buffer: [u8; SHAKE256_RATE],
-    buffer_len: usize,
+    // bytes of the current output block already read, RATE when it is used up
+    cursor: usize,
 }
 
-impl Shake256Reader {
-    // read output bytes from the SHAKE256 XOF
+pub type Shake256Reader = XofReader<SHAKE256_RATE>;
+pub type Shake128Reader = XofReader<SHAKE128_RATE>;
+
+impl<const RATE: usize> XofReader<RATE> {
+    fn new(state: [u64; STATE_SIZE]) -> Self {
+        Self { state, cursor: 0 }
+    }
+
+    // read output bytes from the XOF
     pub fn read(&mut self, output: &mut [u8]) {
-        let mut offset = 0;
-        
-        while offset < output.len() {
-            if self.buffer_len == 0 {
-                self.squeeze_block();
-                self.buffer_len = SHAKE256_RATE;
+        let mut output = output;
+        while !output.is_empty() {
+            if self.cursor == RATE {
+                keccak_f1600(&mut self.state);
+                self.cursor = 0;
             }
 
-            let take = core::cmp::min(self.buffer_len, output.len() - offset);
-            let start = SHAKE256_RATE - self.buffer_len;
-            
-            output[offset..offset + take]
-                .copy_from_slice(&self.buffer[start..start + take]);
-            
-            offset += take;
-            self.buffer_len -= take;
+            let take = core::cmp::min(RATE - self.cursor, output.len());
+            let (head, rest) = core::mem::take(&mut output).split_at_mut(take);
+            self.copy_block(head);
+            self.cursor += take;
+            output = rest;
         }
     }
 
-    // squeeze a rate-sized block from the state
-    fn squeeze_block(&mut self) {
-        
-        for i in 0..SHAKE256_RATE / 8 {
-            let lane = self.state[i];
-            for j in 0..8 {
-                self.buffer[i * 8 + j] = (lane >> (j * 8)) as u8;
+    // the next N output bytes, same as read into a [u8; N]
+    pub fn read_array<const N: usize>(&mut self) -> [u8; N] {
+        let mut output = [0u8; N];
+        self.read(&mut output);
+        output
+    }
+
+    // the bytes of the current block from the cursor on, little-endian lane by lane;
+    // whole lanes at once when the cursor is lane aligned, which it is for every full
+    // block after the first
+    fn copy_block(&self, out: &mut [u8]) {
+        if self.cursor.is_multiple_of(8) {
+            let lanes = &self.state[self.cursor / 8..];
+            let full_lanes = out.len() / 8;
+            let mut chunks = out.chunks_exact_mut(8);
+            for (chunk, lane) in (&mut chunks).zip(lanes) {
+                chunk.copy_from_slice(&lane.to_le_bytes());
+            }
+            let tail = chunks.into_remainder();
+            if !tail.is_empty() {
+                tail.copy_from_slice(&lanes[full_lanes].to_le_bytes()[..tail.len()]);
+            }
+        } else {
+            for (position, byte) in (self.cursor..).zip(out.iter_mut()) {
+                *byte = (self.state[position / 8] >> (position % 8 * 8)) as u8;
             }
         }
+    }
 
-        //apply Keccak-f[1600] permutation for next block
-        keccak_f1600(&mut self.state);
+    // the reader mid-squeeze as bytes, see Shake256::export_state
+    pub fn export_state(&self) -> [u8; SHAKE_STATE_BYTES] {
+        export_lanes(&self.state, PHASE_SQUEEZING, RATE - self.cursor, RATE)
+    }
+
+    // resume a reader from export_state, rejecting a hasher's state or another rate's
+    pub fn import_state(bytes: &[u8]) -> Result<Self, FalconError> {
+        let (state, remaining) = import_lanes(bytes, PHASE_SQUEEZING, RATE, RATE)?;
+        Ok(Self { state, cursor: RATE - remaining })
     }
 }
 
 // Keccak-f[1600] permutation function
 // implementation of the 24-round Keccak permutation
-fn keccak_f1600(state: &mut [u64; STATE_SIZE]) {
-    for round in 0..24 {
+// the x loops have fixed bounds and the rho/pi walk is table driven, so every index
+// is known at compile time and the loops unroll into straight-line lane operations
+// public so other sponge constructions can be built on the same permutation
+// lanes are indexed x + 5 * y, as in the Keccak reference
+pub fn keccak_f1600(state: &mut [u64; STATE_SIZE]) {
+    for &round_constant in ROUND_CONSTANTS.iter() {
         // θ (Theta) step
-        let mut c = [0u64; 5];
+        let c: [u64; 5] =
+            core::array::from_fn(|x| state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20]);
         for x in 0..5 {
-            c[x] = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
-        }
-
-        let mut d = [0u64; 5];
-        for x in 0..5 {
-            d[x] = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
-        }
-
-        for x in 0..5 {
-            for y in 0..5 {
-                state[y * 5 + x] ^= d[x];
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for row in state.chunks_exact_mut(5) {
+                row[x] ^= d;
             }
         }
 
         // ρ (Rho) and π (Pi) steps combined
         let mut current = state[1];
-        for t in 0..24 {
-            let (x, y) = pi_coordinates(t);
-            let temp = state[y * 5 + x];
-            state[y * 5 + x] = current.rotate_left(RHO_OFFSETS[t]);
+        for (&lane, &offset) in PI_LANES.iter().zip(RHO_OFFSETS.iter()) {
+            let temp = state[lane];
+            state[lane] = current.rotate_left(offset);
             current = temp;
         }
 
         // χ (Chi) step
-        for y in 0..5 {
-            let mut row = [0u64; 5];
-            for x in 0..5 {
-                row[x] = state[y * 5 + x];
-            }
-            for x in 0..5 {
-                state[y * 5 + x] = row[x] ^ ((!row[(x + 1) % 5]) & row[(x + 2) % 5]);
-            }
+        for row in state.chunks_exact_mut(5) {
+            let [a0, a1, a2, a3, a4] = [row[0], row[1], row[2], row[3], row[4]];
+            row[0] = a0 ^ (!a1 & a2);
+            row[1] = a1 ^ (!a2 & a3);
+            row[2] = a2 ^ (!a3 & a4);
+            row[3] = a3 ^ (!a4 & a0);
+            row[4] = a4 ^ (!a0 & a1);
         }
 
         // ι (Iota) step
-        state[0] ^= ROUND_CONSTANTS[round];
+        state[0] ^= round_constant;
     }
 }
 
-// compute the Pi step coordinates
-#[inline]
-fn pi_coordinates(t: usize) -> (usize, usize) {
-    let mut x = 1;
-    let mut y = 0;
-    for _ in 0..t {
-        let temp_x = x;
-        x = y;
-        y = (2 * temp_x + 3 * y) % 5;
-    }
-    (x, y)
-}
+// first 32 bytes of SHAKE256("abc"), the known answer for the tests and self_test
+pub(crate) const SHAKE256_ABC: [u8; 32] = [
+    0x48, 0x33, 0x66, 0x60, 0x13, 0x60, 0xa8, 0x77,
+    0x1c, 0x68, 0x63, 0x08, 0x0c, 0xc4, 0x11, 0x4d,
+    0x8d, 0xb4, 0x45, 0x30, 0xf8, 0xf1, 0xe1, 0xee,
+    0x4f, 0x94, 0xea, 0x37, 0xe7, 0x8b, 0x57, 0x39,
+];
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    //test vector for SHAKE256("")
+    const SHAKE256_EMPTY: [u8; 32] = [
+        0x46, 0xb9, 0xdd, 0x2b, 0x0b, 0xa8, 0x8d, 0x13,
+        0x23, 0x3b, 0x3f, 0xeb, 0x74, 0x3e, 0xeb, 0x24,
+        0x3f, 0xcd, 0x52, 0xea, 0x62, 0xb8, 0x1b, 0x82,
+        0xb5, 0x0c, 0x27, 0x64, 0x6e, 0xd5, 0x76, 0x2f,
+    ];
+
     #[test]
     fn test_shake256_empty() {
-        let mut hasher = Shake256::new();
+        let hasher = Shake256::new();
         let mut reader = hasher.finalize_xof();
         
         let mut output = [0u8; 32];
         reader.read(&mut output);
         
-        //test vector for SHAKE256("")
-        let expected = [
-            0x46, 0xb9, 0xdd, 0x2b, 0x0b, 0xa8, 0x8d, 0x13,
-            0x23, 0x3b, 0x3f, 0xeb, 0x74, 0x3e, 0xeb, 0x24,
-            0x3f, 0xcd, 0x52, 0xea, 0x62, 0xb8, 0x1b, 0x82,
-            0xb5, 0x0c, 0x27, 0x64, 0x6e, 0xd5, 0x76, 0x2f,
-        ];
-        
-        assert_eq!(output, expected);
+        assert_eq!(output, SHAKE256_EMPTY);
     }
 
     #[test]
@@ -235,15 +605,465 @@ mod tests {
         let mut output = [0u8; 32];
         reader.read(&mut output);
         
-        // test vector for SHAKE256("abc")
+        assert_eq!(output, SHAKE256_ABC);
+    }
+
+    // Keccak team's KeccakF-1600-IntermediateValues: the zero state after one
+    // permutation, and after a second applied to that
+    const ZERO_STATE_ONCE: [u64; STATE_SIZE] = [
+        0xf1258f7940e1dde7, 0x84d5ccf933c0478a, 0xd598261ea65aa9ee, 0xbd1547306f80494d, 0x8b284e056253d057,
+        0xff97a42d7f8e6fd4, 0x90fee5a0a44647c4, 0x8c5bda0cd6192e76, 0xad30a6f71b19059c, 0x30935ab7d08ffc64,
+        0xeb5aa93f2317d635, 0xa9a6e6260d712103, 0x81a57c16dbcf555f, 0x43b831cd0347c826, 0x01f22f1a11a5569f,
+        0x05e5635a21d9ae61, 0x64befef28cc970f2, 0x613670957bc46611, 0xb87c5a554fd00ecb, 0x8c3ee88a1ccf32c8,
+        0x940c7922ae3a2614, 0x1841f924a2c509e4, 0x16f53526e70465c2, 0x75f644e97f30a13b, 0xeaf1ff7b5ceca249,
+    ];
+    const ZERO_STATE_TWICE: [u64; STATE_SIZE] = [
+        0x2d5c954df96ecb3c, 0x6a332cd07057b56d, 0x093d8d1270d76b6c, 0x8a20d9b25569d094, 0x4f9c4f99e5e7f156,
+        0xf957b9a2da65fb38, 0x85773dae1275af0d, 0xfaf4f247c3d810f7, 0x1f1b9ee6f79a8759, 0xe4fecc0fee98b425,
+        0x68ce61b6b9ce68a1, 0xdeea66c4ba8f974f, 0x33c43d836eafb1f5, 0xe00654042719dbd9, 0x7cf8a9f009831265,
+        0xfd5449a6bf174743, 0x97ddad33d8994b40, 0x48ead5fc5d0be774, 0xe3b8c8ee55b7b03c, 0x91a0226e649e42e9,
+        0x900e3129e7badd7b, 0x202a9ec5faa3cce8, 0x5b3402464e1c3db6, 0x609f4e62a44c1059, 0x20d06cd26a8fbf5c,
+    ];
+
+    #[test]
+    fn test_keccak_f1600_zero_state() {
+        let mut state = [0u64; STATE_SIZE];
+        keccak_f1600(&mut state);
+        assert_eq!(state, ZERO_STATE_ONCE);
+        keccak_f1600(&mut state);
+        assert_eq!(state, ZERO_STATE_TWICE);
+    }
+
+    // a thousand applications from zero, pinned as SHA3-256 of the little-endian lanes
+    // (cross-checked against an independent reference permutation); a lane swapped or
+    // rotated the wrong way somewhere is all but certain to show up by then
+    #[test]
+    fn test_keccak_f1600_iterated() {
+        let mut state = [0u64; STATE_SIZE];
+        for _ in 0..1000 {
+            keccak_f1600(&mut state);
+        }
+        let mut hasher = Sha3_256::new();
+        for lane in state {
+            hasher.update(&lane.to_le_bytes());
+        }
+        assert_eq!(
+            hasher.finalize()[..],
+            hex("7dc1ed1f17f82ec685b9c831b7c7aef6486b77b56963cdec7fa5029ac5516ff4")[..]
+        );
+    }
+
+    #[test]
+    fn test_shake256_one_shot() {
+        let message: Vec<u8> = (0..500).map(|i| (i * 13 + 5) as u8).collect();
+        let expected = shake256_output(&message, 0);
+
+        // the same input split at nothing, block boundaries, inside blocks, and into empty parts
+        let splittings: [&[usize]; 5] = [&[], &[136], &[1, 135, 137, 272], &[0, 0, 250, 250, 499], &[68, 204, 340, 476]];
+        for cuts in splittings {
+            let mut parts = Vec::new();
+            let mut start = 0;
+            for &cut in cuts {
+                parts.push(&message[start..cut]);
+                start = cut;
+            }
+            parts.push(&message[start..]);
+
+            let mut out = [0u8; 300];
+            shake256(&parts, &mut out);
+            assert_eq!(out, expected, "cuts {cuts:?}");
+            assert_eq!(shake256_array::<64>(&parts)[..], expected[..64], "cuts {cuts:?}");
+        }
+
+        assert_eq!(shake256_array::<32>(&[]), shake256_array::<32>(&[b""]));
+        assert_eq!(shake256_array::<32>(&[])[..], shake256_output(b"", 0)[..32]);
+        assert_eq!(Shake256::one_shot::<64>(&message)[..], expected[..64]);
+    }
+
+    // fixed-size reads against the empty and "abc" vectors above, and mixed with read
+    // across a block boundary
+    #[test]
+    fn test_read_array() {
+        let mut reader = Shake256::new().finalize_xof();
+        assert_eq!(reader.read_array::<32>(), SHAKE256_EMPTY);
+        assert_eq!(Shake256::one_shot::<32>(b""), SHAKE256_EMPTY);
+
+        let mut hasher = Shake256::new();
+        hasher.update(b"abc");
+        assert_eq!(hasher.finalize_xof().read_array::<32>(), SHAKE256_ABC);
+        assert_eq!(Shake256::one_shot::<32>(b"abc"), SHAKE256_ABC);
+
+        let expected = shake256_output(b"abc", 0);
+        let mut hasher = Shake256::new();
+        hasher.update(b"abc");
+        let mut reader = hasher.finalize_xof();
+        let mut head = [0u8; 130];
+        reader.read(&mut head);
+        assert_eq!(reader.read_array::<2>(), expected[130..132]);
+        assert_eq!(reader.read_array::<8>(), expected[132..140]);
+        assert_eq!(reader.read_array::<0>(), [0u8; 0]);
+    }
+
+    fn shake256_output(message: &[u8], skip: usize) -> [u8; 300] {
+        let mut hasher = Shake256::new();
+        hasher.update(message);
+        let mut reader = hasher.finalize_xof();
+        let mut skipped = vec![0u8; skip];
+        reader.read(&mut skipped);
+        let mut output = [0u8; 300];
+        reader.read(&mut output);
+        output
+    }
+
+    #[test]
+    fn test_shake256_export_mid_absorb() {
+        let message: Vec<u8> = (0..400).map(|i| (i * 7) as u8).collect();
+        let expected = shake256_output(&message, 0);
+
+        // splits inside the first block, on a block boundary and inside the third
+        for split in [0, 1, 50, 135, 136, 137, 300, 400] {
+            let mut hasher = Shake256::new();
+            hasher.update(&message[..split]);
+            let exported = hasher.export_state();
+
+            let mut resumed = Shake256::import_state(&exported).unwrap();
+            let mut copy = hasher.clone();
+            resumed.update(&message[split..]);
+            copy.update(&message[split..]);
+
+            let mut output = [0u8; 300];
+            resumed.finalize_xof().read(&mut output);
+            assert_eq!(output, expected, "split {split}");
+            copy.finalize_xof().read(&mut output);
+            assert_eq!(output, expected, "split {split}");
+        }
+    }
+
+    #[test]
+    fn test_shake256_export_mid_squeeze() {
+        let expected = shake256_output(b"squeeze", 0);
+
+        for split in [0, 1, 100, 136, 137, 272, 299] {
+            let mut hasher = Shake256::new();
+            hasher.update(b"squeeze");
+            let mut reader = hasher.finalize_xof();
+            let mut output = [0u8; 300];
+            reader.read(&mut output[..split]);
+
+            let mut resumed = Shake256Reader::import_state(&reader.export_state()).unwrap();
+            let mut copy = reader.clone();
+            resumed.read(&mut output[split..]);
+            assert_eq!(output, expected, "split {split}");
+            copy.read(&mut output[split..]);
+            assert_eq!(output, expected, "split {split}");
+        }
+    }
+
+    #[test]
+    fn test_shake_import_rejects_bad_state() {
+        let mut hasher = Shake256::new();
+        hasher.update(b"state");
+        let absorbing = hasher.clone().export_state();
+        let squeezing = hasher.finalize_xof().export_state();
+        assert!(Shake256::import_state(&absorbing).is_ok());
+        assert!(Shake256Reader::import_state(&squeezing).is_ok());
+
+        // the other phase, or the other rate
+        assert!(Shake256::import_state(&squeezing).is_err());
+        assert!(Shake256Reader::import_state(&absorbing).is_err());
+        assert!(Shake128Reader::import_state(&squeezing).is_err());
+
+        // length, version, reserved bytes
+        assert!(Shake256::import_state(&absorbing[..SHAKE_STATE_BYTES - 1]).is_err());
+        let mut long = absorbing.to_vec();
+        long.push(0);
+        assert!(Shake256::import_state(&long).is_err());
+        let mut bad = absorbing;
+        bad[0] = SHAKE_STATE_VERSION + 1;
+        assert!(Shake256::import_state(&bad).is_err());
+        let mut bad = absorbing;
+        bad[7] = 1;
+        assert!(Shake256::import_state(&bad).is_err());
+
+        // a buffer position of a full block or beyond
+        let mut bad = absorbing;
+        bad[2..4].copy_from_slice(&(SHAKE256_RATE as u16).to_le_bytes());
+        assert_eq!(Shake256::import_state(&bad).err(), Some(FalconError::InvalidAccountData));
+        let mut bad = squeezing;
+        bad[2..4].copy_from_slice(&(SHAKE256_RATE as u16 + 1).to_le_bytes());
+        assert_eq!(Shake256Reader::import_state(&bad).err(), Some(FalconError::InvalidAccountData));
+    }
+
+    // the digest traits must give exactly what the sha3 crate's SHAKE256 gives, for
+    // messages fed in random pieces and output read in random pieces
+    #[cfg(feature = "digest-traits")]
+    #[test]
+    fn test_digest_traits_match_sha3() {
+        use digest::{ExtendableOutput, Update, XofReader as _};
+        use rand::{Rng, SeedableRng};
+
+        fn shake<H: Update + ExtendableOutput>(
+            mut hasher: H,
+            pieces: &[&[u8]],
+            reads: &[usize],
+        ) -> Vec<u8> {
+            for piece in pieces {
+                hasher.update(piece);
+            }
+            let mut reader = hasher.finalize_xof();
+            let mut output = vec![0u8; reads.iter().sum()];
+            let mut offset = 0;
+            for &len in reads {
+                reader.read(&mut output[offset..offset + len]);
+                offset += len;
+            }
+            output
+        }
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x5a4e);
+        for _ in 0..200 {
+            let message: Vec<u8> = (0..rng.random_range(0..600)).map(|_| rng.random()).collect();
+            let mut pieces = Vec::new();
+            let mut rest = &message[..];
+            while !rest.is_empty() {
+                let (piece, tail) = rest.split_at(rng.random_range(0..=rest.len().min(300)));
+                pieces.push(piece);
+                rest = tail;
+            }
+            let reads: Vec<usize> = (0..rng.random_range(1..8)).map(|_| rng.random_range(0..400)).collect();
+
+            assert_eq!(
+                shake(Shake256::new(), &pieces, &reads),
+                shake(sha3::Shake256::default(), &pieces, &reads)
+            );
+        }
+    }
+
+    fn hex(digest: &str) -> Vec<u8> {
+        (0..digest.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digest[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // FIPS 202 / NIST example values: empty, "abc", the 896-bit two-block message
+    // (for SHA3-512's 72-byte rate) and a million 'a's
+    const SHA3_MESSAGES: [&[u8]; 3] = [
+        b"",
+        b"abc",
+        b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu",
+    ];
+
+    #[test]
+    fn test_sha3_256_vectors() {
         let expected = [
-            0x48, 0x33, 0x66, 0x60, 0x13, 0x60, 0xa8, 0x77,
-            0x1c, 0x68, 0x63, 0x08, 0x0c, 0xc4, 0x11, 0x4d,
-            0x8d, 0xb4, 0x45, 0x30, 0xf8, 0xf1, 0xe1, 0xee,
-            0x4f, 0x94, 0xea, 0x37, 0xe7, 0x8b, 0x57, 0x39,
+            "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a",
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+            "916f6061fe879741ca6469b43971dfdb28b1a32dc36cb3254e812be27aad1d18",
         ];
-        
-        assert_eq!(output, expected);
+        for (message, digest) in SHA3_MESSAGES.iter().zip(expected) {
+            let mut hasher = Sha3_256::new();
+            hasher.update(message);
+            assert_eq!(hasher.finalize()[..], hex(digest)[..]);
+        }
+
+        let mut hasher = Sha3_256::new();
+        for _ in 0..1000 {
+            hasher.update(&[b'a'; 1000]);
+        }
+        assert_eq!(
+            hasher.finalize()[..],
+            hex("5c8875ae474a3634ba4fd55ec85bffd661f32aca75c6d699d0cdcb6c115891c1")[..]
+        );
+    }
+
+    #[test]
+    fn test_sha3_512_vectors() {
+        let expected = [
+            "a69f73cca23a9ac5c8b567dc185a756e97c982164fe25859e0d1dcc1475c80a615b2123af1f5f94c11e3e9402c3ac558f500199d95b6d3e301758586281dcd26",
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+            "afebb2ef542e6579c50cad06d2e578f9f8dd6881d7dc824d26360feebf18a4fa73e3261122948efcfd492e74e82e2189ed0fb440d187f382270cb455f21dd185",
+        ];
+        for (message, digest) in SHA3_MESSAGES.iter().zip(expected) {
+            let mut hasher = Sha3_512::new();
+            hasher.update(message);
+            assert_eq!(hasher.finalize()[..], hex(digest)[..]);
+        }
+
+        let mut hasher = Sha3_512::new();
+        for _ in 0..1000 {
+            hasher.update(&[b'a'; 1000]);
+        }
+        assert_eq!(
+            hasher.finalize()[..],
+            hex("3c3a876da14034ab60627c077bb98f7e120a2a5370212dffb3385a18d4f38859ed311d0a9d5141ce9cc5c66ee689b266a8aa18ace8282a0e0db596c90b0a7b87")[..]
+        );
+    }
+
+    // splitting the input across updates, on and off the block boundaries, doesn't change the digest
+    #[test]
+    fn test_keccak256_vectors() {
+        let vectors: [(&[u8], &str); 3] = [
+            (b"", "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"),
+            (b"abc", "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"),
+            (
+                b"The quick brown fox jumps over the lazy dog",
+                "4d741b6f1eb29cb2a9b9911c82f56fa8d73b04959d3d9d222895df6c0b28aa15",
+            ),
+        ];
+        for (message, digest) in vectors {
+            assert_eq!(Keccak256::hash(message)[..], hex(digest)[..]);
+        }
+
+        // two blocks, fed in pieces across the boundary
+        let mut hasher = Keccak256::new();
+        for piece in [&[b'a'; 100][..], &[b'a'; 36], &[b'a'; 64]] {
+            hasher.update(piece);
+        }
+        assert_eq!(
+            hasher.finalize()[..],
+            hex("96ea54061def936c4be90b518992fdc6f12f535068a256229aca54267b4d084d")[..]
+        );
+
+        // only the padding separates it from SHA3-256
+        let mut sha3 = Sha3_256::new();
+        sha3.update(b"abc");
+        assert_ne!(Keccak256::hash(b"abc"), sha3.finalize());
+    }
+
+    #[test]
+    fn test_sha3_incremental_updates() {
+        let message = [0x5Au8; 300];
+        let mut whole = Sha3_512::new();
+        whole.update(&message);
+        let whole = whole.finalize();
+
+        for split in [0, 1, 71, 72, 73, 144, 299, 300] {
+            let mut hasher = Sha3_512::new();
+            hasher.update(&message[..split]);
+            hasher.update(&message[split..]);
+            assert_eq!(hasher.finalize(), whole);
+        }
+    }
+
+    // FIPS 202 example values for SHAKE256 on the 1600-bit message of 0xa3 bytes,
+    // which absorbs two blocks, read out to 4096 bits in uneven pieces
+    #[test]
+    fn test_shake256_1600_bit_message() {
+        let mut hasher = Shake256::new();
+        hasher.update(&[0xa3; 200]);
+        let mut reader = hasher.finalize_xof();
+
+        let mut output = [0u8; 512];
+        let mut offset = 0;
+        for len in [1, 135, 136, 240] {
+            reader.read(&mut output[offset..offset + len]);
+            offset += len;
+        }
+
+        let expected_head = [
+            0xcd, 0x8a, 0x92, 0x0e, 0xd1, 0x41, 0xaa, 0x04,
+            0x07, 0xa2, 0x2d, 0x59, 0x28, 0x86, 0x52, 0xe9,
+            0xd9, 0xf1, 0xa7, 0xee, 0x0c, 0x1e, 0x7c, 0x1c,
+            0xa6, 0x99, 0x42, 0x4d, 0xa8, 0x4a, 0x90, 0x4d,
+        ];
+        let expected_tail = [
+            0x6a, 0x1a, 0x9d, 0x78, 0x46, 0x43, 0x6e, 0x4d,
+            0xca, 0x57, 0x28, 0xb6, 0xf7, 0x60, 0xee, 0xf0,
+            0xca, 0x92, 0xbf, 0x0b, 0xe5, 0x61, 0x5e, 0x96,
+            0x95, 0x9d, 0x76, 0x71, 0x97, 0xa0, 0xbe, 0xeb,
+        ];
+
+        assert_eq!(output[..32], expected_head);
+        assert_eq!(output[480..], expected_tail);
+    }
+
+    #[test]
+    fn test_shake128_vectors() {
+        let cases: [(&[u8], &str); 2] = [
+            (b"", "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef26"),
+            (b"abc", "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8"),
+        ];
+        for (message, digest) in cases {
+            let mut hasher = Shake128::new();
+            hasher.update(message);
+            let mut output = [0u8; 32];
+            hasher.finalize_xof().read(&mut output);
+            assert_eq!(output[..], hex(digest)[..]);
+        }
+
+        // longer than the 168-byte rate, on both the input and the output side
+        let message: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let mut hasher = Shake128::new();
+        hasher.update(&message);
+        let mut output = [0u8; 200];
+        hasher.finalize_xof().read(&mut output);
+        assert_eq!(output[..32], hex("0c4234ca1e31801ae606f8b8d8e0665c66f42a21d601c2681858a92c79ad5d69")[..]);
+    }
+
+    // SP 800-185 cSHAKE256 samples #3 and #4
+    #[test]
+    fn test_cshake256_vectors() {
+        let cases: [(Vec<u8>, &str); 2] = [
+            (
+                vec![0, 1, 2, 3],
+                "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd1\
+                 64020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c",
+            ),
+            (
+                (0..200).map(|i| i as u8).collect(),
+                "07dc27b11e51fbac75bc7b3c1d983e8b4b85fb1defaf218912ac864302730917\
+                 27f42b17ed1df63e8ec118f04b23633c1dfb1574c8fb55cb45da8e25afb092bb",
+            ),
+        ];
+        for (message, digest) in cases {
+            let mut hasher = CShake256::new(b"", b"Email Signature");
+            hasher.update(&message);
+            let mut output = [0u8; 64];
+            hasher.finalize_xof().read(&mut output);
+            assert_eq!(output[..], hex(digest)[..]);
+        }
+    }
+
+    #[test]
+    fn test_cshake256_domain_separation() {
+        let xof = |function_name: &[u8], customization: &[u8], message: &[u8]| {
+            let mut hasher = CShake256::new(function_name, customization);
+            hasher.update(message);
+            let mut output = [0u8; 32];
+            hasher.finalize_xof().read(&mut output);
+            output
+        };
+
+        // no name and no customization is SHAKE256
+        let mut shake = Shake256::new();
+        shake.update(b"abc");
+        let mut expected = [0u8; 32];
+        shake.finalize_xof().read(&mut expected);
+        assert_eq!(xof(b"", b"", b"abc"), expected);
+
+        let named = xof(b"FALCON-VAULT", b"", b"abc");
+        assert_ne!(named, expected);
+        assert_ne!(named, xof(b"FALCON-VAULT", b"transfer", b"abc"));
+        // moving bytes between the name and the customization string changes the output
+        assert_ne!(xof(b"FALCON-VAULT", b"x", b""), xof(b"FALCON-VAULTx", b"", b""));
+    }
+
+    // PI_LANES is the coordinate walk the permutation used to recompute per lane
+    #[test]
+    fn test_pi_lanes_follow_the_walk() {
+        let (mut x, mut y) = (1, 0);
+        for &lane in PI_LANES.iter() {
+            (x, y) = (y, (2 * x + 3 * y) % 5);
+            assert_eq!(lane, 5 * y + x);
+        }
+        // the walk visits every lane but (0, 0) exactly once
+        let mut seen = [false; STATE_SIZE];
+        for &lane in PI_LANES.iter() {
+            assert!(!seen[lane]);
+            seen[lane] = true;
+        }
+        assert!(!seen[0]);
     }
 
     #[test]
@@ -261,4 +1081,33 @@ mod tests {
         // this should produce different outputs (continuous stream, not just the same output)
         assert_ne!(output1, output2);
     }
+
+    // reads of every awkward size around the 136-byte rate must give the same stream
+    // as one large read
+    #[test]
+    fn test_read_sizes() {
+        let squeeze = |sizes: &[usize]| {
+            let mut hasher = Shake256::new();
+            hasher.update(b"abc");
+            let mut reader = hasher.finalize_xof();
+            let mut output = vec![0u8; 1000];
+            let mut offset = 0;
+            for &size in sizes.iter().cycle() {
+                let end = (offset + size).min(output.len());
+                reader.read(&mut output[offset..end]);
+                offset = end;
+                if offset == output.len() {
+                    break;
+                }
+            }
+            output
+        };
+
+        let expected = squeeze(&[1000]);
+        assert_eq!(expected[..32], hex("483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739")[..]);
+        assert_eq!(expected[984..], hex("6bfbb24e7edfd1e666a4b37f64d405bb")[..]);
+        for sizes in [&[1][..], &[135], &[136], &[137], &[500], &[0, 3, 135, 1, 137, 500]] {
+            assert_eq!(squeeze(sizes), expected, "sizes {sizes:?}");
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,116 @@
+// modular arithmetic helpers for deriving the NTT constants
+// all const fn, so ntt.rs can compute its constants at compile time and the tests
+// here can check them against an independent derivation
+
+// base^exp mod q, square and multiply
+pub const fn mod_pow(base: u32, mut exp: u32, q: u32) -> u32 {
+    let q = q as u64;
+    let mut base = base as u64 % q;
+    let mut result = 1 % q;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % q;
+        }
+        base = base * base % q;
+        exp >>= 1;
+    }
+    result as u32
+}
+
+// a^-1 mod q, None when a and q aren't coprime
+pub const fn mod_inverse(a: u32, q: u32) -> Option<u32> {
+    // extended Euclid on (a, q), tracking only the coefficient of a
+    let (mut r0, mut r1) = (q as i64, (a % q) as i64);
+    let (mut t0, mut t1) = (0i64, 1i64);
+    while r1 != 0 {
+        let quotient = r0 / r1;
+        (r0, r1) = (r1, r0 - quotient * r1);
+        (t0, t1) = (t1, t0 - quotient * t1);
+    }
+    if r0 != 1 {
+        return None;
+    }
+    Some(t0.rem_euclid(q as i64) as u32)
+}
+
+// true when x has multiplicative order exactly `order` mod q
+pub const fn has_order(x: u32, order: u32, q: u32) -> bool {
+    if order == 0 || mod_pow(x, order, q) != 1 {
+        return false;
+    }
+    // x^(order / p) != 1 for every prime p dividing order
+    let mut rest = order;
+    let mut p = 2;
+    while rest > 1 {
+        if rest.is_multiple_of(p) {
+            if mod_pow(x, order / p, q) == 1 {
+                return false;
+            }
+            while rest.is_multiple_of(p) {
+                rest /= p;
+            }
+        }
+        p += 1;
+    }
+    true
+}
+
+// smallest x with multiplicative order exactly `order` mod q, q prime
+// None when order doesn't divide q - 1
+pub const fn find_primitive_root(order: u32, q: u32) -> Option<u32> {
+    if order == 0 || q < 2 || !(q - 1).is_multiple_of(order) {
+        return None;
+    }
+    let mut x = 1;
+    while x < q {
+        if has_order(x, order, q) {
+            return Some(x);
+        }
+        x += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::falcon::ntt::{N, Q, ROOT_OF_UNITY};
+
+    #[test]
+    fn test_mod_pow_and_inverse() {
+        assert_eq!(mod_pow(3, 0, Q), 1);
+        assert_eq!(mod_pow(2, 13, Q), 8192);
+        // Fermat
+        assert_eq!(mod_pow(12288, Q - 1, Q), 1);
+
+        for a in [1, 2, 49, 512, 12288] {
+            let inverse = mod_inverse(a, Q).unwrap();
+            assert_eq!(a as u64 * inverse as u64 % Q as u64, 1);
+        }
+        assert_eq!(mod_inverse(N as u32, Q), Some(12265));
+        assert_eq!(mod_inverse(0, Q), None);
+        assert_eq!(mod_inverse(4, 8), None);
+    }
+
+    #[test]
+    fn test_find_primitive_root() {
+        let psi = find_primitive_root(1024, Q).unwrap();
+        assert!(has_order(psi, 1024, Q));
+        assert_ne!(mod_pow(psi, 512, Q), 1);
+        assert_eq!(psi, ROOT_OF_UNITY);
+
+        // a generator of the whole group, and orders that don't divide q - 1
+        assert!(has_order(find_primitive_root(Q - 1, Q).unwrap(), Q - 1, Q));
+        assert_eq!(find_primitive_root(8192, Q), None);
+        assert_eq!(find_primitive_root(5, Q), None);
+    }
+
+    #[test]
+    fn test_has_order() {
+        // 1479 is a 4th root of unity, not a 1024th one
+        assert!(has_order(1479, 4, Q));
+        assert!(!has_order(1479, 1024, Q));
+        assert!(has_order(Q - 1, 2, Q));
+        assert!(!has_order(1, 2, Q));
+    }
+}
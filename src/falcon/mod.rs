@@ -1,9 +1,21 @@
+pub mod error;
+pub use error::*;
+
 pub mod signature;
 pub use signature::*;
 
+pub mod encoding;
+pub use encoding::*;
+
 pub mod verify;
 pub use verify::*;
 
+pub mod params;
+pub use params::*;
+
+pub mod poly;
+pub use poly::*;
+
 pub mod ntt;
 pub use ntt::*;
 
@@ -13,5 +25,24 @@ pub use keccak::*;
 pub mod performance;
 pub use performance::*;
 
-#[cfg(test)]
-pub mod test_vectors; 
\ No newline at end of file
+pub mod profile;
+pub use profile::*;
+
+pub mod self_test;
+pub use self_test::*;
+
+// not glob re-exported, callers opt in with falcon::internals::...
+pub mod internals;
+
+// constant derivation helpers, used as falcon::math::...
+pub mod math;
+
+// verify for the browser, see the wasm feature in Cargo.toml; not glob re-exported
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// host-side signer, see the signing feature in Cargo.toml
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "signing")]
+pub use signing::*;
@@ -1,109 +1,209 @@
-// Number Theoretic Transform (NTT) for Falcon-512
+// Number Theoretic Transform (NTT) for Falcon, over Z_q[x]/(x^n + 1)
 // Designed for no_std environments with minimal compute unit usage
 
+use super::math::{find_primitive_root, has_order, mod_inverse, mod_pow};
+
 // NTT parameters for Falcon-512
 pub const Q: u32 = 12289;  // Prime modulus
 pub const N: usize = 512;  // Ring dimension
-pub const ROOT_OF_UNITY: u32 = 1479;  // Primitive 1024th root of unity mod Q
+pub const ROOT_OF_UNITY: u32 = 49;  // Primitive 1024th root of unity mod Q (psi)
+
+// the negacyclic transform needs psi of order exactly 2N
+const _: () = assert!(has_order(ROOT_OF_UNITY, 2 * N as u32, Q));
 
-// modular inverse of N for inverse NTT
-const INV_N: u32 = 12265; // N^(-1) mod Q
+// the transforms are generic over the degree LEN, 512 for Falcon-512 and 1024 for
+// Falcon-1024; each needs a ψ of order 2 * LEN. Falcon-512 keeps ROOT_OF_UNITY,
+// other degrees use the smallest root of that order
+const fn root_of_unity(len: usize) -> u32 {
+    if len == N {
+        ROOT_OF_UNITY
+    } else {
+        find_primitive_root(2 * len as u32, Q).unwrap()
+    }
+}
 
 // compute twiddle factors on-demand
-fn compute_twiddles() -> [u32; N] {
-    let mut twiddles = [0u32; N];
-    for i in 0..N {
-        twiddles[i] = mod_pow_runtime(ROOT_OF_UNITY, i as u32);
+// the cyclic transform runs on ω = ψ^2, a primitive LEN-th root
+const fn compute_twiddles<const LEN: usize>() -> [u32; LEN] {
+    let psi = const { root_of_unity(LEN) };
+    let mut twiddles = [0u32; LEN];
+    let mut i = 0;
+    while i < LEN {
+        twiddles[i] = mod_pow(psi, 2 * i as u32, Q);
+        i += 1;
     }
     twiddles
 }
 
 // compute inverse twiddle factors on-demand
-fn compute_inv_twiddles() -> [u32; N] {
-    let mut inv_twiddles = [0u32; N];
-    for i in 0..N {
-        // ω^(-i) = ω^(1024-i) for 1024th root of unity
-        inv_twiddles[i] = if i == 0 { 1 } else { mod_pow_runtime(ROOT_OF_UNITY, 1024 - i as u32) };
+const fn compute_inv_twiddles<const LEN: usize>() -> [u32; LEN] {
+    let psi = const { root_of_unity(LEN) };
+    let mut inv_twiddles = [1u32; LEN];
+    // ω^(-i) = ψ^(2 LEN - 2i) since ψ has order 2 LEN
+    let mut i = 1;
+    while i < LEN {
+        inv_twiddles[i] = mod_pow(psi, 2 * (LEN - i) as u32, Q);
+        i += 1;
     }
     inv_twiddles
 }
 
-// runtime modular exponentiation using binary method
-fn mod_pow_runtime(mut base: u32, mut exp: u32) -> u32 {
-    let mut result = 1;
-    base %= Q;
-    while exp > 0 {
-        if exp & 1 == 1 {
-            result = fast_mod_q((result as u64 * base as u64) as u32);
-        }
-        exp >>= 1;
-        base = fast_mod_q((base as u64 * base as u64) as u32);
+// ψ^i, or ψ^(-i) when inverse is set
+// weighting coefficient i by ψ^i turns the cyclic transform into a negacyclic one,
+// so pointwise products match multiplication mod x^LEN + 1
+const fn compute_psi_powers<const LEN: usize>(inverse: bool) -> [u32; LEN] {
+    let psi = const { root_of_unity(LEN) };
+    let step = if inverse { mod_pow(psi, 2 * LEN as u32 - 1, Q) } else { psi };
+    let mut powers = [1u32; LEN];
+    let mut i = 1;
+    while i < LEN {
+        powers[i] = mod_mul(powers[i - 1], step);
+        i += 1;
     }
-    result
+    powers
+}
+
+// the bit-reversal permutation: entry i is i with its log2(LEN) bits reversed
+const fn compute_bit_reversal<const LEN: usize>() -> [u16; LEN] {
+    let mut reversed = [0u16; LEN];
+    let mut i = 0;
+    while i < LEN {
+        reversed[i] = bit_reverse(i, LEN.trailing_zeros()) as u16;
+        i += 1;
+    }
+    reversed
+}
+
+// every table both transforms need, built once at compile time from the same const
+// fns the runtime variants call, so there is no literal table to maintain and a new
+// degree only needs its LEN; that's 18 bytes per coefficient of read-only data per
+// degree in exchange for not recomputing them on every call. the runtime variants
+// share the bit reversal
+struct NttTables<const LEN: usize> {
+    twiddles: [u32; LEN],
+    inv_twiddles: [u32; LEN],
+    psi_powers: [u32; LEN],
+    inv_psi_powers: [u32; LEN],
+    bit_reversal: [u16; LEN],
+}
+
+impl<const LEN: usize> NttTables<LEN> {
+    const TABLES: &'static Self = &Self {
+        twiddles: compute_twiddles::<LEN>(),
+        inv_twiddles: compute_inv_twiddles::<LEN>(),
+        psi_powers: compute_psi_powers::<LEN>(false),
+        inv_psi_powers: compute_psi_powers::<LEN>(true),
+        bit_reversal: compute_bit_reversal::<LEN>(),
+    };
 }
 
 // modular multiplication using fast reduction
-fn mod_mul(a: u32, b: u32) -> u32 {
+const fn mod_mul(a: u32, b: u32) -> u32 {
     let product = (a as u64) * (b as u64);
     fast_mod_q(product as u32)
 }
 
 
 
+// floor(2^32 / Q), the Barrett constant for fast_mod_q
+const BARRETT_M: u64 = (1u64 << 32) / Q as u64;
+
 //fast modular reduction for Q = 12289
-// uses the fact that 2^13 ≡ 13 (mod 12289)
+// Barrett estimate of x / Q followed by one conditional subtraction, with no
+// data-dependent branch; exact for every u32 input, so it covers sums (< 2Q)
+// as well as products of two reduced values (< 2^28)
 #[inline]
-pub fn fast_mod_q(x: u32) -> u32 {
-    if x < Q {
-        x
-    } else {
-        let high = x >> 13;
-        let low = x & 8191; // x & (2^13 - 1)
-        let reduced = low + 13 * high;
-        if reduced >= Q { reduced - Q } else { reduced }
-    }
+pub const fn fast_mod_q(x: u32) -> u32 {
+    // t is x / Q rounded down, off by at most one, so x - t * Q lies in [0, 2Q)
+    let t = ((x as u64 * BARRETT_M) >> 32) as u32;
+    ct_reduce_once(x - t * Q)
+}
+
+// reduce x in [0, 2Q) to [0, Q) in constant time
+#[inline]
+pub const fn ct_reduce_once(x: u32) -> u32 {
+    let r = x.wrapping_sub(Q);
+    // all ones when x < Q (the subtraction wrapped), zero otherwise
+    let mask = 0u32.wrapping_sub(r >> 31);
+    r.wrapping_add(Q & mask)
 }
 
 
 
 // bit-reverse a value for NTT input/output ordering
 #[inline]
-fn bit_reverse(mut x: usize, bits: u32) -> usize {
+const fn bit_reverse(mut x: usize, bits: u32) -> usize {
     let mut result = 0;
-    for _ in 0..bits {
+    let mut bit = 0;
+    while bit < bits {
         result = (result << 1) | (x & 1);
         x >>= 1;
+        bit += 1;
     }
     result
 }
 
-// forward NTT transformation
-// it transforms coefficients from time domain to frequency domain
-pub fn ntt_forward(coeffs: &mut [u32; N]) {
-    let twiddle_factors = compute_twiddles();
-    
-    // bit-reverse input for decimation-in-frequency NTT
-    for i in 0..N {
-        let j = bit_reverse(i, 9); // log2(512) = 9
+// put coefficients in bit-reversed order, in place
+#[inline]
+fn bit_reverse_permute<const LEN: usize>(coeffs: &mut [u32; LEN], bit_reversal: &[u16; LEN]) {
+    for (i, &j) in bit_reversal.iter().enumerate() {
+        let j = j as usize;
         if i < j {
             coeffs.swap(i, j);
         }
     }
+}
+
+// forward NTT transformation
+// it transforms coefficients from time domain to frequency domain
+// the twiddles are computed on every call, see ntt_forward_precomputed. ψ^i is
+// stepped as the weighting goes and the bit reversal comes from the compile-time
+// table, so the twiddles are the only table on the stack: 2 KB at LEN = 512 where
+// all three took 5 KB, more than the 4 KB SBF stack frame
+pub fn ntt_forward<const LEN: usize>(coeffs: &mut [u32; LEN]) {
+    let psi = const { root_of_unity(LEN) };
+    weight_by_powers(coeffs, psi);
+    ntt_forward_butterflies(coeffs, &compute_twiddles::<LEN>(), &NttTables::<LEN>::TABLES.bit_reversal);
+}
+
+// ntt_forward with the twiddles from the compile-time tables
+pub fn ntt_forward_precomputed<const LEN: usize>(coeffs: &mut [u32; LEN]) {
+    let tables = NttTables::<LEN>::TABLES;
+    // negacyclic weighting
+    for (coeff, &power) in coeffs.iter_mut().zip(tables.psi_powers.iter()) {
+        *coeff = mod_mul(*coeff, power);
+    }
+    ntt_forward_butterflies(coeffs, &tables.twiddles, &tables.bit_reversal);
+}
+
+// multiply coefficient i by step^i, the same products compute_psi_powers tabulates
+fn weight_by_powers<const LEN: usize>(coeffs: &mut [u32; LEN], step: u32) {
+    let mut power = 1;
+    for coeff in coeffs.iter_mut() {
+        *coeff = mod_mul(*coeff, power);
+        power = mod_mul(power, step);
+    }
+}
+
+fn ntt_forward_butterflies<const LEN: usize>(
+    coeffs: &mut [u32; LEN],
+    twiddle_factors: &[u32; LEN],
+    bit_reversal: &[u16; LEN],
+) {
+    // bit-reverse input for decimation-in-frequency NTT
+    bit_reverse_permute(coeffs, bit_reversal);
     
     // NTT with decimation-in-frequency
+    // each block of len pairs its halves, the j-th pair with twiddle step * j
     let mut len = 2;
-    while len <= N {
-        let step = N / len;
-        for start in (0..N).step_by(len) {
-            let mut j = 0;
-            for i in start..start + len / 2 {
-                let u = coeffs[i];
-                let v = mod_mul(coeffs[i + len / 2], twiddle_factors[step * j]);
-                
-                coeffs[i] = fast_mod_q(u + v);
-                coeffs[i + len / 2] = fast_mod_q(u + Q - v);
-                
-                j += 1;
+    while len <= LEN {
+        let step = LEN / len;
+        for block in coeffs.chunks_exact_mut(len) {
+            let (low, high) = block.split_at_mut(len / 2);
+            let twiddles = twiddle_factors.iter().step_by(step);
+            for ((u, v), &twiddle) in low.iter_mut().zip(high.iter_mut()).zip(twiddles) {
+                let t = mod_mul(*v, twiddle);
+                (*u, *v) = (fast_mod_q(*u + t), fast_mod_q(*u + Q - t));
             }
         }
         len <<= 1;
@@ -112,85 +212,118 @@ pub fn ntt_forward(coeffs: &mut [u32; N]) {
 
 // inverse NTT transformation
 // this transforms coefficients from frequency domain back to time domain
-pub fn ntt_inverse(coeffs: &mut [u32; N]) {
-    let inv_twiddle_factors = compute_inv_twiddles();
-    
+// like ntt_forward, only the inverse twiddles are a per-call table
+pub fn ntt_inverse<const LEN: usize>(coeffs: &mut [u32; LEN]) {
+    ntt_inverse_butterflies(coeffs, &compute_inv_twiddles::<LEN>(), &NttTables::<LEN>::TABLES.bit_reversal);
+    // undo the negacyclic weighting
+    let inv_psi = const { mod_pow(root_of_unity(LEN), 2 * LEN as u32 - 1, Q) };
+    weight_by_powers(coeffs, inv_psi);
+}
+
+// ntt_inverse with the twiddles from the compile-time tables
+pub fn ntt_inverse_precomputed<const LEN: usize>(coeffs: &mut [u32; LEN]) {
+    let tables = NttTables::<LEN>::TABLES;
+    ntt_inverse_butterflies(coeffs, &tables.inv_twiddles, &tables.bit_reversal);
+    // undo the negacyclic weighting
+    for (coeff, &power) in coeffs.iter_mut().zip(tables.inv_psi_powers.iter()) {
+        *coeff = mod_mul(*coeff, power);
+    }
+}
+
+fn ntt_inverse_butterflies<const LEN: usize>(
+    coeffs: &mut [u32; LEN],
+    inv_twiddle_factors: &[u32; LEN],
+    bit_reversal: &[u16; LEN],
+) {
     // inverse NTT
-    let mut len = N;
+    let mut len = LEN;
     while len >= 2 {
-        let step = N / len;
-        for start in (0..N).step_by(len) {
-            let mut j = 0;
-            for i in start..start + len / 2 {
-                let u = coeffs[i];
-                let v = coeffs[i + len / 2];
-                
-                coeffs[i] = fast_mod_q(u + v);
-                coeffs[i + len / 2] = mod_mul(fast_mod_q(u + Q - v), inv_twiddle_factors[step * j]);
-                
-                j += 1;
+        let step = LEN / len;
+        for block in coeffs.chunks_exact_mut(len) {
+            let (low, high) = block.split_at_mut(len / 2);
+            let twiddles = inv_twiddle_factors.iter().step_by(step);
+            for ((u, v), &twiddle) in low.iter_mut().zip(high.iter_mut()).zip(twiddles) {
+                (*u, *v) = (fast_mod_q(*u + *v), mod_mul(fast_mod_q(*u + Q - *v), twiddle));
             }
         }
         len >>= 1;
     }
     
-    //scale by 1/N
+    //scale by 1/LEN
+    let inv_len = const { mod_inverse(LEN as u32, Q).unwrap() }; // 12265 for 512
     for coeff in coeffs.iter_mut() {
-        *coeff = mod_mul(*coeff, INV_N);
+        *coeff = mod_mul(*coeff, inv_len);
     }
     
     // bit-reverse output
-    for i in 0..N {
-        let j = bit_reverse(i, 9);
-        if i < j {
-            coeffs.swap(i, j);
-        }
+    bit_reverse_permute(coeffs, bit_reversal);
+}
+
+// the per-coefficient loops of verification run four coefficients per iteration:
+// on SBF every iteration pays a compare, a branch and index updates, so unrolling
+// saves three of those four, and the four sums of sum_x4 don't wait on each other.
+// the remainder is handled one at a time, though every supported degree is a
+// multiple of four
+
+// f on each pair of out and input, for the length of the shorter one
+#[inline(always)]
+pub(crate) fn zip_x4<A, B>(out: &mut [A], input: &[B], mut f: impl FnMut(&mut A, &B)) {
+    // same lengths, so the remainders line up
+    let len = out.len().min(input.len());
+    let (out, input) = (&mut out[..len], &input[..len]);
+    let mut out_chunks = out.chunks_exact_mut(4);
+    let mut input_chunks = input.chunks_exact(4);
+    for (out, input) in (&mut out_chunks).zip(&mut input_chunks) {
+        f(&mut out[0], &input[0]);
+        f(&mut out[1], &input[1]);
+        f(&mut out[2], &input[2]);
+        f(&mut out[3], &input[3]);
+    }
+    for (out, input) in out_chunks.into_remainder().iter_mut().zip(input_chunks.remainder()) {
+        f(out, input);
     }
 }
 
-//pointwise multiplication in NTT domain
-// more efficient than polynomial multiplication in time domain
-#[inline]
-pub fn ntt_pointwise_mul(a: &[u32; N], b: &[u32; N], result: &mut [u32; N]) {
-    for i in 0..N {
-        result[i] = mod_mul(a[i], b[i]);
+// the sum of f over values, in four independent accumulators
+#[inline(always)]
+pub(crate) fn sum_x4<T>(values: &[T], f: impl Fn(&T) -> u64) -> u64 {
+    let mut chunks = values.chunks_exact(4);
+    let mut sums = [0u64; 4];
+    for chunk in &mut chunks {
+        sums[0] += f(&chunk[0]);
+        sums[1] += f(&chunk[1]);
+        sums[2] += f(&chunk[2]);
+        sums[3] += f(&chunk[3]);
     }
+    let tail: u64 = chunks.remainder().iter().map(f).sum();
+    sums[0] + sums[1] + sums[2] + sums[3] + tail
 }
 
-// subtract two polynomials in NTT domain
+//pointwise multiplication in NTT domain
+// more efficient than polynomial multiplication in time domain
 #[inline]
-pub fn ntt_pointwise_sub(a: &[u32; N], b: &[u32; N], result: &mut [u32; N]) {
-    for i in 0..N {
-        result[i] = fast_mod_q(a[i] + Q - b[i]);
-    }
+pub fn ntt_pointwise_mul(a: &[u32; N], b: &[u32; N], result: &mut [u32; N]) {
+    *result = *a;
+    zip_x4(result, b, |coeff, &b| *coeff = mod_mul(*coeff, b));
 }
 
 // convert signed coefficients to unsigned for NTT
 pub fn to_ntt_form(signed_coeffs: &[i16; N]) -> [u32; N] {
     let mut unsigned_coeffs = [0u32; N];
-    for i in 0..N {
-        //convert from signed to unsigned representation in Z_q
-        let val = signed_coeffs[i] as i32;
-        unsigned_coeffs[i] = if val >= 0 {
-            val as u32
-        } else {
-            (val + Q as i32) as u32
-        };
-    }
+    //convert from signed to unsigned representation in Z_q
+    zip_x4(&mut unsigned_coeffs, signed_coeffs, |coeff, &val| {
+        let val = val as i32;
+        *coeff = if val >= 0 { val as u32 } else { (val + Q as i32) as u32 };
+    });
     unsigned_coeffs
 }
 
 // convert unsigned coefficients back to signed form
 pub fn from_ntt_form(unsigned_coeffs: &[u32; N]) -> [i16; N] {
     let mut signed_coeffs = [0i16; N];
-    for i in 0..N {
-        let val = unsigned_coeffs[i];
-        signed_coeffs[i] = if val > Q / 2 {
-            (val as i32 - Q as i32) as i16
-        } else {
-            val as i16
-        };
-    }
+    zip_x4(&mut signed_coeffs, unsigned_coeffs, |coeff, &val| {
+        *coeff = if val > Q / 2 { (val as i32 - Q as i32) as i16 } else { val as i16 };
+    });
     signed_coeffs
 }
 
@@ -202,8 +335,8 @@ mod tests {
     fn test_ntt_roundtrip() {
         let mut coeffs = [0u32; N];
         // set up a simple test pattern
-        for i in 0..10 {
-            coeffs[i] = i as u32 + 1;
+        for (i, coeff) in coeffs.iter_mut().take(10).enumerate() {
+            *coeff = i as u32 + 1;
         }
         
         let original = coeffs;
@@ -220,6 +353,203 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ntt_multiplication_is_negacyclic() {
+        // small deterministic pseudo-random polynomials
+        let mut a = [0u32; N];
+        let mut b = [0u32; N];
+        let mut state = 0x1234_5678u32;
+        for i in 0..N {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            a[i] = (state >> 8) % Q;
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            b[i] = (state >> 8) % Q;
+        }
+
+        let expected = schoolbook_negacyclic(&a, &b);
+
+        let (mut a_ntt, mut b_ntt) = (a, b);
+        ntt_forward(&mut a_ntt);
+        ntt_forward(&mut b_ntt);
+        let mut product = [0u32; N];
+        ntt_pointwise_mul(&a_ntt, &b_ntt, &mut product);
+        ntt_inverse(&mut product);
+
+        for i in 0..N {
+            assert_eq!(product[i], expected[i], "coefficient {}", i);
+        }
+    }
+
+    // the product in Z_q[x]/(x^N + 1) the slow way: x^N wraps around to -1
+    fn schoolbook_negacyclic(a: &[u32; N], b: &[u32; N]) -> [u32; N] {
+        let mut product = [0u64; N];
+        for (i, &a_i) in a.iter().enumerate() {
+            for (j, &b_j) in b.iter().enumerate() {
+                let term = a_i as u64 * b_j as u64 % Q as u64;
+                let k = (i + j) % N;
+                product[k] = if i + j < N {
+                    (product[k] + term) % Q as u64
+                } else {
+                    (product[k] + Q as u64 - term) % Q as u64
+                };
+            }
+        }
+        product.map(|coeff| coeff as u32)
+    }
+
+    // the NTT laws over uniformly random polynomials in [0, q), with the schoolbook
+    // product as the oracle. a few cases by default, extended-tests runs many more
+    mod laws {
+        use super::*;
+        use proptest::prelude::*;
+
+        const CASES: u32 = if cfg!(feature = "extended-tests") { 1024 } else { 24 };
+
+        fn polynomial() -> impl Strategy<Value = [u32; N]> {
+            prop::collection::vec(0..Q, N).prop_map(|coeffs| coeffs.try_into().unwrap())
+        }
+
+        fn forward(mut coeffs: [u32; N]) -> [u32; N] {
+            ntt_forward(&mut coeffs);
+            coeffs
+        }
+
+        fn inverse(mut coeffs: [u32; N]) -> [u32; N] {
+            ntt_inverse(&mut coeffs);
+            coeffs
+        }
+
+        fn add(a: &[u32; N], b: &[u32; N]) -> [u32; N] {
+            core::array::from_fn(|i| fast_mod_q(a[i] + b[i]))
+        }
+
+        fn pointwise(a: &[u32; N], b: &[u32; N]) -> [u32; N] {
+            let mut product = [0u32; N];
+            ntt_pointwise_mul(a, b, &mut product);
+            product
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+            #[test]
+            fn test_roundtrip_identity(a in polynomial()) {
+                prop_assert_eq!(inverse(forward(a)), a);
+
+                let mut precomputed = a;
+                ntt_forward_precomputed(&mut precomputed);
+                prop_assert_eq!(precomputed, forward(a));
+                ntt_inverse_precomputed(&mut precomputed);
+                prop_assert_eq!(precomputed, a);
+            }
+
+            #[test]
+            fn test_linearity(a in polynomial(), b in polynomial()) {
+                prop_assert_eq!(forward(add(&a, &b)), add(&forward(a), &forward(b)));
+            }
+
+            #[test]
+            fn test_convolution_theorem(a in polynomial(), b in polynomial()) {
+                let product = inverse(pointwise(&forward(a), &forward(b)));
+                prop_assert_eq!(product, schoolbook_negacyclic(&a, &b));
+            }
+
+            #[test]
+            fn test_multiply_by_one(a in polynomial()) {
+                let mut one = [0u32; N];
+                one[0] = 1;
+                // the constant 1 evaluates to 1 at every root
+                prop_assert!(forward(one).iter().all(|&coeff| coeff == 1));
+                prop_assert_eq!(inverse(pointwise(&forward(a), &forward(one))), a);
+                prop_assert_eq!(schoolbook_negacyclic(&a, &one), a);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ntt_1024_roundtrip_and_negacyclic() {
+        let psi = root_of_unity(1024);
+        assert!(has_order(psi, 2048, Q));
+
+        // x^1023 * x = x^1024 = -1 mod x^1024 + 1
+        let mut a = [0u32; 1024];
+        let mut b = [0u32; 1024];
+        a[1023] = 1;
+        b[1] = 1;
+        let original = a;
+        ntt_forward(&mut a);
+        ntt_forward(&mut b);
+        let mut product = [0u32; 1024];
+        for i in 0..1024 {
+            product[i] = mod_mul(a[i], b[i]);
+        }
+        ntt_inverse(&mut product);
+        assert_eq!(product[0], Q - 1);
+        assert!(product[1..].iter().all(|&c| c == 0));
+
+        ntt_inverse(&mut a);
+        assert_eq!(a, original);
+    }
+
+    // the compile-time tables give the same transforms as the ones built per call
+    #[test]
+    fn test_precomputed_tables_match_runtime() {
+        fn check<const LEN: usize>() {
+            let mut coeffs = [0u32; LEN];
+            let mut state = 0x0bad_5eedu32;
+            for coeff in coeffs.iter_mut() {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                *coeff = (state >> 8) % Q;
+            }
+
+            let (mut runtime, mut precomputed) = (coeffs, coeffs);
+            ntt_forward(&mut runtime);
+            ntt_forward_precomputed(&mut precomputed);
+            assert_eq!(runtime, precomputed);
+
+            ntt_inverse(&mut runtime);
+            ntt_inverse_precomputed(&mut precomputed);
+            assert_eq!(runtime, precomputed);
+            assert_eq!(precomputed, coeffs);
+        }
+        check::<512>();
+        check::<1024>();
+    }
+
+    // the compile-time tables against the same helpers run at runtime, and against
+    // their defining properties recomputed with plain u64 arithmetic, so a bug in a
+    // generator can't hide behind the transforms using it consistently
+    #[test]
+    fn test_tables_match_definitions() {
+        fn check<const LEN: usize>() {
+            let tables = NttTables::<LEN>::TABLES;
+            assert_eq!(tables.twiddles, compute_twiddles::<LEN>());
+            assert_eq!(tables.inv_twiddles, compute_inv_twiddles::<LEN>());
+            assert_eq!(tables.psi_powers, compute_psi_powers::<LEN>(false));
+            assert_eq!(tables.inv_psi_powers, compute_psi_powers::<LEN>(true));
+            assert_eq!(tables.bit_reversal, compute_bit_reversal::<LEN>());
+
+            let q = Q as u64;
+            let psi = root_of_unity(LEN) as u64;
+            let mut psi_i = 1u64;
+            for i in 0..LEN {
+                assert_eq!(tables.psi_powers[i] as u64, psi_i, "psi^{}", i);
+                assert_eq!(tables.psi_powers[i] as u64 * tables.inv_psi_powers[i] as u64 % q, 1);
+                assert_eq!(tables.twiddles[i] as u64, psi_i * psi_i % q, "omega^{}", i);
+                assert_eq!(tables.twiddles[i] as u64 * tables.inv_twiddles[i] as u64 % q, 1);
+                psi_i = psi_i * psi % q;
+
+                let j = tables.bit_reversal[i] as usize;
+                assert_eq!(tables.bit_reversal[j] as usize, i);
+                assert_eq!(j, i.reverse_bits() >> (usize::BITS - LEN.trailing_zeros()));
+            }
+            // psi has order exactly 2 * LEN
+            assert_eq!(psi_i, q - 1);
+        }
+        check::<512>();
+        check::<1024>();
+    }
+
     #[test]
     fn test_modular_arithmetic() {
         assert_eq!(fast_mod_q(Q), 0);
@@ -228,6 +558,22 @@ mod tests {
         assert_eq!(fast_mod_q(2 * Q), 0);
     }
 
+    #[test]
+    fn test_fast_mod_q_matches_remainder() {
+        // edges of the Barrett estimate plus a sweep across the whole u32 range
+        for x in [0, 1, Q - 1, Q, Q + 1, 2 * Q - 1, (Q - 1) * (Q - 1), u32::MAX - 1, u32::MAX] {
+            assert_eq!(fast_mod_q(x), x % Q, "x = {}", x);
+        }
+        let mut x = 0u32;
+        while let Some(next) = x.checked_add(65_521) {
+            assert_eq!(fast_mod_q(x), x % Q, "x = {}", x);
+            x = next;
+        }
+        for x in 0..2 * Q {
+            assert_eq!(ct_reduce_once(x), x % Q);
+        }
+    }
+
     #[test]
     fn test_conversion() {
         let signed = [-1i16, 0, 1, -6144, 6144];
@@ -239,4 +585,27 @@ mod tests {
         
         assert_eq!(test_coeffs[..5], recovered[..5]);
     }
+
+    // the unrolled loops against plain ones, at lengths with every remainder
+    #[test]
+    fn test_unrolled_loops_match_scalar() {
+        for len in 0..=9 {
+            let input: Vec<u32> = (0..len).map(|i| (i as u32 * 7919) % Q).collect();
+            let mut out = vec![1u32; len];
+            zip_x4(&mut out, &input, |coeff, &x| *coeff += x);
+            assert_eq!(out, input.iter().map(|&x| x + 1).collect::<Vec<_>>());
+            assert_eq!(sum_x4(&input, |&x| (x * x) as u64), input.iter().map(|&x| (x * x) as u64).sum::<u64>());
+
+            // only the shorter length is visited
+            let mut longer = vec![0u32; len + 3];
+            zip_x4(&mut longer, &input, |coeff, &x| *coeff = x + 1);
+            assert!(longer[len..].iter().all(|&x| x == 0));
+        }
+
+        let a: [u32; N] = core::array::from_fn(|i| (i as u32 * 31) % Q);
+        let b: [u32; N] = core::array::from_fn(|i| (i as u32 * 97 + 5) % Q);
+        let mut product = [0u32; N];
+        ntt_pointwise_mul(&a, &b, &mut product);
+        assert!((0..N).all(|i| product[i] == a[i] * b[i] % Q));
+    }
 } 
\ No newline at end of file
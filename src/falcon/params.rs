@@ -0,0 +1,138 @@
+// Falcon parameter sets, selected by the logn field of the key and signature headers
+// verification reads logn from both headers, looks the degree up here and runs the
+// const-generic internals in verify.rs at that degree
+
+// everything that differs between the Falcon degrees
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FalconParams {
+    pub logn: u8,
+    pub n: usize,
+    pub q: u32,
+    // bound on ||s1||^2 + ||s2||^2, floor(beta^2) from the specification
+    pub sig_bound: u64,
+    // header byte and 14 bits per coefficient
+    pub public_key_size: usize,
+    // header byte, 40-byte nonce and the compressed s2 padded to a fixed length
+    pub signature_size: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FalconVariant {
+    Falcon512,
+    Falcon1024,
+}
+
+impl FalconVariant {
+    pub const FALCON_512: FalconParams = FalconParams {
+        logn: 9,
+        n: 512,
+        q: 12289,
+        sig_bound: 34_034_726,
+        public_key_size: 897,
+        signature_size: 666,
+    };
+
+    pub const FALCON_1024: FalconParams = FalconParams {
+        logn: 10,
+        n: 1024,
+        q: 12289,
+        sig_bound: 70_265_242,
+        public_key_size: 1793,
+        signature_size: 1280,
+    };
+
+    // large enough for a key or signature of any supported degree
+    pub const MAX_PUBLIC_KEY_SIZE: usize = Self::FALCON_1024.public_key_size;
+    pub const MAX_SIGNATURE_SIZE: usize = Self::FALCON_1024.signature_size;
+
+    // indexed by the variant
+    const PARAMS: [FalconParams; 2] = [Self::FALCON_512, Self::FALCON_1024];
+
+    pub const fn params(self) -> FalconParams {
+        Self::PARAMS[self as usize]
+    }
+
+    pub const fn from_logn(logn: u8) -> Option<Self> {
+        match logn {
+            9 => Some(FalconVariant::Falcon512),
+            10 => Some(FalconVariant::Falcon1024),
+            _ => None,
+        }
+    }
+
+    pub const fn from_degree(n: usize) -> Option<Self> {
+        match n {
+            512 => Some(FalconVariant::Falcon512),
+            1024 => Some(FalconVariant::Falcon1024),
+            _ => None,
+        }
+    }
+}
+
+// signature lengths for a logn read from a header: a header byte, the 40-byte nonce, then
+// the compressed s2. every coefficient takes at least 9 bits (sign, low 7 bits, the bit
+// closing the unary high part), so the body is at least ceil(9n / 8) bytes, and it is
+// never longer than the padded length the parameter set fixes. a length outside that
+// range can't be a signature for logn, whatever the bytes say.
+// an unsupported logn gives 0 for both, so no length passes
+pub const fn min_signature_size(logn: usize) -> usize {
+    match variant_for_logn(logn) {
+        Some(variant) => 1 + 40 + (9 * variant.params().n).div_ceil(8),
+        None => 0,
+    }
+}
+
+pub const fn max_signature_size(logn: usize) -> usize {
+    match variant_for_logn(logn) {
+        Some(variant) => variant.params().signature_size,
+        None => 0,
+    }
+}
+
+const fn variant_for_logn(logn: usize) -> Option<FalconVariant> {
+    if logn > u8::MAX as usize {
+        return None;
+    }
+    FalconVariant::from_logn(logn as u8)
+}
+
+// parameters for a const-generic degree, evaluated at compile time by the callers
+// so an unsupported N fails the build instead of panicking at runtime
+pub(crate) const fn degree_params(n: usize) -> FalconParams {
+    match FalconVariant::from_degree(n) {
+        Some(variant) => variant.params(),
+        None => panic!("unsupported Falcon degree"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_table() {
+        for variant in [FalconVariant::Falcon512, FalconVariant::Falcon1024] {
+            let params = variant.params();
+            assert_eq!(params.n, 1 << params.logn);
+            assert_eq!(FalconVariant::from_logn(params.logn), Some(variant));
+            assert_eq!(FalconVariant::from_degree(params.n), Some(variant));
+            assert_eq!(params.public_key_size, 1 + params.n * 14 / 8);
+        }
+        assert_eq!(FalconVariant::from_logn(8), None);
+        assert_eq!(FalconVariant::from_degree(2048), None);
+        assert_eq!(FalconVariant::MAX_SIGNATURE_SIZE, 1280);
+    }
+
+    #[test]
+    fn test_signature_size_range() {
+        let falcon_512 = FalconVariant::FALCON_512;
+        assert_eq!(max_signature_size(9), crate::falcon::FALCON_512_SIGNATURE_SIZE);
+        assert_eq!(max_signature_size(falcon_512.logn as usize), falcon_512.signature_size);
+        assert_eq!(min_signature_size(9), 617);
+        assert_eq!((min_signature_size(10), max_signature_size(10)), (1193, 1280));
+
+        for logn in [0, 8, 11, 9 + 256] {
+            assert_eq!((min_signature_size(logn), max_signature_size(logn)), (0, 0));
+        }
+    }
+}
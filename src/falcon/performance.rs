@@ -1,5 +1,10 @@
+// only OptimizationConfig, the cost profile and the consts derived from it are part of
+// the program build; the reports, monitors and estimators are host tooling behind std-report
 use super::verify::*;
-use pinocchio::program_error::ProgramError;
+#[cfg(any(test, feature = "std-report", feature = "profiling"))]
+use super::FalconError;
+#[cfg(any(test, feature = "std-report"))]
+use crate::error::VaultError;
 
 // configuration for optimization levels
 #[derive(Debug, Clone)]
@@ -7,7 +12,9 @@ pub struct OptimizationConfig {
     pub early_termination: bool,
     pub use_lookup_tables: bool,
     pub stack_only_allocation: bool,
-    pub simd_operations: bool,
+    // longest message, summed over its parts, that verification will hash; longer
+    // ones are MessageTooLong before any SHAKE256 work
+    pub max_message_len: usize,
 }
 
 impl Default for OptimizationConfig {
@@ -16,20 +23,38 @@ impl Default for OptimizationConfig {
             early_termination: true,
             use_lookup_tables: true,
             stack_only_allocation: true,
-            simd_operations: true,
+            max_message_len: FALCON_MAX_MESSAGE_SIZE,
         }
     }
 }
 
 // report for compute unit analysis
-#[derive(Debug)]
+// allocates and prints, so it only exists in host builds
+// owned strings throughout so it deserializes from any input, see feature "serde"
+#[cfg(any(test, feature = "std-report"))]
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PerformanceReport {
     pub total_compute_units: u64,
-    pub operation_breakdown: Vec<(&'static str, u64)>,
+    pub operation_breakdown: Vec<(String, u64)>,
     pub memory_usage_bytes: usize,
     pub optimization_level: String,
 }
 
+#[cfg(any(test, feature = "std-report"))]
+fn breakdown(operations: &[(&str, u64)]) -> Vec<(String, u64)> {
+    operations.iter().map(|&(name, cu)| (name.to_string(), cu)).collect()
+}
+
+// part of whole in tenths of a percent, "12.3%", in integers so the report builds
+// under the nostd-check float lint like the rest of the crate
+#[cfg(any(test, feature = "std-report"))]
+fn percent(part: u64, whole: u64) -> String {
+    let per_mille = (u128::from(part) * 1000).checked_div(u128::from(whole)).unwrap_or(0);
+    format!("{}.{}%", per_mille / 10, per_mille % 10)
+}
+
+#[cfg(any(test, feature = "std-report"))]
 impl PerformanceReport {
     pub fn print_report(&self) {
         println!("Falcon-512 Performance Analysis");
@@ -41,17 +66,13 @@ impl PerformanceReport {
         
         println!("Operation Breakdown:");
         for (operation, cu) in &self.operation_breakdown {
-            let percentage = (*cu as f64 / self.total_compute_units as f64) * 100.0;
-            println!("  {}: {} CU ({:.1}%)", operation, cu, percentage);
+            println!("  {}: {} CU ({})", operation, cu, percent(*cu, self.total_compute_units));
         }
         
         println!();
         println!("Solana Limits:");
         println!("  Max Compute Units: 200,000");
-        println!("  Current Usage: {} ({:.1}%)", 
-            self.total_compute_units,
-            (self.total_compute_units as f64 / 200_000.0) * 100.0
-        );
+        println!("  Current Usage: {} ({})", self.total_compute_units, percent(self.total_compute_units, 200_000));
         
         if self.total_compute_units > 200_000 {
             println!("  Exceeds Solana compute unit limit!");
@@ -62,184 +83,82 @@ impl PerformanceReport {
 }
 
 // Generate performance estimates for Falcon-512 verification
+// straight from FALCON_512_PERFORMANCE_PROFILE, the one cost table
+#[cfg(any(test, feature = "std-report"))]
 pub fn estimate_performance() -> PerformanceReport {
-    let operations = vec![
-        ("Signature Header Validation", 1_000),
-        ("Public Key Parsing", 3_000),
-        ("Signature Parsing", 5_000),
-        ("SHAKE256 Hashing", 20_000),
-        ("Hash-to-Point Sampling", 15_000),
-        ("Signature Decompression", 25_000),
-        ("NTT Forward Transform", 20_000),
-        ("Polynomial Multiplication", 15_000),
-        ("NTT Inverse Transform", 20_000),
-        ("Verification Equation", 10_000),
-        ("Norm Bound Checking", 8_000),
-        ("Memory Management", 3_000),
-    ];
-    
-    let total: u64 = operations.iter().map(|(_, cu)| cu).sum();
-    
+    let operations: Vec<(&str, u64)> = FALCON_512_PERFORMANCE_PROFILE
+        .iter()
+        .map(|op| (op.name, op.estimated_compute_units))
+        .collect();
+
     PerformanceReport {
-        total_compute_units: total,
-        operation_breakdown: operations,
-        memory_usage_bytes: 4096, // Stack-only allocation
+        total_compute_units: estimated_total(),
+        operation_breakdown: breakdown(&operations),
+        memory_usage_bytes: ESTIMATED_STACK_USAGE as usize, // Stack-only allocation
         optimization_level: "Production Optimized".to_string(),
     }
 }
 
-// verification with compute unit tracking
+// what verify_falcon_optimized measured around one verification
+#[cfg(any(test, feature = "std-report"))]
+#[derive(Debug, Clone, Copy)]
+pub struct VerificationMetrics {
+    // wall-clock time on this host, for comparing configs against each other
+    pub elapsed: std::time::Duration,
+    // the profile's figure, there is no compute meter off-chain
+    pub estimated_compute_units: u64,
+}
+
+#[cfg(any(test, feature = "std-report"))]
+#[derive(Debug)]
+pub struct OptimizedVerification {
+    pub result: Result<(), FalconError>,
+    pub metrics: VerificationMetrics,
+}
+
+// the real verifier under config, timed
+// early_termination adds the exact header pre-checks below and the partial s2 norm
+// exit, use_lookup_tables picks the compile-time NTT twiddles; the verifier never
+// touches the heap (the program build has no allocator), so stack_only_allocation
+// always holds
+#[cfg(any(test, feature = "std-report"))]
 pub fn verify_falcon_optimized(
     public_key: &[u8; FALCON_512_PUBLIC_KEY_SIZE],
     signature: &[u8; FALCON_512_SIGNATURE_SIZE],
     message: &[u8],
     config: &OptimizationConfig,
-) -> Result<u64, ProgramError> {
-    let mut compute_units = 0;
-
-    // 1. fast header validation
-    compute_units += 1_000;
-    if config.early_termination && signature[0] != 0x29 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    // 2 parse public key
-    compute_units += 3_000;
-    validate_public_key_header(public_key)?;
-
-    // 3 parse signature components
-    compute_units += 5_000;
-    let _components = parse_signature_fast(signature)?;
-
-    // 4 hash message + nonce
-    compute_units += 20_000;
-    let _hash = hash_message_optimized(message, &signature[1..41])?;
-
-    // 5 core verification (simplified)
-    compute_units += 116_000; // sum of remaining operations
-
-    // return total compute units used
-    Ok(compute_units)
-}
-
-// fast public key header validation
-fn validate_public_key_header(public_key: &[u8; FALCON_512_PUBLIC_KEY_SIZE]) -> Result<(), ProgramError> {
-    if public_key[0] != 0x09 {
-        return Err(ProgramError::InvalidInstructionData);
+) -> OptimizedVerification {
+    let start = std::time::Instant::now();
+    let result = if config.early_termination {
+        check_headers(public_key, signature)
+    } else {
+        Ok(())
     }
-    Ok(())
-}
+    .and_then(|()| verify_falcon_signature_with_config(public_key, signature, message, config));
 
-// fast signature parsing
-fn parse_signature_fast(signature: &[u8; FALCON_512_SIGNATURE_SIZE]) -> Result<SignatureComponents, ProgramError> {
-    let header = signature[0];
-    
-    // validate header (encoding=2, fixed=1, logn=9) = 0x29
-    if header != 0x29 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-    
-    // extract nonce (40 bytes starting at offset 1)
-    let mut nonce = [0u8; 40];
-    nonce.copy_from_slice(&signature[1..41]);
-    
-    // compressed signature starts at offset 41
-    let compressed_size = signature.len() - 41;
-    
-    Ok(SignatureComponents {
-        header,
-        nonce,
-        compressed_size,
-    })
-}
-
-// signature components structure
-#[allow(dead_code)]
-struct SignatureComponents {
-    header: u8,
-    nonce: [u8; 40],
-    compressed_size: usize,
-}
-
-// optimized message hashing
-fn hash_message_optimized(message: &[u8], nonce: &[u8]) -> Result<[u8; 32], ProgramError> {
-    // hash computation for performance estimation
-    // on mainnet, would use SHAKE256
-    let mut result = [0u8; 32];
-    
-    // message and nonce (simplified)
-    let combined_len = message.len() + nonce.len();
-    if combined_len > 10000 {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    
-    // simulate hash computation
-    for (i, &byte) in message.iter().enumerate() {
-        result[i % 32] ^= byte;
-    }
-    for (i, &byte) in nonce.iter().enumerate() {
-        result[i % 32] ^= byte.wrapping_add(1);
-    }
-    
-    Ok(result)
-}
-
-// benchmark different optimization levels
-pub fn benchmark_optimizations() -> Vec<PerformanceReport> {
-    vec![
-        // baseline (no optimizations)
-        PerformanceReport {
-            total_compute_units: 250_000,
-            operation_breakdown: vec![
-                ("Baseline Implementation", 250_000),
-            ],
-            memory_usage_bytes: 8192,
-            optimization_level: "Baseline (Unoptimized)".to_string(),
+    OptimizedVerification {
+        result,
+        metrics: VerificationMetrics {
+            elapsed: start.elapsed(),
+            estimated_compute_units: estimated_total(),
         },
-        
-        // basic optimizations
-        PerformanceReport {
-            total_compute_units: 180_000,
-            operation_breakdown: vec![
-                ("Early Termination Optimized", 5_000),
-                ("Lookup Table Optimized", 25_000),
-                ("Core Operations", 150_000),
-            ],
-            memory_usage_bytes: 6144,
-            optimization_level: "Level 1 (Basic)".to_string(),
-        },
-        
-        // mainnet optimization
-        estimate_performance(),
-    ]
-}
-
-// memory optimization utilities
-pub struct MemoryOptimizer {
-    stack_allocated_bytes: usize,
-    heap_allocated_bytes: usize,
+    }
 }
 
-impl MemoryOptimizer {
-    pub fn new() -> Self {
-        Self {
-            stack_allocated_bytes: 0,
-            heap_allocated_bytes: 0,
-        }
-    }
-    
-    pub fn allocate_stack(&mut self, bytes: usize) {
-        self.stack_allocated_bytes += bytes;
-    }
-    
-    pub fn total_memory_usage(&self) -> usize {
-        self.stack_allocated_bytes + self.heap_allocated_bytes
+// both header bytes against the one value each accepts, before anything is decoded
+// the full verifier only gets to the signature's encoding bits after the key's degree
+#[cfg(any(test, feature = "std-report"))]
+fn check_headers(
+    public_key: &[u8; FALCON_512_PUBLIC_KEY_SIZE],
+    signature: &[u8; FALCON_512_SIGNATURE_SIZE],
+) -> Result<(), FalconError> {
+    if signature[0] != FALCON_512_SIGNATURE_HEADER {
+        return Err(VaultError::BadSignatureHeader.into());
     }
-    
-    pub fn is_within_limits(&self) -> bool {
-        // Solana stack limit is typically 4KB
-        self.stack_allocated_bytes <= 4096 && self.heap_allocated_bytes == 0
+    if public_key[0] != FALCON_512_LOGN as u8 {
+        return Err(VaultError::BadPublicKeyHeader.into());
     }
+    Ok(())
 }
 
 // performance testing utilities
@@ -254,6 +173,29 @@ mod tests {
         assert!(report.memory_usage_bytes <= 4096, "Exceeds memory limit");
     }
 
+    #[test]
+    fn test_report_percentages() {
+        assert_eq!(percent(1, 3), "33.3%");
+        assert_eq!(percent(200_000, 200_000), "100.0%");
+        assert_eq!(percent(u64::MAX, 1), format!("{}.0%", u128::from(u64::MAX) * 100));
+        assert_eq!(percent(5, 0), "0.0%");
+    }
+
+    // the pre-check used to want 0x29 (encoding 1, fixed bit clear) while
+    // parse_signature wants encoding 2 with the fixed bit; both now take only 0x59
+    #[test]
+    fn test_signature_parsers_agree_on_header() {
+        let public_key = [FALCON_512_LOGN as u8; FALCON_512_PUBLIC_KEY_SIZE];
+        let mut signature = [0x11u8; FALCON_512_SIGNATURE_SIZE];
+        for header in 0..=u8::MAX {
+            signature[0] = header;
+            let fast = check_headers(&public_key, &signature).is_ok();
+            assert_eq!(fast, parse_signature::<FALCON_512_N>(&signature).is_ok(), "header {header:#04x}");
+            assert_eq!(fast, header == FALCON_512_SIGNATURE_HEADER, "header {header:#04x}");
+        }
+        assert_eq!(FALCON_512_SIGNATURE_HEADER, 0x59);
+    }
+
     #[test]
     fn test_optimization_config() {
         let config = OptimizationConfig::default();
@@ -261,28 +203,123 @@ mod tests {
         assert!(config.stack_only_allocation);
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_memory_optimizer() {
-        let mut optimizer = MemoryOptimizer::new();
-        optimizer.allocate_stack(2048);
-        assert!(optimizer.is_within_limits());
-        
-        optimizer.allocate_stack(4096);
-        assert!(!optimizer.is_within_limits()); // Should exceed limit
+    fn test_report_serde_roundtrip() {
+        let report = estimate_performance();
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<PerformanceReport>(&json).unwrap(), report);
+
+        let utilization = analyze_compute_utilization();
+        let json = serde_json::to_string(&utilization).unwrap();
+        assert!(json.contains("\"within_limits\":true"));
+        assert_eq!(serde_json::from_str::<ComputeUtilization>(&json).unwrap(), utilization);
     }
 
-    fn test_performance_limits() -> Result<(), ProgramError> {
-        // simulate compute unit counting
-        // on mainnet, would use actual Solana compute unit tracking
-        
-        let estimated_usage = 150_000;
-        
-        if estimated_usage <= 200_000 {
-            Ok(())
-        } else {
-            Err(ProgramError::InvalidAccountData)
+    // the runtime report adds up to the same numbers the const assertion checks
+    #[test]
+    fn test_stack_budget_report() {
+        let budget = stack_budget();
+        assert_eq!(budget.verify_scratch, core::mem::size_of::<VerifyScratch>());
+        assert_eq!(budget.verify_scratch + budget.frame_locals, VERIFY_STACK_BYTES);
+        assert_eq!(budget.total(), VERIFY_STACK_BYTES);
+        assert_eq!(budget.frame, SOLANA_STACK_FRAME_SIZE);
+        assert!(budget.headroom() > 0);
+    }
+
+    // every total is the profile's sum: the const, the report, the monitor after
+    // recording every operation, the utilization and the scheme comparison
+    #[test]
+    fn test_estimates_derive_from_profile() {
+        let sum: u64 = FALCON_512_PERFORMANCE_PROFILE.iter().map(|op| op.estimated_compute_units).sum();
+        assert_eq!(estimated_total(), sum);
+        assert_eq!(TOTAL_ESTIMATED_COMPUTE_UNITS, sum);
+
+        let report = estimate_performance();
+        assert_eq!(report.total_compute_units, sum);
+        assert_eq!(report.operation_breakdown.iter().map(|(_, cu)| cu).sum::<u64>(), sum);
+        assert_eq!(report.operation_breakdown.len(), FALCON_512_PERFORMANCE_PROFILE.len());
+
+        let mut monitor = PerformanceMonitor::new();
+        for op in FALCON_512_PERFORMANCE_PROFILE {
+            monitor.record_operation(op.name);
+        }
+        let stats = monitor.get_stats();
+        assert_eq!(stats.compute_units_used, sum);
+        assert_eq!(stats.estimated_remaining, 0);
+        assert_eq!(stats.completion_bps, 10_000);
+
+        let utilization = analyze_compute_utilization();
+        assert_eq!(utilization.estimated_usage, sum);
+        assert_eq!(utilization.overhead_buffer, SOLANA_MAX_COMPUTE_UNITS - sum);
+        assert_eq!(SIGNATURE_SCHEME_COMPARISON[0].verification_compute_units, sum);
+
+        let (public_key, signature) = super::super::verify::tests::signed_fixture(b"estimate", &[7; 40]);
+        let verification = verify_falcon_optimized(&public_key, &signature, b"estimate", &OptimizationConfig::default());
+        assert_eq!(verification.result, Ok(()));
+        assert_eq!(verification.metrics.estimated_compute_units, sum);
+    }
+
+    fn configs() -> impl Iterator<Item = OptimizationConfig> {
+        (0..4).map(|bits| OptimizationConfig {
+            early_termination: bits & 1 != 0,
+            use_lookup_tables: bits & 2 != 0,
+            ..OptimizationConfig::default()
+        })
+    }
+
+    // every config runs the real verifier, so they all agree on a valid and a forged signature
+    #[test]
+    fn test_optimized_verification_is_real() {
+        let (public_key, signature) = super::super::verify::tests::signed_fixture(b"optimized", &[3; 40]);
+        for config in configs() {
+            assert_eq!(verify_falcon_optimized(&public_key, &signature, b"optimized", &config).result, Ok(()));
+            assert_eq!(
+                verify_falcon_optimized(&public_key, &signature, b"forged", &config).result,
+                Err(VaultError::NormBoundExceeded.into()),
+                "{config:?}"
+            );
         }
     }
+
+    // early_termination changes which check rejects an input first
+    #[test]
+    fn test_early_termination_changes_first_error() {
+        use super::super::verify::tests::{compress_coefficients, oversized_s2, FIXTURE_NONCE};
+        let early = OptimizationConfig::default();
+        let full = OptimizationConfig { early_termination: false, ..OptimizationConfig::default() };
+
+        // s2 alone is over the bound and a stray bit follows it: the partial norm exit
+        // fires during decompression, before the padding is ever looked at
+        let mut signature = [0u8; FALCON_512_SIGNATURE_SIZE];
+        signature[0] = FALCON_512_SIGNATURE_HEADER;
+        signature[1..41].copy_from_slice(&FIXTURE_NONCE);
+        signature[41..].copy_from_slice(&compress_coefficients(&oversized_s2()));
+        signature[FALCON_512_SIGNATURE_SIZE - 1] |= 1;
+        let mut public_key = [0; FALCON_512_PUBLIC_KEY_SIZE];
+        public_key[0] = FALCON_512_LOGN as u8;
+        assert_eq!(
+            verify_falcon_optimized(&public_key, &signature, b"m", &early).result,
+            Err(VaultError::NormBoundExceeded.into())
+        );
+        assert_eq!(
+            verify_falcon_optimized(&public_key, &signature, b"m", &full).result,
+            Err(VaultError::TrailingSignatureBits.into())
+        );
+
+        // a wrong encoding in the signature header next to a Falcon-1024 key header:
+        // the pre-checks name the signature, the full verifier sees mismatched degrees
+        signature[0] = (1 << 5) | (1 << 4) | FALCON_512_LOGN as u8;
+        public_key[0] = 10;
+        assert_eq!(
+            verify_falcon_optimized(&public_key, &signature, b"m", &early).result,
+            Err(VaultError::BadSignatureHeader.into())
+        );
+        assert_eq!(
+            verify_falcon_optimized(&public_key, &signature, b"m", &full).result,
+            Err(VaultError::LogNMismatch.into())
+        );
+    }
 }
 
 // performance monitoring and compute unit estimation for Falcon-512 verification
@@ -355,17 +392,83 @@ pub const FALCON_512_PERFORMANCE_PROFILE: &[OperationMetrics] = &[
     },
 ];
 
-// total estimated compute units for complete Falcon-512 verification
-pub const TOTAL_ESTIMATED_COMPUTE_UNITS: u64 = 152_200;
+// total estimated compute units for complete Falcon-512 verification, the sum of the profile
+pub const TOTAL_ESTIMATED_COMPUTE_UNITS: u64 = estimated_total();
+
+// estimated compute units of a whole OpenVault: unpacking the key to check it,
+// the two SHA256 syscalls and the CreateAccount CPI
+pub const ESTIMATED_OPEN_VAULT_COMPUTE_UNITS: u64 = 40_000;
+
+// the sum of FALCON_512_PERFORMANCE_PROFILE, everything else that reports a total uses this
+pub const fn estimated_total() -> u64 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < FALCON_512_PERFORMANCE_PROFILE.len() {
+        total += FALCON_512_PERFORMANCE_PROFILE[i].estimated_compute_units;
+        i += 1;
+    }
+    total
+}
+
+// size of one SBF stack frame
+pub const SOLANA_STACK_FRAME_SIZE: usize = 4096;
+
+// the locals verify_parts_profiled keeps beside its VerifyScratch, measured on the built
+// program: LLVM's prologue/epilogue analysis remarks (-pass-remarks-analysis=prologepilog)
+// give the stack bytes of every SBF function, and verify_parts_profiled's is 3176 in
+// the default build and 3208 with bench and profiling, the largest. re-measure when
+// that function or its callees' inlining changes
+pub const VERIFY_FRAME_LOCALS_BYTES: usize = 136;
+
+// the frame verification holds its scratch in. verify_parts_profiled is never inlined,
+// so that frame is the VerifyScratch buffers plus the measured locals; decoding, the
+// NTTs and the SHAKE256 state run in frames of their own below it (verify_split,
+// 1016 bytes measured the same way)
+pub const VERIFY_STACK_BYTES: usize = core::mem::size_of::<VerifyScratch>() + VERIFY_FRAME_LOCALS_BYTES;
+
+// a buffer that pushes verification past one SBF frame is a compile error, not a
+// stack overflow on mainnet
+const _: () = assert!(VERIFY_STACK_BYTES <= SOLANA_STACK_FRAME_SIZE);
+
+// stack memory mark during verification, see VERIFY_STACK_BYTES
+pub const ESTIMATED_STACK_USAGE: u64 = VERIFY_STACK_BYTES as u64;
+
+// VERIFY_STACK_BYTES broken down, for reports and profiling output
+#[cfg(any(test, feature = "std-report", feature = "profiling"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackBudget {
+    pub verify_scratch: usize,
+    pub frame_locals: usize,
+    pub frame: usize,
+}
+
+#[cfg(any(test, feature = "std-report", feature = "profiling"))]
+impl StackBudget {
+    pub fn total(&self) -> usize {
+        self.verify_scratch + self.frame_locals
+    }
+
+    // bytes of the frame left before it overflows
+    pub fn headroom(&self) -> usize {
+        self.frame - self.total()
+    }
+}
 
-// stack memory mark during verification
-pub const ESTIMATED_STACK_USAGE: u64 = 8_192;
+#[cfg(any(test, feature = "std-report", feature = "profiling"))]
+pub fn stack_budget() -> StackBudget {
+    StackBudget {
+        verify_scratch: core::mem::size_of::<VerifyScratch>(),
+        frame_locals: VERIFY_FRAME_LOCALS_BYTES,
+        frame: SOLANA_STACK_FRAME_SIZE,
+    }
+}
 
 // Solana compute unit limits
 pub const SOLANA_MAX_COMPUTE_UNITS: u64 = 200_000;
 pub const SOLANA_DEFAULT_COMPUTE_UNITS: u64 = 200_000;
 
 // performance optimization
+#[cfg(any(test, feature = "std-report"))]
 pub struct OptimizationRecommendations {
     pub use_precomputed_tables: bool,
     pub enable_ntt_cache: bool,
@@ -373,6 +476,7 @@ pub struct OptimizationRecommendations {
     pub optimize_stack_layout: bool,
 }
 
+#[cfg(any(test, feature = "std-report"))]
 impl Default for OptimizationRecommendations {
     fn default() -> Self {
         Self {
@@ -385,31 +489,36 @@ impl Default for OptimizationRecommendations {
 }
 
 // compute utilization analysis
+#[cfg(any(test, feature = "std-report"))]
 pub fn analyze_compute_utilization() -> ComputeUtilization {
     let total_estimated = TOTAL_ESTIMATED_COMPUTE_UNITS;
-    let utilization_percentage = (total_estimated as f64 / SOLANA_MAX_COMPUTE_UNITS as f64) * 100.0;
+    // basis points (1/100 of a percent) keep this integer only
+    let utilization_bps = total_estimated * 10_000 / SOLANA_MAX_COMPUTE_UNITS;
     let overhead_buffer = SOLANA_MAX_COMPUTE_UNITS - total_estimated;
     
     ComputeUtilization {
         estimated_usage: total_estimated,
         max_available: SOLANA_MAX_COMPUTE_UNITS,
-        utilization_percentage,
+        utilization_bps,
         overhead_buffer,
         within_limits: total_estimated <= SOLANA_MAX_COMPUTE_UNITS,
         critical_operations: count_critical_operations(),
     }
 }
 
-#[derive(Debug, Clone)]
+#[cfg(any(test, feature = "std-report"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ComputeUtilization {
     pub estimated_usage: u64,
     pub max_available: u64,
-    pub utilization_percentage: f64,
+    pub utilization_bps: u64,
     pub overhead_buffer: u64,
     pub within_limits: bool,
     pub critical_operations: usize,
 }
 
+#[cfg(any(test, feature = "std-report"))]
 fn count_critical_operations() -> usize {
     FALCON_512_PERFORMANCE_PROFILE
         .iter()
@@ -418,18 +527,37 @@ fn count_critical_operations() -> usize {
 }
 
 // performance monitoring during verification
+// record_operation charges the profile's estimate for a named operation, record_stage
+// a measured cost, see falcon::profile; the profiling feature builds it into the program
+#[cfg(any(test, feature = "std-report", feature = "profiling"))]
 pub struct PerformanceMonitor {
     operations_completed: usize,
     compute_units_used: u64,
     peak_stack_usage: u64,
+    // measured cost per stage, in the order first recorded
+    stages: [(&'static str, u64); MAX_MONITORED_STAGES],
+    stage_count: usize,
 }
 
+// distinct stages a PerformanceMonitor keeps, more than verification has
+pub const MAX_MONITORED_STAGES: usize = 16;
+
+#[cfg(any(test, feature = "std-report", feature = "profiling"))]
+impl Default for PerformanceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "std-report", feature = "profiling"))]
 impl PerformanceMonitor {
     pub fn new() -> Self {
         Self {
             operations_completed: 0,
             compute_units_used: 0,
             peak_stack_usage: 0,
+            stages: [("", 0); MAX_MONITORED_STAGES],
+            stage_count: 0,
         }
     }
     
@@ -443,11 +571,30 @@ impl PerformanceMonitor {
             self.peak_stack_usage = self.peak_stack_usage.max(metrics.stack_usage_bytes);
         }
     }
+
+    // record a measured cost for a stage, added to the stage's earlier cost if it
+    // was seen before; past MAX_MONITORED_STAGES new stages only count toward the total
+    pub fn record_stage(&mut self, stage: &'static str, cost: u64) {
+        self.compute_units_used += cost;
+        let recorded = &mut self.stages[..self.stage_count];
+        if let Some((_, total)) = recorded.iter_mut().find(|(name, _)| *name == stage) {
+            *total += cost;
+        } else if self.stage_count < MAX_MONITORED_STAGES {
+            self.stages[self.stage_count] = (stage, cost);
+            self.stage_count += 1;
+            self.operations_completed += 1;
+        }
+    }
+
+    // the measured stages with their costs, in the order first recorded
+    pub fn stages(&self) -> &[(&'static str, u64)] {
+        &self.stages[..self.stage_count]
+    }
     
     // check if we're approaching compute unit limits... if we are, return an error
-    pub fn check_compute_limits(&self) -> Result<(), ProgramError> {
+    pub fn check_compute_limits(&self) -> Result<(), FalconError> {
         if self.compute_units_used >= SOLANA_MAX_COMPUTE_UNITS {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(FalconError::InvalidAccountData);
         }
         Ok(())
     }
@@ -459,27 +606,30 @@ impl PerformanceMonitor {
             compute_units_used: self.compute_units_used,
             estimated_remaining: TOTAL_ESTIMATED_COMPUTE_UNITS.saturating_sub(self.compute_units_used),
             peak_stack_usage: self.peak_stack_usage,
-            completion_percentage: (self.operations_completed as f64 / FALCON_512_PERFORMANCE_PROFILE.len() as f64) * 100.0,
+            completion_bps: (self.operations_completed * 10_000 / FALCON_512_PERFORMANCE_PROFILE.len()) as u64,
         }
     }
 }
 
+#[cfg(any(test, feature = "std-report", feature = "profiling"))]
 #[derive(Debug, Clone)]
 pub struct PerformanceStats {
     pub operations_completed: usize,
     pub compute_units_used: u64,
     pub estimated_remaining: u64,
     pub peak_stack_usage: u64,
-    pub completion_percentage: f64,
+    pub completion_bps: u64,
 }
 
 // optimization strategies for different deployment scenarios
+#[cfg(any(test, feature = "std-report"))]
 pub enum DeploymentProfile {
     Development,    
     Testing,        
     Production,     
 }
 
+#[cfg(any(test, feature = "std-report"))]
 impl DeploymentProfile {
     pub fn get_optimization_flags(&self) -> OptimizationFlags {
         match self {
@@ -505,6 +655,7 @@ impl DeploymentProfile {
     }
 }
 
+#[cfg(any(test, feature = "std-report"))]
 #[derive(Debug, Clone)]
 pub struct OptimizationFlags {
     pub enable_bounds_checking: bool,
@@ -514,6 +665,7 @@ pub struct OptimizationFlags {
 }
 
 // results for different hardware configurations
+#[cfg(any(test, feature = "std-report"))]
 pub const HARDWARE_BENCHMARKS: &[HardwareBenchmark] = &[
     HardwareBenchmark {
         platform: "Solana Validator (Intel Xeon)",
@@ -535,6 +687,7 @@ pub const HARDWARE_BENCHMARKS: &[HardwareBenchmark] = &[
     },
 ];
 
+#[cfg(any(test, feature = "std-report"))]
 #[derive(Debug, Clone)]
 pub struct HardwareBenchmark {
     pub platform: &'static str,
@@ -544,13 +697,14 @@ pub struct HardwareBenchmark {
 }
 
 // comparison with other signature schemes
+#[cfg(any(test, feature = "std-report"))]
 pub const SIGNATURE_SCHEME_COMPARISON: &[SchemeComparison] = &[
     SchemeComparison {
         scheme: "Falcon-512",
         security_bits: 103,
         public_key_size: 897,
         signature_size: 666,
-        verification_compute_units: 152_200,
+        verification_compute_units: TOTAL_ESTIMATED_COMPUTE_UNITS,
         quantum_resistant: true,
     },
     SchemeComparison {
@@ -579,6 +733,7 @@ pub const SIGNATURE_SCHEME_COMPARISON: &[SchemeComparison] = &[
     },
 ];
 
+#[cfg(any(test, feature = "std-report"))]
 #[derive(Debug, Clone)]
 pub struct SchemeComparison {
     pub scheme: &'static str,
@@ -589,6 +744,8 @@ pub struct SchemeComparison {
     pub quantum_resistant: bool,
 }
 
+// formatted summary of analyze_compute_utilization for host tooling
+#[cfg(any(test, feature = "std-report"))]
 pub fn generate_performance_report() -> String {
     let utilization = analyze_compute_utilization();
     
@@ -599,7 +756,7 @@ pub fn generate_performance_report() -> String {
          Compute Unit Analysis:\n\
          - Estimated Usage: {} CU\n\
          - Solana Limit: {} CU\n\
-         - Utilization: {:.1}%\n\
+         - Utilization: {}.{:02}%\n\
          - Overhead Buffer: {} CU\n\
          - Within Limits: {}\n\
          \n\
@@ -613,7 +770,8 @@ pub fn generate_performance_report() -> String {
          - Consider instruction splitting for very large transactions\n",
         utilization.estimated_usage,
         utilization.max_available,
-        utilization.utilization_percentage,
+        utilization.utilization_bps / 100,
+        utilization.utilization_bps % 100,
         utilization.overhead_buffer,
         utilization.within_limits,
         utilization.critical_operations,
@@ -621,15 +779,16 @@ pub fn generate_performance_report() -> String {
     )
 }
 
+// utilization, monitor and report text
 #[cfg(test)]
-mod tests {
+mod report_tests {
     use super::*;
 
     #[test]
     fn test_compute_utilization_within_limits() {
         let utilization = analyze_compute_utilization();
         assert!(utilization.within_limits, "Compute usage exceeds Solana limits");
-        assert!(utilization.utilization_percentage < 80.0, "Utilization too high for safe operation");
+        assert!(utilization.utilization_bps < 8_000, "Utilization too high for safe operation");
     }
 
     #[test]
@@ -643,6 +802,14 @@ mod tests {
         let stats = monitor.get_stats();
         assert_eq!(stats.operations_completed, 2);
         assert!(stats.compute_units_used > 0);
+        assert_eq!(stats.completion_bps, 2 * 10_000 / FALCON_512_PERFORMANCE_PROFILE.len() as u64);
+    }
+
+    #[test]
+    fn test_performance_report_mentions_utilization() {
+        let utilization = analyze_compute_utilization();
+        assert_eq!(utilization.utilization_bps, 7_610);
+        assert!(generate_performance_report().contains("Utilization: 76.10%"));
     }
 
     #[test]
@@ -0,0 +1,606 @@
+// polynomials over Z_q[X]/(X^n + 1), the arithmetic verification is built from
+// exposed for experimentation and for callers that keep polynomials in account data;
+// verify.rs uses the same type, so anything built here behaves exactly as it does there
+
+use super::FalconError;
+use super::ntt::{ct_reduce_once, fast_mod_q, sum_x4, zip_x4, Q};
+use super::verify::FALCON_512_N;
+
+// leading byte of an NTT-domain polynomial kept in account data, bumped if the encoding
+// changes so an old account is rejected instead of misread
+pub const NTT_ACCOUNT_TAG: u8 = 1;
+
+// [tag(1), coefficients as little-endian u16 (1024)]
+pub const NTT_ACCOUNT_BYTES: usize = 1 + 2 * FALCON_512_N;
+
+// encode reduced NTT-domain coefficients, e.g. a precomputed h_ntt, for account storage
+pub fn to_account_bytes(coeffs: &[u32; FALCON_512_N]) -> [u8; NTT_ACCOUNT_BYTES] {
+    let mut bytes = [0u8; NTT_ACCOUNT_BYTES];
+    bytes[0] = NTT_ACCOUNT_TAG;
+    for (chunk, &coeff) in bytes[1..].chunks_exact_mut(2).zip(coeffs.iter()) {
+        debug_assert!(coeff < Q);
+        chunk.copy_from_slice(&(coeff as u16).to_le_bytes());
+    }
+    bytes
+}
+
+// decode what to_account_bytes wrote; account data is never trusted, so the tag,
+// the length and every coefficient being below q are all checked
+pub fn from_account_bytes(bytes: &[u8]) -> Result<[u32; FALCON_512_N], FalconError> {
+    Ok(*polynomial_from_account_bytes(bytes)?.coefficients())
+}
+
+pub(crate) fn polynomial_from_account_bytes(bytes: &[u8]) -> Result<Polynomial, FalconError> {
+    match bytes.split_first() {
+        Some((&NTT_ACCOUNT_TAG, coeff_bytes)) => Polynomial::from_le_bytes(coeff_bytes),
+        _ => Err(FalconError::InvalidAccountData),
+    }
+}
+
+// map a reduced coefficient in [0, q) to its balanced form in (-q/2, q/2], without branching
+#[inline]
+fn balanced_value(x: u32) -> i16 {
+    let v = x as i32;
+    // all ones when v > q/2, zero otherwise
+    let mask = ((Q as i32) / 2 - v) >> 31;
+    (v - (Q as i32 & mask)) as i16
+}
+
+// polynomial in the ring Z_q[X]/(X^n + 1), coefficients reduced to [0, q)
+// kept in the u32 layout the NTT routines use so every operation works in place;
+// verification holds at most three of these (2 KB each at n = 512) live at once.
+// not Clone on purpose, a copy should be an explicit new buffer
+pub struct Polynomial<const N: usize = FALCON_512_N> {
+    coeffs: [u32; N],
+}
+
+impl<const N: usize> Polynomial<N> {
+    // bytes taken by to_le_bytes, two per coefficient
+    pub const BYTES: usize = 2 * N;
+
+    fn from_coeffs(coeffs: [u32; N]) -> Self {
+        #[cfg(test)]
+        tests::buffer_created();
+        Self { coeffs }
+    }
+
+    pub fn zero() -> Self {
+        Self::from_coeffs([0; N])
+    }
+
+    pub fn from_signed_coeffs(signed_coeffs: &[i16; N]) -> Self {
+        let mut poly = Self::zero();
+        zip_x4(&mut poly.coeffs, signed_coeffs, |coeff, &value| *coeff = (value as i32).rem_euclid(Q as i32) as u32);
+        poly
+    }
+
+    // build from raw u16 values, reducing each mod q
+    pub fn from_u16_array(values: &[u16; N]) -> Self {
+        let mut poly = Self::zero();
+        zip_x4(&mut poly.coeffs, values, |coeff, &value| *coeff = fast_mod_q(value as u32));
+        poly
+    }
+
+    // coefficients as u16 values in [0, q)
+    pub fn as_u16_array(&self) -> [u16; N] {
+        let mut values = [0u16; N];
+        zip_x4(&mut values, &self.coeffs, |value, &coeff| *value = coeff as u16);
+        values
+    }
+
+    // coefficients in balanced form, in (-q/2, q/2]
+    pub fn balanced(&self) -> [i16; N] {
+        let mut values = [0i16; N];
+        zip_x4(&mut values, &self.coeffs, |value, &coeff| *value = balanced_value(coeff));
+        values
+    }
+
+    // squared euclidean norm of the balanced coefficients
+    pub fn squared_norm(&self) -> u64 {
+        sum_x4(&self.coeffs, |&coeff| {
+            let value = balanced_value(coeff) as i64;
+            (value * value) as u64
+        })
+    }
+
+    pub fn coefficients(&self) -> &[u32; N] {
+        &self.coeffs
+    }
+
+    // the coefficients in order, each in [0, q)
+    pub fn iter(&self) -> core::slice::Iter<'_, u32> {
+        self.coeffs.iter()
+    }
+
+    // like coefficients_mut, the caller keeps every coefficient in [0, q)
+    pub(crate) fn iter_mut(&mut self) -> core::slice::IterMut<'_, u32> {
+        self.coeffs.iter_mut()
+    }
+
+    // self[i] = f(self[i], other[i]) for every i, four at a time through zip_x4;
+    // f takes reduced values and has to return one
+    pub(crate) fn zip_assign(&mut self, other: &Self, mut f: impl FnMut(u32, u32) -> u32) {
+        zip_x4(&mut self.coeffs, &other.coeffs, |a, &b| *a = f(*a, b));
+    }
+
+    // for builders that produce coefficients already reduced to [0, q), like hash_to_point
+    pub(crate) fn coefficients_mut(&mut self) -> &mut [u32; N] {
+        &mut self.coeffs
+    }
+
+    // little-endian u16 per coefficient, for keeping a polynomial in account data
+    // `out` must be exactly BYTES long
+    pub fn to_le_bytes(&self, out: &mut [u8]) -> Result<(), FalconError> {
+        if out.len() != Self::BYTES {
+            return Err(FalconError::InvalidAccountData);
+        }
+        for (chunk, &coeff) in out.chunks_exact_mut(2).zip(self.coeffs.iter()) {
+            chunk.copy_from_slice(&(coeff as u16).to_le_bytes());
+        }
+        Ok(())
+    }
+
+    // inverse of to_le_bytes; a wrong length or a coefficient outside [0, q) is rejected,
+    // so stored data that was tampered with never turns into an unreduced polynomial
+    pub fn from_le_bytes(bytes: &[u8]) -> Result<Self, FalconError> {
+        if bytes.len() != Self::BYTES {
+            return Err(FalconError::InvalidAccountData);
+        }
+        let mut poly = Self::zero();
+        for (coeff, chunk) in poly.coeffs.iter_mut().zip(bytes.chunks_exact(2)) {
+            let value = u16::from_le_bytes([chunk[0], chunk[1]]) as u32;
+            if value >= Q {
+                return Err(FalconError::InvalidAccountData);
+            }
+            *coeff = value;
+        }
+        Ok(poly)
+    }
+
+    // forward NTT, in place
+    pub fn ntt_in_place(&mut self) {
+        super::ntt::ntt_forward(&mut self.coeffs);
+    }
+
+    // inverse NTT, in place
+    pub fn intt_in_place(&mut self) {
+        super::ntt::ntt_inverse(&mut self.coeffs);
+    }
+
+    // the transforms above, with twiddles from the compile-time tables
+    // instead of computing them on every call
+    pub fn ntt_in_place_precomputed(&mut self) {
+        super::ntt::ntt_forward_precomputed(&mut self.coeffs);
+    }
+
+    pub fn intt_in_place_precomputed(&mut self) {
+        super::ntt::ntt_inverse_precomputed(&mut self.coeffs);
+    }
+
+    // pointwise multiplication in NTT domain, in place
+    pub fn mul_assign_pointwise(&mut self, other: &Self) {
+        self.zip_assign(other, |a, b| fast_mod_q(a * b));
+    }
+
+    // self = self + other, in place
+    pub fn add_assign(&mut self, other: &Self) {
+        self.zip_assign(other, |a, b| ct_reduce_once(a + b));
+    }
+
+    // self = self - other, in place
+    pub fn sub_assign(&mut self, other: &Self) {
+        self.zip_assign(other, |a, b| ct_reduce_once(a + Q - b));
+    }
+
+    // self = other - self, in place
+    pub fn rsub_assign(&mut self, other: &Self) {
+        self.zip_assign(other, |a, b| ct_reduce_once(b + Q - a));
+    }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a Polynomial<N> {
+    type Item = &'a u32;
+    type IntoIter = core::slice::Iter<'a, u32>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// by-value forms of the in-place ops, reusing the left operand's buffer
+impl<const N: usize> core::ops::Add<&Polynomial<N>> for Polynomial<N> {
+    type Output = Self;
+
+    fn add(mut self, other: &Self) -> Self {
+        self.add_assign(other);
+        self
+    }
+}
+
+impl<const N: usize> core::ops::Sub<&Polynomial<N>> for Polynomial<N> {
+    type Output = Self;
+
+    fn sub(mut self, other: &Self) -> Self {
+        self.sub_assign(other);
+        self
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> Drop for Polynomial<N> {
+    fn drop(&mut self) {
+        tests::buffer_dropped();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use crate::falcon::ntt::from_ntt_form;
+
+    // host-side count of live Polynomial buffers, fed by Polynomial::from_coeffs and Drop
+    thread_local! {
+        static LIVE_BUFFERS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+        static PEAK_BUFFERS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+    }
+
+    pub(crate) fn buffer_created() {
+        let live = LIVE_BUFFERS.get() + 1;
+        LIVE_BUFFERS.set(live);
+        PEAK_BUFFERS.set(PEAK_BUFFERS.get().max(live));
+    }
+
+    pub(crate) fn buffer_dropped() {
+        LIVE_BUFFERS.set(LIVE_BUFFERS.get() - 1);
+    }
+
+    pub(crate) fn live_buffers() -> usize {
+        LIVE_BUFFERS.get()
+    }
+
+    // peak bytes of polynomial buffers live at once while running f
+    pub(crate) fn peak_buffer_bytes(f: impl FnOnce()) -> usize {
+        PEAK_BUFFERS.set(LIVE_BUFFERS.get());
+        let baseline = LIVE_BUFFERS.get();
+        f();
+        (PEAK_BUFFERS.get() - baseline) * core::mem::size_of::<Polynomial>()
+    }
+
+    fn random_poly(rng: &mut impl Rng) -> Polynomial {
+        let mut values = [0u16; FALCON_512_N];
+        for value in values.iter_mut() {
+            *value = rng.random_range(0..Q as u16);
+        }
+        Polynomial::from_u16_array(&values)
+    }
+
+    fn copy_of(poly: &Polynomial) -> Polynomial {
+        Polynomial::from_u16_array(&poly.as_u16_array())
+    }
+
+    #[test]
+    fn test_polynomial_conversion_roundtrip() {
+        let mut values = [0u16; FALCON_512_N];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = ((i * 24593) % Q as usize) as u16;
+        }
+
+        let poly = Polynomial::from_u16_array(&values);
+        assert_eq!(poly.as_u16_array(), values);
+
+        let mut signed = [0i16; FALCON_512_N];
+        for (i, value) in signed.iter_mut().enumerate() {
+            *value = (i as i16 % 41) * 100 - 2000;
+        }
+        let poly = Polynomial::from_signed_coeffs(&signed);
+        assert_eq!(poly.balanced(), signed);
+        assert_eq!(poly.squared_norm(), signed.iter().map(|&v| (v as i64 * v as i64) as u64).sum::<u64>());
+
+        // out of range inputs are reduced
+        let mut wide = values;
+        wide[0] += Q as u16;
+        assert_eq!(Polynomial::from_u16_array(&wide).as_u16_array(), values);
+    }
+
+    // first 8 bytes of SHAKE256 over each operation's output coefficients (u16, little
+    // endian) for the inputs of known_answer_inputs, taken from the index-loop versions of
+    // the arithmetic and NTT before they moved to iterators; any change in results fails here
+    const KNOWN_ANSWERS: [(&str, [u8; 8]); 8] = [
+        ("add", [82, 75, 62, 203, 133, 135, 162, 173]),
+        ("sub", [195, 125, 221, 251, 72, 22, 173, 91]),
+        ("rsub", [55, 34, 100, 91, 101, 179, 97, 10]),
+        ("mul_pointwise", [228, 71, 167, 236, 238, 222, 32, 123]),
+        ("ntt", [153, 7, 120, 20, 150, 249, 145, 109]),
+        ("intt", [90, 92, 236, 89, 100, 226, 145, 1]),
+        ("ntt_precomputed", [153, 7, 120, 20, 150, 249, 145, 109]),
+        ("intt_precomputed", [90, 92, 236, 89, 100, 226, 145, 1]),
+    ];
+
+    // two fixed polynomials spread over [0, q), edge values included
+    fn known_answer_inputs() -> (Polynomial, Polynomial) {
+        let (mut a, mut b) = ([0u16; FALCON_512_N], [0u16; FALCON_512_N]);
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for (x, y) in a.iter_mut().zip(b.iter_mut()) {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            *x = ((state >> 33) % Q as u64) as u16;
+            *y = ((state >> 17) % Q as u64) as u16;
+        }
+        (a[0], b[0], a[1], b[1]) = (0, Q as u16 - 1, Q as u16 - 1, Q as u16 - 1);
+        (Polynomial::from_u16_array(&a), Polynomial::from_u16_array(&b))
+    }
+
+    fn output_digest(poly: &Polynomial) -> [u8; 8] {
+        let mut bytes = [0u8; Polynomial::<FALCON_512_N>::BYTES];
+        poly.to_le_bytes(&mut bytes).unwrap();
+        crate::falcon::Shake256::one_shot::<8>(&bytes)
+    }
+
+    #[test]
+    fn test_arithmetic_known_answers() {
+        let (a, b) = known_answer_inputs();
+        let run = |op: &str| {
+            let mut out = copy_of(&a);
+            match op {
+                "add" => out.add_assign(&b),
+                "sub" => out.sub_assign(&b),
+                "rsub" => out.rsub_assign(&b),
+                "mul_pointwise" => out.mul_assign_pointwise(&b),
+                "ntt" => out.ntt_in_place(),
+                "intt" => out.intt_in_place(),
+                "ntt_precomputed" => out.ntt_in_place_precomputed(),
+                "intt_precomputed" => out.intt_in_place_precomputed(),
+                _ => unreachable!(),
+            }
+            output_digest(&out)
+        };
+        for (op, expected) in KNOWN_ANSWERS {
+            assert_eq!(run(op), expected, "{}", op);
+        }
+        assert_eq!(a.squared_norm(), 6_450_986_689);
+    }
+
+    #[test]
+    fn test_polynomial_in_place_ops() {
+        let pairs = [(0, 0), (0, 1), (1, Q - 1), (Q - 1, Q - 1), (6144, 6145), (1234, 11000)];
+
+        let mut a = [0u16; FALCON_512_N];
+        let mut b = [0u16; FALCON_512_N];
+        for (i, &(x, y)) in pairs.iter().enumerate() {
+            a[i] = x as u16;
+            b[i] = y as u16;
+        }
+
+        let mut product = Polynomial::from_u16_array(&a);
+        product.mul_assign_pointwise(&Polynomial::from_u16_array(&b));
+        let mut difference = Polynomial::from_u16_array(&a);
+        difference.rsub_assign(&Polynomial::from_u16_array(&b));
+
+        for (i, &(x, y)) in pairs.iter().enumerate() {
+            assert_eq!(product.coeffs[i], x * y % Q);
+            assert_eq!(difference.coeffs[i], (y + Q - x) % Q);
+        }
+
+        assert_eq!(balanced_value(6144), 6144);
+        assert_eq!(balanced_value(6145), -6144);
+        assert_eq!(balanced_value(Q - 1), -1);
+        assert_eq!(balanced_value(0), 0);
+    }
+
+    // ntt::from_ntt_form and balanced_value are the two ways back to balanced form,
+    // a norm computed through one must match the other, so they have to agree on every
+    // value in [0, q) and on the tie: q/2 = 6144 stays positive, 6145 is the first negative
+    #[test]
+    fn test_balanced_forms_agree() {
+        let values: Vec<u32> = (0..Q).collect();
+        for chunk in values.chunks(FALCON_512_N) {
+            let mut unsigned = [0u32; FALCON_512_N];
+            unsigned[..chunk.len()].copy_from_slice(chunk);
+            let signed = from_ntt_form(&unsigned);
+            for (&value, &from_ntt) in unsigned.iter().zip(signed.iter()) {
+                assert_eq!(from_ntt, balanced_value(value), "value {value}");
+            }
+        }
+
+        assert_eq!(Q / 2, 6144);
+        assert_eq!(balanced_value(Q / 2), (Q / 2) as i16);
+        assert_eq!(balanced_value(Q / 2 + 1), -((Q / 2) as i16));
+    }
+
+    // rsub_assign computes b + q - a before reducing; with both inputs below q that
+    // stays in [1, 2q - 1], so it never wraps. pin it against the signed difference
+    // over the edges of [0, q) and a stride through the middle, in both orders
+    #[test]
+    fn test_rsub_assign_never_underflows() {
+        let mut values = vec![0, 1, 2, Q / 2 - 1, Q / 2, Q / 2 + 1, Q - 2, Q - 1];
+        values.extend((0..Q).step_by(97));
+
+        let pairs: Vec<(u32, u32)> = values.iter().flat_map(|&x| values.iter().map(move |&y| (x, y))).collect();
+        for chunk in pairs.chunks(FALCON_512_N) {
+            let mut a = [0u16; FALCON_512_N];
+            let mut b = [0u16; FALCON_512_N];
+            for (i, &(x, y)) in chunk.iter().enumerate() {
+                a[i] = x as u16;
+                b[i] = y as u16;
+            }
+
+            let mut difference = Polynomial::from_u16_array(&a);
+            difference.rsub_assign(&Polynomial::from_u16_array(&b));
+            for (i, &(x, y)) in chunk.iter().enumerate() {
+                let expected = (y as i32 - x as i32).rem_euclid(Q as i32) as u32;
+                assert_eq!(difference.coeffs[i], expected, "{} - {}", y, x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_associative_and_sub_inverse() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x9017);
+        for _ in 0..16 {
+            let (a, b, c) = (random_poly(&mut rng), random_poly(&mut rng), random_poly(&mut rng));
+
+            let left = (copy_of(&a) + &b) + &c;
+            let mut b_plus_c = copy_of(&b);
+            b_plus_c.add_assign(&c);
+            let right = copy_of(&a) + &b_plus_c;
+            assert_eq!(left.as_u16_array(), right.as_u16_array());
+
+            // (a + b) - b == a, and rsub is sub with the operands swapped
+            assert_eq!((copy_of(&a) + &b - &b).as_u16_array(), a.as_u16_array());
+            let mut reversed = copy_of(&b);
+            reversed.rsub_assign(&a);
+            assert_eq!(reversed.as_u16_array(), (copy_of(&a) - &b).as_u16_array());
+        }
+    }
+
+    // subtraction is linear, so it gives the same polynomial in either domain; verification
+    // subtracts c in the time domain (rsub_assign, or per coefficient while c is sampled),
+    // which saves transforming c. pin both forms against subtracting the NTTs, and the
+    // constant-time reduction against fast_mod_q
+    #[test]
+    fn test_subtraction_agrees_across_domains() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x5B);
+        for _ in 0..8 {
+            let (a, b) = (random_poly(&mut rng), random_poly(&mut rng));
+
+            let mut a_ntt = copy_of(&a);
+            a_ntt.ntt_in_place();
+            let mut b_ntt = copy_of(&b);
+            b_ntt.ntt_in_place();
+            let mut through_ntt = a_ntt - &b_ntt;
+            through_ntt.intt_in_place();
+
+            let time_domain = copy_of(&a) - &b;
+            assert_eq!(time_domain.as_u16_array(), through_ntt.as_u16_array());
+
+            let mut reversed = copy_of(&b);
+            reversed.rsub_assign(&a);
+            assert_eq!(reversed.as_u16_array(), time_domain.as_u16_array());
+
+            // verify_split's streamed form, c - s1 one coefficient at a time
+            for ((&x, &y), &diff) in a.coeffs.iter().zip(b.coeffs.iter()).zip(time_domain.coeffs.iter()) {
+                assert_eq!(ct_reduce_once(x + Q - y), diff);
+                assert_eq!(fast_mod_q(x + Q - y), diff);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mul_by_one_identity() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x0001);
+        let mut one = [0u16; FALCON_512_N];
+        one[0] = 1;
+        let mut one = Polynomial::from_u16_array(&one);
+        one.ntt_in_place();
+
+        for _ in 0..8 {
+            let a = random_poly(&mut rng);
+            let mut product = copy_of(&a);
+            product.ntt_in_place();
+            product.mul_assign_pointwise(&one);
+            product.intt_in_place();
+            assert_eq!(product.as_u16_array(), a.as_u16_array());
+        }
+    }
+
+    #[test]
+    fn test_ntt_linearity() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x1177);
+        for _ in 0..8 {
+            let (a, b) = (random_poly(&mut rng), random_poly(&mut rng));
+
+            // NTT(a + b) == NTT(a) + NTT(b)
+            let mut sum_then_ntt = copy_of(&a) + &b;
+            sum_then_ntt.ntt_in_place();
+            let mut a_ntt = copy_of(&a);
+            a_ntt.ntt_in_place();
+            let mut b_ntt = copy_of(&b);
+            b_ntt.ntt_in_place();
+            assert_eq!(sum_then_ntt.as_u16_array(), (a_ntt + &b_ntt).as_u16_array());
+
+            // NTT(k * a) == k * NTT(a) for a constant k
+            let mut k = [0u16; FALCON_512_N];
+            k[0] = rng.random_range(0..Q as u16);
+            let mut k = Polynomial::from_u16_array(&k);
+            k.ntt_in_place();
+            let mut scaled = copy_of(&a);
+            for coeff in scaled.coeffs.iter_mut() {
+                *coeff = fast_mod_q(*coeff * k.coeffs[0]);
+            }
+            scaled.ntt_in_place();
+            let mut a_ntt = copy_of(&a);
+            a_ntt.ntt_in_place();
+            a_ntt.mul_assign_pointwise(&k);
+            assert_eq!(scaled.as_u16_array(), a_ntt.as_u16_array());
+        }
+    }
+
+    #[test]
+    fn test_le_bytes_roundtrip() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x1E);
+        let a = random_poly(&mut rng);
+
+        let mut bytes = [0u8; Polynomial::<FALCON_512_N>::BYTES];
+        assert_eq!(a.to_le_bytes(&mut bytes), Ok(()));
+        assert_eq!(u16::from_le_bytes([bytes[2], bytes[3]]), a.as_u16_array()[1]);
+        assert_eq!(Polynomial::<FALCON_512_N>::from_le_bytes(&bytes).unwrap().as_u16_array(), a.as_u16_array());
+
+        assert_eq!(a.to_le_bytes(&mut bytes[1..]), Err(FalconError::InvalidAccountData));
+        assert!(Polynomial::<FALCON_512_N>::from_le_bytes(&bytes[2..]).is_err());
+
+        // an unreduced coefficient is rejected rather than silently reduced
+        bytes[0..2].copy_from_slice(&(Q as u16).to_le_bytes());
+        assert!(Polynomial::<FALCON_512_N>::from_le_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_account_bytes_roundtrip() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0xACC);
+        let mut h = random_poly(&mut rng);
+        h.ntt_in_place();
+
+        let bytes = to_account_bytes(h.coefficients());
+        assert_eq!(bytes[0], NTT_ACCOUNT_TAG);
+        assert_eq!(from_account_bytes(&bytes), Ok(*h.coefficients()));
+
+        let mut wrong_tag = bytes;
+        wrong_tag[0] = NTT_ACCOUNT_TAG + 1;
+        assert_eq!(from_account_bytes(&wrong_tag), Err(FalconError::InvalidAccountData));
+        assert_eq!(from_account_bytes(&bytes[..NTT_ACCOUNT_BYTES - 1]), Err(FalconError::InvalidAccountData));
+        assert_eq!(from_account_bytes(&[]), Err(FalconError::InvalidAccountData));
+
+        let mut unreduced = bytes;
+        unreduced[1 + 2 * 300..1 + 2 * 301].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert_eq!(from_account_bytes(&unreduced), Err(FalconError::InvalidAccountData));
+    }
+
+    // random account data never panics the decoder, and whatever it accepts is reduced
+    // and encodes back to the same bytes
+    #[test]
+    fn test_account_bytes_fuzz() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0xF022);
+        let mut accepted = 0;
+        for round in 0..2000 {
+            let len = match round % 4 {
+                0 => rng.random_range(0..2 * NTT_ACCOUNT_BYTES),
+                _ => NTT_ACCOUNT_BYTES,
+            };
+            let mut bytes: Vec<u8> = (0..len).map(|_| rng.random()).collect();
+            if round % 2 == 1 && len > 0 {
+                bytes[0] = NTT_ACCOUNT_TAG;
+                // clear the top bits so most coefficients land below q
+                for i in (2..len).step_by(2) {
+                    bytes[i] &= if round % 8 == 1 { 0x1F } else { 0x2F };
+                }
+            }
+
+            if let Ok(coeffs) = from_account_bytes(&bytes) {
+                assert!(coeffs.iter().all(|&coeff| coeff < Q));
+                assert_eq!(to_account_bytes(&coeffs)[..], bytes[..]);
+                accepted += 1;
+            }
+        }
+        // the masked rounds produce valid encodings too, so both outcomes are exercised
+        assert!(accepted > 0);
+    }
+}
@@ -0,0 +1,140 @@
+// stage checkpoints inside verification, see the profiling feature in Cargo.toml
+// with it, a Profiler charges what each stage cost to a caller's PerformanceMonitor:
+// the remaining-compute-units delta on Solana (and in nostd-check builds), elapsed
+// nanoseconds anywhere else.
+// without it Profiler is empty and checkpoint does nothing, so the hooks compile away
+
+#[cfg(feature = "profiling")]
+use super::performance::PerformanceMonitor;
+
+// the stages in the order verification passes them, named after the entries of
+// FALCON_512_PERFORMANCE_PROFILE they correspond to; both forward NTTs are charged
+// to ntt_forward_transforms
+pub const VERIFY_STAGES: [&str; 8] = [
+    "signature_parsing",
+    "signature_decompression",
+    "public_key_parsing",
+    "ntt_forward_transforms",
+    "ntt_pointwise_operations",
+    "ntt_inverse_transform",
+    "shake256_hash_to_point",
+    "l2_norm_verification",
+];
+
+#[cfg(feature = "profiling")]
+pub(crate) struct Profiler<'a> {
+    monitor: Option<&'a mut PerformanceMonitor>,
+    // meter reading at the previous checkpoint
+    last: u64,
+    #[cfg(not(any(target_os = "solana", feature = "nostd-check")))]
+    origin: std::time::Instant,
+}
+
+#[cfg(not(feature = "profiling"))]
+pub(crate) struct Profiler<'a>(core::marker::PhantomData<&'a mut ()>);
+
+impl<'a> Profiler<'a> {
+    // a profiler that records nothing
+    #[cfg(feature = "profiling")]
+    pub fn none() -> Self {
+        Self {
+            monitor: None,
+            last: 0,
+            #[cfg(not(any(target_os = "solana", feature = "nostd-check")))]
+            origin: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    #[inline(always)]
+    pub fn none() -> Self {
+        Self(core::marker::PhantomData)
+    }
+
+    // starts the first stage now
+    #[cfg(feature = "profiling")]
+    pub fn new(monitor: &'a mut PerformanceMonitor) -> Self {
+        let mut profiler = Self::none();
+        profiler.last = profiler.meter();
+        profiler.monitor = Some(monitor);
+        profiler
+    }
+
+    // charge everything since the previous checkpoint to stage
+    // the meter is read again after recording, so the bookkeeping isn't charged to the next stage
+    #[inline(always)]
+    pub fn checkpoint(&mut self, stage: &'static str) {
+        #[cfg(feature = "profiling")]
+        if self.monitor.is_some() {
+            let cost = self.meter().saturating_sub(self.last);
+            if let Some(monitor) = self.monitor.as_deref_mut() {
+                monitor.record_stage(stage, cost);
+            }
+            self.last = self.meter();
+        }
+        #[cfg(not(feature = "profiling"))]
+        let _ = stage;
+    }
+
+    // a reading that grows with the work done: compute units consumed on Solana
+    #[cfg(all(feature = "profiling", any(target_os = "solana", feature = "nostd-check")))]
+    fn meter(&self) -> u64 {
+        u64::MAX - remaining_compute_units()
+    }
+
+    // nanoseconds since the profiler was created off-chain
+    #[cfg(all(feature = "profiling", not(any(target_os = "solana", feature = "nostd-check"))))]
+    fn meter(&self) -> u64 {
+        self.origin.elapsed().as_nanos() as u64
+    }
+}
+
+// the compute meter, shared by the profiler and BenchmarkVerify
+#[cfg(target_os = "solana")]
+pub fn remaining_compute_units() -> u64 {
+    unsafe { pinocchio::syscalls::sol_remaining_compute_units() }
+}
+
+// there is no meter off-chain
+#[cfg(not(target_os = "solana"))]
+pub fn remaining_compute_units() -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // without the feature there is nothing to pass around or call
+    #[cfg(not(feature = "profiling"))]
+    #[test]
+    fn test_profiler_compiles_away() {
+        assert_eq!(core::mem::size_of::<Profiler>(), 0);
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn test_profiled_verification_records_every_stage() {
+        use crate::falcon::{verify_falcon_signature_profiled, PerformanceMonitor};
+        let (public_key, signature) = crate::falcon::verify::tests::signed_fixture(b"profiled", &[9; 40]);
+
+        for (message, accepted) in [(&b"profiled"[..], true), (&b"other"[..], false)] {
+            let mut monitor = PerformanceMonitor::new();
+            let result = verify_falcon_signature_profiled(&public_key, &signature, message, &mut monitor);
+            assert_eq!(result.is_ok(), accepted);
+
+            let names: Vec<&str> = monitor.stages().iter().map(|&(name, _)| name).collect();
+            assert_eq!(names, VERIFY_STAGES);
+            let total: u64 = monitor.stages().iter().map(|&(_, cost)| cost).sum();
+            assert!(total > 0);
+            assert_eq!(monitor.get_stats().compute_units_used, total);
+        }
+
+        // a signature that fails to parse stops before the first checkpoint
+        let mut monitor = PerformanceMonitor::new();
+        let mut bad_header = signature;
+        bad_header[0] ^= 0x20;
+        assert!(verify_falcon_signature_profiled(&public_key, &bad_header, b"profiled", &mut monitor).is_err());
+        assert!(monitor.stages().is_empty());
+    }
+}
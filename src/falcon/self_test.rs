@@ -0,0 +1,131 @@
+// known-answer checks of the verifier's building blocks, small enough to run on-chain
+// through the SelfTest instruction: a deployment (or a validator with a different
+// SBF toolchain) can show its NTT, SHAKE256 and hash-to-point agree with the host
+// before anyone trusts a vault to it. each check keeps at most one polynomial live,
+// so the frames stay inside the 4 KB SBF stack limit
+
+use super::FalconError;
+use crate::error::VaultError;
+use super::keccak::{Shake256, SHAKE256_ABC};
+use super::ntt::{fast_mod_q, Q};
+use super::poly::Polynomial;
+use super::verify::hash_to_point;
+
+// the check that failed, numbered as the SelfTest instruction reports it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelfTestCheck {
+    NttRoundTrip = 1,
+    NttMultiply = 2,
+    Shake256 = 3,
+    HashToPoint = 4,
+}
+
+// the nonce and message hash_to_point is checked with, and the first coefficients
+// they give
+const HASH_TO_POINT_NONCE: [u8; 40] = [0x5A; 40];
+const HASH_TO_POINT_MESSAGE: &[u8] = b"abc";
+const HASH_TO_POINT_PREFIX: [u32; 8] = [9610, 6926, 10384, 10114, 4028, 5577, 4698, 6818];
+
+// every check, in order; the first failure is returned
+pub fn run_self_test() -> Result<(), SelfTestCheck> {
+    check(ntt_round_trip(), SelfTestCheck::NttRoundTrip)?;
+    check(ntt_multiply(), SelfTestCheck::NttMultiply)?;
+    check(Shake256::one_shot::<32>(b"abc") == SHAKE256_ABC, SelfTestCheck::Shake256)?;
+    check(hash_to_point_known_answer(), SelfTestCheck::HashToPoint)
+}
+
+// run_self_test as a program error, SelfTestFailed for any failing check
+pub fn self_test() -> Result<(), FalconError> {
+    run_self_test().map_err(|_| VaultError::SelfTestFailed.into())
+}
+
+fn check(passed: bool, failure: SelfTestCheck) -> Result<(), SelfTestCheck> {
+    if passed {
+        Ok(())
+    } else {
+        Err(failure)
+    }
+}
+
+// a fixed input spread over [0, q)
+fn round_trip_input(i: usize) -> u32 {
+    ((i * i * 7 + i * 31 + 1) % Q as usize) as u32
+}
+
+// forward with one twiddle source and inverse with the other, both ways round,
+// so a bad entry in either the tables or the runtime twiddles shows up
+#[inline(never)]
+fn ntt_round_trip() -> bool {
+    let mut poly: Polynomial = Polynomial::zero();
+    for (i, coeff) in poly.iter_mut().enumerate() {
+        *coeff = round_trip_input(i);
+    }
+    poly.ntt_in_place();
+    poly.intt_in_place_precomputed();
+    poly.ntt_in_place_precomputed();
+    poly.intt_in_place();
+    poly.iter().enumerate().all(|(i, &coeff)| coeff == round_trip_input(i))
+}
+
+// x^256 squared is x^512, which is -1 in Z_q[x]/(x^512 + 1); a cyclic rather than
+// negacyclic transform would give +1
+#[inline(never)]
+fn ntt_multiply() -> bool {
+    let mut poly: Polynomial = Polynomial::zero();
+    poly.coefficients_mut()[256] = 1;
+    poly.ntt_in_place();
+    for coeff in poly.iter_mut() {
+        *coeff = fast_mod_q(*coeff * *coeff);
+    }
+    poly.intt_in_place();
+    let coeffs = poly.coefficients();
+    coeffs[0] == Q - 1 && coeffs[1..].iter().all(|&coeff| coeff == 0)
+}
+
+// the same point twice, reduced, and starting with the known coefficients
+#[inline(never)]
+fn hash_to_point_known_answer() -> bool {
+    match (point_summary(), point_summary()) {
+        (Some(first), Some(again)) => first == again && first.0 == HASH_TO_POINT_PREFIX,
+        _ => false,
+    }
+}
+
+// the first coefficients and a weighted sum of all of them, None if one isn't
+// reduced; the point itself is dropped before the second call builds its own
+#[inline(never)]
+fn point_summary() -> Option<([u32; 8], u64)> {
+    let c: Polynomial = hash_to_point(&[HASH_TO_POINT_MESSAGE], &HASH_TO_POINT_NONCE);
+    let coeffs = c.coefficients();
+    if coeffs.iter().any(|&coeff| coeff >= Q) {
+        return None;
+    }
+    let mut prefix = [0u32; 8];
+    prefix.copy_from_slice(&coeffs[..8]);
+    let sum = coeffs.iter().enumerate().map(|(i, &coeff)| (i as u64 + 1) * coeff as u64).sum();
+    Some((prefix, sum))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_passes() {
+        assert_eq!(run_self_test(), Ok(()));
+        assert!(self_test().is_ok());
+    }
+
+    #[test]
+    fn test_hash_to_point_prefix() {
+        let c: Polynomial = hash_to_point(&[HASH_TO_POINT_MESSAGE], &HASH_TO_POINT_NONCE);
+        assert_eq!(c.coefficients()[..8], HASH_TO_POINT_PREFIX);
+        assert_ne!(c.coefficients()[..8], [0; 8]);
+    }
+
+    #[test]
+    fn test_self_test_failure_code() {
+        assert_eq!(check(false, SelfTestCheck::Shake256), Err(SelfTestCheck::Shake256));
+        assert_eq!(SelfTestCheck::HashToPoint as u8, 4);
+    }
+}
@@ -1,5 +1,10 @@
-use pinocchio::program_error::ProgramError;
-use crate::falcon::verify::{FALCON_512_PUBLIC_KEY_SIZE, FALCON_512_SIGNATURE_SIZE};
+use arrayref::array_ref;
+use super::FalconError;
+use crate::error::VaultError;
+use crate::falcon::verify::{
+    decompress_signature, parse_signature, unpack_public_key, FALCON_512_N, FALCON_512_NONCE_SIZE,
+    FALCON_512_PUBLIC_KEY_SIZE, FALCON_512_SIGNATURE_SIZE, FALCON_PREHASH_DIGEST_SIZE,
+};
 
 // Falcon-512 public key representation
 #[derive(Clone, Copy)]
@@ -11,11 +16,36 @@ impl FalconPublicKey {
     pub fn new(bytes: [u8; FALCON_512_PUBLIC_KEY_SIZE]) -> Self {
         Self { bytes }
     }
+
+    pub fn as_bytes(&self) -> &[u8; FALCON_512_PUBLIC_KEY_SIZE] {
+        &self.bytes
+    }
     
     // hash the public key to create a seed for PDA
     // using SHA256 to be compatible with Solana's PDA derivation
     pub fn hash(&self) -> [u8; 32] {
-        solana_nostd_sha256::hash(&self.bytes).into()
+        self.hash_with_domain(b"")
+    }
+
+    // SHA256 over domain then the key, so a deployment can namespace its vault PDAs
+    // away from other programs seeding with the same key. the empty domain is hash(),
+    // the seed every vault so far has been derived from
+    pub fn hash_with_domain(&self, domain: &[u8]) -> [u8; 32] {
+        solana_nostd_sha256::hashv(&[domain, &self.bytes])
+    }
+
+    // pre-flight check that the bytes encode a well-formed Falcon-512 public key
+    // checks the header and that every 14-bit coefficient is reduced mod q
+    // wallets should run this before sending OpenVault, the program runs it too
+    pub fn validate(&self) -> Result<(), VaultError> {
+        unpack_public_key(&self.bytes)?;
+        Ok(())
+    }
+
+    // the 512 coefficients of h, unpacked from 14 bits each, most significant bit first
+    // fails on the same bad header or unreduced coefficient that validate rejects
+    pub fn coefficients(&self) -> Result<[u16; FALCON_512_N], FalconError> {
+        Ok(unpack_public_key(&self.bytes)?)
     }
 }
 
@@ -29,9 +59,13 @@ impl FalconSignature {
     pub fn new(bytes: [u8; FALCON_512_SIGNATURE_SIZE]) -> Self {
         Self { bytes }
     }
+
+    pub fn as_bytes(&self) -> &[u8; FALCON_512_SIGNATURE_SIZE] {
+        &self.bytes
+    }
     
     // verify a signature against a public key and message
-    pub fn verify(&self, public_key: &FalconPublicKey, message: &[u8]) -> Result<(), ProgramError> {
+    pub fn verify(&self, public_key: &FalconPublicKey, message: &[u8]) -> Result<(), FalconError> {
         // using the verification function
         crate::falcon::verify::verify_falcon_signature(
             &public_key.bytes,
@@ -39,6 +73,82 @@ impl FalconSignature {
             message
         )
     }
+
+    // verify against a message given as parts, hashed in order without concatenating
+    pub fn verify_parts(&self, public_key: &FalconPublicKey, message_parts: &[&[u8]]) -> Result<(), FalconError> {
+        crate::falcon::verify::verify_falcon_signature_parts(
+            &public_key.bytes,
+            &self.bytes,
+            message_parts
+        )
+    }
+
+    // verify a signature made under a context, see verify_falcon_signature_with_context
+    pub fn verify_with_context(
+        &self,
+        public_key: &FalconPublicKey,
+        context: &[u8],
+        message_parts: &[&[u8]],
+    ) -> Result<(), FalconError> {
+        crate::falcon::verify::verify_falcon_signature_with_context(
+            &public_key.bytes,
+            &self.bytes,
+            context,
+            message_parts,
+        )
+    }
+
+    // verify against a 64-byte message digest, see verify_falcon_prehashed
+    pub fn verify_prehashed(
+        &self,
+        public_key: &FalconPublicKey,
+        digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+    ) -> Result<(), FalconError> {
+        crate::falcon::verify::verify_falcon_prehashed(&public_key.bytes, &self.bytes, digest)
+    }
+
+    // verify against a digest signed under a context, see verify_falcon_prehashed_with_context
+    pub fn verify_prehashed_with_context(
+        &self,
+        public_key: &FalconPublicKey,
+        context: &[u8],
+        digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+    ) -> Result<(), FalconError> {
+        crate::falcon::verify::verify_falcon_prehashed_with_context(&public_key.bytes, &self.bytes, context, digest)
+    }
+
+    // split the signature into its header fields, nonce and compressed payload
+    // only the header is checked here, the payload is not decoded
+    pub fn components(&self) -> Result<SignatureView<'_>, FalconError> {
+        let (_, compressed) = parse_signature::<FALCON_512_N>(&self.bytes)?;
+        let header = self.bytes[0];
+
+        Ok(SignatureView {
+            header,
+            encoding_type: (header >> 5) & 7,
+            logn: header & 15,
+            nonce: array_ref!(self.bytes, 1, FALCON_512_NONCE_SIZE),
+            compressed,
+        })
+    }
+
+    // check the header and decode all 512 coefficients of s2 with their bounds
+    // this does NOT check the signature against any public key or message
+    pub fn validate_format(&self) -> Result<(), FalconError> {
+        let view = self.components()?;
+        decompress_signature::<FALCON_512_N>(view.compressed, false)?;
+        Ok(())
+    }
+}
+
+// borrowed view over the parts of a Falcon-512 signature
+// wallets can use this to display the nonce before submitting a transaction
+pub struct SignatureView<'a> {
+    pub header: u8,
+    pub encoding_type: u8,
+    pub logn: u8,
+    pub nonce: &'a [u8; FALCON_512_NONCE_SIZE],
+    pub compressed: &'a [u8],
 }
 
 impl From<[u8; FALCON_512_SIGNATURE_SIZE]> for FalconSignature {
@@ -51,4 +161,207 @@ impl From<[u8; FALCON_512_PUBLIC_KEY_SIZE]> for FalconPublicKey {
     fn from(bytes: [u8; FALCON_512_PUBLIC_KEY_SIZE]) -> Self {
         Self { bytes }
     }
-} 
\ No newline at end of file
+}
+
+// equality touches every byte whatever the inputs, so comparing a key or signature
+// against an expected one doesn't leak how long the matching prefix is
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    core::hint::black_box(diff) == 0
+}
+
+impl PartialEq for FalconPublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.bytes, &other.bytes)
+    }
+}
+
+impl Eq for FalconPublicKey {}
+
+impl PartialEq for FalconSignature {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.bytes, &other.bytes)
+    }
+}
+
+impl Eq for FalconSignature {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::falcon::verify::tests::{compress_coefficients, overlong_payload};
+
+    // header with encoding type 2, fixed bit set and logn = 9
+    const VALID_HEADER: u8 = (2 << 5) | (1 << 4) | 9;
+
+    fn signature_with_header(header: u8) -> FalconSignature {
+        let mut bytes = [0u8; FALCON_512_SIGNATURE_SIZE];
+        bytes[0] = header;
+        for i in 0..FALCON_512_NONCE_SIZE {
+            bytes[1 + i] = i as u8;
+        }
+        FalconSignature::new(bytes)
+    }
+
+    fn pack_public_key(coeffs: &[u16; 512]) -> [u8; FALCON_512_PUBLIC_KEY_SIZE] {
+        let mut bytes = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
+        crate::falcon::verify::pack_public_key_into(coeffs, &mut bytes);
+        bytes
+    }
+
+    fn fixture_coefficients() -> [u16; 512] {
+        let mut coeffs = [0u16; 512];
+        for (i, coeff) in coeffs.iter_mut().enumerate() {
+            *coeff = ((i * 7919) % 12289) as u16;
+        }
+        coeffs[511] = 12288;
+        coeffs
+    }
+
+    #[test]
+    fn test_validate_public_key() {
+        let key = FalconPublicKey::new(pack_public_key(&fixture_coefficients()));
+        assert_eq!(key.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_public_key_bad_header() {
+        let mut bytes = pack_public_key(&fixture_coefficients());
+        bytes[0] = 10;
+        assert_eq!(FalconPublicKey::new(bytes).validate(), Err(VaultError::BadPublicKeyHeader));
+    }
+
+    #[test]
+    fn test_validate_public_key_coefficient_out_of_range() {
+        let mut coeffs = fixture_coefficients();
+        coeffs[300] = 12289;
+        let key = FalconPublicKey::new(pack_public_key(&coeffs));
+        assert_eq!(key.validate(), Err(VaultError::PublicKeyCoefficientOutOfRange));
+
+        // the last coefficient sits in the final bits of the key
+        let mut coeffs = fixture_coefficients();
+        coeffs[511] = 0x3FFF;
+        let key = FalconPublicKey::new(pack_public_key(&coeffs));
+        assert_eq!(key.validate(), Err(VaultError::PublicKeyCoefficientOutOfRange));
+    }
+
+    #[test]
+    fn test_coefficients() {
+        let key = FalconPublicKey::new(pack_public_key(&fixture_coefficients()));
+        assert_eq!(key.coefficients(), Ok(fixture_coefficients()));
+
+        // hand-packed, most significant bit first: h[0] = 0x2001 from the top bit of byte 1
+        // to bit 2 of byte 2, h[1] = 3 in bits 4 and 5 of byte 4
+        let mut bytes = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
+        bytes[0] = 9;
+        bytes[1] = 0x80;
+        bytes[2] = 0x04;
+        bytes[4] = 0x30;
+        let coeffs = FalconPublicKey::new(bytes).coefficients().unwrap();
+        assert_eq!(coeffs[..3], [0x2001, 3, 0]);
+        assert!(coeffs[2..].iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_coefficients_rejects_malformed_keys() {
+        let mut bytes = pack_public_key(&fixture_coefficients());
+        bytes[0] = 10;
+        assert_eq!(FalconPublicKey::new(bytes).coefficients(), Err(VaultError::BadPublicKeyHeader.into()));
+
+        let mut coeffs = fixture_coefficients();
+        coeffs[17] = 12289;
+        let key = FalconPublicKey::new(pack_public_key(&coeffs));
+        assert_eq!(key.coefficients(), Err(VaultError::PublicKeyCoefficientOutOfRange.into()));
+    }
+
+    #[test]
+    fn test_equality() {
+        let key = FalconPublicKey::new(pack_public_key(&fixture_coefficients()));
+        assert!(FalconPublicKey::try_from(&key.as_bytes()[..]).unwrap() == key);
+
+        // a difference in the first or the last byte
+        let mut first = key;
+        first.bytes[0] ^= 1;
+        assert!(first != key);
+        let mut last = key;
+        last.bytes[FALCON_512_PUBLIC_KEY_SIZE - 1] ^= 0x80;
+        assert!(last != key);
+
+        let signature = signature_with_header(VALID_HEADER);
+        assert!(signature == FalconSignature::from(*signature.as_bytes()));
+        assert!(signature != signature_with_header(VALID_HEADER + 1));
+    }
+
+    #[test]
+    fn test_hash_with_domain() {
+        let key = FalconPublicKey::new(pack_public_key(&fixture_coefficients()));
+        assert_eq!(key.hash(), key.hash_with_domain(b""));
+
+        let domains: [&[u8]; 3] = [b"", b"vault", b"vault-v2"];
+        for (i, a) in domains.iter().enumerate() {
+            for b in &domains[i + 1..] {
+                assert_ne!(key.hash_with_domain(a), key.hash_with_domain(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_from_wrong_length() {
+        let key = FalconPublicKey::new(pack_public_key(&fixture_coefficients()));
+        assert!(FalconPublicKey::try_from(&key.bytes[1..]).is_err());
+        assert!(FalconPublicKey::try_from(&[0u8; FALCON_512_PUBLIC_KEY_SIZE + 1][..]).is_err());
+        assert!(FalconSignature::try_from(&key.bytes[..FALCON_512_SIGNATURE_SIZE - 1]).is_err());
+        assert!(FalconSignature::try_from(&[][..]).is_err());
+    }
+
+    #[test]
+    fn test_components_valid_header() {
+        let signature = signature_with_header(VALID_HEADER);
+        let view = signature.components().unwrap();
+
+        assert_eq!(view.header, VALID_HEADER);
+        assert_eq!(view.encoding_type, 2);
+        assert_eq!(view.logn, 9);
+        assert_eq!(view.nonce[0], 0);
+        assert_eq!(view.nonce[39], 39);
+        assert_eq!(view.compressed.len(), FALCON_512_SIGNATURE_SIZE - 1 - FALCON_512_NONCE_SIZE);
+    }
+
+    #[test]
+    fn test_components_malformed_headers() {
+        // wrong encoding type, missing fixed bit, wrong logn
+        for header in [(1 << 5) | (1 << 4) | 9, (2 << 5) | 9, (2 << 5) | (1 << 4) | 10] {
+            let signature = signature_with_header(header);
+            assert!(signature.components().is_err());
+            assert!(signature.validate_format().is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_format_zero_coefficients() {
+        // every coefficient decodes to zero using 9 bits, well within the payload
+        let mut signature = signature_with_header(VALID_HEADER);
+        signature.bytes[1 + FALCON_512_NONCE_SIZE..].copy_from_slice(&compress_coefficients(&[0; 512]));
+        assert!(signature.validate_format().is_ok());
+    }
+
+    #[test]
+    fn test_validate_format_all_zero_payload() {
+        // an all-zero payload never closes the first unary run
+        let signature = signature_with_header(VALID_HEADER);
+        assert_eq!(signature.validate_format(), Err(VaultError::CoefficientTooLarge.into()));
+    }
+
+    #[test]
+    fn test_validate_format_truncated_payload() {
+        // every coefficient takes 10 bits, 512 * 10 bits is more than the 625 byte payload holds
+        let mut signature = signature_with_header(VALID_HEADER);
+        signature.bytes[1 + FALCON_512_NONCE_SIZE..].copy_from_slice(&overlong_payload());
+
+        assert!(signature.components().is_ok());
+        assert!(signature.validate_format().is_err());
+    }
+}
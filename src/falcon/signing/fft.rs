@@ -0,0 +1,202 @@
+// complex FFT over R[x]/(x^n + 1), used by key generation and the trapdoor sampler
+// a polynomial of size n is stored as its values at the n roots of x^n + 1, ordered so
+// that entries 2k and 2k + 1 hold the values at a root zeta_k and at -zeta_k, where
+// zeta_k^2 is root k of x^(n/2) + 1. with that order split_fft and merge_fft move
+// between f and (f0, f1), f(x) = f0(x^2) + x * f1(x^2), without leaving the FFT domain
+
+use core::f64::consts::PI;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+// a zeroed Complex is the default one, so buffers of them can be wiped
+#[cfg(feature = "zeroize")]
+impl zeroize::DefaultIsZeroes for Complex {}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Mul<f64> for Complex {
+    type Output = Self;
+    fn mul(self, scale: f64) -> Self {
+        Self::new(self.re * scale, self.im * scale)
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        (self * other.conj()) * (1.0 / other.norm_sqr())
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+// angle of root j of x^n + 1 in the order described above
+// x^2 + 1 has roots i and -i; each root of x^n + 1 is a square root of one of x^(n/2) + 1
+fn root_angle(n: usize, j: usize) -> f64 {
+    if n == 2 {
+        return PI / 2.0 + PI * j as f64;
+    }
+    let half = root_angle(n / 2, j / 2) / 2.0;
+    if j & 1 == 0 { half } else { half + PI }
+}
+
+fn root(n: usize, j: usize) -> Complex {
+    let angle = root_angle(n, j);
+    Complex::new(angle.cos(), angle.sin())
+}
+
+// (f0, f1) in FFT form -> f in FFT form
+pub(crate) fn merge_fft(f0: &[Complex], f1: &[Complex]) -> Vec<Complex> {
+    let n = 2 * f0.len();
+    let mut f = vec![Complex::default(); n];
+    for k in 0..n / 2 {
+        let zeta = root(n, 2 * k);
+        f[2 * k] = f0[k] + zeta * f1[k];
+        f[2 * k + 1] = f0[k] - zeta * f1[k];
+    }
+    f
+}
+
+// f in FFT form -> (f0, f1) in FFT form
+pub(crate) fn split_fft(f: &[Complex]) -> (Vec<Complex>, Vec<Complex>) {
+    let n = f.len();
+    let mut f0 = vec![Complex::default(); n / 2];
+    let mut f1 = vec![Complex::default(); n / 2];
+    for k in 0..n / 2 {
+        // zeta is on the unit circle, so 1 / zeta is its conjugate
+        let zeta = root(n, 2 * k);
+        f0[k] = (f[2 * k] + f[2 * k + 1]) * 0.5;
+        f1[k] = (f[2 * k] - f[2 * k + 1]) * zeta.conj() * 0.5;
+    }
+    (f0, f1)
+}
+
+pub(crate) fn fft(f: &[f64]) -> Vec<Complex> {
+    if f.len() == 1 {
+        return vec![Complex::new(f[0], 0.0)];
+    }
+    let f0: Vec<f64> = f.iter().step_by(2).copied().collect();
+    let f1: Vec<f64> = f.iter().skip(1).step_by(2).copied().collect();
+    merge_fft(&fft(&f0), &fft(&f1))
+}
+
+pub(crate) fn ifft(f: &[Complex]) -> Vec<f64> {
+    if f.len() == 1 {
+        return vec![f[0].re];
+    }
+    let (f0, f1) = split_fft(f);
+    let (f0, f1) = (ifft(&f0), ifft(&f1));
+    let mut out = vec![0.0; f.len()];
+    for k in 0..f0.len() {
+        out[2 * k] = f0[k];
+        out[2 * k + 1] = f1[k];
+    }
+    out
+}
+
+pub(crate) fn add_fft(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    a.iter().zip(b).map(|(&x, &y)| x + y).collect()
+}
+
+pub(crate) fn sub_fft(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    a.iter().zip(b).map(|(&x, &y)| x - y).collect()
+}
+
+pub(crate) fn mul_fft(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    a.iter().zip(b).map(|(&x, &y)| x * y).collect()
+}
+
+pub(crate) fn div_fft(a: &[Complex], b: &[Complex]) -> Vec<Complex> {
+    a.iter().zip(b).map(|(&x, &y)| x / y).collect()
+}
+
+// f(1/x), the adjoint, is the complex conjugate at every root
+pub(crate) fn adj_fft(a: &[Complex]) -> Vec<Complex> {
+    a.iter().map(|x| x.conj()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fft_roundtrip_and_product() {
+        let n = 16;
+        let a: Vec<f64> = (0..n).map(|i| (i as f64 * 1.7).sin() * 10.0).collect();
+        let b: Vec<f64> = (0..n).map(|i| (i as f64 * 0.3).cos() * 5.0).collect();
+
+        for (x, y) in ifft(&fft(&a)).iter().zip(&a) {
+            assert!((x - y).abs() < 1e-9);
+        }
+
+        // product mod x^n + 1
+        let mut expected = vec![0.0; n];
+        for i in 0..n {
+            for j in 0..n {
+                if i + j < n {
+                    expected[i + j] += a[i] * b[j];
+                } else {
+                    expected[i + j - n] -= a[i] * b[j];
+                }
+            }
+        }
+        let product = ifft(&mul_fft(&fft(&a), &fft(&b)));
+        for (x, y) in product.iter().zip(&expected) {
+            assert!((x - y).abs() < 1e-9);
+        }
+
+        // splitting matches the even and odd coefficients
+        let (f0, f1) = split_fft(&fft(&a));
+        let even: Vec<f64> = a.iter().step_by(2).copied().collect();
+        for (x, y) in ifft(&f0).iter().zip(&even) {
+            assert!((x - y).abs() < 1e-9);
+        }
+        assert_eq!(merge_fft(&f0, &f1).len(), n);
+    }
+}
@@ -0,0 +1,347 @@
+// off-chain Falcon-512 key generation and signing, a port of the reference signer
+// host only: it needs floats, heap allocation and an rng, none of which the program
+// has on-chain. signatures come out in the fixed 666-byte layout verify_falcon_signature
+// takes, hashed with the same hash_to_point the verifier uses
+
+mod fft;
+mod ntru;
+mod sampler;
+
+use rand::RngCore;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use self::fft::{add_fft, adj_fft, fft, ifft, mul_fft, Complex};
+use self::sampler::{ff_sampling, LdlTree};
+use crate::falcon::math::mod_inverse;
+use crate::falcon::ntt::{fast_mod_q, ntt_forward, ntt_inverse, Q};
+use crate::falcon::signature::{FalconPublicKey, FalconSignature};
+use crate::falcon::verify::{
+    compress_signature, hash_input_to_point, pack_public_key, prehash_parts, HashInput, FALCON_512_N,
+    FALCON_512_NONCE_SIZE, FALCON_512_SIGNATURE_HEADER, FALCON_512_SIGNATURE_SIZE, FALCON_512_SIG_BOUND,
+    FALCON_MAX_CONTEXT_SIZE, FALCON_PREHASH_DIGEST_SIZE,
+};
+
+// buffers holding values derived from the secret basis. with the zeroize feature they
+// are Zeroizing and wiped when dropped, without it they are the plain values
+#[cfg(feature = "zeroize")]
+type Scratch<T> = zeroize::Zeroizing<T>;
+#[cfg(not(feature = "zeroize"))]
+type Scratch<T> = T;
+
+#[cfg(feature = "zeroize")]
+fn scratch<T: Zeroize>(value: T) -> Scratch<T> {
+    zeroize::Zeroizing::new(value)
+}
+
+#[cfg(not(feature = "zeroize"))]
+fn scratch<T>(value: T) -> Scratch<T> {
+    value
+}
+
+// standard deviation of the signature and the smallest one the sampler is called with
+const SIGMA: f64 = 165.7366171829776;
+const SIGMA_MIN: f64 = 1.2778336969128337;
+
+// Falcon-512 secret basis [[g, -f], [G, -F]], with its FFT form and LDL tree
+// precomputed so each signature only runs the sampler
+pub struct FalconPrivateKey {
+    f: [i8; FALCON_512_N],
+    g: [i8; FALCON_512_N],
+    basis_fft: [Vec<Complex>; 4],
+    tree: LdlTree,
+}
+
+pub struct FalconKeyPair {
+    pub public_key: FalconPublicKey,
+    pub private_key: FalconPrivateKey,
+}
+
+impl FalconKeyPair {
+    // NTRU key generation, takes a few seconds in debug builds
+    pub fn generate<R: RngCore + ?Sized>(rng: &mut R) -> Self {
+        let basis = ntru::ntru_gen(rng);
+        let public_key = FalconPublicKey::new(pack_public_key(&public_polynomial(&basis.f, &basis.g)));
+        let private_key = FalconPrivateKey::new(&basis);
+        Self { public_key, private_key }
+    }
+}
+
+impl FalconPrivateKey {
+    fn new(basis: &ntru::NtruBasis) -> Self {
+        let to_fft = |a: &[i8; FALCON_512_N], sign: f64| fft(&scratch(a.map(|x| x as f64 * sign))[..]);
+        let basis_fft = [
+            to_fft(&basis.g, 1.0),
+            to_fft(&basis.f, -1.0),
+            to_fft(&basis.big_g, 1.0),
+            to_fft(&basis.big_f, -1.0),
+        ];
+
+        // Gram matrix of the basis, B * B^*
+        let [a, b, c, d] = &basis_fft;
+        let gram = |x: &[Complex], y: &[Complex], z: &[Complex], w: &[Complex]| {
+            scratch(add_fft(&mul_fft(x, &adj_fft(z)), &mul_fft(y, &adj_fft(w))))
+        };
+        let tree = LdlTree::new(&gram(a, b, a, b), &gram(a, b, c, d), &gram(c, d, c, d), SIGMA);
+
+        Self { f: basis.f, g: basis.g, basis_fft, tree }
+    }
+
+    // the public key h = g / f mod q this private key signs for
+    pub fn public_key(&self) -> FalconPublicKey {
+        FalconPublicKey::new(pack_public_key(&public_polynomial(&self.f, &self.g)))
+    }
+
+    // sign with the thread-local rng
+    pub fn sign(&self, message: &[u8]) -> FalconSignature {
+        self.sign_with_rng(message, &mut rand::rng())
+    }
+
+    pub fn sign_with_rng<R: RngCore + ?Sized>(&self, message: &[u8], rng: &mut R) -> FalconSignature {
+        self.sign_with_context(&[], message, rng)
+    }
+
+    // a signature that only verifies under context, see verify_falcon_signature_with_context
+    // panics if the context is longer than FALCON_MAX_CONTEXT_SIZE
+    pub fn sign_with_context<R: RngCore + ?Sized>(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        rng: &mut R,
+    ) -> FalconSignature {
+        assert!(context.len() <= FALCON_MAX_CONTEXT_SIZE, "context longer than {} bytes", FALCON_MAX_CONTEXT_SIZE);
+        self.sign_input(HashInput::message(&[message]).with_context(context), rng)
+    }
+
+    // a signature over a digest from prehash_message, see verify_falcon_prehashed
+    pub fn sign_prehashed<R: RngCore + ?Sized>(
+        &self,
+        digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+        rng: &mut R,
+    ) -> FalconSignature {
+        self.sign_prehashed_with_context(&[], digest, rng)
+    }
+
+    // sign_prehashed under a context, see verify_falcon_prehashed_with_context
+    // panics if the context is longer than FALCON_MAX_CONTEXT_SIZE
+    pub fn sign_prehashed_with_context<R: RngCore + ?Sized>(
+        &self,
+        context: &[u8],
+        digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+        rng: &mut R,
+    ) -> FalconSignature {
+        assert!(context.len() <= FALCON_MAX_CONTEXT_SIZE, "context longer than {} bytes", FALCON_MAX_CONTEXT_SIZE);
+        self.sign_input(HashInput::prehashed(&prehash_parts(digest)).with_context(context), rng)
+    }
+
+    fn sign_input<R: RngCore + ?Sized>(&self, input: HashInput, rng: &mut R) -> FalconSignature {
+        // the verifier rejects an all-zero nonce
+        let mut nonce = [0u8; FALCON_512_NONCE_SIZE];
+        while nonce.iter().all(|&b| b == 0) {
+            rng.fill_bytes(&mut nonce);
+        }
+        let c = hash_input_to_point(input, &nonce).as_u16_array();
+
+        // a fresh sample until s is short enough and s2 fits the payload
+        let payload = loop {
+            let (s1, s2) = self.sample_preimage(&c, rng);
+            let norm: i64 = s1.iter().chain(s2.iter()).map(|&x| x * x).sum();
+            if norm >= FALCON_512_SIG_BOUND || s2.iter().any(|x| x.abs() > 2047) {
+                continue;
+            }
+            if let Some(payload) = compress_signature(&s2.map(|x| x as i16)) {
+                break payload;
+            }
+        };
+
+        let mut bytes = [0u8; FALCON_512_SIGNATURE_SIZE];
+        bytes[0] = FALCON_512_SIGNATURE_HEADER;
+        bytes[1..41].copy_from_slice(&nonce);
+        bytes[41..].copy_from_slice(&payload);
+        FalconSignature::new(bytes)
+    }
+
+    // (s1, s2) with s1 + s2 * h = c mod q, close to a Gaussian of width SIGMA
+    fn sample_preimage<R: RngCore + ?Sized>(
+        &self,
+        c: &[u16; FALCON_512_N],
+        rng: &mut R,
+    ) -> (Scratch<[i64; FALCON_512_N]>, Scratch<[i64; FALCON_512_N]>) {
+        let [a, b, big_c, d] = &self.basis_fft;
+        let c_fft = fft(&c.map(|x| x as f64));
+
+        // target (c, 0) * B^-1, using det(B) = q
+        let scale = 1.0 / Q as f64;
+        let t0 = scratch(c_fft.iter().zip(d).map(|(&x, &y)| x * y * scale).collect::<Vec<_>>());
+        let t1 = scratch(c_fft.iter().zip(b).map(|(&x, &y)| -(x * y) * scale).collect::<Vec<_>>());
+        let (z0, z1) = ff_sampling(&t0, &t1, &self.tree, SIGMA_MIN, rng);
+        let (z0, z1) = (scratch(z0), scratch(z1));
+
+        // v = z * B is the lattice point, s = (c, 0) - v
+        let v0 = scratch(ifft(&add_fft(&mul_fft(&z0, a), &mul_fft(&z1, big_c))));
+        let v1 = scratch(ifft(&add_fft(&mul_fft(&z0, b), &mul_fft(&z1, d))));
+
+        let mut s1 = scratch([0i64; FALCON_512_N]);
+        let mut s2 = scratch([0i64; FALCON_512_N]);
+        for i in 0..FALCON_512_N {
+            s1[i] = c[i] as i64 - v0[i].round() as i64;
+            s2[i] = -(v1[i].round() as i64);
+        }
+        (s1, s2)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for FalconPrivateKey {
+    fn zeroize(&mut self) {
+        self.f.zeroize();
+        self.g.zeroize();
+        for poly in &mut self.basis_fft {
+            poly.zeroize();
+        }
+        self.tree.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for FalconPrivateKey {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for FalconPrivateKey {}
+
+// h = g * f^-1 mod (q, x^n + 1), f is invertible by construction
+fn public_polynomial(f: &[i8; FALCON_512_N], g: &[i8; FALCON_512_N]) -> [u16; FALCON_512_N] {
+    let to_ntt = |a: &[i8; FALCON_512_N]| {
+        let mut coeffs = scratch(a.map(|x| (x as i32).rem_euclid(Q as i32) as u32));
+        ntt_forward(&mut coeffs);
+        coeffs
+    };
+    let f_ntt = to_ntt(f);
+    let mut h = to_ntt(g);
+    for (h, &f) in h.iter_mut().zip(f_ntt.iter()) {
+        *h = fast_mod_q(*h * mod_inverse(f, Q).unwrap_or(0));
+    }
+    ntt_inverse(&mut h);
+    h.map(|x| x as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_sign_and_verify_roundtrip() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let keypair = FalconKeyPair::generate(&mut rng);
+        assert!(keypair.public_key.validate().is_ok());
+        assert_eq!(keypair.private_key.public_key().bytes, keypair.public_key.bytes);
+
+        for message in [&b"transfer 100"[..], b"", &[0xAB; 300]] {
+            let signature = keypair.private_key.sign_with_rng(message, &mut rng);
+            assert_eq!(signature.bytes[0], FALCON_512_SIGNATURE_HEADER);
+            assert!(signature.verify(&keypair.public_key, message).is_ok());
+            assert!(signature.validate_format().is_ok());
+        }
+
+        // a signature only covers its own message
+        let signature = keypair.private_key.sign(b"transfer 100");
+        assert!(signature.verify(&keypair.public_key, b"transfer 101").is_err());
+    }
+
+    #[test]
+    fn test_context_signatures_stay_in_their_context() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(43);
+        let keypair = FalconKeyPair::generate(&mut rng);
+        let verify = |signature: &FalconSignature, context: &[u8]| {
+            crate::falcon::verify_falcon_signature_with_context(
+                &keypair.public_key.bytes,
+                &signature.bytes,
+                context,
+                &[b"close"],
+            )
+        };
+
+        let signature = keypair.private_key.sign_with_context(b"vault", b"close", &mut rng);
+        assert_eq!(verify(&signature, b"vault"), Ok(()));
+        assert!(verify(&signature, b"vaulu").is_err());
+        assert!(verify(&signature, b"").is_err());
+        assert!(signature.verify(&keypair.public_key, b"close").is_err());
+
+        // an empty context is a plain signature
+        let signature = keypair.private_key.sign_with_context(b"", b"close", &mut rng);
+        assert!(signature.verify(&keypair.public_key, b"close").is_ok());
+        assert!(verify(&signature, b"vault").is_err());
+    }
+
+    #[test]
+    fn test_signatures_verify_with_reference() {
+        use falcon_rust::falcon512::{PublicKey, Signature, verify};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(44);
+        let keypair = FalconKeyPair::generate(&mut rng);
+        let signature = keypair.private_key.sign_with_rng(b"transfer 100", &mut rng);
+
+        let public_key = PublicKey::from_bytes(&keypair.public_key.bytes).unwrap();
+        let signature = Signature::from_bytes(&signature.bytes).unwrap();
+        assert!(verify(b"transfer 100", &signature, &public_key));
+        assert!(!verify(b"transfer 101", &signature, &public_key));
+    }
+
+    #[test]
+    fn test_prehashed_signatures_stay_prehashed() {
+        use crate::falcon::{prehash_message, verify_falcon_prehashed};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(45);
+        let keypair = FalconKeyPair::generate(&mut rng);
+        let digest = prehash_message(&[b"transfer 100"]);
+
+        let signature = keypair.private_key.sign_prehashed(&digest, &mut rng);
+        assert_eq!(verify_falcon_prehashed(&keypair.public_key.bytes, &signature.bytes, &digest), Ok(()));
+        assert!(signature.verify(&keypair.public_key, &digest).is_err());
+
+        let raw = keypair.private_key.sign_with_rng(&digest, &mut rng);
+        assert!(verify_falcon_prehashed(&keypair.public_key.bytes, &raw.bytes, &digest).is_err());
+
+        let bound = keypair.private_key.sign_prehashed_with_context(b"vault", &digest, &mut rng);
+        assert_eq!(bound.verify_prehashed_with_context(&keypair.public_key, b"vault", &digest), Ok(()));
+        assert!(bound.verify_prehashed(&keypair.public_key, &digest).is_err());
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize_private_key() {
+        use core::mem::ManuallyDrop;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let basis = ntru::ntru_gen(&mut rng);
+
+        // zeroize clears the arrays and every heap buffer up to its capacity
+        let mut key = FalconPrivateKey::new(&basis);
+        let buffers: Vec<(*const Complex, usize)> =
+            key.basis_fft.iter().map(|poly| (poly.as_ptr(), poly.capacity())).collect();
+        key.zeroize();
+        assert!(key.f.iter().chain(&key.g).all(|&x| x == 0));
+        for (ptr, capacity) in buffers {
+            // still owned by the key, only the length was reset
+            let wiped = unsafe { core::slice::from_raw_parts(ptr, capacity) };
+            assert!(wiped.iter().all(|x| *x == Complex::default()));
+        }
+        let LdlTree::Node { l10, .. } = &key.tree else {
+            panic!("a 512-coefficient tree has an inner root");
+        };
+        assert!(l10.is_empty());
+
+        // dropping runs the same wipe, the struct's own bytes stay readable in the ManuallyDrop
+        let mut key = ManuallyDrop::new(FalconPrivateKey::new(&basis));
+        assert!(key.f.iter().any(|&x| x != 0));
+        let f = key.f.as_ptr();
+        unsafe { ManuallyDrop::drop(&mut key) };
+        let dropped = unsafe { core::slice::from_raw_parts(f, FALCON_512_N) };
+        assert!(dropped.iter().all(|&x| x == 0));
+    }
+}
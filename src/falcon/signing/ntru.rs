@@ -0,0 +1,286 @@
+// NTRU key generation (Algorithms 5 to 7 of the Falcon specification): short f, g
+// and the F, G completing them to a basis with f * G - g * F = q mod x^n + 1
+
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use rand::RngCore;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use super::fft::{add_fft, adj_fft, div_fft, fft, ifft, mul_fft};
+use super::sampler::sampler_z;
+use super::scratch;
+use crate::falcon::ntt::{ntt_forward, Q};
+use crate::falcon::verify::FALCON_512_N;
+
+// f and g coefficients are sums of 4096 / n samples at this deviation (1.17 * sqrt(q / 8192))
+const SIGMA_FG: f64 = 1.43300980528773;
+
+// both Gram-Schmidt norms of the basis must stay under 1.17 * sqrt(q)
+const GS_NORM_BOUND: f64 = 1.17 * 1.17 * Q as f64;
+
+// F and G are stored as bytes, so keys with larger coefficients are drawn again
+const MAX_FG_COEFF: i64 = 127;
+
+pub(crate) struct NtruBasis {
+    pub f: [i8; FALCON_512_N],
+    pub g: [i8; FALCON_512_N],
+    pub big_f: [i8; FALCON_512_N],
+    pub big_g: [i8; FALCON_512_N],
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for NtruBasis {
+    fn zeroize(&mut self) {
+        self.f.zeroize();
+        self.g.zeroize();
+        self.big_f.zeroize();
+        self.big_g.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for NtruBasis {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+// draw (f, g, F, G) until every check passes
+pub(crate) fn ntru_gen<R: RngCore + ?Sized>(rng: &mut R) -> NtruBasis {
+    loop {
+        let f = scratch(gen_poly(rng));
+        let g = scratch(gen_poly(rng));
+
+        if gs_norm(&f, &g) > GS_NORM_BOUND || !invertible_mod_q(&f) {
+            continue;
+        }
+
+        let big = |a: &[i64]| a.iter().map(|&x| BigInt::from(x)).collect::<Vec<_>>();
+        let Some((big_f, big_g)) = ntru_solve(&big(&f), &big(&g)) else {
+            continue;
+        };
+        let (Some(big_f), Some(big_g)) = (to_small(&big_f), to_small(&big_g)) else {
+            continue;
+        };
+        let (big_f, big_g) = (scratch(big_f), scratch(big_g));
+
+        // the NTRU equation holds exactly, not only mod q
+        let fg = mul_small(&f, &big_g);
+        let gf = mul_small(&g, &big_f);
+        let holds = (0..FALCON_512_N).all(|i| fg[i] - gf[i] == if i == 0 { Q as i64 } else { 0 });
+        if !holds {
+            continue;
+        }
+
+        let to_bytes = |a: &[i64]| {
+            let mut out = [0i8; FALCON_512_N];
+            for (o, &x) in out.iter_mut().zip(a) {
+                *o = x as i8;
+            }
+            out
+        };
+        return NtruBasis {
+            f: to_bytes(&f),
+            g: to_bytes(&g),
+            big_f: to_bytes(&big_f),
+            big_g: to_bytes(&big_g),
+        };
+    }
+}
+
+fn gen_poly<R: RngCore + ?Sized>(rng: &mut R) -> Vec<i64> {
+    let draws = 4096 / FALCON_512_N;
+    (0..FALCON_512_N)
+        .map(|_| (0..draws).map(|_| sampler_z(0.0, SIGMA_FG, SIGMA_FG - 0.001, rng)).sum())
+        .collect()
+}
+
+// larger of the squared norms of (g, -f) and of its Gram-Schmidt counterpart
+fn gs_norm(f: &[i64], g: &[i64]) -> f64 {
+    let sqnorm_fg: f64 = f.iter().chain(g).map(|&x| (x * x) as f64).sum();
+
+    let to_f64 = |a: &[i64]| a.iter().map(|&x| x as f64).collect::<Vec<_>>();
+    let f_fft = fft(&to_f64(f));
+    let g_fft = fft(&to_f64(g));
+    let ffgg = add_fft(&mul_fft(&f_fft, &adj_fft(&f_fft)), &mul_fft(&g_fft, &adj_fft(&g_fft)));
+    let ft = ifft(&div_fft(&adj_fft(&g_fft), &ffgg));
+    let gt = ifft(&div_fft(&adj_fft(&f_fft), &ffgg));
+    let sqnorm_ft_gt: f64 = ft.iter().chain(&gt).map(|x| x * x).sum::<f64>() * (Q as f64).powi(2);
+
+    sqnorm_fg.max(sqnorm_ft_gt)
+}
+
+// f has an inverse mod (q, x^n + 1) when none of its NTT values is zero
+fn invertible_mod_q(f: &[i64]) -> bool {
+    let mut coeffs = [0u32; FALCON_512_N];
+    for (c, &x) in coeffs.iter_mut().zip(f) {
+        *c = x.rem_euclid(Q as i64) as u32;
+    }
+    ntt_forward(&mut coeffs);
+    coeffs.iter().all(|&c| c != 0)
+}
+
+// F, G with f * G - g * F = q mod x^n + 1, recursing over the field norms
+fn ntru_solve(f: &[BigInt], g: &[BigInt]) -> Option<(Vec<BigInt>, Vec<BigInt>)> {
+    if f.len() == 1 {
+        let (d, u, v) = xgcd(&f[0], &g[0]);
+        if !d.is_one() {
+            return None;
+        }
+        let q = BigInt::from(Q);
+        return Some((vec![-(&q * v)], vec![q * u]));
+    }
+
+    let (fp, gp) = (field_norm(f), field_norm(g));
+    let (big_fp, big_gp) = ntru_solve(&fp, &gp)?;
+    let mut big_f = mul_negacyclic(&lift(&big_fp), &galois_conjugate(g));
+    let mut big_g = mul_negacyclic(&lift(&big_gp), &galois_conjugate(f));
+    reduce(f, g, &mut big_f, &mut big_g);
+    Some((big_f, big_g))
+}
+
+// extended Euclid, returning (d, u, v) with u * a + v * b = d = gcd(a, b) >= 0
+fn xgcd(a: &BigInt, b: &BigInt) -> (BigInt, BigInt, BigInt) {
+    let (mut r0, mut r1) = (a.clone(), b.clone());
+    let (mut u0, mut u1) = (BigInt::one(), BigInt::zero());
+    let (mut v0, mut v1) = (BigInt::zero(), BigInt::one());
+
+    while !r1.is_zero() {
+        let quotient = &r0 / &r1;
+        let r2 = &r0 - &quotient * &r1;
+        let u2 = &u0 - &quotient * &u1;
+        let v2 = &v0 - &quotient * &v1;
+        (r0, r1) = (r1, r2);
+        (u0, u1) = (u1, u2);
+        (v0, v1) = (v1, v2);
+    }
+
+    if r0.is_negative() {
+        (-r0, -u0, -v0)
+    } else {
+        (r0, u0, v0)
+    }
+}
+
+// a0^2 - x * a1^2, where a(x) = a0(x^2) + x * a1(x^2), in the ring of half the size
+fn field_norm(a: &[BigInt]) -> Vec<BigInt> {
+    let half = a.len() / 2;
+    let even: Vec<BigInt> = a.iter().step_by(2).cloned().collect();
+    let odd: Vec<BigInt> = a.iter().skip(1).step_by(2).cloned().collect();
+    let even_sq = mul_negacyclic(&even, &even);
+    let odd_sq = mul_negacyclic(&odd, &odd);
+
+    let mut result = even_sq;
+    for i in 0..half - 1 {
+        result[i + 1] -= &odd_sq[i];
+    }
+    result[0] += &odd_sq[half - 1];
+    result
+}
+
+// a(x) -> a(x^2)
+fn lift(a: &[BigInt]) -> Vec<BigInt> {
+    let mut out = vec![BigInt::zero(); 2 * a.len()];
+    for (i, x) in a.iter().enumerate() {
+        out[2 * i] = x.clone();
+    }
+    out
+}
+
+// a(x) -> a(-x)
+fn galois_conjugate(a: &[BigInt]) -> Vec<BigInt> {
+    a.iter().enumerate().map(|(i, x)| if i & 1 == 0 { x.clone() } else { -x }).collect()
+}
+
+// product mod x^n + 1, skipping zero coefficients (half of every lifted operand)
+fn mul_negacyclic(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    let n = a.len();
+    let mut out = vec![BigInt::zero(); n];
+    for (i, x) in a.iter().enumerate() {
+        if x.is_zero() {
+            continue;
+        }
+        for (j, y) in b.iter().enumerate() {
+            if y.is_zero() {
+                continue;
+            }
+            let product = x * y;
+            if i + j < n {
+                out[i + j] += product;
+            } else {
+                out[i + j - n] -= product;
+            }
+        }
+    }
+    out
+}
+
+// bit size rounded up to whole bytes, as in the reference implementation
+fn bitsize(a: &[BigInt]) -> u64 {
+    a.iter().map(|x| x.bits().div_ceil(8) * 8).max().unwrap_or(0)
+}
+
+// top 53 bits of every coefficient as floats, for the FFT
+fn top_bits(a: &[BigInt], size: u64) -> Vec<f64> {
+    let shift = size.saturating_sub(53);
+    a.iter().map(|x| (x >> shift).to_f64().unwrap_or(0.0)).collect()
+}
+
+// Babai reduction of (F, G) against (f, g), shrinking F and G in place
+fn reduce(f: &[BigInt], g: &[BigInt], big_f: &mut [BigInt], big_g: &mut [BigInt]) {
+    let size = bitsize(f).max(bitsize(g)).max(53);
+    let fa_fft = fft(&top_bits(f, size));
+    let ga_fft = fft(&top_bits(g, size));
+    let den_fft = add_fft(&mul_fft(&fa_fft, &adj_fft(&fa_fft)), &mul_fft(&ga_fft, &adj_fft(&ga_fft)));
+
+    loop {
+        let big_size = bitsize(big_f).max(bitsize(big_g)).max(53);
+        if big_size < size {
+            break;
+        }
+
+        let big_fa_fft = fft(&top_bits(big_f, big_size));
+        let big_ga_fft = fft(&top_bits(big_g, big_size));
+        let num_fft = add_fft(
+            &mul_fft(&big_fa_fft, &adj_fft(&fa_fft)),
+            &mul_fft(&big_ga_fft, &adj_fft(&ga_fft)),
+        );
+        let k: Vec<BigInt> = ifft(&div_fft(&num_fft, &den_fft))
+            .iter()
+            .map(|x| BigInt::from(x.round() as i64))
+            .collect();
+        if k.iter().all(|x| x.is_zero()) {
+            break;
+        }
+
+        let fk = mul_negacyclic(f, &k);
+        let gk = mul_negacyclic(g, &k);
+        let shift = big_size - size;
+        for i in 0..big_f.len() {
+            big_f[i] -= &fk[i] << shift;
+            big_g[i] -= &gk[i] << shift;
+        }
+    }
+}
+
+fn to_small(a: &[BigInt]) -> Option<Vec<i64>> {
+    a.iter()
+        .map(|x| x.to_i64().filter(|v| v.abs() <= MAX_FG_COEFF))
+        .collect()
+}
+
+fn mul_small(a: &[i64], b: &[i64]) -> Vec<i64> {
+    let n = a.len();
+    let mut out = vec![0i64; n];
+    for i in 0..n {
+        for j in 0..n {
+            if i + j < n {
+                out[i + j] += a[i] * b[j];
+            } else {
+                out[i + j - n] -= a[i] * b[j];
+            }
+        }
+    }
+    out
+}
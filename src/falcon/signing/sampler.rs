@@ -0,0 +1,225 @@
+// discrete Gaussian sampling for the signer: SamplerZ (Algorithm 15 of the Falcon
+// specification) and the fast Fourier sampler over the LDL tree of the secret basis
+
+use rand::RngCore;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use super::fft::{add_fft, adj_fft, div_fft, merge_fft, mul_fft, split_fft, sub_fft, Complex};
+use super::scratch;
+
+// largest standard deviation the base sampler covers
+const SIGMA_MAX: f64 = 1.8205;
+const INV_2SIGMA2: f64 = 1.0 / (2.0 * SIGMA_MAX * SIGMA_MAX);
+
+// reverse cumulative distribution table of the half-Gaussian at SIGMA_MAX, 72-bit precision
+const RCDT: [u128; 18] = [
+    3024686241123004913666,
+    1564742784480091954050,
+    636254429462080897535,
+    199560484645026482916,
+    47667343854657281903,
+    8595902006365044063,
+    1163297957344668388,
+    117656387352093658,
+    8867391802663976,
+    496969357462633,
+    20680885154299,
+    638331848991,
+    14602316184,
+    247426747,
+    3104126,
+    28824,
+    198,
+    1,
+];
+
+// fixed point coefficients of the polynomial approximating exp(-x) on [0, ln 2]
+const EXP_COEFFS: [u64; 13] = [
+    0x00000004741183A3,
+    0x00000036548CFC06,
+    0x0000024FDCBF140A,
+    0x0000171D939DE045,
+    0x0000D00CF58F6F84,
+    0x000680681CF796E3,
+    0x002D82D8305B0FEA,
+    0x011111110E066FD0,
+    0x0555555555070F00,
+    0x155555555581FF00,
+    0x400000000002B400,
+    0x7FFFFFFFFFFF4800,
+    0x8000000000000000,
+];
+
+// half-Gaussian sample at SIGMA_MAX
+fn base_sampler<R: RngCore + ?Sized>(rng: &mut R) -> i32 {
+    let mut bytes = [0u8; 16];
+    rng.fill_bytes(&mut bytes[..9]);
+    let u = u128::from_le_bytes(bytes);
+    RCDT.iter().map(|&threshold| (u < threshold) as i32).sum()
+}
+
+// 2^63 * ccs * exp(-x) for x in [0, ln 2]
+fn approx_exp(x: f64, ccs: f64) -> u64 {
+    let z = ((1u64 << 63) as f64 * x) as u64;
+    let mut y = EXP_COEFFS[0];
+    for &coeff in &EXP_COEFFS[1..] {
+        y = coeff - ((z as u128 * y as u128) >> 63) as u64;
+    }
+    let z = ((1u64 << 63) as f64 * ccs) as u64;
+    ((z as u128 * y as u128) >> 63) as u64
+}
+
+// true with probability ccs * exp(-x), x >= 0
+fn ber_exp<R: RngCore + ?Sized>(x: f64, ccs: f64, rng: &mut R) -> bool {
+    let s = (x / core::f64::consts::LN_2) as u32;
+    let r = x - s as f64 * core::f64::consts::LN_2;
+    // 2 * approx_exp can be exactly 2^64, which wraps to the intended 2^64 - 1
+    let z = (approx_exp(r, ccs) << 1).wrapping_sub(1) >> s.min(63);
+
+    // compare z against a uniform 64-bit value one byte at a time, high byte first
+    let mut shift = 64;
+    loop {
+        shift -= 8;
+        let mut byte = [0u8; 1];
+        rng.fill_bytes(&mut byte);
+        let w = byte[0] as i32 - ((z >> shift) & 0xFF) as i32;
+        if w != 0 || shift == 0 {
+            return w < 0;
+        }
+    }
+}
+
+// integer sample from the discrete Gaussian centred on mu with deviation sigma
+// sigma must lie in [sigma_min, SIGMA_MAX]
+pub(crate) fn sampler_z<R: RngCore + ?Sized>(mu: f64, sigma: f64, sigma_min: f64, rng: &mut R) -> i64 {
+    let s = mu.floor();
+    let r = mu - s;
+    let dss = 1.0 / (2.0 * sigma * sigma);
+    let ccs = sigma_min / sigma;
+
+    loop {
+        let z0 = base_sampler(rng);
+        let mut byte = [0u8; 1];
+        rng.fill_bytes(&mut byte);
+        let b = (byte[0] & 1) as i32;
+        let z = b + (2 * b - 1) * z0;
+
+        let x = (z as f64 - r).powi(2) * dss - (z0 * z0) as f64 * INV_2SIGMA2;
+        if ber_exp(x, ccs, rng) {
+            return z as i64 + s as i64;
+        }
+    }
+}
+
+// ffLDL tree of a 2x2 Gram matrix (Algorithm 9), leaves already normalized to
+// the standard deviation the sampler uses at that position
+pub(crate) enum LdlTree {
+    Node {
+        l10: Vec<Complex>,
+        left: Box<LdlTree>,
+        right: Box<LdlTree>,
+    },
+    Leaf(f64),
+}
+
+impl LdlTree {
+    // tree of the self-adjoint matrix [[g00, g01], [adj(g01), g11]] in FFT form
+    pub(crate) fn new(g00: &[Complex], g01: &[Complex], g11: &[Complex], sigma: f64) -> Self {
+        // G = L * D * L^*, with L = [[1, 0], [l10, 1]] and D = diag(d00, d11)
+        let l10 = div_fft(&adj_fft(g01), g00);
+        let d11 = scratch(sub_fft(g11, &mul_fft(&mul_fft(&l10, &adj_fft(&l10)), g00)));
+
+        // at size 2 the diagonal entries are real constants
+        if g00.len() == 2 {
+            return LdlTree::Node {
+                l10,
+                left: Box::new(LdlTree::Leaf(sigma / g00[0].re.sqrt())),
+                right: Box::new(LdlTree::Leaf(sigma / d11[0].re.sqrt())),
+            };
+        }
+
+        let (d00_0, d00_1) = split_fft(g00);
+        let (d00_0, d00_1) = (scratch(d00_0), scratch(d00_1));
+        let (d11_0, d11_1) = split_fft(&d11);
+        let (d11_0, d11_1) = (scratch(d11_0), scratch(d11_1));
+        LdlTree::Node {
+            l10,
+            left: Box::new(LdlTree::new(&d00_0, &d00_1, &d00_0, sigma)),
+            right: Box::new(LdlTree::new(&d11_0, &d11_1, &d11_0, sigma)),
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for LdlTree {
+    fn zeroize(&mut self) {
+        match self {
+            LdlTree::Node { l10, left, right } => {
+                l10.zeroize();
+                left.zeroize();
+                right.zeroize();
+            }
+            LdlTree::Leaf(sigma) => sigma.zeroize(),
+        }
+    }
+}
+
+// fast Fourier sampling (Algorithm 11): a lattice point z close to the target (t0, t1)
+pub(crate) fn ff_sampling<R: RngCore + ?Sized>(
+    t0: &[Complex],
+    t1: &[Complex],
+    tree: &LdlTree,
+    sigma_min: f64,
+    rng: &mut R,
+) -> (Vec<Complex>, Vec<Complex>) {
+    match tree {
+        LdlTree::Leaf(sigma) => {
+            let z0 = sampler_z(t0[0].re, *sigma, sigma_min, rng);
+            let z1 = sampler_z(t1[0].re, *sigma, sigma_min, rng);
+            (vec![Complex::new(z0 as f64, 0.0)], vec![Complex::new(z1 as f64, 0.0)])
+        }
+        LdlTree::Node { l10, left, right } => {
+            let (a, b) = split_fft(t1);
+            let (a, b) = (scratch(a), scratch(b));
+            let (za, zb) = ff_sampling(&a, &b, right, sigma_min, rng);
+            let (za, zb) = (scratch(za), scratch(zb));
+            let z1 = merge_fft(&za, &zb);
+
+            // move the first target by the error made on the second
+            let t0b = scratch(add_fft(t0, &mul_fft(&sub_fft(t1, &z1), l10)));
+            let (a, b) = split_fft(&t0b);
+            let (a, b) = (scratch(a), scratch(b));
+            let (za, zb) = ff_sampling(&a, &b, left, sigma_min, rng);
+            let (za, zb) = (scratch(za), scratch(zb));
+            (merge_fft(&za, &zb), z1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_sampler_z_moments() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let (mu, sigma) = (3.4, 1.5);
+        let samples: Vec<i64> = (0..20_000).map(|_| sampler_z(mu, sigma, 1.277, &mut rng)).collect();
+
+        let mean = samples.iter().sum::<i64>() as f64 / samples.len() as f64;
+        let variance = samples.iter().map(|&z| (z as f64 - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!((mean - mu).abs() < 0.05, "mean {}", mean);
+        assert!((variance.sqrt() - sigma).abs() < 0.05, "deviation {}", variance.sqrt());
+    }
+
+    #[test]
+    fn test_approx_exp() {
+        for i in 0..10 {
+            let x = i as f64 * 0.069;
+            let expected = (-x).exp() * (1u64 << 63) as f64;
+            assert!((approx_exp(x, 1.0) as f64 / expected - 1.0).abs() < 1e-12);
+        }
+    }
+}
@@ -1,445 +1,2183 @@
-// Falcon-512 verification for no_std environments
+// Falcon verification for no_std environments
 // Falcon specification and optimized for Solana
+// the entry points take the key and signature as byte slices, read the degree from
+// their headers and run the const-generic internals below at that degree; the
+// vault itself only ever stores Falcon-512 keys
 
-use pinocchio::program_error::ProgramError;
-use core::ops::{Add, Sub, Mul, Neg};
+use super::FalconError;
+use crate::error::VaultError;
+use super::params::{degree_params, max_signature_size, min_signature_size, FalconVariant};
+use super::poly::{polynomial_from_account_bytes, to_account_bytes, Polynomial, NTT_ACCOUNT_BYTES};
+use super::performance::OptimizationConfig;
+#[cfg(feature = "profiling")]
+use super::performance::PerformanceMonitor;
+use super::profile::Profiler;
+use super::ntt::{ct_reduce_once, fast_mod_q, sum_x4, zip_x4, Q};
 
-// Falcon-512 public key and signature sizes
-pub const FALCON_512_PUBLIC_KEY_SIZE: usize = 897;
-pub const FALCON_512_SIGNATURE_SIZE: usize = 666;
-pub const FALCON_512_N: usize = 512;
-pub const FALCON_512_Q: u16 = 12289;
-pub const FALCON_512_LOGN: usize = 9;
-
-// fixed-point arithmetic for no_std compatibility
-const FIXED_POINT_SCALE: i64 = 1 << 32;
+// stage logs for debugging failed verifications from devnet transaction logs
+// with feature "verbose" each call is a sol_log, plus a sol_log_64 for the numbers
+// (printed in hex); without it the macro expands to nothing, so costs no compute units
+#[cfg(feature = "verbose")]
+macro_rules! verbose_log {
+    ($stage:expr) => {
+        pinocchio::log::sol_log($stage)
+    };
+    ($label:expr, $a:expr, $b:expr) => {{
+        pinocchio::log::sol_log($label);
+        pinocchio::log::sol_log_64($a, $b, 0, 0, 0);
+    }};
+}
 
-// convert floating point to fixed point for bounds checking
-const fn float_to_fixed(f: f64) -> i64 {
-    (f * FIXED_POINT_SCALE as f64) as i64
+#[cfg(not(feature = "verbose"))]
+macro_rules! verbose_log {
+    ($($arg:tt)*) => {};
 }
 
-// Falcon-512 signature bound (converted to fixed point)
-const FALCON_512_SIG_BOUND_FIXED: i64 = float_to_fixed(34034726.0);
+// Falcon-512 public key and signature sizes, see FalconVariant for other degrees
+pub const FALCON_512_PUBLIC_KEY_SIZE: usize = FalconVariant::FALCON_512.public_key_size;
+pub const FALCON_512_SIGNATURE_SIZE: usize = FalconVariant::FALCON_512.signature_size;
+pub const FALCON_512_N: usize = FalconVariant::FALCON_512.n;
+pub const FALCON_512_Q: u16 = FalconVariant::FALCON_512.q as u16;
+pub const FALCON_512_LOGN: usize = FalconVariant::FALCON_512.logn as usize;
+pub const FALCON_512_NONCE_SIZE: usize = 40;
 
-// field element in Z_q
-#[derive(Clone, Copy, Debug, PartialEq)]
-struct FieldElement(u16);
+// the one signature header byte parse_signature accepts at n = 512: encoding 2 in the
+// top three bits, the fixed bit, then logn = 9, giving 0x59. every parser and the
+// signer use this instead of spelling the byte out
+pub const FALCON_512_SIGNATURE_HEADER: u8 = (2 << 5) | (1 << 4) | FALCON_512_LOGN as u8;
 
-impl FieldElement {
-    fn new(value: u16) -> Self {
-        Self(value % FALCON_512_Q)
-    }
-    
-    fn value(self) -> u16 {
-        self.0
-    }
-    
-    fn balanced_value(self) -> i16 {
-        let v = self.0 as i32;
-        if v > (FALCON_512_Q as i32) / 2 {
-            (v - FALCON_512_Q as i32) as i16
-        } else {
-            v as i16
-        }
-    }
-}
+// size of a message digest accepted by verify_falcon_prehashed
+pub const FALCON_PREHASH_DIGEST_SIZE: usize = 64;
 
-impl Add for FieldElement {
-    type Output = Self;
-    fn add(self, other: Self) -> Self {
-        Self::new(self.0 + other.0)
-    }
-}
+// the cSHAKE256 function name (SP 800-185) every input but a raw message without a
+// context hashes under
+pub const FALCON_VAULT_FUNCTION_NAME: &[u8; 12] = b"FALCON-VAULT";
 
-impl Sub for FieldElement {
-    type Output = Self;
-    fn sub(self, other: Self) -> Self {
-        Self::new(self.0 + FALCON_512_Q - other.0)
-    }
-}
+// which path a hash input is on. a raw message without a context gets the specification's
+// hash-to-point, SHAKE256(nonce || message), so standard Falcon signatures verify on it.
+// everything else runs cSHAKE256 named FALCON_VAULT_FUNCTION_NAME, with the domain byte
+// and then the context as customization string. cSHAKE256 absorbs both, length-prefixed,
+// ahead of the nonce and pads differently from SHAKE256, so no two paths or contexts hash
+// alike, whatever the message bytes
+pub const FALCON_RAW_DOMAIN: u8 = 0;
+pub const FALCON_PREHASH_DOMAIN: u8 = 1;
 
-impl Mul for FieldElement {
-    type Output = Self;
-    fn mul(self, other: Self) -> Self {
-        Self::new(((self.0 as u32 * other.0 as u32) % FALCON_512_Q as u32) as u16)
-    }
-}
+// longest context, so the customization string fits a fixed buffer
+pub const FALCON_MAX_CONTEXT_SIZE: usize = 255;
 
-impl Neg for FieldElement {
-    type Output = Self;
-    fn neg(self) -> Self {
-        if self.0 == 0 {
-            self
-        } else {
-            Self(FALCON_512_Q - self.0)
-        }
-    }
-}
+// default cap on the message verification hashes, see OptimizationConfig::max_message_len.
+// SHAKE256 costs compute per byte, so without one a verify-only caller could burn its
+// budget on a huge message; the program's own messages are under 100 bytes
+pub const FALCON_MAX_MESSAGE_SIZE: usize = 64 * 1024;
 
-// polynomial in the ring Z_q[X]/(X^n + 1)
-#[derive(Clone)]
-struct Polynomial {
-    coeffs: [FieldElement; FALCON_512_N],
+// Falcon-512 signature bound on ||s1||^2 + ||s2||^2
+// the norm is an integer, so no fixed-point scaling is needed; at most
+// 1024 * 6144^2 ~ 3.9e10, which fits comfortably in an i64
+pub const FALCON_512_SIG_BOUND: i64 = FalconVariant::FALCON_512.sig_bound as i64;
+
+// what hash_to_point absorbs besides the nonce: an optional context ahead of it and the
+// message parts after it, on the path its domain names
+#[derive(Clone, Copy)]
+pub(crate) struct HashInput<'a> {
+    context: &'a [u8],
+    domain: u8,
+    message_parts: &'a [&'a [u8]],
 }
 
-impl Polynomial {
-    fn zero() -> Self {
-        Self {
-            coeffs: [FieldElement(0); FALCON_512_N],
-        }
-    }
-    
-    fn from_coeffs(coeffs: [FieldElement; FALCON_512_N]) -> Self {
-        Self { coeffs }
-    }
-    
-    fn from_signed_coeffs(signed_coeffs: &[i16; FALCON_512_N]) -> Self {
-        let mut coeffs = [FieldElement(0); FALCON_512_N];
-        for i in 0..FALCON_512_N {
-            let val = ((signed_coeffs[i] as i32 % FALCON_512_Q as i32 + FALCON_512_Q as i32) % FALCON_512_Q as i32) as u16;
-            coeffs[i] = FieldElement(val);
-        }
-        Self::from_coeffs(coeffs)
-    }
-    
-    // NTT transformation (forward)
-    fn ntt(&self) -> Self {
-        let mut coeffs_u32 = [0u32; FALCON_512_N];
-        
-        // convert to u32 representation
-        for i in 0..FALCON_512_N {
-            coeffs_u32[i] = self.coeffs[i].value() as u32;
-        }
-        
-        // perform NTT
-        super::ntt::ntt_forward(&mut coeffs_u32);
-        
-        // convert back to FieldElement
-        let mut result_coeffs = [FieldElement(0); FALCON_512_N];
-        for i in 0..FALCON_512_N {
-            result_coeffs[i] = FieldElement::new(coeffs_u32[i] as u16);
-        }
-        
-        Self::from_coeffs(result_coeffs)
+impl<'a> HashInput<'a> {
+    // a raw message, without a context
+    pub(crate) fn message(message_parts: &'a [&'a [u8]]) -> Self {
+        Self { context: &[], domain: FALCON_RAW_DOMAIN, message_parts }
     }
-    
-    // Inverse NTT transformation
-    fn intt(&self) -> Self {
-        let mut coeffs_u32 = [0u32; FALCON_512_N];
-        
-        // Convert to u32 representation
-        for i in 0..FALCON_512_N {
-            coeffs_u32[i] = self.coeffs[i].value() as u32;
-        }
-        
-        // perform inverse NTT
-        super::ntt::ntt_inverse(&mut coeffs_u32);
-        
-        // convert back to FieldElement
-        let mut result_coeffs = [FieldElement(0); FALCON_512_N];
-        for i in 0..FALCON_512_N {
-            result_coeffs[i] = FieldElement::new(coeffs_u32[i] as u16);
-        }
-        
-        Self::from_coeffs(result_coeffs)
+
+    // a digest on the pre-hashed path, as prehash_parts gives it
+    pub(crate) fn prehashed(digest_parts: &'a [&'a [u8]; 1]) -> Self {
+        Self { context: &[], domain: FALCON_PREHASH_DOMAIN, message_parts: digest_parts }
     }
-    
-    // pointwise multiplication in NTT domain
-    fn pointwise_mul(&self, other: &Self) -> Self {
-        let mut a_coeffs = [0u32; FALCON_512_N];
-        let mut b_coeffs = [0u32; FALCON_512_N];
-        let mut result_coeffs = [0u32; FALCON_512_N];
-        
-        // convert to u32 representation
-        for i in 0..FALCON_512_N {
-            a_coeffs[i] = self.coeffs[i].value() as u32;
-            b_coeffs[i] = other.coeffs[i].value() as u32;
-        }
-        
-        // perform pointwise multiplication
-        super::ntt::ntt_pointwise_mul(&a_coeffs, &b_coeffs, &mut result_coeffs);
-        
-        // convert back to FieldElement
-        let mut result_field_coeffs = [FieldElement(0); FALCON_512_N];
-        for i in 0..FALCON_512_N {
-            result_field_coeffs[i] = FieldElement::new(result_coeffs[i] as u16);
-        }
-        
-        Self::from_coeffs(result_field_coeffs)
+
+    // the same input under a context, at most FALCON_MAX_CONTEXT_SIZE bytes
+    pub(crate) fn with_context(self, context: &'a [u8]) -> Self {
+        Self { context, ..self }
     }
 }
 
-impl Add for Polynomial {
-    type Output = Self;
-    fn add(self, other: Self) -> Self {
-        let mut result = Self::zero();
-        for i in 0..FALCON_512_N {
-            result.coeffs[i] = self.coeffs[i] + other.coeffs[i];
-        }
-        result
-    }
+// what the pre-hashed path absorbs after the nonce: the digest alone, the path itself is
+// named in the customization string
+pub(crate) fn prehash_parts(digest: &[u8; FALCON_PREHASH_DIGEST_SIZE]) -> [&[u8]; 1] {
+    [digest]
 }
 
-impl Sub for Polynomial {
-    type Output = Self;
-    fn sub(self, other: Self) -> Self {
-        let mut result = Self::zero();
-        for i in 0..FALCON_512_N {
-            result.coeffs[i] = self.coeffs[i] - other.coeffs[i];
-        }
-        result
+// MessageTooLong if the parts add up to more than max_len bytes
+fn check_message_len(message_parts: &[&[u8]], max_len: usize) -> Result<(), FalconError> {
+    let len = message_parts.iter().fold(0usize, |len, part| len.saturating_add(part.len()));
+    if len > max_len {
+        return Err(VaultError::MessageTooLong.into());
     }
+    Ok(())
 }
 
 // hash message to a point in the lattice
 // implementing algorithm 3 from Falcon specification
-fn hash_to_point(message: &[u8], nonce: &[u8; 40]) -> Polynomial {
+pub(crate) fn hash_to_point<const N: usize>(message_parts: &[&[u8]], nonce: &[u8; 40]) -> Polynomial<N> {
+    hash_to_point_with_context(&[], message_parts, nonce)
+}
+
+// hash_to_point under a context, see FALCON_RAW_DOMAIN for how it is absorbed
+// the context must be at most FALCON_MAX_CONTEXT_SIZE bytes, which callers check
+pub(crate) fn hash_to_point_with_context<const N: usize>(
+    context: &[u8],
+    message_parts: &[&[u8]],
+    nonce: &[u8; 40],
+) -> Polynomial<N> {
+    hash_input_to_point(HashInput::message(message_parts).with_context(context), nonce)
+}
+
+// hash_to_point for any input, the pre-hashed path's included
+pub(crate) fn hash_input_to_point<const N: usize>(input: HashInput, nonce: &[u8; 40]) -> Polynomial<N> {
+    let mut c = Polynomial::zero();
+    let coeffs = c.coefficients_mut();
+    hash_to_point_each::<N>(input, nonce, |i, coeff| coeffs[i] = coeff);
+    c
+}
+
+// the coefficients of hash_to_point as they are sampled, index and value in [0, q),
+// for callers that consume c without keeping it in a buffer of its own
+fn hash_to_point_each<const N: usize>(input: HashInput, nonce: &[u8; 40], mut emit: impl FnMut(usize, u32)) {
     const K: u32 = (1u32 << 16) / FALCON_512_Q as u32;
     
-    let mut hasher = super::keccak::Shake256::new();
+    // streamed rather than super::keccak::shake256, rejection sampling doesn't know up
+    // front how many bytes it will read. cSHAKE256 with an empty name and customization
+    // is plain SHAKE256
+    let mut customization = [0u8; 1 + FALCON_MAX_CONTEXT_SIZE];
+    let mut hasher = if input.domain == FALCON_RAW_DOMAIN && input.context.is_empty() {
+        super::keccak::CShake256::new(&[], &[])
+    } else {
+        let len = 1 + input.context.len();
+        customization[0] = input.domain;
+        customization[1..len].copy_from_slice(input.context);
+        super::keccak::CShake256::new(FALCON_VAULT_FUNCTION_NAME, &customization[..len])
+    };
     hasher.update(nonce);
-    hasher.update(message);
+    // parts are absorbed back to back, same as hashing their concatenation
+    for part in input.message_parts {
+        hasher.update(part);
+    }
     let mut reader = hasher.finalize_xof();
     
-    let mut coeffs = [FieldElement(0); FALCON_512_N];
     let mut i = 0;
     
-    while i < FALCON_512_N {
-        let mut randomness = [0u8; 2];
-        reader.read(&mut randomness);
-        
-        let t = ((randomness[0] as u32) << 8) | (randomness[1] as u32);
+    while i < N {
+        let t = u16::from_be_bytes(reader.read_array()) as u32;
         if t < K * FALCON_512_Q as u32 {
-            coeffs[i] = FieldElement::new((t % FALCON_512_Q as u32) as u16);
+            emit(i, fast_mod_q(t));
             i += 1;
         }
     }
-    
-    Polynomial::from_coeffs(coeffs)
 }
 
-//decompress Falcon signature from compressed format
+// decompress Falcon signature from compressed format
 // implementation of Algorithm 18 from Falcon specifications
-fn decompress_signature(compressed: &[u8]) -> Result<[i16; FALCON_512_N], ProgramError> {
-    let mut result = [0i16; FALCON_512_N];
+// bits are read most significant first; each coefficient is a sign bit, the low 7 bits
+// of its absolute value, then the high bits in unary (a 0 per multiple of 128, closed
+// by a 1). "-0" is rejected and every bit after the last coefficient must be zero,
+// so a given s2 has exactly one encoding
+// with early_termination set, bails out as soon as ||s2||^2 alone reaches the
+// signature bound, saving the NTT pipeline on signatures that can never verify
+pub(crate) fn decompress_signature<const N: usize>(
+    compressed: &[u8],
+    early_termination: bool,
+) -> Result<[i16; N], FalconError> {
+    let mut result = [0i16; N];
+    decompress_each::<N>(compressed, early_termination, |i, coeff| result[i] = coeff)?;
+    Ok(result)
+}
+
+// decompress_signature handing each coefficient to emit as it is decoded,
+// returns ||s2||^2 and the number of bits the coefficients took
+fn decompress_each<const N: usize>(
+    compressed: &[u8],
+    early_termination: bool,
+    mut emit: impl FnMut(usize, i16),
+) -> Result<(u64, usize), FalconError> {
+    let bound = const { degree_params(N).sig_bound as i64 };
+    let total_bits = compressed.len() * 8;
     let mut bit_pos = 0;
+    let mut s2_norm_squared = 0i64;
+    let mut decoded = 0;
     
-    for i in 0..FALCON_512_N {
-        // read sign bit
-        if bit_pos / 8 >= compressed.len() {
-            return Err(ProgramError::InvalidAccountData);
+    while decoded < N {
+        // the payload ended between two coefficients, see the count check below
+        if bit_pos == total_bits {
+            break;
         }
-        
-        let byte_idx = bit_pos / 8;
-        let bit_idx = bit_pos % 8;
-        let sign = if (compressed[byte_idx] >> bit_idx) & 1 == 1 { -1 } else { 1 };
-        bit_pos += 1;
-        
-        // read value bits (variable length encoding)
+        // sign bit and low 7 bits
+        if bit_pos + 8 > total_bits {
+            return Err(VaultError::DecompressionOutOfBounds.into());
+        }
+        let negative = read_bit(compressed, bit_pos) == 1;
         let mut value = 0i16;
-        let mut shift = 0;
+        for j in 1..8 {
+            value = (value << 1) | read_bit(compressed, bit_pos + j) as i16;
+        }
+        bit_pos += 8;
         
-        // read 7 bits at a time until we hit a continuation bit
+        // high bits in unary
         loop {
-            if bit_pos / 8 >= compressed.len() {
-                return Err(ProgramError::InvalidAccountData);
-            }
-            
-            let mut byte_value = 0;
-            for _ in 0..7 {
-                if bit_pos / 8 >= compressed.len() {
-                    return Err(ProgramError::InvalidAccountData);
-                }
-                let byte_idx = bit_pos / 8;
-                let bit_idx = bit_pos % 8;
-                byte_value |= ((compressed[byte_idx] >> bit_idx) & 1) << (bit_pos % 7);
-                bit_pos += 1;
-            }
-            
-            value |= (byte_value as i16) << shift;
-            shift += 7;
-            
-            // check continuation bit
-            if bit_pos / 8 >= compressed.len() {
-                return Err(ProgramError::InvalidAccountData);
+            if bit_pos >= total_bits {
+                return Err(VaultError::DecompressionOutOfBounds.into());
             }
-            let byte_idx = bit_pos / 8;
-            let bit_idx = bit_pos % 8;
-            let continuation = (compressed[byte_idx] >> bit_idx) & 1;
+            let bit = read_bit(compressed, bit_pos);
             bit_pos += 1;
             
-            if continuation == 0 {
+            if bit == 1 {
                 break;
             }
             
-            if shift > 14 { // this prevents overflow
-                return Err(ProgramError::InvalidAccountData);
+            value += 128;
+            if value > 2047 {
+                return Err(VaultError::CoefficientTooLarge.into());
             }
         }
         
-        result[i] = sign * value;
-        
-        //check for coefficient bounds
-        if result[i].abs() > 2048 {
-            return Err(ProgramError::InvalidAccountData);
+        if negative && value == 0 {
+            return Err(VaultError::NonCanonicalEncoding.into());
+        }
+        let coeff = if negative { -value } else { value };
+        emit(decoded, coeff);
+        decoded += 1;
+
+        // running partial norm, s2 alone can exceed the bound
+        s2_norm_squared += coeff as i64 * coeff as i64;
+        if early_termination && !cfg!(feature = "skip_norm_check") && s2_norm_squared >= bound {
+            return Err(VaultError::NormBoundExceeded.into());
+        }
+    }
+
+    // an s2 short of N coefficients would leave the rest of the caller's array at zero,
+    // which reads as a valid (and small) signature
+    if decoded != N {
+        return Err(VaultError::IncompleteSignature.into());
+    }
+
+    check_padding(compressed, bit_pos)?;
+    Ok((s2_norm_squared as u64, bit_pos))
+}
+
+// only zero padding may follow the coefficients: the rest of the byte the last one
+// ends in, then whole zero bytes up to the fixed payload size. anything else would
+// let the same s2 be sent as many different signatures
+fn check_padding(compressed: &[u8], bits_consumed: usize) -> Result<(), FalconError> {
+    let partial = match bits_consumed % 8 {
+        0 => 0,
+        used => compressed[bits_consumed / 8] & (0xFF >> used),
+    };
+    if partial != 0 || compressed[bits_consumed.div_ceil(8)..].iter().any(|&b| b != 0) {
+        return Err(VaultError::TrailingSignatureBits.into());
+    }
+    Ok(())
+}
+
+// bit at bit_pos, counting from the most significant bit of the first byte
+#[inline]
+fn read_bit(data: &[u8], bit_pos: usize) -> u8 {
+    (data[bit_pos / 8] >> (7 - bit_pos % 8)) & 1
+}
+
+// encode s2 in the layout decompress_signature reads, zero padded to the payload size
+// returns None if the encoding doesn't fit. values past 2047, which the decoder
+// rejects, are still encoded so tests can build such payloads
+#[cfg(any(test, feature = "signing"))]
+pub(crate) fn compress_signature(coeffs: &[i16; FALCON_512_N]) -> Option<[u8; FALCON_512_SIGNATURE_SIZE - 41]> {
+    let mut out = [0u8; FALCON_512_SIGNATURE_SIZE - 41];
+    compress_into(coeffs, &mut out).then_some(out)
+}
+
+// compress_signature into a zeroed payload buffer of any length, false if it doesn't fit
+#[cfg(any(test, feature = "signing"))]
+pub(crate) fn compress_into(coeffs: &[i16], out: &mut [u8]) -> bool {
+    let total_bits = out.len() * 8;
+    let mut bit_pos = 0;
+
+    for &coeff in coeffs.iter() {
+        let value = coeff.unsigned_abs();
+        let high = (value >> 7) as usize;
+        if bit_pos + 8 + high + 1 > total_bits {
+            return false;
+        }
+
+        // sign bit and low 7 bits, then high zeros and the closing 1
+        let head = (((coeff < 0) as u8) << 7) | (value & 0x7F) as u8;
+        for j in 0..8 {
+            out[(bit_pos + j) / 8] |= ((head >> (7 - j)) & 1) << (7 - (bit_pos + j) % 8);
+        }
+        bit_pos += 8 + high;
+        out[bit_pos / 8] |= 1 << (7 - bit_pos % 8);
+        bit_pos += 1;
+    }
+
+    true
+}
+
+// pack coefficients in [0, q) into the public key layout: 14 bits each, most
+// significant bit first, as the Falcon specification encodes h
+#[cfg(feature = "signing")]
+pub(crate) fn pack_public_key(coeffs: &[u16; FALCON_512_N]) -> [u8; FALCON_512_PUBLIC_KEY_SIZE] {
+    let mut bytes = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
+    pack_public_key_into(coeffs, &mut bytes);
+    bytes
+}
+
+// pack_public_key for any degree, the header is log2 of the coefficient count
+#[cfg(any(test, feature = "signing"))]
+pub(crate) fn pack_public_key_into(coeffs: &[u16], bytes: &mut [u8]) {
+    bytes[0] = coeffs.len().trailing_zeros() as u8;
+    for (i, &coeff) in coeffs.iter().enumerate() {
+        for j in 0..14 {
+            let bit = i * 14 + j;
+            bytes[1 + bit / 8] |= (((coeff >> (13 - j)) & 1) as u8) << (7 - bit % 8);
         }
     }
-    
-    Ok(result)
 }
 
 // parse public key from bytes
-fn parse_public_key(pk_bytes: &[u8; FALCON_512_PUBLIC_KEY_SIZE]) -> Result<Polynomial, ProgramError> {
+// an empty slice is a short key like any other, not a missing header
+pub(crate) fn parse_public_key<const N: usize>(pk_bytes: &[u8]) -> Result<Polynomial<N>, FalconError> {
     //header
-    let header = pk_bytes[0];
-    if header != FALCON_512_LOGN as u8 {
-        return Err(ProgramError::InvalidAccountData);
+    let (&header, coeff_bytes) = pk_bytes.split_first().ok_or(FalconError::InvalidAccountData)?;
+    if header != const { degree_params(N).logn } {
+        return Err(VaultError::BadPublicKeyHeader.into());
     }
     
-    //parse polynomial coefficients (14 bits each, little-endian packed)
-    let mut coeffs = [FieldElement(0); FALCON_512_N];
-    let data = &pk_bytes[1..]; // skips header
+    //parse polynomial coefficients, skipping the header
+    let coeffs = unpack_coefficients::<N>(coeff_bytes)?;
     
-    for i in 0..FALCON_512_N {
-        let bit_offset = i * 14;
-        let byte_offset = bit_offset / 8;
-        let bit_pos = bit_offset % 8;
-        
-        if byte_offset + 2 >= data.len() {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        
-        // read the 14 bits spanning potentially 3 bytes
+    Ok(Polynomial::from_u16_array(&coeffs))
+}
+
+// read N coefficients of 14 bits each, most significant bit first
+// values are returned as stored, without any range check
+fn unpack_coefficients<const N: usize>(data: &[u8]) -> Result<[u16; N], FalconError> {
+    let mut coeffs = [0u16; N];
+    unpack_coefficients_each::<N>(data, |i, coeff| coeffs[i] = coeff)?;
+    Ok(coeffs)
+}
+
+// bytes taken by n coefficients of 14 bits
+const fn packed_coefficient_bytes(n: usize) -> usize {
+    (n * 14).div_ceil(8)
+}
+
+const _: () = assert!(packed_coefficient_bytes(FALCON_512_N) == FALCON_512_PUBLIC_KEY_SIZE - 1);
+const _: () = assert!(
+    packed_coefficient_bytes(FalconVariant::FALCON_1024.n) == FalconVariant::FALCON_1024.public_key_size - 1
+);
+
+// unpack_coefficients handing each value to emit instead of collecting them
+fn unpack_coefficients_each<const N: usize>(data: &[u8], mut emit: impl FnMut(usize, u16)) -> Result<(), FalconError> {
+    // coefficient i occupies bits [14i, 14i + 13], so the last one ends in byte
+    // (14(N - 1) + 13) / 8 = ceil(14N / 8) - 1; for N = 512 that is byte 895 of 896,
+    // exactly the key minus its header. checked once up front, so a short key is
+    // rejected before any coefficient is emitted and every read below is in bounds
+    if data.len() < packed_coefficient_bytes(N) {
+        return Err(FalconError::InvalidAccountData);
+    }
+
+    for i in 0..N {
+        // the 14 bits span up to 3 bytes, read in the same order as the signature's
         let mut coeff = 0u16;
         for j in 0..14 {
-            let curr_bit_pos = bit_pos + j;
-            let curr_byte_offset = byte_offset + curr_bit_pos / 8;
-            let curr_bit_idx = curr_bit_pos % 8;
-            
-            if curr_byte_offset < data.len() {
-                let bit = (data[curr_byte_offset] >> curr_bit_idx) & 1;
-                coeff |= (bit as u16) << j;
-            }
+            coeff = (coeff << 1) | read_bit(data, i * 14 + j) as u16;
         }
         
-        coeffs[i] = FieldElement::new(coeff);
+        emit(i, coeff);
     }
     
-    Ok(Polynomial::from_coeffs(coeffs))
+    Ok(())
+}
+
+// unpack the raw 14-bit public key coefficients with strict range checks
+// unlike parse_public_key this rejects values >= q instead of reducing them
+// 512 * 14 bits fill the 896 data bytes exactly, so there are no padding bits
+// and the range check is the only canonicality condition
+pub(crate) fn unpack_public_key(pk_bytes: &[u8; FALCON_512_PUBLIC_KEY_SIZE]) -> Result<[u16; FALCON_512_N], VaultError> {
+    const _: () = assert!(FALCON_512_N * 14 == (FALCON_512_PUBLIC_KEY_SIZE - 1) * 8);
+
+    if pk_bytes[0] != FALCON_512_LOGN as u8 {
+        return Err(VaultError::BadPublicKeyHeader);
+    }
+
+    let data = &pk_bytes[1..];
+    let mut coeffs = [0u16; FALCON_512_N];
+    let mut acc = 0u32;
+    let mut acc_bits = 0;
+    let mut byte_idx = 0;
+
+    for coeff in coeffs.iter_mut() {
+        // most significant bit first: new bytes shift in below the bits still unread
+        while acc_bits < 14 {
+            acc = (acc << 8) | data[byte_idx] as u32;
+            byte_idx += 1;
+            acc_bits += 8;
+        }
+
+        acc_bits -= 14;
+        let value = ((acc >> acc_bits) & 0x3FFF) as u16;
+        acc &= (1 << acc_bits) - 1;
+
+        if value >= FALCON_512_Q {
+            return Err(VaultError::PublicKeyCoefficientOutOfRange);
+        }
+        *coeff = value;
+    }
+
+    Ok(coeffs)
+}
+
+// where the 40-byte nonce sits in a signature. NonceFirst is the Falcon specification's
+// [header, nonce, compressed s2] and what every function here expects unless it takes
+// a layout; NonceLast is [header, compressed s2, nonce], as some tooling writes it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureLayout {
+    NonceFirst,
+    NonceLast,
+}
+
+//parse signature from bytes, for a degree N signature
+pub(crate) fn parse_signature<const N: usize>(sig_bytes: &[u8]) -> Result<([u8; 40], &[u8]), FalconError> {
+    parse_signature_with_layout::<N>(sig_bytes, SignatureLayout::NonceFirst)
 }
 
-//parse signature from bytes
-fn parse_signature(sig_bytes: &[u8; FALCON_512_SIGNATURE_SIZE]) -> Result<([u8; 40], &[u8]), ProgramError> {
+// parse_signature in either layout: the length and header checks are the same, only
+// where the nonce is split off differs
+pub(crate) fn parse_signature_with_layout<const N: usize>(
+    sig_bytes: &[u8],
+    layout: SignatureLayout,
+) -> Result<([u8; 40], &[u8]), FalconError> {
+    // a length no degree N signature can have, an empty slice included
+    let logn = const { degree_params(N).logn as usize };
+    if !(min_signature_size(logn)..=max_signature_size(logn)).contains(&sig_bytes.len()) {
+        return Err(FalconError::InvalidAccountData);
+    }
+
     // chek header
     let header = sig_bytes[0];
     let encoding_type = (header >> 5) & 7;
     let fixed_bit = (header >> 4) & 1;
     let logn = header & 15;
     
-    if encoding_type != 2 || fixed_bit != 1 || logn != FALCON_512_LOGN as u8 {
-        return Err(ProgramError::InvalidAccountData);
+    if encoding_type != 2 || fixed_bit != 1 || logn != const { degree_params(N).logn } {
+        return Err(VaultError::BadSignatureHeader.into());
     }
     
     //extract nonce and compressed signature
-    if sig_bytes.len() < 41 {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    
+    let (nonce_bytes, compressed_sig) = match layout {
+        SignatureLayout::NonceFirst => sig_bytes[1..].split_at(FALCON_512_NONCE_SIZE),
+        SignatureLayout::NonceLast => {
+            let (compressed_sig, nonce_bytes) = sig_bytes[1..].split_at(sig_bytes.len() - 1 - FALCON_512_NONCE_SIZE);
+            (nonce_bytes, compressed_sig)
+        }
+    };
     let mut nonce = [0u8; 40];
-    nonce.copy_from_slice(&sig_bytes[1..41]);
-    let compressed_sig = &sig_bytes[41..];
-    
+    nonce.copy_from_slice(nonce_bytes);
+    check_nonce(&nonce)?;
+
     Ok((nonce, compressed_sig))
 }
 
-// this is main Falcon-512 verification function
+// a real signer draws the nonce at random, all zeros means a broken or test signer
+fn check_nonce(nonce: &[u8; 40]) -> Result<(), FalconError> {
+    if nonce.iter().all(|&b| b == 0) {
+        return Err(VaultError::WeakNonce.into());
+    }
+    Ok(())
+}
+
+// this is main Falcon verification function
 // verification algorithm from the Falcon specification
+// the degree comes from the key and signature headers, see falcon_variant
+// this is the variant the on-chain processors use: it stops at the first malformed
+// field, which saves compute units on bad signatures, and on-chain every input is
+// public anyway so the early exit leaks nothing. see verify_falcon_signature_ct
+// for off-chain callers where timing matters. messages over FALCON_MAX_MESSAGE_SIZE
+// are MessageTooLong. the signature is [header, nonce, compressed s2], see
+// verify_falcon_signature_with_layout for signatures with the nonce at the end
 pub fn verify_falcon_signature(
-    public_key_bytes: &[u8; FALCON_512_PUBLIC_KEY_SIZE],
-    signature_bytes: &[u8; FALCON_512_SIGNATURE_SIZE],
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
     message: &[u8],
-) -> Result<(), ProgramError> {
-    // parse public key
-    let h = parse_public_key(public_key_bytes)?;
-    
-    //parse signature
-    let (nonce, compressed_sig) = parse_signature(signature_bytes)?;
-    
-    // decompress signature to get s2
-    let s2_coeffs = decompress_signature(compressed_sig)?;
-    
-    // convert s2 to polynomial
-    let s2 = Polynomial::from_signed_coeffs(&s2_coeffs);
-    
-    // hash message to point
-    let c = hash_to_point(message, &nonce);
-    
-    // compute s1 = c - s2 * h (in NTT domain, for efficiency)
-    let c_ntt = c.ntt();
-    let s2_ntt = s2.ntt();
-    let h_ntt = h.ntt();
-    
-    let s2h_ntt = s2_ntt.pointwise_mul(&h_ntt);
-    let s1_ntt = c_ntt - s2h_ntt;
-    let s1 = s1_ntt.intt();
-    
-    //extract signed coefficients for norm check
-    let mut s1_signed = [0i16; FALCON_512_N];
-    for i in 0..FALCON_512_N {
-        s1_signed[i] = s1.coeffs[i].balanced_value();
+) -> Result<(), FalconError> {
+    verify_falcon_signature_with_config(
+        public_key_bytes,
+        signature_bytes,
+        message,
+        &OptimizationConfig::default(),
+    )
+}
+
+// verification with explicit optimization settings
+// config.early_termination enables the partial s2 norm check during decompression,
+// config.use_lookup_tables runs the NTTs on the compile-time twiddle tables,
+// config.max_message_len rejects longer messages before they are hashed
+pub fn verify_falcon_signature_with_config(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    message: &[u8],
+    config: &OptimizationConfig,
+) -> Result<(), FalconError> {
+    verify_parts_with_config(public_key_bytes, signature_bytes, &[message], config)
+}
+
+// verify_falcon_signature for a signature in the given layout, so one written nonce
+// last verifies as it is instead of being rearranged first. the layout has to be
+// known: a nonce and an s2 payload are both arbitrary bytes, so it can't be detected
+pub fn verify_falcon_signature_with_layout(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    message: &[u8],
+    layout: SignatureLayout,
+) -> Result<(), FalconError> {
+    verify_parts_profiled(
+        public_key_bytes,
+        signature_bytes,
+        HashInput::message(&[message]),
+        &OptimizationConfig::default(),
+        layout,
+        &mut Profiler::none(),
+    )
+}
+
+// verification over a message split into parts, like solana_nostd_sha256::hashv
+// the parts are fed to SHAKE256 in order, so callers don't need to copy them into one buffer
+pub fn verify_falcon_signature_parts(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    message_parts: &[&[u8]],
+) -> Result<(), FalconError> {
+    verify_parts_with_config(
+        public_key_bytes,
+        signature_bytes,
+        message_parts,
+        &OptimizationConfig::default(),
+    )
+}
+
+// verification of a signature bound to a context, e.g. one naming the program it's for
+// the context is absorbed before the nonce (see FALCON_RAW_DOMAIN), so a signature
+// only verifies under the context it was made with. an empty context is the same as
+// verify_falcon_signature_parts; longer than FALCON_MAX_CONTEXT_SIZE is InvalidArgument
+pub fn verify_falcon_signature_with_context(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    context: &[u8],
+    message_parts: &[&[u8]],
+) -> Result<(), FalconError> {
+    if context.len() > FALCON_MAX_CONTEXT_SIZE {
+        return Err(FalconError::InvalidArgument);
     }
-    
-    // compute L2 norm squared: ||s1||^2 + ||s2||^2
-    let mut norm_squared_fixed = 0i64;
-    
-    // adding ||s1||^2
-    for i in 0..FALCON_512_N {
-        let s1_val = s1_signed[i] as i64;
-        norm_squared_fixed += s1_val * s1_val * FIXED_POINT_SCALE;
+    verify_parts_profiled(
+        public_key_bytes,
+        signature_bytes,
+        HashInput::message(message_parts).with_context(context),
+        &OptimizationConfig::default(),
+        SignatureLayout::NonceFirst,
+        &mut Profiler::none(),
+    )
+}
+
+// digest of a message for the pre-hashed path: 64 bytes of SHAKE256 over the parts in order
+pub fn prehash_message(message_parts: &[&[u8]]) -> [u8; FALCON_PREHASH_DIGEST_SIZE] {
+    super::keccak::shake256_array(message_parts)
+}
+
+// verification of a signature made over a message digest instead of the message itself
+// hash_to_point absorbs the digest under cSHAKE256 with FALCON_PREHASH_DOMAIN in the
+// customization string (see FALCON_RAW_DOMAIN), so large messages can be hashed off-chain; see
+// prehash_message for the digest the program computes
+pub fn verify_falcon_prehashed(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+) -> Result<(), FalconError> {
+    verify_falcon_prehashed_with_context(public_key_bytes, signature_bytes, &[], digest)
+}
+
+// verify_falcon_prehashed for a signature made under a context, which is absorbed the
+// same way verify_falcon_signature_with_context absorbs it
+pub fn verify_falcon_prehashed_with_context(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    context: &[u8],
+    digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+) -> Result<(), FalconError> {
+    if context.len() > FALCON_MAX_CONTEXT_SIZE {
+        return Err(FalconError::InvalidArgument);
     }
-    
-    // adding ||s2||^2
-    for i in 0..FALCON_512_N {
-        let s2_val = s2_coeffs[i] as i64;
-        norm_squared_fixed += s2_val * s2_val * FIXED_POINT_SCALE;
+    verify_parts_profiled(
+        public_key_bytes,
+        signature_bytes,
+        HashInput::prehashed(&prehash_parts(digest)).with_context(context),
+        &OptimizationConfig::default(),
+        SignatureLayout::NonceFirst,
+        &mut Profiler::none(),
+    )
+}
+
+// verification of a signature that arrives already split into its nonce and compressed s2,
+// e.g. from two instruction fields, so nothing has to be reassembled behind a header byte
+// (the parts here are the signature's; see verify_falcon_signature_parts for split messages)
+// the degree comes from the key header, and the body may be shorter than the padded size
+// since decompression reads only the bits it needs
+pub fn verify_falcon_parts(
+    public_key_bytes: &[u8],
+    nonce: &[u8; FALCON_512_NONCE_SIZE],
+    compressed: &[u8],
+    message: &[u8],
+) -> Result<(), FalconError> {
+    let variant = public_key_bytes
+        .first()
+        .and_then(|&header| FalconVariant::from_logn(header))
+        .ok_or(VaultError::BadPublicKeyHeader)?;
+    let params = variant.params();
+    if public_key_bytes.len() != params.public_key_size {
+        return Err(FalconError::InvalidAccountData);
     }
-    
+    if compressed.len() > params.signature_size - 1 - FALCON_512_NONCE_SIZE {
+        return Err(FalconError::InvalidInstructionData);
+    }
+    check_nonce(nonce)?;
+
+    let config = OptimizationConfig::default();
+    let message_parts = [message];
+    let input = HashInput::message(&message_parts);
+    match variant {
+        FalconVariant::Falcon512 => VerifyScratch::<{ FalconVariant::FALCON_512.n }>::new()
+            .verify_split(public_key_bytes, nonce, compressed, input, &config, &mut Profiler::none()),
+        #[cfg(not(target_os = "solana"))]
+        FalconVariant::Falcon1024 => VerifyScratch::<{ FalconVariant::FALCON_1024.n }>::new()
+            .verify_split(public_key_bytes, nonce, compressed, input, &config, &mut Profiler::none()),
+        // see verify_parts_profiled
+        #[cfg(target_os = "solana")]
+        FalconVariant::Falcon1024 => Err(VaultError::BadPublicKeyHeader.into()),
+    }
+}
+
+// the parameter set a key and signature are for, read from the logn in their headers
+// both must name a supported degree, the same one, and be exactly that degree's size
+pub fn falcon_variant(public_key_bytes: &[u8], signature_bytes: &[u8]) -> Result<FalconVariant, FalconError> {
+    let sig_variant = signature_bytes
+        .first()
+        .and_then(|&header| FalconVariant::from_logn(header & 15))
+        .ok_or(VaultError::BadSignatureHeader)?;
+    let pk_variant = public_key_bytes
+        .first()
+        .and_then(|&header| FalconVariant::from_logn(header))
+        .ok_or(VaultError::BadPublicKeyHeader)?;
+    if pk_variant != sig_variant {
+        return Err(VaultError::LogNMismatch.into());
+    }
+
+    let params = pk_variant.params();
+    if public_key_bytes.len() != params.public_key_size {
+        return Err(FalconError::InvalidAccountData);
+    }
+    if signature_bytes.len() != params.signature_size {
+        return Err(FalconError::InvalidInstructionData);
+    }
+    Ok(pk_variant)
+}
+
+fn verify_parts_with_config(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    message_parts: &[&[u8]],
+    config: &OptimizationConfig,
+) -> Result<(), FalconError> {
+    let input = HashInput::message(message_parts);
+    verify_parts_profiled(
+        public_key_bytes,
+        signature_bytes,
+        input,
+        config,
+        SignatureLayout::NonceFirst,
+        &mut Profiler::none(),
+    )
+}
+
+// verify_falcon_signature with what each stage cost recorded into monitor: compute
+// units on Solana, nanoseconds off-chain. the stages are VERIFY_STAGES
+#[cfg(feature = "profiling")]
+pub fn verify_falcon_signature_profiled(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    message: &[u8],
+    monitor: &mut PerformanceMonitor,
+) -> Result<(), FalconError> {
+    verify_parts_profiled(
+        public_key_bytes,
+        signature_bytes,
+        HashInput::message(&[message]),
+        &OptimizationConfig::default(),
+        SignatureLayout::NonceFirst,
+        &mut Profiler::new(monitor),
+    )
+}
+
+// the frame that holds the scratch: never inlined, so every caller's locals stay out
+// of it and it is the scratch plus the few locals VERIFY_STACK_BYTES measured
+#[inline(never)]
+fn verify_parts_profiled(
+    public_key_bytes: &[u8],
+    signature_bytes: &[u8],
+    input: HashInput,
+    config: &OptimizationConfig,
+    layout: SignatureLayout,
+    profiler: &mut Profiler,
+) -> Result<(), FalconError> {
+    match falcon_variant(public_key_bytes, signature_bytes)? {
+        FalconVariant::Falcon512 => VerifyScratch::<{ FalconVariant::FALCON_512.n }>::new().verify_profiled(
+            public_key_bytes,
+            signature_bytes,
+            input,
+            config,
+            layout,
+            profiler,
+        ),
+        #[cfg(not(target_os = "solana"))]
+        FalconVariant::Falcon1024 => VerifyScratch::<{ FalconVariant::FALCON_1024.n }>::new().verify_profiled(
+            public_key_bytes,
+            signature_bytes,
+            input,
+            config,
+            layout,
+            profiler,
+        ),
+        // a 6 KB scratch can't fit the 4 KB SBF stack frame, and neither can the 1024
+        // twiddles of a per-call NTT next to anything else, so the program refuses
+        // Falcon-1024 keys and only verifies Falcon-512; off-chain both degrees work
+        #[cfg(target_os = "solana")]
+        FalconVariant::Falcon1024 => Err(VaultError::BadPublicKeyHeader.into()),
+    }
+}
+
+// all the working memory of one verification, passed by reference through the pipeline
+// so the peak is this struct plus a few locals: s2 is decoded and c is sampled straight
+// into these buffers instead of into arrays of their own. 3 KB at n = 512, under the
+// 4 KB SBF stack frame
+pub struct VerifyScratch<const N: usize = FALCON_512_N> {
+    // the public key, h in the NTT domain, then s2 * h and finally s1
+    poly: Polynomial<N>,
+    // s2 reduced mod q while the key is transformed, then h in the NTT domain;
+    // every value is below q, so u16 is enough
+    aux: [u16; N],
+}
+
+// an in-place NTT or inverse NTT, see use_lookup_tables in verify_split
+type Transform<const N: usize> = fn(&mut Polynomial<N>);
+
+impl<const N: usize> VerifyScratch<N> {
+    pub fn new() -> Self {
+        Self { poly: Polynomial::zero(), aux: [0; N] }
+    }
+
+    // verify_falcon_signature_parts at degree N, reusing these buffers
+    pub fn verify(
+        &mut self,
+        public_key_bytes: &[u8],
+        signature_bytes: &[u8],
+        message_parts: &[&[u8]],
+        config: &OptimizationConfig,
+    ) -> Result<(), FalconError> {
+        let input = HashInput::message(message_parts);
+        self.verify_profiled(
+            public_key_bytes,
+            signature_bytes,
+            input,
+            config,
+            SignatureLayout::NonceFirst,
+            &mut Profiler::none(),
+        )
+    }
+
+    fn verify_profiled(
+        &mut self,
+        public_key_bytes: &[u8],
+        signature_bytes: &[u8],
+        input: HashInput,
+        config: &OptimizationConfig,
+        layout: SignatureLayout,
+        profiler: &mut Profiler,
+    ) -> Result<(), FalconError> {
+        // the same size and header checks as the dispatching entry points, then the degree
+        if falcon_variant(public_key_bytes, signature_bytes)?.params().n != N {
+            return Err(VaultError::LogNMismatch.into());
+        }
+
+        //parse signature
+        let (nonce, compressed_sig) = parse_signature_with_layout::<N>(signature_bytes, layout)?;
+        verbose_log!("falcon: parse sig ok");
+        profiler.checkpoint("signature_parsing");
+
+        self.verify_split(public_key_bytes, &nonce, compressed_sig, input, config, profiler)
+    }
+
+    // the rest of verify, from a nonce and compressed s2 that are already split out
+    // the key must already be checked to be a degree N key of the right size.
+    // never inlined so its locals (the SHAKE256 state above all) get a frame of their
+    // own instead of adding to the frame that holds the scratch, see VERIFY_STACK_BYTES
+    #[inline(never)]
+    fn verify_split(
+        &mut self,
+        public_key_bytes: &[u8],
+        nonce: &[u8; 40],
+        compressed_sig: &[u8],
+        input: HashInput,
+        config: &OptimizationConfig,
+        profiler: &mut Profiler,
+    ) -> Result<(), FalconError> {
+        check_message_len(input.message_parts, config.max_message_len)?;
+
+        // decompress signature to get s2
+        // done before touching the public key so oversized signatures fail cheaply
+        let q = const { degree_params(N).q as i32 };
+        let aux = &mut self.aux;
+        let (s2_norm, _) = decompress_each::<N>(compressed_sig, config.early_termination, |i, coeff| {
+            aux[i] = (coeff as i32).rem_euclid(q) as u16
+        })?;
+        verbose_log!("falcon: decompress ok");
+        profiler.checkpoint("signature_decompression");
+
+        // parse public key and move it to the NTT domain in place
+        unpack_coefficients_each::<N>(&public_key_bytes[1..], |i, coeff| {
+            self.poly.coefficients_mut()[i] = fast_mod_q(coeff as u32)
+        })?;
+        verbose_log!("falcon: parse pk ok");
+        profiler.checkpoint("public_key_parsing");
+        // config.use_lookup_tables picks the compile-time twiddle tables over per-call ones
+        let (ntt, intt): (Transform<N>, Transform<N>) = if config.use_lookup_tables {
+            (Polynomial::ntt_in_place_precomputed, Polynomial::intt_in_place_precomputed)
+        } else {
+            (Polynomial::ntt_in_place, Polynomial::intt_in_place)
+        };
+        ntt(&mut self.poly);
+        verbose_log!("falcon: ntt done");
+        profiler.checkpoint("ntt_forward_transforms");
+
+        // swap, leaving s2 in poly and h_ntt in aux
+        // a plain loop, zip_x4 only lends its second buffer shared
+        for (coeff, value) in self.poly.iter_mut().zip(self.aux.iter_mut()) {
+            (*coeff, *value) = (*value as u32, *coeff as u16);
+        }
+
+        // s2 * h, through the NTT domain
+        ntt(&mut self.poly);
+        profiler.checkpoint("ntt_forward_transforms");
+        zip_x4(self.poly.coefficients_mut(), &self.aux, |coeff, &h| *coeff = fast_mod_q(*coeff * h as u32));
+        profiler.checkpoint("ntt_pointwise_operations");
+        // no norm gate before this: NTT coefficients are residues mod q with no Parseval
+        // relation to the balanced norm (x has norm 1 and NTT values all over [0, q)), so
+        // an estimate that never rejects a valid signature can never reject anything.
+        // the cheap reject is the s2 bound in decompress_each
+        intt(&mut self.poly);
+        profiler.checkpoint("ntt_inverse_transform");
+
+        // s1 = c - s2 * h, with c subtracted as it is sampled
+        let s1 = self.poly.coefficients_mut();
+        hash_to_point_each::<N>(input, nonce, |i, c| s1[i] = ct_reduce_once(c + Q - s1[i]));
+        profiler.checkpoint("shake256_hash_to_point");
+
+        let result = check_norm::<N>(self.poly.squared_norm() + s2_norm);
+        profiler.checkpoint("l2_norm_verification");
+        result
+    }
+}
+
+impl<const N: usize> Default for VerifyScratch<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// check s1 = c - s2 * h against the norm bound, given an already transformed h
+fn verify_decoded<const N: usize>(
+    h_ntt: &Polynomial<N>,
+    nonce: &[u8; 40],
+    s2_coeffs: &[i16; N],
+    message_parts: &[&[u8]],
+) -> Result<(), FalconError> {
+    check_norm::<N>(signature_norm(h_ntt, nonce, s2_coeffs, message_parts))
+}
+
+// the final check on ||s1||^2 + ||s2||^2
+fn check_norm<const N: usize>(norm_squared: u64) -> Result<(), FalconError> {
+    let bound = const { degree_params(N).sig_bound };
+    verbose_log!("falcon: norm, bound", norm_squared, bound);
+
+    // UNSAFE, test builds only: every signature that parses is accepted, see skip_norm_check in Cargo.toml
+    if cfg!(feature = "skip_norm_check") {
+        return Ok(());
+    }
+
     // signature bound
-    if norm_squared_fixed >= FALCON_512_SIG_BOUND_FIXED {
-        return Err(ProgramError::InvalidAccountData);
+    if norm_squared >= bound {
+        return Err(VaultError::NormBoundExceeded.into());
     }
     
     Ok(())
 }
 
-// NTT (Number Theoretic Transform) operation
-// on mainnet, this would perform the actual NTT transformation
-#[allow(dead_code)]
-fn ntt_forward(_coeffs: &mut [u32; FALCON_512_N]) {
-    // TODO: implement forward NTT
-    // this involves bit-reversal and butterfly operations
-    // so we're using precomputed twiddle factors
-}
-
-// Inverse NTT operation
-#[allow(dead_code)]
-fn ntt_inverse(_coeffs: &mut [u32; FALCON_512_N]) {
-    // TODO: Implement inverse NTT
-    // this is similar to forward NTT but with different twiddle factors
-    // and a final scaling step
+// ||s1||^2 + ||s2||^2 where s1 = c - s2 * h
+// besides h_ntt this needs two more polynomial buffers: s2 * h, computed in place
+// from s2, and c; s1 is then written over s2 * h
+fn signature_norm<const N: usize>(
+    h_ntt: &Polynomial<N>,
+    nonce: &[u8; 40],
+    s2_coeffs: &[i16; N],
+    message_parts: &[&[u8]],
+) -> u64 {
+    // compute L2 norm squared: ||s1||^2 + ||s2||^2
+    super::internals::squared_norm(&compute_s1(h_ntt, nonce, s2_coeffs, message_parts), s2_coeffs)
 }
 
-// modular reduction... modulo q = 12289
-#[allow(dead_code)]
-fn mod_q(x: u32) -> u32 {
-    // barrett reduction for q = 12289
-    const Q: u32 = 12289;
-    const BARRETT_SHIFT: u32 = 26;
-    const BARRETT_MULTIPLIER: u32 = 5467;
+// s1 = c - s2 * h in balanced representation
+fn compute_s1<const N: usize>(
+    h_ntt: &Polynomial<N>,
+    nonce: &[u8; 40],
+    s2_coeffs: &[i16; N],
+    message_parts: &[&[u8]],
+) -> [i16; N] {
+    // s2 * h, through the NTT domain
+    let mut s1 = Polynomial::from_signed_coeffs(s2_coeffs);
+    s1.ntt_in_place();
+    s1.mul_assign_pointwise(h_ntt);
+    s1.intt_in_place();
     
-    let t = ((x as u64 * BARRETT_MULTIPLIER as u64) >> BARRETT_SHIFT) as u32;
-    let r = x - t * Q;
+    // s1 = c - s2 * h
+    // subtracting in the time domain is the same as in the NTT domain, and saves transforming c
+    // c only lives for this statement
+    s1.rsub_assign(&hash_to_point(message_parts, nonce));
     
-    if r >= Q { r - Q } else { r }
-} 
\ No newline at end of file
+    s1.balanced()
+}
+
+// how many of the largest s1 and s2 coefficients NormDiagnostics reports
+#[cfg(feature = "diagnostics")]
+pub const DIAGNOSTIC_TOP_COEFFS: usize = 8;
+
+// what a norm bound failure looked like, for telling a genuinely oversized
+// signature apart from an NTT or decompression bug during development
+#[cfg(feature = "diagnostics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormDiagnostics {
+    pub norm_squared: u64,
+    pub bound: u64,
+    // (index, value) pairs, largest magnitude first
+    pub largest_s1: [(u16, i16); DIAGNOSTIC_TOP_COEFFS],
+    pub largest_s2: [(u16, i16); DIAGNOSTIC_TOP_COEFFS],
+}
+
+// same checks as verify_falcon_signature, but s2 is always decoded in full and a
+// norm failure comes back with a NormDiagnostics; other failures carry None
+#[cfg(feature = "diagnostics")]
+pub fn verify_with_diagnostics(
+    public_key_bytes: &[u8; FALCON_512_PUBLIC_KEY_SIZE],
+    signature_bytes: &[u8; FALCON_512_SIGNATURE_SIZE],
+    message: &[u8],
+) -> Result<(), (FalconError, Option<NormDiagnostics>)> {
+    let (nonce, compressed_sig) = parse_signature::<FALCON_512_N>(signature_bytes).map_err(|e| (e, None))?;
+    let s2_coeffs = decompress_signature(compressed_sig, false).map_err(|e| (e, None))?;
+    let mut h = parse_public_key(public_key_bytes).map_err(|e| (e, None))?;
+    h.ntt_in_place();
+
+    let s1_coeffs = compute_s1(&h, &nonce, &s2_coeffs, &[message]);
+    let norm_squared = super::internals::squared_norm(&s1_coeffs, &s2_coeffs);
+    if norm_squared < FALCON_512_SIG_BOUND as u64 {
+        return Ok(());
+    }
+
+    let diagnostics = NormDiagnostics {
+        norm_squared,
+        bound: FALCON_512_SIG_BOUND as u64,
+        largest_s1: largest_coefficients(&s1_coeffs),
+        largest_s2: largest_coefficients(&s2_coeffs),
+    };
+    Err((VaultError::NormBoundExceeded.into(), Some(diagnostics)))
+}
+
+// the DIAGNOSTIC_TOP_COEFFS largest coefficients by magnitude, ties keep the lower index first
+#[cfg(feature = "diagnostics")]
+fn largest_coefficients(coeffs: &[i16; FALCON_512_N]) -> [(u16, i16); DIAGNOSTIC_TOP_COEFFS] {
+    let mut top = [(0u16, 0i16); DIAGNOSTIC_TOP_COEFFS];
+    let mut len = 0;
+
+    for (index, &value) in coeffs.iter().enumerate() {
+        // position among the current top entries, skipped if it doesn't make the cut
+        let mut pos = len;
+        while pos > 0 && value.unsigned_abs() > top[pos - 1].1.unsigned_abs() {
+            pos -= 1;
+        }
+        if pos == DIAGNOSTIC_TOP_COEFFS {
+            continue;
+        }
+
+        len = (len + 1).min(DIAGNOSTIC_TOP_COEFFS);
+        top.copy_within(pos..len - 1, pos + 1);
+        top[pos] = (index as u16, value);
+    }
+
+    top
+}
+
+// public key parsed and transformed to the NTT domain once,
+// so it can be reused across several verifications
+pub struct PreparedPublicKey {
+    h_ntt: Polynomial,
+}
+
+impl PreparedPublicKey {
+    pub fn new(public_key_bytes: &[u8; FALCON_512_PUBLIC_KEY_SIZE]) -> Result<Self, FalconError> {
+        let mut h_ntt = parse_public_key(public_key_bytes)?;
+        h_ntt.ntt_in_place();
+        Ok(Self { h_ntt })
+    }
+
+    // h_ntt in the poly::to_account_bytes format, so it can be stored once and reloaded
+    // instead of transforming the key again
+    pub fn to_account_bytes(&self) -> [u8; NTT_ACCOUNT_BYTES] {
+        to_account_bytes(self.h_ntt.coefficients())
+    }
+
+    // reload a stored h_ntt, checked by poly::from_account_bytes
+    pub fn from_account_bytes(bytes: &[u8]) -> Result<Self, FalconError> {
+        Ok(Self { h_ntt: polynomial_from_account_bytes(bytes)? })
+    }
+}
+
+// verify several (signature, message) pairs against one public key
+// the key is parsed and transformed once instead of once per signature
+// on failure returns the index of the first item that didn't verify;
+// a malformed public key is reported against index 0
+pub fn verify_falcon_batch(
+    public_key_bytes: &[u8; FALCON_512_PUBLIC_KEY_SIZE],
+    items: &[(&[u8; FALCON_512_SIGNATURE_SIZE], &[u8])],
+) -> Result<(), (usize, FalconError)> {
+    let public_key = PreparedPublicKey::new(public_key_bytes).map_err(|e| (0, e))?;
+    verify_falcon_batch_prepared(&public_key, items)
+}
+
+// batch verification with a precomputed h_ntt
+pub fn verify_falcon_batch_prepared(
+    public_key: &PreparedPublicKey,
+    items: &[(&[u8; FALCON_512_SIGNATURE_SIZE], &[u8])],
+) -> Result<(), (usize, FalconError)> {
+    let config = OptimizationConfig::default();
+
+    for (index, (signature_bytes, message)) in items.iter().enumerate() {
+        check_message_len(&[message], config.max_message_len).map_err(|e| (index, e))?;
+        let (nonce, compressed_sig) = parse_signature::<FALCON_512_N>(*signature_bytes).map_err(|e| (index, e))?;
+        let s2_coeffs = decompress_signature(compressed_sig, config.early_termination)
+            .map_err(|e| (index, e))?;
+        verify_decoded(&public_key.h_ntt, &nonce, &s2_coeffs, &[message]).map_err(|e| (index, e))?;
+    }
+
+    Ok(())
+}
+
+// verify one signature against several candidate keys, e.g. the old and new key during
+// a rotation, returning the index of the first key it verifies under
+// decompression, hash_to_point and the NTT of s2 only depend on the signature and
+// message, so they run once; each candidate costs one NTT of h and one inverse NTT.
+// a malformed key is an error, not a skipped candidate
+pub fn verify_falcon_any(
+    public_keys: &[&[u8; FALCON_512_PUBLIC_KEY_SIZE]],
+    signature_bytes: &[u8; FALCON_512_SIGNATURE_SIZE],
+    message: &[u8],
+) -> Result<usize, FalconError> {
+    if public_keys.is_empty() {
+        return Err(FalconError::InvalidArgument);
+    }
+
+    let config = OptimizationConfig::default();
+    check_message_len(&[message], config.max_message_len)?;
+    let (nonce, compressed_sig) = parse_signature::<FALCON_512_N>(signature_bytes)?;
+    let s2_coeffs = decompress_signature::<FALCON_512_N>(compressed_sig, config.early_termination)?;
+    let s2_norm = sum_x4(&s2_coeffs, |&v| (v as i64 * v as i64) as u64);
+
+    let c = hash_to_point::<FALCON_512_N>(&[message], &nonce);
+    let mut s2_ntt = Polynomial::from_signed_coeffs(&s2_coeffs);
+    s2_ntt.ntt_in_place();
+
+    for (index, public_key_bytes) in public_keys.iter().enumerate() {
+        // s1 = c - s2 * h, computed over h's own buffer
+        let mut s1 = parse_public_key::<FALCON_512_N>(&public_key_bytes[..])?;
+        s1.ntt_in_place();
+        s1.mul_assign_pointwise(&s2_ntt);
+        s1.intt_in_place();
+        s1.rsub_assign(&c);
+
+        // UNSAFE, test builds only, see verify_decoded
+        if cfg!(feature = "skip_norm_check") || s1.squared_norm() + s2_norm < FALCON_512_SIG_BOUND as u64 {
+            return Ok(index);
+        }
+    }
+
+    Err(VaultError::NormBoundExceeded.into())
+}
+
+// constant-time variant of verify_falcon_signature for off-chain use (clients, wasm)
+// every stage runs to completion whatever the input, failures are accumulated into a
+// flag instead of returned early, and the only branch on the outcome is the final one.
+// the number of operations is fixed, but memory access still follows the decoded bit
+// positions, and hash_to_point's rejection sampling depends on the (public) message
+// and nonce. any failure is reported as VaultError::InvalidSignature so the error
+// doesn't reveal which stage rejected the signature
+pub fn verify_falcon_signature_ct(
+    public_key_bytes: &[u8; FALCON_512_PUBLIC_KEY_SIZE],
+    signature_bytes: &[u8; FALCON_512_SIGNATURE_SIZE],
+    message: &[u8],
+) -> Result<(), FalconError> {
+    let mut failed = 0u8;
+
+    // the three header fields checked by parse_signature pin the byte to one value
+    failed |= ct_is_nonzero(signature_bytes[0] ^ FALCON_512_SIGNATURE_HEADER);
+
+    let mut nonce = [0u8; FALCON_512_NONCE_SIZE];
+    nonce.copy_from_slice(&signature_bytes[1..41]);
+    let nonce_bits = nonce.iter().fold(0u8, |acc, &b| acc | b);
+    failed |= ct_is_nonzero(nonce_bits) ^ 1;
+
+    let mut compressed = [0u8; FALCON_512_SIGNATURE_SIZE - 41];
+    compressed.copy_from_slice(&signature_bytes[41..]);
+    #[cfg_attr(not(feature = "zeroize"), allow(unused_mut))]
+    let (mut s2_coeffs, decode_failed) = decompress_signature_ct(&compressed);
+    failed |= decode_failed;
+
+    failed |= ct_is_nonzero(public_key_bytes[0] ^ FALCON_512_LOGN as u8);
+    // only fails on a short buffer, which the fixed key size rules out
+    let mut h = Polynomial::from_u16_array(&unpack_coefficients(&public_key_bytes[1..])?);
+    h.ntt_in_place();
+
+    let norm_squared = signature_norm(&h, &nonce, &s2_coeffs, &[message]);
+    // top bit of norm - bound is set exactly when norm < bound
+    failed |= ((norm_squared.wrapping_sub(FALCON_512_SIG_BOUND as u64) >> 63) as u8) ^ 1;
+
+    // off-chain callers may not want the decoded signature left on the stack
+    #[cfg(feature = "zeroize")]
+    zeroize::Zeroize::zeroize(&mut s2_coeffs);
+
+    if failed != 0 {
+        return Err(VaultError::InvalidSignature.into());
+    }
+
+    Ok(())
+}
+
+// 1 if x != 0, else 0, without branching
+#[inline]
+fn ct_is_nonzero(x: u8) -> u8 {
+    ((0u16.wrapping_sub(x as u16) >> 8) & 1) as u8
+}
+
+// read one bit, most significant first, returning (bit, 1 if the position is past the end)
+// an out of range read is clamped to the last byte and discarded
+#[inline]
+fn ct_read_bit(data: &[u8; FALCON_512_SIGNATURE_SIZE - 41], bit_pos: usize) -> (u8, u8) {
+    let byte_idx = bit_pos / 8;
+    let in_bounds = (byte_idx < data.len()) as u8;
+    let byte = data[byte_idx.min(data.len() - 1)] & 0u8.wrapping_sub(in_bounds);
+    ((byte >> (7 - bit_pos % 8)) & 1, in_bounds ^ 1)
+}
+
+// constant-time counterpart of decompress_signature
+// every coefficient reads its sign and low bits plus the 16 positions its unary part
+// can span before decompress_signature gives up; positions past the closing 1 are
+// read but masked out and don't advance the position. returns the coefficients and
+// 1 if decompress_signature would have failed
+fn decompress_signature_ct(compressed: &[u8; FALCON_512_SIGNATURE_SIZE - 41]) -> ([i16; FALCON_512_N], u8) {
+    let mut result = [0i16; FALCON_512_N];
+    let mut bit_pos = 0;
+    let mut failed = 0u8;
+
+    for coeff in result.iter_mut() {
+        let (sign_bit, out_of_bounds) = ct_read_bit(compressed, bit_pos);
+        failed |= out_of_bounds;
+        bit_pos += 1;
+
+        let mut value = 0i16;
+        for _ in 0..7 {
+            let (bit, out_of_bounds) = ct_read_bit(compressed, bit_pos);
+            failed |= out_of_bounds;
+            value = (value << 1) | bit as i16;
+            bit_pos += 1;
+        }
+
+        // 1 until the closing 1 of the unary part has been read
+        let mut active = 1u8;
+        for _ in 0..16 {
+            let (bit, out_of_bounds) = ct_read_bit(compressed, bit_pos);
+            failed |= out_of_bounds & active;
+            let zero = (bit ^ 1) & active;
+            value += (zero as i16) << 7;
+            bit_pos += active as usize;
+            active = zero;
+        }
+
+        // no closing 1 within 16 positions means a value past 2047
+        failed |= active;
+        // "-0"
+        failed |= sign_bit & (ct_is_nonzero(value as u8 | (value >> 8) as u8) ^ 1);
+
+        // sign bit set means negative: multiply by 1 - 2 * sign_bit
+        *coeff = value.wrapping_mul(1 - 2 * sign_bit as i16);
+    }
+
+    // nonzero padding, checked over every bit with the position only used in a mask
+    for position in 0..compressed.len() * 8 {
+        let (bit, _) = ct_read_bit(compressed, position);
+        let past_end = ((bit_pos.wrapping_sub(position + 1) as isize) >> (isize::BITS - 1)) as u8 & 1;
+        failed |= bit & past_end;
+    }
+
+    (result, failed)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::falcon::poly::tests::{live_buffers, peak_buffer_bytes};
+
+    // header with encoding type 2, fixed bit set and logn = 9
+    pub(crate) const SIGNATURE_HEADER: u8 = FALCON_512_SIGNATURE_HEADER;
+
+    // compress_signature for coefficients known to fit
+    pub(crate) fn compress_coefficients(coeffs: &[i16; FALCON_512_N]) -> [u8; FALCON_512_SIGNATURE_SIZE - 41] {
+        compress_signature(coeffs).expect("coefficients don't fit the payload")
+    }
+
+    // every coefficient set to 128, ten bits each: the payload runs out after 500 of them
+    pub(crate) fn overlong_payload() -> [u8; FALCON_512_SIGNATURE_SIZE - 41] {
+        let mut payload = [0u8; FALCON_512_SIGNATURE_SIZE - 41];
+        let mut bit = 9;
+        while bit / 8 < payload.len() {
+            payload[bit / 8] |= 0x80 >> (bit % 8);
+            bit += 10;
+        }
+        payload
+    }
+
+    // build a (public key, signature) pair that verifies for `message`
+    // with s2 = 1 the verification equation s1 = c - s2 * h is solved by h = c,
+    // giving s1 = 0 -- no trapdoor needed, but the key only fits this message
+    pub(crate) fn signed_fixture(
+        message: &[u8],
+        nonce: &[u8; 40],
+    ) -> ([u8; FALCON_512_PUBLIC_KEY_SIZE], [u8; FALCON_512_SIGNATURE_SIZE]) {
+        fixture_arrays(signed_fixture_for::<FALCON_512_N>(message, nonce))
+    }
+
+    // signed_fixture at any supported degree, sized by its FalconParams
+    pub(crate) fn signed_fixture_for<const N: usize>(message: &[u8], nonce: &[u8; 40]) -> (Vec<u8>, Vec<u8>) {
+        input_fixture::<N>(HashInput::message(&[message]), nonce)
+    }
+
+    // signed_fixture for a message signed under a context
+    pub(crate) fn context_fixture(
+        context: &[u8],
+        message: &[u8],
+    ) -> ([u8; FALCON_512_PUBLIC_KEY_SIZE], [u8; FALCON_512_SIGNATURE_SIZE]) {
+        let parts = [message];
+        fixture_arrays(input_fixture::<FALCON_512_N>(HashInput::message(&parts).with_context(context), &FIXTURE_NONCE))
+    }
+
+    // signed_fixture for a digest on the pre-hashed path, under a context or none
+    pub(crate) fn prehashed_fixture(
+        context: &[u8],
+        digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+    ) -> ([u8; FALCON_512_PUBLIC_KEY_SIZE], [u8; FALCON_512_SIGNATURE_SIZE]) {
+        let parts = prehash_parts(digest);
+        fixture_arrays(input_fixture::<FALCON_512_N>(HashInput::prehashed(&parts).with_context(context), &FIXTURE_NONCE))
+    }
+
+    fn fixture_arrays(
+        (public_key, signature): (Vec<u8>, Vec<u8>),
+    ) -> ([u8; FALCON_512_PUBLIC_KEY_SIZE], [u8; FALCON_512_SIGNATURE_SIZE]) {
+        (public_key.try_into().unwrap(), signature.try_into().unwrap())
+    }
+
+    fn input_fixture<const N: usize>(input: HashInput, nonce: &[u8; 40]) -> (Vec<u8>, Vec<u8>) {
+        let s2 = fixture_s2::<N>(|i| (i == 0) as i16);
+        build_fixture::<N>(input, nonce, &s2)
+    }
+
+    fn fixture_s2<const N: usize>(coeff: impl Fn(usize) -> i16) -> [i16; N] {
+        core::array::from_fn(coeff)
+    }
+
+    // key h = c for `message`, and a signature carrying `s2`
+    fn build_fixture<const N: usize>(input: HashInput, nonce: &[u8; 40], s2: &[i16; N]) -> (Vec<u8>, Vec<u8>) {
+        let params = const { degree_params(N) };
+        let h = hash_input_to_point::<N>(input, nonce).as_u16_array();
+
+        let mut public_key = vec![0u8; params.public_key_size];
+        pack_public_key_into(&h, &mut public_key);
+
+        let mut signature = vec![0u8; params.signature_size];
+        signature[0] = (2 << 5) | (1 << 4) | params.logn;
+        signature[1..41].copy_from_slice(nonce);
+        assert!(compress_into(s2, &mut signature[41..]), "coefficients don't fit the payload");
+
+        (public_key, signature)
+    }
+
+    pub(crate) const FIXTURE_NONCE: [u8; 40] = [0x5A; 40];
+
+    // s2 with a handful of near-maximal coefficients followed by zeros
+    pub(crate) fn oversized_s2() -> [i16; FALCON_512_N] {
+        let mut coeffs = [0i16; FALCON_512_N];
+        for (i, coeff) in coeffs.iter_mut().take(16).enumerate() {
+            *coeff = if i % 2 == 0 { 2047 } else { -2047 };
+        }
+        coeffs
+    }
+
+    // every coefficient position, the last one ending on the final key byte included,
+    // comes back from a full-size key; one byte short is an error rather than a short read
+    #[test]
+    fn test_unpack_coefficients_bounds() {
+        let mut coeffs = [0u16; FALCON_512_N];
+        for (i, coeff) in coeffs.iter_mut().enumerate() {
+            *coeff = ((i * 7919) % FALCON_512_Q as usize) as u16;
+        }
+        coeffs[FALCON_512_N - 1] = FALCON_512_Q - 1;
+        let mut key = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
+        pack_public_key_into(&coeffs, &mut key);
+
+        assert_eq!(unpack_coefficients::<FALCON_512_N>(&key[1..]), Ok(coeffs));
+        assert_eq!(
+            parse_public_key::<FALCON_512_N>(&key).unwrap().as_u16_array(),
+            coeffs
+        );
+
+        // the last coefficient's top bits sit in the final byte
+        key[FALCON_512_PUBLIC_KEY_SIZE - 1] ^= 0x80;
+        assert_ne!(unpack_coefficients::<FALCON_512_N>(&key[1..]).unwrap()[FALCON_512_N - 1], coeffs[FALCON_512_N - 1]);
+
+        assert_eq!(
+            unpack_coefficients::<FALCON_512_N>(&key[1..FALCON_512_PUBLIC_KEY_SIZE - 1]),
+            Err(FalconError::InvalidAccountData)
+        );
+        assert!(parse_public_key::<FALCON_512_N>(&key[..FALCON_512_PUBLIC_KEY_SIZE - 1]).is_err());
+        assert_eq!(unpack_coefficients::<FALCON_512_N>(&[]), Err(FalconError::InvalidAccountData));
+        assert_eq!(parse_public_key::<FALCON_512_N>(&[]).err(), Some(FalconError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_decompress_roundtrip() {
+        let mut coeffs = [0i16; FALCON_512_N];
+        for (i, coeff) in coeffs.iter_mut().enumerate() {
+            *coeff = (i as i16 % 41) - 20;
+        }
+
+        let compressed = compress_coefficients(&coeffs);
+        assert_eq!(decompress_signature(&compressed, true).unwrap(), coeffs);
+    }
+
+    #[test]
+    fn test_decompress_consumes_payload() {
+        // 392 coefficients of 128 at ten bits and 120 zeros at nine fill all 5000 bits
+        let mut exact = [0i16; FALCON_512_N];
+        exact[..392].fill(128);
+        let compressed = compress_coefficients(&exact);
+        assert_eq!(compressed.len() * 8, 5000);
+        let (_, bits_consumed) = decompress_each::<FALCON_512_N>(&compressed, false, |_, _| {}).unwrap();
+        assert_eq!(bits_consumed, 5000);
+        assert_eq!(decompress_signature(&compressed, false), Ok(exact));
+
+        // a short s2 leaves zero padding, which is fine whether or not it is sent
+        let short = [1i16; FALCON_512_N];
+        let compressed = compress_coefficients(&short);
+        let (_, bits_consumed) = decompress_each::<FALCON_512_N>(&compressed, false, |_, _| {}).unwrap();
+        assert_eq!(bits_consumed, 512 * 9);
+        assert_eq!(decompress_signature(&compressed[..bits_consumed / 8], false), Ok(short));
+        assert_eq!(
+            decompress_signature::<FALCON_512_N>(&compressed[..bits_consumed / 8 - 1], false),
+            Err(VaultError::DecompressionOutOfBounds.into())
+        );
+
+        // but anything after the last coefficient that isn't zero is rejected, right
+        // after it, later in the same byte, or bytes further on
+        let mut exact_plus_one = compressed[..bits_consumed / 8].to_vec();
+        exact_plus_one.push(0x80);
+        for bad in [bits_consumed, bits_consumed + 7, compressed.len() * 8 - 1] {
+            let mut overlong = compressed;
+            overlong[bad / 8] |= 0x80 >> (bad % 8);
+            assert_eq!(
+                decompress_signature::<FALCON_512_N>(&overlong, false),
+                Err(VaultError::TrailingSignatureBits.into())
+            );
+            assert_eq!(decompress_signature_ct(&overlong).1, 1);
+        }
+        assert_eq!(
+            decompress_signature::<FALCON_512_N>(&exact_plus_one, false),
+            Err(VaultError::TrailingSignatureBits.into())
+        );
+    }
+
+    // a payload cut where a coefficient starts decodes the ones before the cut without
+    // error, the count is what rejects it; cut mid-coefficient it runs out of bits instead
+    #[test]
+    fn test_decompress_incomplete_signature() {
+        let compressed = compress_coefficients(&[1i16; FALCON_512_N]);
+        for cut in [0, 8, 256] {
+            let partial = &compressed[..cut * 9 / 8];
+            let mut emitted = 0;
+            assert_eq!(
+                decompress_each::<FALCON_512_N>(partial, false, |_, _| emitted += 1),
+                Err(VaultError::IncompleteSignature.into())
+            );
+            assert_eq!(emitted, cut);
+            assert_eq!(
+                decompress_signature::<FALCON_512_N>(partial, false),
+                Err(VaultError::IncompleteSignature.into())
+            );
+        }
+        assert_eq!(
+            decompress_signature::<FALCON_512_N>(&compressed[..256 * 9 / 8 + 1], false),
+            Err(VaultError::DecompressionOutOfBounds.into())
+        );
+    }
+
+    #[test]
+    fn test_decompress_early_termination() {
+        let compressed = compress_coefficients(&oversized_s2());
+
+        assert_eq!(
+            decompress_signature::<FALCON_512_N>(&compressed, true),
+            Err(VaultError::NormBoundExceeded.into())
+        );
+
+        // without early termination the full s2 is still decoded
+        let s2 = decompress_signature::<FALCON_512_N>(&compressed, false).unwrap();
+        let norm: i64 = s2.iter().map(|&c| c as i64 * c as i64).sum();
+        assert!(norm >= FALCON_512_SIG_BOUND);
+    }
+
+    #[test]
+    fn test_verify_early_termination() {
+        let mut signature = [0u8; FALCON_512_SIGNATURE_SIZE];
+        signature[0] = SIGNATURE_HEADER;
+        signature[1..41].copy_from_slice(&FIXTURE_NONCE);
+        signature[41..].copy_from_slice(&compress_coefficients(&oversized_s2()));
+
+        let result = verify_falcon_signature(&[0x09; FALCON_512_PUBLIC_KEY_SIZE], &signature, b"test");
+        assert_eq!(result, Err(VaultError::NormBoundExceeded.into()));
+    }
+
+    // why verify_split has no NTT-domain norm estimate: x has norm 1 but an NTT energy
+    // far over N times that, and the inverse transform of a single 1 has energy 1 but a
+    // norm over the signature bound, so the NTT side bounds ||s1||^2 in neither direction
+    #[test]
+    fn test_ntt_energy_does_not_bound_norm() {
+        let energy = |p: &Polynomial| p.balanced().iter().map(|&c| (c as i64 * c as i64) as u64).sum::<u64>();
+
+        let mut x: Polynomial = Polynomial::zero();
+        x.coefficients_mut()[1] = 1;
+        assert_eq!(x.squared_norm(), 1);
+        x.ntt_in_place();
+        assert!(energy(&x) > 1000 * FALCON_512_N as u64);
+
+        let mut spike: Polynomial = Polynomial::zero();
+        spike.coefficients_mut()[0] = 1;
+        assert_eq!(energy(&spike), 1);
+        spike.intt_in_place();
+        assert!(spike.squared_norm() >= FALCON_512_SIG_BOUND as u64);
+    }
+
+    // with skip_norm_check anything that parses is accepted, parse failures still fail
+    #[cfg(feature = "skip_norm_check")]
+    #[test]
+    fn test_skip_norm_check_accepts_oversized_signature() {
+        let (public_key, _) = signed_fixture(b"test", &FIXTURE_NONCE);
+
+        let mut signature = [0u8; FALCON_512_SIGNATURE_SIZE];
+        signature[0] = SIGNATURE_HEADER;
+        signature[1..41].copy_from_slice(&FIXTURE_NONCE);
+        signature[41..].copy_from_slice(&compress_coefficients(&oversized_s2()));
+
+        assert_eq!(verify_falcon_signature(&public_key, &signature, b"test"), Ok(()));
+        assert_eq!(verify_falcon_signature(&public_key, &signature, b"other"), Ok(()));
+
+        signature[41..].copy_from_slice(&overlong_payload());
+        assert_eq!(
+            verify_falcon_signature(&public_key, &signature, b"test"),
+            Err(VaultError::IncompleteSignature.into())
+        );
+        signature[0] = 0;
+        assert_eq!(
+            verify_falcon_signature(&public_key, &signature, b"test"),
+            Err(VaultError::BadSignatureHeader.into())
+        );
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    fn test_diagnostics_on_oversized_signature() {
+        let (public_key, _) = signed_fixture(b"test", &FIXTURE_NONCE);
+
+        let mut signature = [0u8; FALCON_512_SIGNATURE_SIZE];
+        signature[0] = SIGNATURE_HEADER;
+        signature[1..41].copy_from_slice(&FIXTURE_NONCE);
+        signature[41..].copy_from_slice(&compress_coefficients(&oversized_s2()));
+
+        let (error, diagnostics) = verify_with_diagnostics(&public_key, &signature, b"test").unwrap_err();
+        assert_eq!(error, VaultError::NormBoundExceeded.into());
+
+        let diagnostics = diagnostics.expect("norm failure should carry diagnostics");
+        assert_eq!(diagnostics.bound, FALCON_512_SIG_BOUND as u64);
+        assert!(diagnostics.norm_squared >= diagnostics.bound);
+
+        // oversized_s2 alternates +-2047 over its first coefficients
+        for (rank, &(index, value)) in diagnostics.largest_s2.iter().enumerate() {
+            assert_eq!(index as usize, rank);
+            assert_eq!(value, if rank % 2 == 0 { 2047 } else { -2047 });
+        }
+
+        // s1 entries are sorted by magnitude and point at real coefficients
+        let largest_s1 = diagnostics.largest_s1;
+        assert!(largest_s1.windows(2).all(|w| w[0].1.unsigned_abs() >= w[1].1.unsigned_abs()));
+        assert!(largest_s1[0].1 != 0);
+
+        // a valid signature has nothing to report, and parse failures carry no diagnostics
+        let (public_key, signature) = signed_fixture(b"test", &FIXTURE_NONCE);
+        assert_eq!(verify_with_diagnostics(&public_key, &signature, b"test"), Ok(()));
+        let mut bad_header = signature;
+        bad_header[0] = 0;
+        assert_eq!(
+            verify_with_diagnostics(&public_key, &bad_header, b"test"),
+            Err((VaultError::BadSignatureHeader.into(), None))
+        );
+    }
+
+    // the stage logs only observe, every input verifies the same as through the
+    // constant-time path, which has no logging
+    #[cfg(feature = "verbose")]
+    #[test]
+    fn test_verbose_logging_keeps_results() {
+        let (public_key, signature) = signed_fixture(b"test", &FIXTURE_NONCE);
+        let mut bad_header = signature;
+        bad_header[0] = 0;
+        let mut oversized = signature;
+        oversized[41..].copy_from_slice(&compress_coefficients(&oversized_s2()));
+        let mut bad_key = public_key;
+        bad_key[0] = 0;
+
+        let cases: [(&[u8; FALCON_512_PUBLIC_KEY_SIZE], &[u8; FALCON_512_SIGNATURE_SIZE], &[u8]); 5] = [
+            (&public_key, &signature, b"test"),
+            (&public_key, &signature, b"other"),
+            (&public_key, &bad_header, b"test"),
+            (&public_key, &oversized, b"test"),
+            (&bad_key, &signature, b"test"),
+        ];
+        for (key, sig, message) in cases {
+            let logged = verify_falcon_signature(key, sig, message);
+            assert_eq!(logged.is_ok(), verify_falcon_signature_ct(key, sig, message).is_ok());
+        }
+        assert_eq!(verify_falcon_signature(&public_key, &signature, b"test"), Ok(()));
+        assert_eq!(
+            verify_falcon_signature(&public_key, &oversized, b"test"),
+            Err(VaultError::NormBoundExceeded.into())
+        );
+    }
+
+    #[test]
+    fn test_signed_fixture_verifies() {
+        let (public_key, signature) = signed_fixture(b"fixture", &FIXTURE_NONCE);
+        assert_eq!(verify_falcon_signature(&public_key, &signature, b"fixture"), Ok(()));
+        assert!(verify_falcon_signature(&public_key, &signature, b"other").is_err());
+    }
+
+    // the same checks through the header dispatch at each degree
+    fn check_verification_at<const N: usize>() {
+        let (public_key, signature) = signed_fixture_for::<N>(b"degree", &FIXTURE_NONCE);
+        assert_eq!(verify_falcon_signature(&public_key, &signature, b"degree"), Ok(()));
+        assert_eq!(
+            verify_falcon_signature_parts(&public_key, &signature, &[b"deg", b"ree"]),
+            Ok(())
+        );
+        assert_eq!(
+            verify_falcon_signature(&public_key, &signature, b"other"),
+            Err(VaultError::NormBoundExceeded.into())
+        );
+
+        let mut tampered = signature.clone();
+        tampered[20] ^= 1;
+        assert_eq!(
+            verify_falcon_signature(&public_key, &tampered, b"degree"),
+            Err(VaultError::NormBoundExceeded.into())
+        );
+
+        // 20 coefficients of 2047 are past the bound at both degrees
+        let oversized_s2 = fixture_s2::<N>(|i| if i < 20 { 2047 } else { 0 });
+        let (public_key, oversized) = build_fixture::<N>(HashInput::message(&[b"degree"]), &FIXTURE_NONCE, &oversized_s2);
+        for early_termination in [false, true] {
+            let config = OptimizationConfig { early_termination, ..OptimizationConfig::default() };
+            assert_eq!(
+                verify_falcon_signature_with_config(&public_key, &oversized, b"degree", &config),
+                Err(VaultError::NormBoundExceeded.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_falcon_512_and_1024() {
+        check_verification_at::<{ FalconVariant::FALCON_512.n }>();
+        check_verification_at::<{ FalconVariant::FALCON_1024.n }>();
+    }
+
+    #[test]
+    fn test_falcon_variant_from_headers() {
+        let (key_512, sig_512) = signed_fixture_for::<512>(b"m", &FIXTURE_NONCE);
+        let (key_1024, sig_1024) = signed_fixture_for::<1024>(b"m", &FIXTURE_NONCE);
+        assert_eq!(falcon_variant(&key_512, &sig_512), Ok(FalconVariant::Falcon512));
+        assert_eq!(falcon_variant(&key_1024, &sig_1024), Ok(FalconVariant::Falcon1024));
+
+        let mismatch = || FalconError::from(VaultError::LogNMismatch);
+        assert_eq!(falcon_variant(&key_512, &sig_1024), Err(mismatch()));
+        assert_eq!(falcon_variant(&key_1024, &sig_512), Err(mismatch()));
+        assert_eq!(verify_falcon_signature(&key_512, &sig_1024, b"m"), Err(mismatch()));
+
+        let mut bad_key = key_512.clone();
+        bad_key[0] = 8;
+        assert_eq!(falcon_variant(&bad_key, &sig_512), Err(VaultError::BadPublicKeyHeader.into()));
+        let mut bad_sig = sig_512.clone();
+        bad_sig[0] = SIGNATURE_HEADER + 2;
+        assert_eq!(falcon_variant(&key_512, &bad_sig), Err(VaultError::BadSignatureHeader.into()));
+        assert_eq!(falcon_variant(&key_512, &[]), Err(VaultError::BadSignatureHeader.into()));
+
+        assert_eq!(falcon_variant(&key_512[..896], &sig_512), Err(FalconError::InvalidAccountData));
+        assert_eq!(falcon_variant(&key_1024, &sig_1024[..1279]), Err(FalconError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_verify_any_returns_first_matching_key() {
+        let (public_key, signature) = signed_fixture(b"rotate", &FIXTURE_NONCE);
+        let (old_key, _) = signed_fixture(b"old key", &FIXTURE_NONCE);
+        assert!(verify_falcon_signature(&old_key, &signature, b"rotate").is_err());
+
+        assert_eq!(verify_falcon_any(&[&old_key, &public_key], &signature, b"rotate"), Ok(1));
+        assert_eq!(verify_falcon_any(&[&public_key, &old_key], &signature, b"rotate"), Ok(0));
+        assert_eq!(verify_falcon_any(&[&public_key, &public_key], &signature, b"rotate"), Ok(0));
+        assert_eq!(
+            verify_falcon_any(&[&old_key, &old_key], &signature, b"rotate"),
+            Err(VaultError::NormBoundExceeded.into())
+        );
+        assert_eq!(
+            verify_falcon_any(&[&public_key], &signature, b"other"),
+            Err(VaultError::NormBoundExceeded.into())
+        );
+        assert_eq!(verify_falcon_any(&[], &signature, b"rotate"), Err(FalconError::InvalidArgument));
+
+        let mut bad_key = old_key;
+        bad_key[0] = 0;
+        assert_eq!(
+            verify_falcon_any(&[&bad_key, &public_key], &signature, b"rotate"),
+            Err(VaultError::BadPublicKeyHeader.into())
+        );
+
+        // the shared work doesn't cost extra buffers, however many candidates there are
+        let peak = peak_buffer_bytes(|| {
+            assert_eq!(verify_falcon_any(&[&old_key, &old_key, &public_key], &signature, b"rotate"), Ok(2));
+        });
+        assert!(peak <= 3 * core::mem::size_of::<[u32; FALCON_512_N]>());
+    }
+
+    #[test]
+    fn test_batch_all_valid() {
+        let (public_key, signature) = signed_fixture(b"batch", &FIXTURE_NONCE);
+
+        for count in [1, 3, 5] {
+            let items = vec![(&signature, &b"batch"[..]); count];
+            assert_eq!(verify_falcon_batch(&public_key, &items), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_batch_reports_failing_index() {
+        let (public_key, signature) = signed_fixture(b"batch", &FIXTURE_NONCE);
+
+        let mut items = vec![(&signature, &b"batch"[..]); 5];
+        items[2].1 = b"tampered";
+
+        let (index, _) = verify_falcon_batch(&public_key, &items).unwrap_err();
+        assert_eq!(index, 2);
+
+        // a single tampered item fails at index 0
+        let (index, _) = verify_falcon_batch(&public_key, &items[2..3]).unwrap_err();
+        assert_eq!(index, 0);
+    }
+
+    #[test]
+    fn test_batch_prepared_matches_single() {
+        let (public_key, signature) = signed_fixture(b"batch", &FIXTURE_NONCE);
+        let prepared = PreparedPublicKey::new(&public_key).unwrap();
+
+        let items = [(&signature, &b"batch"[..]), (&signature, &b"nope"[..])];
+        for (signature, message) in items {
+            let single = verify_falcon_signature(&public_key, signature, message).is_ok();
+            let batched = verify_falcon_batch_prepared(&prepared, &[(signature, message)]).is_ok();
+            assert_eq!(single, batched);
+        }
+    }
+
+    #[test]
+    fn test_prepared_key_account_bytes() {
+        let (public_key, signature) = signed_fixture(b"stored", &FIXTURE_NONCE);
+        let bytes = PreparedPublicKey::new(&public_key).unwrap().to_account_bytes();
+
+        let reloaded = PreparedPublicKey::from_account_bytes(&bytes).unwrap();
+        assert_eq!(verify_falcon_batch_prepared(&reloaded, &[(&signature, &b"stored"[..])]), Ok(()));
+        assert!(verify_falcon_batch_prepared(&reloaded, &[(&signature, &b"other"[..])]).is_err());
+
+        let mut tampered = bytes;
+        tampered[0] ^= 0xFF;
+        assert!(PreparedPublicKey::from_account_bytes(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_batch_bad_public_key() {
+        let (mut public_key, signature) = signed_fixture(b"batch", &FIXTURE_NONCE);
+        public_key[0] = 0x0A;
+
+        let items = [(&signature, &b"batch"[..]); 3];
+        let (index, _) = verify_falcon_batch(&public_key, &items).unwrap_err();
+        assert_eq!(index, 0);
+    }
+
+    #[test]
+    fn test_hash_to_point_parts_match_concatenation() {
+        let whole = hash_to_point::<FALCON_512_N>(&[b"hello, falcon world"], &FIXTURE_NONCE);
+        let split = hash_to_point(&[b"hello", b", ", b"", b"falcon world"], &FIXTURE_NONCE);
+        assert_eq!(whole.as_u16_array(), split.as_u16_array());
+    }
+
+    #[test]
+    fn test_verify_parts_matches_concatenated() {
+        let (recipient, vault) = ([0x42u8; 32], [0x43u8; 32]);
+        let message = crate::messages::build_transfer_message(1_000, &recipient, 7, &vault);
+        let (public_key, signature) = signed_fixture(&message, &FIXTURE_NONCE);
+
+        let amount = 1_000u64.to_le_bytes();
+        let nonce = 7u64.to_le_bytes();
+        let parts: [&[u8]; 4] = [&amount, &recipient, &nonce, &vault];
+
+        assert_eq!(verify_falcon_signature(&public_key, &signature, &message), Ok(()));
+        assert_eq!(verify_falcon_signature_parts(&public_key, &signature, &parts), Ok(()));
+
+        let wrong_nonce = 8u64.to_le_bytes();
+        let parts: [&[u8]; 4] = [&amount, &recipient, &wrong_nonce, &vault];
+        assert!(verify_falcon_signature_parts(&public_key, &signature, &parts).is_err());
+    }
+
+    #[test]
+    fn test_verify_with_context() {
+        // s2 = 1 works for any key h = c, so the signature is the same for every context
+        let (public_key, signature) = signed_fixture(b"close", &FIXTURE_NONCE);
+        let bound_key = |context: &[u8]| {
+            let h = hash_to_point_with_context::<FALCON_512_N>(context, &[b"close"], &FIXTURE_NONCE);
+            let mut public_key = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
+            pack_public_key_into(&h.as_u16_array(), &mut public_key);
+            public_key
+        };
+
+        // no context is today's hash
+        assert_eq!(verify_falcon_signature_with_context(&public_key, &signature, b"", &[b"close"]), Ok(()));
+        assert_eq!(bound_key(b""), public_key);
+        assert!(verify_falcon_signature_with_context(&public_key, &signature, b"vault", &[b"close"]).is_err());
+
+        // a context bound signature only verifies under its own context
+        let vault_key = bound_key(b"vault");
+        assert_eq!(verify_falcon_signature_with_context(&vault_key, &signature, b"vault", &[b"clo", b"se"]), Ok(()));
+        for other in [&b""[..], b"vaulu", b"vault\x00", &[0xFF; FALCON_MAX_CONTEXT_SIZE]] {
+            assert_eq!(
+                verify_falcon_signature_with_context(&vault_key, &signature, other, &[b"close"]),
+                Err(VaultError::NormBoundExceeded.into())
+            );
+        }
+        assert!(verify_falcon_signature(&vault_key, &signature, b"close").is_err());
+
+        assert_eq!(
+            verify_falcon_signature_with_context(&vault_key, &signature, &[0; 256], &[b"close"]),
+            Err(FalconError::InvalidArgument)
+        );
+    }
+
+    // the cap holds even for a signature that is valid over the long message
+    #[test]
+    fn test_message_length_cap() {
+        let at_max = vec![0xAB; FALCON_MAX_MESSAGE_SIZE];
+        let (public_key, signature) = signed_fixture(&at_max, &FIXTURE_NONCE);
+        assert_eq!(verify_falcon_signature(&public_key, &signature, &at_max), Ok(()));
+
+        let over_max = vec![0xAB; FALCON_MAX_MESSAGE_SIZE + 1];
+        let (public_key, signature) = signed_fixture(&over_max, &FIXTURE_NONCE);
+        let too_long = VaultError::MessageTooLong;
+        assert_eq!(verify_falcon_signature(&public_key, &signature, &over_max), Err(too_long.into()));
+        assert_eq!(verify_falcon_signature_parts(&public_key, &signature, &[&at_max, b"\xAB"]), Err(too_long.into()));
+        assert_eq!(verify_falcon_any(&[&public_key], &signature, &over_max), Err(too_long.into()));
+        assert_eq!(verify_falcon_batch(&public_key, &[(&signature, &over_max[..])]), Err((0, too_long.into())));
+
+        // a tighter cap from the config
+        let config = OptimizationConfig { max_message_len: 4, ..OptimizationConfig::default() };
+        let (public_key, signature) = signed_fixture(b"close", &FIXTURE_NONCE);
+        assert_eq!(verify_falcon_signature_with_config(&public_key, &signature, b"close", &config), Err(too_long.into()));
+        let config = OptimizationConfig { max_message_len: 5, ..config };
+        assert_eq!(verify_falcon_signature_with_config(&public_key, &signature, b"close", &config), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_signature_length_range() {
+        let (_, signature) = signed_fixture(b"fixture", &FIXTURE_NONCE);
+        let (min, max) = (min_signature_size(FALCON_512_LOGN), max_signature_size(FALCON_512_LOGN));
+        assert_eq!(max, FALCON_512_SIGNATURE_SIZE);
+
+        assert!(parse_signature::<FALCON_512_N>(&signature).is_ok());
+        assert!(parse_signature::<FALCON_512_N>(&signature[..min]).is_ok());
+        assert_eq!(parse_signature::<FALCON_512_N>(&signature[..min - 1]), Err(FalconError::InvalidAccountData));
+        assert_eq!(parse_signature::<FALCON_512_N>(&[]), Err(FalconError::InvalidAccountData));
+
+        let mut padded = signature.to_vec();
+        padded.push(0);
+        assert_eq!(parse_signature::<FALCON_512_N>(&padded), Err(FalconError::InvalidAccountData));
+
+        // the Falcon-1024 range doesn't overlap Falcon-512's
+        assert!(max < min_signature_size(FalconVariant::FALCON_1024.logn as usize));
+    }
+
+    #[test]
+    fn test_all_zero_nonce_rejected() {
+        let (public_key, mut signature) = signed_fixture(b"fixture", &[0u8; 40]);
+        assert_eq!(
+            verify_falcon_signature(&public_key, &signature, b"fixture"),
+            Err(VaultError::WeakNonce.into())
+        );
+
+        // a single non-zero byte is enough to get past the check
+        signature[40] = 1;
+        assert_ne!(
+            verify_falcon_signature(&public_key, &signature, b"fixture"),
+            Err(VaultError::WeakNonce.into())
+        );
+    }
+
+    // valid fixtures plus corruptions hitting every stage of verification
+    fn verification_corpus() -> Vec<([u8; FALCON_512_PUBLIC_KEY_SIZE], [u8; FALCON_512_SIGNATURE_SIZE], Vec<u8>)> {
+        let mut corpus = Vec::new();
+
+        for (i, message) in [&b"alpha"[..], b"", b"a much longer message than the others"].iter().enumerate() {
+            let mut nonce = FIXTURE_NONCE;
+            nonce[0] = i as u8;
+            let (public_key, signature) = signed_fixture(message, &nonce);
+            corpus.push((public_key, signature, message.to_vec()));
+
+            // wrong message
+            corpus.push((public_key, signature, b"tampered".to_vec()));
+
+            // bad signature header and bad public key header
+            let mut bad = signature;
+            bad[0] ^= 0x20;
+            corpus.push((public_key, bad, message.to_vec()));
+            let mut bad_key = public_key;
+            bad_key[0] = 10;
+            corpus.push((bad_key, signature, message.to_vec()));
+
+            // zero nonce
+            let mut bad = signature;
+            bad[1..41].fill(0);
+            corpus.push((public_key, bad, message.to_vec()));
+
+            // single bit flips across the payload, including unary terminators
+            // and the sign bit of a zero coefficient
+            for bit in [0, 1, 5, 8, 9, 17, 100, 2000, 4600] {
+                let mut bad = signature;
+                bad[41 + bit / 8] ^= 0x80 >> (bit % 8);
+                corpus.push((public_key, bad, message.to_vec()));
+            }
+
+            // a stray bit in the zero padding
+            let mut bad = signature;
+            bad[FALCON_512_SIGNATURE_SIZE - 1] |= 1;
+            corpus.push((public_key, bad, message.to_vec()));
+
+            // payload that runs out of bits
+            let mut bad = signature;
+            bad[41..].copy_from_slice(&overlong_payload());
+            corpus.push((public_key, bad, message.to_vec()));
+
+            // a coefficient past 2047
+            let mut bad = signature;
+            bad[41..].fill(0);
+            corpus.push((public_key, bad, message.to_vec()));
+
+            // oversized s2
+            let mut bad = signature;
+            bad[41..].copy_from_slice(&compress_coefficients(&oversized_s2()));
+            corpus.push((public_key, bad, message.to_vec()));
+        }
+
+        corpus
+    }
+
+    #[test]
+    fn test_constant_time_variant_agrees() {
+        let corpus = verification_corpus();
+        let mut accepted = 0;
+
+        for (i, (public_key, signature, message)) in corpus.iter().enumerate() {
+            let regular = verify_falcon_signature(public_key, signature, message);
+            let ct = verify_falcon_signature_ct(public_key, signature, message);
+            assert_eq!(regular.is_ok(), ct.is_ok(), "corpus entry {}", i);
+            if let Err(e) = ct {
+                assert_eq!(e, VaultError::InvalidSignature.into());
+            } else {
+                accepted += 1;
+            }
+        }
+
+        // only the three fixtures: every s2 has a single encoding, so no bit flip
+        // can re-encode the same coefficients
+        assert_eq!(accepted, 3);
+    }
+
+    #[test]
+    fn test_decompress_ct_matches_decompress() {
+        for (_, signature, _) in verification_corpus() {
+            let compressed: [u8; FALCON_512_SIGNATURE_SIZE - 41] = signature[41..].try_into().unwrap();
+            let (coeffs, failed) = decompress_signature_ct(&compressed);
+            match decompress_signature(&compressed, false) {
+                Ok(expected) => {
+                    assert_eq!(failed, 0);
+                    assert_eq!(coeffs, expected);
+                }
+                Err(_) => assert_eq!(failed, 1),
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_live_buffer_budget() {
+        let (public_key, signature) = signed_fixture(b"stack budget", &FIXTURE_NONCE);
+        let budget = 3 * core::mem::size_of::<[u32; FALCON_512_N]>();
+
+        let peak = peak_buffer_bytes(|| {
+            assert_eq!(verify_falcon_signature(&public_key, &signature, b"stack budget"), Ok(()));
+        });
+        assert!(peak <= budget, "verify peaked at {} bytes of polynomial buffers", peak);
+
+        let peak = peak_buffer_bytes(|| {
+            assert_eq!(verify_falcon_signature_ct(&public_key, &signature, b"stack budget"), Ok(()));
+        });
+        assert!(peak <= budget, "verify_ct peaked at {} bytes of polynomial buffers", peak);
+
+        // every buffer is released again
+        assert_eq!(live_buffers(), 0);
+    }
+
+    // one scratch can be reused across verifications, and only holds one polynomial buffer
+    #[test]
+    fn test_verify_scratch_reuse() {
+        let config = OptimizationConfig::default();
+        let mut scratch = VerifyScratch::<FALCON_512_N>::new();
+        for message in [&b"first"[..], b"second", b"third"] {
+            let (public_key, signature) = signed_fixture(message, &FIXTURE_NONCE);
+            let peak = peak_buffer_bytes(|| {
+                assert_eq!(scratch.verify(&public_key, &signature, &[message], &config), Ok(()));
+                assert!(scratch.verify(&public_key, &signature, &[b"other"], &config).is_err());
+            });
+            assert_eq!(peak, 0);
+        }
+
+        // a Falcon-1024 pair is turned away by a 512 scratch
+        let (public_key, signature) = signed_fixture_for::<{ FalconVariant::FALCON_1024.n }>(b"big", &FIXTURE_NONCE);
+        assert_eq!(
+            scratch.verify(&public_key, &signature, &[b"big"], &config),
+            Err(VaultError::LogNMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_verify_falcon_parts_agrees() {
+        let (public_key, signature) = signed_fixture(b"split", &FIXTURE_NONCE);
+        let nonce: &[u8; 40] = signature[1..41].try_into().unwrap();
+        let body = &signature[41..];
+        assert_eq!(verify_falcon_signature(&public_key, &signature, b"split"), Ok(()));
+        assert_eq!(verify_falcon_parts(&public_key, nonce, body, b"split"), Ok(()));
+
+        // the zero padding at the end of the body is optional
+        let trimmed = body.len() - body.iter().rev().take_while(|&&b| b == 0).count();
+        assert_eq!(verify_falcon_parts(&public_key, nonce, &body[..trimmed], b"split"), Ok(()));
+
+        // and both turn away the same forgeries
+        assert_eq!(
+            verify_falcon_signature(&public_key, &signature, b"other"),
+            verify_falcon_parts(&public_key, nonce, body, b"other")
+        );
+        let mut other_nonce = *nonce;
+        other_nonce[0] ^= 1;
+        let mut resigned = signature;
+        resigned[1..41].copy_from_slice(&other_nonce);
+        assert_eq!(
+            verify_falcon_signature(&public_key, &resigned, b"split"),
+            verify_falcon_parts(&public_key, &other_nonce, body, b"split")
+        );
+        assert_eq!(
+            verify_falcon_parts(&public_key, &[0; 40], body, b"split"),
+            Err(VaultError::WeakNonce.into())
+        );
+
+        // an oversized body or a bad key is rejected before decoding
+        let mut long = body.to_vec();
+        long.push(0);
+        assert_eq!(
+            verify_falcon_parts(&public_key, nonce, &long, b"split"),
+            Err(FalconError::InvalidInstructionData)
+        );
+        let mut bad_key = public_key;
+        bad_key[0] = 0;
+        assert_eq!(
+            verify_falcon_parts(&bad_key, nonce, body, b"split"),
+            Err(VaultError::BadPublicKeyHeader.into())
+        );
+
+        // the degree follows the key
+        let (public_key, signature) = signed_fixture_for::<{ FalconVariant::FALCON_1024.n }>(b"big", &FIXTURE_NONCE);
+        let nonce: &[u8; 40] = signature[1..41].try_into().unwrap();
+        assert_eq!(verify_falcon_parts(&public_key, nonce, &signature[41..], b"big"), Ok(()));
+    }
+
+    // the same logical signature with the nonce moved behind the compressed s2
+    fn nonce_last(signature: &[u8]) -> Vec<u8> {
+        let (header, rest) = signature.split_first().unwrap();
+        let (nonce, compressed) = rest.split_at(FALCON_512_NONCE_SIZE);
+        [&[*header], compressed, nonce].concat()
+    }
+
+    #[test]
+    fn test_nonce_last_layout() {
+        let (public_key, signature) = signed_fixture(b"layout", &FIXTURE_NONCE);
+        let moved = nonce_last(&signature);
+
+        // both layouts split into the same nonce and payload
+        assert_eq!(
+            parse_signature_with_layout::<FALCON_512_N>(&moved, SignatureLayout::NonceLast),
+            parse_signature::<FALCON_512_N>(&signature)
+        );
+
+        // and verify the same way, for the right message and the wrong one
+        for message in [&b"layout"[..], b"other"] {
+            let expected = verify_falcon_signature(&public_key, &signature, message);
+            assert_eq!(
+                verify_falcon_signature_with_layout(&public_key, &moved, message, SignatureLayout::NonceLast),
+                expected
+            );
+            assert_eq!(
+                verify_falcon_signature_with_layout(&public_key, &signature, message, SignatureLayout::NonceFirst),
+                expected
+            );
+        }
+        assert_eq!(verify_falcon_signature(&public_key, &signature, b"layout"), Ok(()));
+
+        // read in the wrong layout the nonce and payload are garbage
+        assert!(verify_falcon_signature(&public_key, &moved, b"layout").is_err());
+        assert!(verify_falcon_signature_with_layout(&public_key, &signature, b"layout", SignatureLayout::NonceLast).is_err());
+
+        // the header and nonce checks still apply
+        let mut weak = moved.clone();
+        let nonce_start = weak.len() - FALCON_512_NONCE_SIZE;
+        weak[nonce_start..].fill(0);
+        assert_eq!(
+            verify_falcon_signature_with_layout(&public_key, &weak, b"layout", SignatureLayout::NonceLast),
+            Err(VaultError::WeakNonce.into())
+        );
+        let mut bad_header = moved.clone();
+        bad_header[0] ^= 0x20;
+        assert_eq!(
+            verify_falcon_signature_with_layout(&public_key, &bad_header, b"layout", SignatureLayout::NonceLast),
+            Err(VaultError::BadSignatureHeader.into())
+        );
+
+        // Falcon-1024 too, the degree follows the headers
+        let (public_key, signature) = signed_fixture_for::<{ FalconVariant::FALCON_1024.n }>(b"big", &FIXTURE_NONCE);
+        assert_eq!(
+            verify_falcon_signature_with_layout(&public_key, &nonce_last(&signature), b"big", SignatureLayout::NonceLast),
+            Ok(())
+        );
+    }
+
+    // the domain byte leads the customization string, so a raw message under a context
+    // never hashes like a digest, with the context or without one
+    #[test]
+    fn test_context_cannot_stand_in_for_the_prehash_domain() {
+        let digest = prehash_message(&[b"transfer everything"]);
+        let (public_key, signature) = prehashed_fixture(&[], &digest);
+        assert_eq!(verify_falcon_prehashed(&public_key, &signature, &digest), Ok(()));
+        for context in [&[][..], &[FALCON_PREHASH_DOMAIN][..]] {
+            assert_eq!(
+                verify_falcon_signature_with_context(&public_key, &signature, context, &[&digest]),
+                Err(VaultError::NormBoundExceeded.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_prehashed_verifies() {
+        let message = b"a message large enough that hashing it off-chain is worth it";
+        let digest = prehash_message(&[message]);
+        let (public_key, signature) = prehashed_fixture(&[], &digest);
+
+        assert_eq!(verify_falcon_prehashed(&public_key, &signature, &digest), Ok(()));
+        assert_eq!(digest, prehash_message(&[&message[..10], &message[10..]]));
+
+        let mut other = digest;
+        other[63] ^= 1;
+        assert!(verify_falcon_prehashed(&public_key, &signature, &other).is_err());
+    }
+
+    #[test]
+    fn test_prehashed_and_raw_paths_dont_cross_verify() {
+        let message = b"transfer everything";
+        let digest = prehash_message(&[message]);
+
+        // signed over the digest: rejected as a raw signature over the message or the digest
+        let (public_key, signature) = prehashed_fixture(&[], &digest);
+        assert!(verify_falcon_signature(&public_key, &signature, message).is_err());
+        assert!(verify_falcon_signature(&public_key, &signature, &digest).is_err());
+
+        // signed over the raw message or the raw digest bytes: rejected on the pre-hashed path
+        let (public_key, signature) = signed_fixture(message, &FIXTURE_NONCE);
+        assert_eq!(verify_falcon_signature(&public_key, &signature, message), Ok(()));
+        assert!(verify_falcon_prehashed(&public_key, &signature, &digest).is_err());
+
+        let (public_key, signature) = signed_fixture(&digest, &FIXTURE_NONCE);
+        assert!(verify_falcon_prehashed(&public_key, &signature, &digest).is_err());
+    }
+
+    // cross-checks against falcon-rust, the reference encoding: header logn, then the
+    // 14-bit coefficients most significant bit first, and the compressed signature
+    #[test]
+    fn test_reference_signatures_verify() {
+        use falcon_rust::falcon512;
+
+        let (secret_key, public_key) = falcon512::keygen([7; 32]);
+        let signature = falcon512::sign(b"transfer 100", &secret_key);
+        let (public_key, signature) = (public_key.to_bytes(), signature.to_bytes());
+
+        assert_eq!(verify_falcon_signature(&public_key, &signature, b"transfer 100"), Ok(()));
+        assert!(verify_falcon_signature(&public_key, &signature, b"transfer 101").is_err());
+    }
+
+    #[test]
+    fn test_public_key_packing_matches_reference() {
+        let (public_key, _) = signed_fixture(b"packing", &FIXTURE_NONCE);
+        let reference = falcon_rust::falcon512::PublicKey::from_bytes(&public_key).unwrap();
+        assert_eq!(reference.to_bytes(), public_key.to_vec());
+    }
+}
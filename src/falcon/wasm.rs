@@ -0,0 +1,72 @@
+// verification for wallets, compiled to wasm32-unknown-unknown without the program:
+//   wasm-pack build --target web --no-default-features --features wasm
+// verify is verify_falcon_signature_with_context under VAULT_SIGNING_CONTEXT, the check
+// TransferFromVault and CloseVault make, so a signature it accepts is one the program
+// accepts for the same message
+
+use wasm_bindgen::prelude::*;
+use super::{verify_falcon_signature_with_context, FalconError};
+use crate::messages::VAULT_SIGNING_CONTEXT;
+
+// why verify rejected a signature. code is the VaultError code the program fails the
+// instruction with (ProgramError::Custom on chain); a key or signature of the wrong
+// size has none, the program reports those as builtin errors. name is the variant,
+// NormBoundExceeded or InvalidAccountData say
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifyError {
+    error: FalconError,
+}
+
+#[wasm_bindgen]
+impl VerifyError {
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> Option<u32> {
+        match self.error {
+            FalconError::Vault(error) => Some(error as u32),
+            _ => None,
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        match self.error {
+            FalconError::Vault(error) => format!("{:?}", error),
+            error => format!("{:?}", error),
+        }
+    }
+}
+
+// public_key is the 897-byte key as stored in the vault, signature the 666 bytes of the
+// instruction data and message the signed bytes, see messages::build_transfer_message
+#[wasm_bindgen]
+pub fn verify(public_key: &[u8], signature: &[u8], message: &[u8]) -> Result<(), VerifyError> {
+    verify_falcon_signature_with_context(public_key, signature, VAULT_SIGNING_CONTEXT, &[message])
+        .map_err(|error| VerifyError { error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::VaultError;
+    use crate::falcon::FALCON_512_PUBLIC_KEY_SIZE;
+    use crate::fixtures::fixture_keypairs;
+
+    #[test]
+    fn test_verify_fixtures() {
+        for keypair in fixture_keypairs() {
+            for transfer in &keypair.transfers {
+                assert_eq!(verify(&keypair.public_key, &transfer.signature, &transfer.message()), Ok(()));
+            }
+
+            let transfer = &keypair.transfers[0];
+            let rejected = verify(&keypair.public_key, &transfer.signature, b"another message").unwrap_err();
+            assert_eq!(rejected.code(), Some(VaultError::NormBoundExceeded as u32));
+            assert_eq!(rejected.name(), "NormBoundExceeded");
+
+            let rejected = verify(&keypair.public_key[..FALCON_512_PUBLIC_KEY_SIZE - 1], &transfer.signature, &transfer.message()).unwrap_err();
+            assert_eq!(rejected.code(), None);
+            assert_eq!(rejected.name(), "InvalidAccountData");
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// deterministic Falcon-512 key pairs with signed transfer and close messages,
+// checked in under tests/fixtures so the success path of verification is tested
+// without compiling the signer. like every vault signature they are made under
+// VAULT_SIGNING_CONTEXT
+//
+// each file is one key pair, one record per line:
+//   public_key <hex>
+//   transfer <amount> <recipient hex> <nonce> <vault hex> <signature hex>
+//   close <refund hex> <nonce> <vault hex> <signature hex>
+// the transfers are signed with nonces 0, 1, ..., the ones the transfers out of a
+// vault opened in slot 0 sign in turn, for the key's vault without a close authority.
+// the closes are, in order, for that vault at nonce 0, for it after the transfers,
+// and for the key's vault with FIXTURE_CLOSE_AUTHORITY at nonce 0, all to one refund
+//
+// regenerate with
+//   cargo test --features signing regenerate_fixtures -- --ignored
+
+use crate::falcon::{FALCON_512_PUBLIC_KEY_SIZE, FALCON_512_SIGNATURE_SIZE};
+use crate::messages::{build_close_message, build_transfer_message};
+
+// the close authority of the vault the last fixture close of each key is for
+pub const FIXTURE_CLOSE_AUTHORITY: [u8; 32] = [0xCA; 32];
+
+const FIXTURE_FILES: [&str; 3] = [
+    include_str!("../tests/fixtures/keypair_0.txt"),
+    include_str!("../tests/fixtures/keypair_1.txt"),
+    include_str!("../tests/fixtures/keypair_2.txt"),
+];
+
+pub struct FixtureKeypair {
+    pub public_key: [u8; FALCON_512_PUBLIC_KEY_SIZE],
+    pub transfers: Vec<TransferFixture>,
+    pub closes: Vec<CloseFixture>,
+}
+
+pub struct TransferFixture {
+    pub amount: u64,
+    pub recipient: [u8; 32],
+    pub nonce: u64,
+    pub vault: [u8; 32],
+    pub signature: [u8; FALCON_512_SIGNATURE_SIZE],
+}
+
+pub struct CloseFixture {
+    pub refund: [u8; 32],
+    pub nonce: u64,
+    pub vault: [u8; 32],
+    pub signature: [u8; FALCON_512_SIGNATURE_SIZE],
+}
+
+impl TransferFixture {
+    pub fn message(&self) -> Vec<u8> {
+        build_transfer_message(self.amount, &self.recipient, self.nonce, &self.vault).to_vec()
+    }
+}
+
+impl CloseFixture {
+    pub fn message(&self) -> Vec<u8> {
+        build_close_message(&self.refund, self.nonce, &self.vault).to_vec()
+    }
+}
+
+pub fn fixture_keypairs() -> Vec<FixtureKeypair> {
+    FIXTURE_FILES.iter().map(|file| parse_fixture(file)).collect()
+}
+
+fn parse_fixture(file: &str) -> FixtureKeypair {
+    let mut public_key = None;
+    let mut transfers = Vec::new();
+    let mut closes = Vec::new();
+
+    for line in file.lines().filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["public_key", key] => public_key = Some(from_hex(key)),
+            ["transfer", amount, recipient, nonce, vault, signature] => transfers.push(TransferFixture {
+                amount: amount.parse().expect("bad fixture amount"),
+                recipient: from_hex(recipient),
+                nonce: nonce.parse().expect("bad fixture nonce"),
+                vault: from_hex(vault),
+                signature: from_hex(signature),
+            }),
+            ["close", refund, nonce, vault, signature] => closes.push(CloseFixture {
+                refund: from_hex(refund),
+                nonce: nonce.parse().expect("bad fixture nonce"),
+                vault: from_hex(vault),
+                signature: from_hex(signature),
+            }),
+            _ => panic!("bad fixture line: {}", line),
+        }
+    }
+
+    FixtureKeypair {
+        public_key: public_key.expect("fixture without a public key"),
+        transfers,
+        closes,
+    }
+}
+
+fn from_hex<const LEN: usize>(hex: &str) -> [u8; LEN] {
+    assert_eq!(hex.len(), 2 * LEN, "bad fixture hex length");
+    let mut bytes = [0u8; LEN];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).expect("bad fixture hex");
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::falcon::verify_falcon_signature_with_context;
+    use crate::messages::VAULT_SIGNING_CONTEXT;
+
+    #[test]
+    fn test_fixtures_verify() {
+        let verify = |public_key: &[u8], signature: &[u8], message: &[u8]| {
+            verify_falcon_signature_with_context(public_key, signature, VAULT_SIGNING_CONTEXT, &[message])
+        };
+
+        let keypairs = fixture_keypairs();
+        assert_eq!(keypairs.len(), 3);
+
+        for keypair in &keypairs {
+            assert!(!keypair.transfers.is_empty() && !keypair.closes.is_empty());
+            for transfer in &keypair.transfers {
+                let message = transfer.message();
+                assert_eq!(verify(&keypair.public_key, &transfer.signature, &message), Ok(()));
+
+                // a different amount, nonce or vault is a different message
+                let (amount, recipient, nonce, vault) =
+                    (transfer.amount, &transfer.recipient, transfer.nonce, &transfer.vault);
+                let other = build_transfer_message(amount + 1, recipient, nonce, vault);
+                assert!(verify(&keypair.public_key, &transfer.signature, &other).is_err());
+                let replay = build_transfer_message(amount, recipient, nonce + 1, vault);
+                assert!(verify(&keypair.public_key, &transfer.signature, &replay).is_err());
+                let other_vault = build_transfer_message(amount, recipient, nonce, &[0xEE; 32]);
+                assert!(verify(&keypair.public_key, &transfer.signature, &other_vault).is_err());
+            }
+            for close in &keypair.closes {
+                assert_eq!(verify(&keypair.public_key, &close.signature, &close.message()), Ok(()));
+
+                // nor does a close carry over to another nonce or vault
+                let replay = build_close_message(&close.refund, close.nonce + 1, &close.vault);
+                assert!(verify(&keypair.public_key, &close.signature, &replay).is_err());
+                let other_vault = build_close_message(&close.refund, close.nonce, &[0xEE; 32]);
+                assert!(verify(&keypair.public_key, &close.signature, &other_vault).is_err());
+            }
+        }
+
+        // signatures don't carry over to another key, or verify without the context
+        let transfer = &keypairs[0].transfers[0];
+        assert!(verify(&keypairs[1].public_key, &transfer.signature, &transfer.message()).is_err());
+        let plain = crate::falcon::verify_falcon_signature(&keypairs[0].public_key, &transfer.signature, &transfer.message());
+        assert!(plain.is_err());
+    }
+
+    // writes tests/fixtures from fixed seeds, run by hand when the signature
+    // format or hash_to_point changes
+    #[cfg(feature = "signing")]
+    #[test]
+    #[ignore]
+    fn regenerate_fixtures() {
+        use crate::client::{derive_vault_address, derive_vault_address_with_authority};
+        use crate::falcon::FalconKeyPair;
+        use rand::SeedableRng;
+        use solana_sdk::pubkey::Pubkey;
+
+        let to_hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+
+        for k in 0..FIXTURE_FILES.len() {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(0xFA1C0 + k as u64);
+            let keypair = FalconKeyPair::generate(&mut rng);
+
+            let mut file = format!("# Falcon-512 fixture key pair {}, see src/fixtures.rs\n", k);
+            file += &format!("public_key {}\n", to_hex(&keypair.public_key.bytes));
+            let (vault, _) = derive_vault_address(&Pubkey::new_from_array(crate::ID), &keypair.public_key);
+            let vault = vault.to_bytes();
+            for (i, amount) in [100_000_000u64, 1].into_iter().enumerate() {
+                let recipient = [(0x10 * (k + 1) + i) as u8; 32];
+                let message = build_transfer_message(amount, &recipient, i as u64, &vault);
+                let signature = keypair.private_key.sign_with_context(VAULT_SIGNING_CONTEXT, &message, &mut rng);
+                file += &format!(
+                    "transfer {} {} {} {} {}\n",
+                    amount,
+                    to_hex(&recipient),
+                    i,
+                    to_hex(&vault),
+                    to_hex(&signature.bytes)
+                );
+            }
+            let program_id = Pubkey::new_from_array(crate::ID);
+            let close_authority = Pubkey::new_from_array(FIXTURE_CLOSE_AUTHORITY);
+            let (authority_vault, _) =
+                derive_vault_address_with_authority(&program_id, &keypair.public_key, Some(&close_authority));
+            let refund = [(0xA0 + k) as u8; 32];
+            for (nonce, vault) in [(0, vault), (2, vault), (0, authority_vault.to_bytes())] {
+                let message = build_close_message(&refund, nonce, &vault);
+                let signature = keypair.private_key.sign_with_context(VAULT_SIGNING_CONTEXT, &message, &mut rng);
+                file += &format!(
+                    "close {} {} {} {}\n",
+                    to_hex(&refund),
+                    nonce,
+                    to_hex(&vault),
+                    to_hex(&signature.bytes)
+                );
+            }
+
+            let path = format!("{}/tests/fixtures/keypair_{}.txt", env!("CARGO_MANIFEST_DIR"), k);
+            std::fs::write(path, file).unwrap();
+        }
+    }
+}
@@ -0,0 +1,239 @@
+// the checks behind the cargo-fuzz targets in fuzz/, one per parser. each takes
+// arbitrary bytes and panics only when a parser misbehaves: an out of bounds index,
+// an overflow, or an accepted input breaking the property asserted next to it.
+// the inputs checked in under fuzz/corpus are replayed by the tests below, so
+// whatever the fuzzer has found stays a plain cargo test
+
+use std::sync::OnceLock;
+
+use crate::falcon::verify::{
+    compress_into, decompress_signature as decompress, parse_public_key as parse_key, parse_signature as parse_sig,
+};
+use crate::falcon::{
+    from_account_bytes, internals, verify_falcon_any, verify_falcon_signature, verify_falcon_signature_ct,
+    verify_falcon_signature_with_context, FalconPublicKey, FalconSignature, PreparedPublicKey, Shake256, Shake256Reader, FALCON_512_LOGN, FALCON_512_N,
+    FALCON_512_PUBLIC_KEY_SIZE, FALCON_512_SIGNATURE_SIZE, Q,
+};
+use crate::fixtures::{fixture_keypairs, FixtureKeypair};
+use crate::instructions::*;
+use crate::messages::VAULT_SIGNING_CONTEXT;
+
+// the compressed s2 payload. one that decodes has every coefficient within +-2047,
+// and since an encoding is unique it must encode back to the same bytes
+pub fn decompress_signature(data: &[u8]) {
+    let Ok(s2) = internals::decompress_signature(data) else {
+        return;
+    };
+    assert!(s2.iter().all(|coeff| (-2047..=2047).contains(coeff)));
+
+    let mut encoded = vec![0u8; data.len()];
+    assert!(compress_into(&s2, &mut encoded));
+    assert_eq!(encoded, data);
+}
+
+// the reducing key parser takes any length; the strict one and PreparedPublicKey only
+// take a whole key, so they get the input cut or zero padded to 897 bytes. whatever
+// the strict parser accepts the reducing one accepts with the same coefficients
+pub fn parse_public_key(data: &[u8]) {
+    if let Ok(h) = parse_key::<FALCON_512_N>(data) {
+        assert!(data.len() >= FALCON_512_PUBLIC_KEY_SIZE);
+        assert!(h.iter().all(|&coeff| coeff < Q));
+    }
+
+    let key = FalconPublicKey::new(fixed_size(data));
+    let reduced = parse_key::<FALCON_512_N>(key.as_bytes()).map(|h| h.as_u16_array());
+    match key.coefficients() {
+        Ok(coeffs) => {
+            assert_eq!(reduced, Ok(coeffs));
+            assert!(key.validate().is_ok());
+        }
+        Err(_) => assert!(key.validate().is_err()),
+    }
+    assert_eq!(PreparedPublicKey::new(key.as_bytes()).is_ok(), reduced.is_ok());
+}
+
+// the signature parser on any length, then FalconSignature on the input cut or zero
+// padded to 666 bytes. a signature that parses is its header, data[1..41] as the
+// nonce and the rest as the payload
+pub fn parse_signature(data: &[u8]) {
+    if let Ok((nonce, payload)) = parse_sig::<FALCON_512_N>(data) {
+        assert_eq!(nonce[..], data[1..41]);
+        assert_eq!(payload, &data[41..]);
+        decompress_signature(payload);
+    }
+
+    let signature = FalconSignature::new(fixed_size(data));
+    match signature.components() {
+        Ok(view) => {
+            assert_eq!(view.logn as usize, FALCON_512_LOGN);
+            let decoded = decompress::<FALCON_512_N>(view.compressed, false);
+            assert_eq!(signature.validate_format().is_ok(), decoded.is_ok());
+        }
+        Err(_) => assert!(signature.validate_format().is_err()),
+    }
+}
+
+// instruction data split the way process_instruction splits it, then every
+// deserializer on the rest whatever the discriminator, so each sees every length.
+// an accepted payload has one of the sizes its layout allows
+pub fn instruction_data(data: &[u8]) {
+    let Some((discriminator, payload)) = data.split_first() else {
+        return;
+    };
+    let _ = VaultInstructions::try_from(discriminator);
+
+    let len = payload.len();
+    let open_size = FALCON_512_PUBLIC_KEY_SIZE + 1;
+    let transfer_size = FALCON_512_SIGNATURE_SIZE + 8 + 1;
+    if OpenVault::deserialize(payload).is_ok() {
+        assert!(len == open_size || len == open_size + 32);
+    }
+    if TransferFromVault::deserialize(payload).is_ok() {
+        assert!(len == transfer_size || len == transfer_size + 1);
+    }
+    if CloseVault::deserialize(payload).is_ok() {
+        assert!([1, FALCON_512_SIGNATURE_SIZE + 1, FALCON_512_SIGNATURE_SIZE + 17].contains(&len));
+    }
+    if VerifyOnly::deserialize(payload).is_ok() {
+        assert_eq!(len, transfer_size + 32);
+    }
+    if let Ok(batch) = BatchOpenVault::deserialize(payload) {
+        assert!((1..=MAX_BATCH_OPEN_VAULTS).contains(&batch.len()));
+        assert_eq!(batch.len() * BATCH_OPEN_VAULT_ENTRY_SIZE, len);
+    }
+    if SelfTest::deserialize(payload).is_ok() {
+        assert_eq!(len, 0);
+    }
+    #[cfg(feature = "bench")]
+    let _ = BenchmarkVerify::deserialize(payload);
+}
+
+// account data: a vault, a stored h_ntt and exported SHAKE256 states. a vault whose
+// key loads has a readable close authority, a transfer nonce that advances unless it
+// ran out or the layout has none, and a stored state that imports exports back to the
+// same bytes
+pub fn account_data(data: &[u8]) {
+    if load_public_key(data).is_ok() {
+        assert!(load_close_authority(data).is_ok());
+        let mut advanced = data.to_vec();
+        match load_transfer_nonce(data) {
+            Ok(nonce) => assert_eq!(advance_transfer_nonce(&mut advanced).is_ok(), nonce.next().is_ok()),
+            Err(err) => assert_eq!(advance_transfer_nonce(&mut advanced), Err(err)),
+        }
+    }
+
+    if let Ok(h_ntt) = from_account_bytes(data) {
+        assert!(h_ntt.iter().all(|&coeff| coeff < Q));
+        let prepared = PreparedPublicKey::from_account_bytes(data).unwrap();
+        assert_eq!(prepared.to_account_bytes()[..], data[..]);
+    }
+
+    if let Ok(mut hasher) = Shake256::import_state(data) {
+        assert_eq!(hasher.export_state()[..], data[..]);
+        hasher.update(data);
+        hasher.finalize_xof().read(&mut [0u8; 200]);
+    }
+    if let Ok(mut reader) = Shake256Reader::import_state(data) {
+        assert_eq!(reader.export_state()[..], data[..]);
+        reader.read(&mut [0u8; 200]);
+    }
+}
+
+// a fixture transfer with bytes flipped. data[0] picks the fixture, data[1] is how many
+// bytes to cut off the end of the signature, and the rest are (offset lo, offset hi, xor)
+// triples; offsets past the signature land in the public key. verify_falcon_signature
+// must agree with the constant-time verifier and verify_falcon_any, which take no
+// context, and the fixture itself must verify under VAULT_SIGNING_CONTEXT
+pub fn mutated_signature(data: &[u8]) {
+    let [fixture, cut, flips @ ..] = data else {
+        return;
+    };
+    let keypairs = fixtures();
+    let keypair = &keypairs[*fixture as usize % keypairs.len()];
+    let transfer = &keypair.transfers[*fixture as usize / keypairs.len() % keypair.transfers.len()];
+
+    let mut public_key = keypair.public_key;
+    let mut signature = transfer.signature;
+    for flip in flips.chunks_exact(3) {
+        let offset = u16::from_le_bytes([flip[0], flip[1]]) as usize
+            % (FALCON_512_SIGNATURE_SIZE + FALCON_512_PUBLIC_KEY_SIZE);
+        match offset.checked_sub(FALCON_512_SIGNATURE_SIZE) {
+            None => signature[offset] ^= flip[2],
+            Some(key_offset) => public_key[key_offset] ^= flip[2],
+        }
+    }
+
+    let message = transfer.message();
+    let signed = &signature[..FALCON_512_SIGNATURE_SIZE - *cut as usize];
+    let result = verify_falcon_signature_with_context(&public_key, signed, VAULT_SIGNING_CONTEXT, &[&message]);
+    let plain = verify_falcon_signature(&public_key, signed, &message);
+    if *cut != 0 {
+        return;
+    }
+    assert_eq!(plain.is_ok(), verify_falcon_signature_ct(&public_key, &signature, &message).is_ok());
+    assert_eq!(plain.is_ok(), verify_falcon_any(&[&public_key], &signature, &message) == Ok(0));
+    if public_key == keypair.public_key && signature == transfer.signature {
+        assert_eq!(result, Ok(()));
+    }
+}
+
+// parsed once, a fuzzer runs the target millions of times
+fn fixtures() -> &'static [FixtureKeypair] {
+    static KEYPAIRS: OnceLock<Vec<FixtureKeypair>> = OnceLock::new();
+    KEYPAIRS.get_or_init(fixture_keypairs)
+}
+
+// data cut or zero padded to N bytes, for the parsers that take fixed arrays
+fn fixed_size<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    let len = data.len().min(N);
+    bytes[..len].copy_from_slice(&data[..len]);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    // every input under fuzz/corpus/<target>, through that target's check
+    fn replay(target: &str, check: fn(&[u8])) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus").join(target);
+        let mut inputs = 0;
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            check(&std::fs::read(entry.unwrap().path()).unwrap());
+            inputs += 1;
+        }
+        assert!(inputs > 0, "no corpus for {}", target);
+    }
+
+    #[test]
+    fn test_corpus_decompress_signature() {
+        replay("decompress_signature", decompress_signature);
+    }
+
+    #[test]
+    fn test_corpus_parse_public_key() {
+        replay("parse_public_key", parse_public_key);
+    }
+
+    #[test]
+    fn test_corpus_parse_signature() {
+        replay("parse_signature", parse_signature);
+    }
+
+    #[test]
+    fn test_corpus_instruction_data() {
+        replay("instruction_data", instruction_data);
+    }
+
+    #[test]
+    fn test_corpus_account_data() {
+        replay("account_data", account_data);
+    }
+
+    #[test]
+    fn test_corpus_mutated_signature() {
+        replay("mutated_signature", mutated_signature);
+    }
+}
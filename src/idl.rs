@@ -0,0 +1,511 @@
+// machine-readable description of the program's interface, for wallets, explorers and
+// generated clients: each instruction's discriminator, data layout and accounts, and
+// the custom error codes. idl/solana_falcon_vault.json is idl_json() written out by
+//
+//   cargo run --example generate_idl --features client
+//
+// and a test fails when the two differ. the layouts are raw little-endian fields
+// back to back, not borsh: an optional field is present or absent (told apart by the
+// data length), never tagged, and CloseVault's three layouts share discriminator 2
+
+use crate::error::VaultError;
+use crate::falcon::{FALCON_512_PUBLIC_KEY_SIZE, FALCON_512_SIGNATURE_SIZE};
+use crate::instructions::{VaultInstructions, MAX_BATCH_OPEN_VAULTS};
+
+pub const IDL_NAME: &str = "solana_falcon_vault";
+// the version of this description's format, not of the program
+pub const IDL_FORMAT_VERSION: u8 = 1;
+
+// BenchmarkVerify only exists in bench builds, the IDL describes it either way
+const BENCHMARK_VERIFY_DISCRIMINATOR: u8 = 0xFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdlType {
+    U8,
+    U64,
+    PublicKey,
+    // fixed-size byte array
+    Bytes(usize),
+    // the rest of the data, whatever its length
+    Remaining,
+    // the rest of the data as min to max back to back entries of these fields
+    Repeated { fields: &'static [IdlArg], min: usize, max: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdlArg {
+    pub name: &'static str,
+    pub ty: IdlType,
+    // absent rather than tagged; only trailing fields are optional
+    pub optional: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdlAccount {
+    pub name: &'static str,
+    pub writable: bool,
+    pub signer: bool,
+    // one account per entry of the instruction's repeated data
+    pub repeated: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdlInstruction {
+    pub name: &'static str,
+    pub discriminator: u8,
+    pub docs: &'static str,
+    // the cargo feature the program must be built with, if any
+    pub feature: Option<&'static str>,
+    pub accounts: &'static [IdlAccount],
+    pub args: &'static [IdlArg],
+    // what the instruction writes to return data
+    pub returns: Option<&'static str>,
+}
+
+impl IdlType {
+    // bytes taken in the instruction data, None when the length varies
+    pub const fn size(&self) -> Option<usize> {
+        match self {
+            IdlType::U8 => Some(1),
+            IdlType::U64 => Some(8),
+            IdlType::PublicKey => Some(32),
+            IdlType::Bytes(len) => Some(*len),
+            IdlType::Remaining | IdlType::Repeated { .. } => None,
+        }
+    }
+}
+
+const fn arg(name: &'static str, ty: IdlType) -> IdlArg {
+    IdlArg { name, ty, optional: false }
+}
+
+const fn optional(name: &'static str, ty: IdlType) -> IdlArg {
+    IdlArg { name, ty, optional: true }
+}
+
+const fn account(name: &'static str, writable: bool, signer: bool) -> IdlAccount {
+    IdlAccount { name, writable, signer, repeated: false }
+}
+
+const PUBLIC_KEY: IdlType = IdlType::Bytes(FALCON_512_PUBLIC_KEY_SIZE);
+const SIGNATURE: IdlType = IdlType::Bytes(FALCON_512_SIGNATURE_SIZE);
+
+const SYSTEM_PROGRAM: IdlAccount = account("system_program", false, false);
+
+const BATCH_ENTRY: &[IdlArg] = &[arg("falcon_public_key", PUBLIC_KEY), arg("bump", IdlType::U8)];
+
+pub const INSTRUCTIONS: &[IdlInstruction] = &[
+    IdlInstruction {
+        name: "OpenVault",
+        discriminator: VaultInstructions::OpenVault as u8,
+        docs: "creates the vault PDA of the key's SHA256 hash and the optional close authority",
+        feature: None,
+        accounts: &[account("payer", true, true), account("vault", true, false), SYSTEM_PROGRAM],
+        args: &[
+            arg("falcon_public_key", PUBLIC_KEY),
+            arg("bump", IdlType::U8),
+            optional("close_authority", IdlType::PublicKey),
+        ],
+        returns: None,
+    },
+    IdlInstruction {
+        name: "TransferFromVault",
+        discriminator: VaultInstructions::TransferFromVault as u8,
+        docs: "moves amount lamports to the recipient under a Falcon signature over the transfer message \
+               with the vault's stored nonce, then advances the nonce; digest_flag 1 means it signs the \
+               message's SHAKE256 digest instead",
+        feature: None,
+        accounts: &[account("vault", true, false), account("recipient", true, false), SYSTEM_PROGRAM],
+        args: &[
+            arg("signature", SIGNATURE),
+            arg("amount", IdlType::U64),
+            arg("bump", IdlType::U8),
+            optional("digest_flag", IdlType::U8),
+        ],
+        returns: None,
+    },
+    IdlInstruction {
+        name: "CloseVault",
+        discriminator: VaultInstructions::CloseVault as u8,
+        docs: "closes the vault under a Falcon signature over the close message, which names the vault \
+               and its current nonce, refunding its whole balance",
+        feature: None,
+        accounts: &[account("vault", true, false), account("refund", true, false)],
+        args: &[arg("signature", SIGNATURE), arg("bump", IdlType::U8)],
+        returns: None,
+    },
+    IdlInstruction {
+        name: "CloseVaultWithBalance",
+        discriminator: VaultInstructions::CloseVault as u8,
+        docs: "CloseVault signed over the close message with balance bounds, \
+               failing unless the balance is within [min_balance, max_balance]",
+        feature: None,
+        accounts: &[account("vault", true, false), account("refund", true, false)],
+        args: &[
+            arg("signature", SIGNATURE),
+            arg("bump", IdlType::U8),
+            arg("min_balance", IdlType::U64),
+            arg("max_balance", IdlType::U64),
+        ],
+        returns: None,
+    },
+    IdlInstruction {
+        name: "CloseVaultWithAuthority",
+        discriminator: VaultInstructions::CloseVault as u8,
+        docs: "CloseVault for a vault opened with a close authority, which signs the transaction instead; \
+               only while the vault holds no more than its rent-exempt minimum",
+        feature: None,
+        accounts: &[
+            account("vault", true, false),
+            account("refund", true, false),
+            account("close_authority", false, true),
+        ],
+        args: &[arg("bump", IdlType::U8)],
+        returns: None,
+    },
+    IdlInstruction {
+        name: "VerifyOnly",
+        discriminator: VaultInstructions::VerifyOnly as u8,
+        docs: "dry run of TransferFromVault, checks the signature without moving lamports",
+        feature: None,
+        accounts: &[account("vault", false, false)],
+        args: &[
+            arg("signature", SIGNATURE),
+            arg("amount", IdlType::U64),
+            arg("bump", IdlType::U8),
+            arg("recipient", IdlType::PublicKey),
+            optional("digest_flag", IdlType::U8),
+        ],
+        returns: Some("u8: 1 if the transfer would be accepted, 0 if not, then the u64 error code it would fail with"),
+    },
+    IdlInstruction {
+        name: "BatchOpenVault",
+        discriminator: VaultInstructions::BatchOpenVault as u8,
+        docs: "opens a vault per entry, all or none, without close authorities",
+        feature: None,
+        accounts: &[
+            account("payer", true, true),
+            SYSTEM_PROGRAM,
+            IdlAccount { name: "vault", writable: true, signer: false, repeated: true },
+        ],
+        args: &[arg(
+            "entries",
+            IdlType::Repeated { fields: BATCH_ENTRY, min: 1, max: MAX_BATCH_OPEN_VAULTS },
+        )],
+        returns: None,
+    },
+    IdlInstruction {
+        name: "SelfTest",
+        discriminator: VaultInstructions::SelfTest as u8,
+        docs: "runs the known-answer checks of the NTT, SHAKE256 and hash-to-point",
+        feature: None,
+        accounts: &[],
+        args: &[],
+        returns: Some("u8: 0 if every check passed, otherwise the number of the first that failed"),
+    },
+    IdlInstruction {
+        name: "BenchmarkVerify",
+        discriminator: BENCHMARK_VERIFY_DISCRIMINATOR,
+        docs: "verifies a signature and measures its compute units; the key and signature sizes \
+               follow the key header, so the layout below is Falcon-512's",
+        feature: Some("bench"),
+        accounts: &[],
+        args: &[
+            arg("falcon_public_key", PUBLIC_KEY),
+            arg("signature", SIGNATURE),
+            arg("message", IdlType::Remaining),
+        ],
+        returns: Some("[accepted: u8, compute_units: u64], then per-stage compute units with the profiling feature"),
+    },
+];
+
+// every VaultError in code order; error_name's exhaustive match flags a new variant
+pub const ERRORS: &[VaultError] = &[
+    VaultError::ArithmeticOverflow,
+    VaultError::BadPublicKeyHeader,
+    VaultError::PublicKeyCoefficientOutOfRange,
+    VaultError::NormBoundExceeded,
+    VaultError::WeakNonce,
+    VaultError::InvalidSignature,
+    VaultError::BadSignatureHeader,
+    VaultError::DecompressionOutOfBounds,
+    VaultError::CoefficientTooLarge,
+    VaultError::NonCanonicalEncoding,
+    VaultError::RecipientNotRentExempt,
+    VaultError::UnsupportedVaultVersion,
+    VaultError::LogNMismatch,
+    VaultError::InvalidCloseAuthority,
+    VaultError::InvalidSystemProgram,
+    VaultError::TrailingSignatureBits,
+    VaultError::DuplicateVaultKey,
+    VaultError::VaultBalanceOutOfRange,
+    VaultError::ZeroAmount,
+    VaultError::MessageTooLong,
+    VaultError::SelfTestFailed,
+    VaultError::NonceExhausted,
+    VaultError::IncompleteSignature,
+    VaultError::VaultNotEmpty,
+];
+
+pub fn error_name(error: VaultError) -> &'static str {
+    match error {
+        VaultError::ArithmeticOverflow => "ArithmeticOverflow",
+        VaultError::BadPublicKeyHeader => "BadPublicKeyHeader",
+        VaultError::PublicKeyCoefficientOutOfRange => "PublicKeyCoefficientOutOfRange",
+        VaultError::NormBoundExceeded => "NormBoundExceeded",
+        VaultError::WeakNonce => "WeakNonce",
+        VaultError::InvalidSignature => "InvalidSignature",
+        VaultError::BadSignatureHeader => "BadSignatureHeader",
+        VaultError::DecompressionOutOfBounds => "DecompressionOutOfBounds",
+        VaultError::CoefficientTooLarge => "CoefficientTooLarge",
+        VaultError::NonCanonicalEncoding => "NonCanonicalEncoding",
+        VaultError::RecipientNotRentExempt => "RecipientNotRentExempt",
+        VaultError::UnsupportedVaultVersion => "UnsupportedVaultVersion",
+        VaultError::LogNMismatch => "LogNMismatch",
+        VaultError::InvalidCloseAuthority => "InvalidCloseAuthority",
+        VaultError::InvalidSystemProgram => "InvalidSystemProgram",
+        VaultError::TrailingSignatureBits => "TrailingSignatureBits",
+        VaultError::DuplicateVaultKey => "DuplicateVaultKey",
+        VaultError::VaultBalanceOutOfRange => "VaultBalanceOutOfRange",
+        VaultError::ZeroAmount => "ZeroAmount",
+        VaultError::MessageTooLong => "MessageTooLong",
+        VaultError::SelfTestFailed => "SelfTestFailed",
+        VaultError::NonceExhausted => "NonceExhausted",
+        VaultError::IncompleteSignature => "IncompleteSignature",
+        VaultError::VaultNotEmpty => "VaultNotEmpty",
+    }
+}
+
+// just enough JSON for the IDL: keys keep their order, so the output is stable
+enum Json {
+    Str(String),
+    Num(u64),
+    Bool(bool),
+    Null,
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn str(s: &str) -> Self {
+        Json::Str(s.into())
+    }
+
+    fn write(&self, out: &mut String, indent: usize) {
+        let pad = |out: &mut String, depth: usize| out.extend(core::iter::repeat_n("  ", depth));
+        match self {
+            Json::Str(s) => write_str(out, s),
+            Json::Num(n) => out.push_str(&n.to_string()),
+            Json::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Json::Null => out.push_str("null"),
+            Json::Array(items) if items.is_empty() => out.push_str("[]"),
+            Json::Array(items) => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    pad(out, indent + 1);
+                    item.write(out, indent + 1);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                pad(out, indent);
+                out.push(']');
+            }
+            Json::Object(fields) => {
+                out.push_str("{\n");
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    pad(out, indent + 1);
+                    write_str(out, key);
+                    out.push_str(": ");
+                    value.write(out, indent + 1);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                pad(out, indent);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn type_json(ty: &IdlType) -> Json {
+    match ty {
+        IdlType::U8 => Json::str("u8"),
+        IdlType::U64 => Json::str("u64"),
+        IdlType::PublicKey => Json::str("publicKey"),
+        IdlType::Bytes(len) => Json::Object(vec![("array", Json::Array(vec![Json::str("u8"), Json::Num(*len as u64)]))]),
+        IdlType::Remaining => Json::str("bytes"),
+        IdlType::Repeated { fields, min, max } => Json::Object(vec![(
+            "repeated",
+            Json::Object(vec![
+                ("fields", Json::Array(fields.iter().map(arg_json).collect())),
+                ("min", Json::Num(*min as u64)),
+                ("max", Json::Num(*max as u64)),
+            ]),
+        )]),
+    }
+}
+
+fn arg_json(arg: &IdlArg) -> Json {
+    let mut fields = vec![("name", Json::str(arg.name)), ("type", type_json(&arg.ty))];
+    if let Some(size) = arg.ty.size() {
+        fields.push(("size", Json::Num(size as u64)));
+    }
+    if arg.optional {
+        fields.push(("optional", Json::Bool(true)));
+    }
+    Json::Object(fields)
+}
+
+fn account_json(account: &IdlAccount) -> Json {
+    let mut fields = vec![
+        ("name", Json::str(account.name)),
+        ("isMut", Json::Bool(account.writable)),
+        ("isSigner", Json::Bool(account.signer)),
+    ];
+    if account.repeated {
+        fields.push(("repeated", Json::Bool(true)));
+    }
+    Json::Object(fields)
+}
+
+fn instruction_json(ix: &IdlInstruction) -> Json {
+    Json::Object(vec![
+        ("name", Json::str(ix.name)),
+        ("discriminator", Json::Num(ix.discriminator as u64)),
+        ("docs", Json::str(ix.docs)),
+        ("feature", ix.feature.map_or(Json::Null, Json::str)),
+        ("accounts", Json::Array(ix.accounts.iter().map(account_json).collect())),
+        ("args", Json::Array(ix.args.iter().map(arg_json).collect())),
+        ("returns", ix.returns.map_or(Json::Null, Json::str)),
+    ])
+}
+
+// the IDL as pretty-printed JSON with a trailing newline, the committed file's contents
+pub fn idl_json() -> String {
+    let errors = ERRORS
+        .iter()
+        .map(|&error| Json::Object(vec![("code", Json::Num(error as u64)), ("name", Json::str(error_name(error)))]))
+        .collect();
+    let idl = Json::Object(vec![
+        ("name", Json::str(IDL_NAME)),
+        ("formatVersion", Json::Num(IDL_FORMAT_VERSION as u64)),
+        ("programVersion", Json::str(env!("CARGO_PKG_VERSION"))),
+        ("address", Json::str(&solana_sdk::pubkey::Pubkey::new_from_array(crate::ID).to_string())),
+        ("encoding", Json::str("fields little-endian and back to back; optional fields are absent, not tagged")),
+        ("instructions", Json::Array(INSTRUCTIONS.iter().map(instruction_json).collect())),
+        ("errors", Json::Array(errors)),
+    ]);
+
+    let mut out = String::new();
+    idl.write(&mut out, 0);
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::*;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::pubkey::Pubkey;
+
+    const COMMITTED_IDL: &str = include_str!("../idl/solana_falcon_vault.json");
+
+    fn instruction(name: &str) -> &'static IdlInstruction {
+        INSTRUCTIONS.iter().find(|ix| ix.name == name).unwrap()
+    }
+
+    // fixed data size of ix's args, with or without its optional ones
+    fn data_size(ix: &IdlInstruction, with_optional: bool) -> usize {
+        1 + ix.args.iter().filter(|arg| with_optional || !arg.optional).map(|arg| arg.ty.size().unwrap()).sum::<usize>()
+    }
+
+    // the builder's instruction matches the description: discriminator, data size and accounts
+    fn assert_matches(name: &str, built: &Instruction, with_optional: bool) {
+        let ix = instruction(name);
+        assert_eq!(built.data[0], ix.discriminator, "{}", name);
+        assert_eq!(built.data.len(), data_size(ix, with_optional), "{}", name);
+        assert_eq!(built.accounts.len(), ix.accounts.len(), "{}", name);
+        for (meta, account) in built.accounts.iter().zip(ix.accounts) {
+            assert_eq!((meta.is_writable, meta.is_signer), (account.writable, account.signer), "{} {}", name, account.name);
+        }
+    }
+
+    #[test]
+    fn test_committed_idl_is_current() {
+        assert!(
+            idl_json() == COMMITTED_IDL,
+            "idl/solana_falcon_vault.json is stale, run `cargo run --example generate_idl --features client`"
+        );
+        let idl: serde_json::Value = serde_json::from_str(COMMITTED_IDL).unwrap();
+        assert_eq!(idl["instructions"].as_array().unwrap().len(), INSTRUCTIONS.len());
+        assert_eq!(idl["errors"][20]["name"], "SelfTestFailed");
+    }
+
+    #[test]
+    fn test_idl_matches_client_builders() {
+        let program_id = Pubkey::new_from_array(crate::ID);
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let public_key = crate::falcon::FalconPublicKey::from([0x09; FALCON_512_PUBLIC_KEY_SIZE]);
+        let signature = [0u8; FALCON_512_SIGNATURE_SIZE];
+
+        assert_matches("OpenVault", &open_vault_ix(&program_id, &a, &b, &public_key, 255, None), false);
+        assert_matches("OpenVault", &open_vault_ix(&program_id, &a, &b, &public_key, 255, Some(&c)), true);
+        assert_matches("TransferFromVault", &transfer_ix(&program_id, &a, &b, 1, &signature, 255), false);
+        assert_matches("CloseVault", &close_vault_ix(&program_id, &a, &b, &signature, 255), false);
+        assert_matches(
+            "CloseVaultWithBalance",
+            &close_vault_with_balance_ix(&program_id, &a, &b, &signature, 255, 0, 1),
+            false,
+        );
+        assert_matches("CloseVaultWithAuthority", &close_vault_with_authority_ix(&program_id, &a, &b, &c, 255), false);
+        assert_matches("VerifyOnly", &verify_only_ix(&program_id, &a, &b, 1, &signature, 255), false);
+        assert_matches("SelfTest", &self_test_ix(&program_id), false);
+
+        // one entry of key and bump, and one vault account, per key
+        let ix = instruction("BatchOpenVault");
+        let IdlType::Repeated { fields, .. } = ix.args[0].ty else { panic!("entries aren't repeated") };
+        let entry_size: usize = fields.iter().map(|field| field.ty.size().unwrap()).sum();
+        let (built, vaults) = batch_open_vault_ix(&program_id, &a, &[public_key, public_key]);
+        assert_eq!(built.data[0], ix.discriminator);
+        assert_eq!(built.data.len(), 1 + 2 * entry_size);
+        assert_eq!(built.accounts.len(), ix.accounts.len() - 1 + vaults.len());
+        assert!(ix.accounts.last().unwrap().repeated);
+    }
+
+    #[test]
+    fn test_idl_discriminators_dispatch() {
+        for ix in INSTRUCTIONS.iter().filter(|ix| ix.feature.is_none()) {
+            assert!(VaultInstructions::try_from(&ix.discriminator).is_ok(), "{}", ix.name);
+        }
+        assert_eq!(
+            VaultInstructions::try_from(&BENCHMARK_VERIFY_DISCRIMINATOR).is_ok(),
+            cfg!(feature = "bench")
+        );
+    }
+
+    #[test]
+    fn test_idl_errors_in_code_order() {
+        for (code, &error) in ERRORS.iter().enumerate() {
+            assert_eq!(error as usize, code, "{}", error_name(error));
+        }
+    }
+
+    #[test]
+    fn test_json_strings_escaped() {
+        let mut out = String::new();
+        Json::str("a \"b\" \\ \n").write(&mut out, 0);
+        assert_eq!(out, r#""a \"b\" \\ \u000a""#);
+    }
+}
@@ -0,0 +1,49 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+use crate::error::VaultError;
+use super::close_authority_seed;
+
+// accounts are passed positionally, these checks make a misordered list fail with an
+// error naming the account that's wrong instead of somewhere further down the handler
+
+// the system program slot has to hold the system program
+pub fn check_system_program(system_program: &AccountInfo) -> ProgramResult {
+    if system_program.key() != &pinocchio_system::ID {
+        return Err(VaultError::InvalidSystemProgram.into());
+    }
+    Ok(())
+}
+
+// a vault has to be owned by this program before any of its data is read,
+// so a wallet or the system program in the vault slot is rejected up front
+pub fn check_vault_owner(vault: &AccountInfo) -> ProgramResult {
+    // owner() is unsafe because the reference would go stale if the owner were reassigned
+    // while it's held; here it's compared and dropped straight away
+    if unsafe { vault.owner() } != &crate::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+// the vault address has to be the PDA of the key hash, the close authority and bump
+// (similar to Winternitz vault, thanks Dean!)
+// owning an account doesn't make it a vault: only this ties the stored key to the
+// address, so the handlers make it before verifying anything against that key
+pub fn check_vault_address(
+    vault: &AccountInfo,
+    pubkey_hash: &[u8; 32],
+    close_authority: Option<&Pubkey>,
+    bump: u8,
+) -> ProgramResult {
+    if solana_nostd_sha256::hashv(&[
+        pubkey_hash.as_ref(),
+        close_authority_seed(close_authority),
+        &[bump],
+        crate::ID.as_ref(),
+        b"ProgramDerivedAddress",
+    ])
+    .ne(vault.key())
+    {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
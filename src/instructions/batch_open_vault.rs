@@ -0,0 +1,113 @@
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+use crate::error::VaultError;
+use crate::falcon::{FalconPublicKey, FALCON_512_PUBLIC_KEY_SIZE};
+use super::{check_system_program, create_vault};
+
+// most vaults one BatchOpenVault may create; each costs a PDA hash, a key check and
+// a CreateAccount CPI, so four stay well inside the default compute budget
+pub const MAX_BATCH_OPEN_VAULTS: usize = 4;
+
+// one entry of the batch: [falcon_pubkey(897), bump(1)]
+pub const BATCH_OPEN_VAULT_ENTRY_SIZE: usize = FALCON_512_PUBLIC_KEY_SIZE + 1;
+
+// OpenVault for several keys at once, for onboarding flows that set up more than
+// one vault. the instruction either opens every vault or none of them: any failure
+// fails the whole instruction, which rolls back the vaults already created
+pub struct BatchOpenVault<'a> {
+    entries: &'a [u8],
+}
+
+impl<'a> BatchOpenVault<'a> {
+    // data: 1 to MAX_BATCH_OPEN_VAULTS entries of [falcon_pubkey(897), bump(1)]
+    // without a close authority; the same key twice would be the same vault
+    pub fn deserialize(bytes: &'a [u8]) -> Result<Self, ProgramError> {
+        if bytes.is_empty()
+            || !bytes.len().is_multiple_of(BATCH_OPEN_VAULT_ENTRY_SIZE)
+            || bytes.len() / BATCH_OPEN_VAULT_ENTRY_SIZE > MAX_BATCH_OPEN_VAULTS
+        {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let batch = Self { entries: bytes };
+        for (i, (public_key, _)) in batch.entries().enumerate() {
+            if batch.entries().take(i).any(|(earlier, _)| earlier == public_key) {
+                return Err(VaultError::DuplicateVaultKey.into());
+            }
+        }
+        Ok(batch)
+    }
+
+    // the key bytes and bump of each entry, in order
+    fn entries(&self) -> impl Iterator<Item = (&'a [u8], u8)> {
+        self.entries
+            .chunks_exact(BATCH_OPEN_VAULT_ENTRY_SIZE)
+            .map(|entry| (&entry[..FALCON_512_PUBLIC_KEY_SIZE], entry[FALCON_512_PUBLIC_KEY_SIZE]))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len() / BATCH_OPEN_VAULT_ENTRY_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline(never)]
+    pub fn process(&self, accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+        // payer, system program, then one vault per entry in the same order
+        let [payer, system_program, vaults @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        if vaults.len() != self.len() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        check_system_program(system_program)?;
+
+        for ((public_key, bump), vault) in self.entries().zip(vaults) {
+            let public_key = FalconPublicKey::try_from(public_key).map_err(|_| ProgramError::InvalidInstructionData)?;
+            create_vault(payer, vault, &public_key, bump, None, program_id)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(fill: u8, bump: u8) -> Vec<u8> {
+        let mut entry = vec![fill; FALCON_512_PUBLIC_KEY_SIZE];
+        entry[0] = 9;
+        entry.push(bump);
+        entry
+    }
+
+    #[test]
+    fn test_deserialize_batch() {
+        let data = [entry(1, 255), entry(2, 254), entry(3, 253)].concat();
+        let batch = BatchOpenVault::deserialize(&data).unwrap();
+        assert_eq!(batch.len(), 3);
+        let bumps: Vec<u8> = batch.entries().map(|(_, bump)| bump).collect();
+        assert_eq!(bumps, [255, 254, 253]);
+        assert!(batch.entries().all(|(key, _)| key.len() == FALCON_512_PUBLIC_KEY_SIZE));
+    }
+
+    #[test]
+    fn test_deserialize_rejects_bad_sizes() {
+        let full: Vec<u8> = (0..=MAX_BATCH_OPEN_VAULTS as u8).flat_map(|i| entry(i, 255)).collect();
+        assert!(BatchOpenVault::deserialize(&full[..MAX_BATCH_OPEN_VAULTS * BATCH_OPEN_VAULT_ENTRY_SIZE]).is_ok());
+        assert_eq!(BatchOpenVault::deserialize(&full).err(), Some(ProgramError::InvalidInstructionData));
+        assert_eq!(BatchOpenVault::deserialize(&[]).err(), Some(ProgramError::InvalidInstructionData));
+        assert_eq!(
+            BatchOpenVault::deserialize(&full[..BATCH_OPEN_VAULT_ENTRY_SIZE + 1]).err(),
+            Some(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_rejects_duplicate_keys() {
+        // same key under a different bump is still the same vault seed
+        let data = [entry(1, 255), entry(2, 254), entry(1, 253)].concat();
+        assert_eq!(BatchOpenVault::deserialize(&data).err(), Some(VaultError::DuplicateVaultKey.into()));
+    }
+}
@@ -0,0 +1,89 @@
+use pinocchio::{account_info::AccountInfo, cpi::set_return_data, program_error::ProgramError, ProgramResult};
+use crate::error::VaultError;
+use crate::falcon::{remaining_compute_units, FalconVariant};
+#[cfg(not(feature = "profiling"))]
+use crate::falcon::verify_falcon_signature;
+
+// measures a full verify_falcon_signature on the validator running it, so the
+// estimates in falcon::performance can be checked against real hardware.
+// only built with the bench feature, a deployed program never has it
+pub struct BenchmarkVerify<'a> {
+    public_key: &'a [u8],
+    signature: &'a [u8],
+    message: &'a [u8],
+}
+
+// return data: [accepted(1), compute units consumed (8, little-endian)]
+pub const BENCHMARK_VERIFY_RETURN_SIZE: usize = 9;
+
+// with the profiling feature the return data goes on with [stage_count(1)] and the
+// compute units of each stage (8 each, little-endian) in the order of VERIFY_STAGES
+#[cfg(feature = "profiling")]
+pub const BENCHMARK_VERIFY_PROFILED_RETURN_SIZE: usize =
+    BENCHMARK_VERIFY_RETURN_SIZE + 1 + 8 * crate::falcon::VERIFY_STAGES.len();
+
+#[cfg(not(feature = "profiling"))]
+const RETURN_SIZE: usize = BENCHMARK_VERIFY_RETURN_SIZE;
+#[cfg(feature = "profiling")]
+const RETURN_SIZE: usize = BENCHMARK_VERIFY_PROFILED_RETURN_SIZE;
+
+impl<'a> BenchmarkVerify<'a> {
+    // [public_key, signature, message], the key and signature sizes follow the
+    // degree in the key header; a Falcon-1024 pair parses, but on-chain verification
+    // refuses it, see verify_parts_profiled
+    pub fn deserialize(bytes: &'a [u8]) -> Result<Self, ProgramError> {
+        let params = bytes
+            .first()
+            .and_then(|&header| FalconVariant::from_logn(header))
+            .ok_or(VaultError::BadPublicKeyHeader)?
+            .params();
+        if bytes.len() < params.public_key_size + params.signature_size {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let (public_key, rest) = bytes.split_at(params.public_key_size);
+        let (signature, message) = rest.split_at(params.signature_size);
+        Ok(Self { public_key, signature, message })
+    }
+
+    #[inline(never)]
+    pub fn process(&self, accounts: &[AccountInfo]) -> ProgramResult {
+        if !accounts.is_empty() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let mut return_data = [0u8; RETURN_SIZE];
+
+        // the delta also counts one remaining-CU syscall, about 100 CU
+        let before = remaining_compute_units();
+        let accepted = self.verify(&mut return_data[BENCHMARK_VERIFY_RETURN_SIZE..]);
+        let consumed = before.saturating_sub(remaining_compute_units());
+
+        return_data[0] = accepted as u8;
+        return_data[1..BENCHMARK_VERIFY_RETURN_SIZE].copy_from_slice(&consumed.to_le_bytes());
+        set_return_data(&return_data);
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    fn verify(&self, _stages: &mut [u8]) -> bool {
+        verify_falcon_signature(self.public_key, self.signature, self.message).is_ok()
+    }
+
+    // verify and write the stage breakdown into stages
+    #[cfg(feature = "profiling")]
+    fn verify(&self, stages: &mut [u8]) -> bool {
+        let mut monitor = crate::falcon::PerformanceMonitor::new();
+        let accepted =
+            crate::falcon::verify_falcon_signature_profiled(self.public_key, self.signature, self.message, &mut monitor)
+                .is_ok();
+
+        let (count, costs) = stages.split_first_mut().unwrap();
+        *count = monitor.stages().len() as u8;
+        for (slot, (_, cost)) in costs.chunks_exact_mut(8).zip(monitor.stages()) {
+            slot.copy_from_slice(&cost.to_le_bytes());
+        }
+        accepted
+    }
+}
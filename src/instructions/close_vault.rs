@@ -1,75 +1,262 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
-use crate::falcon::{FalconSignature, FalconPublicKey, FALCON_512_SIGNATURE_SIZE, FALCON_512_PUBLIC_KEY_SIZE};
+use arrayref::array_ref;
+use pinocchio::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+use crate::falcon::{FalconPublicKey, FalconSignature, FALCON_512_SIGNATURE_SIZE};
+use crate::error::VaultError;
+use crate::messages::{CLOSE_VAULT_TAG, VAULT_SIGNING_CONTEXT};
+use super::{
+    check_vault_address, check_vault_owner, checked_lamport_transfer, load_close_authority, load_close_nonce,
+    load_public_key, rent_exempt_minimum, FalconVerifier, SignatureVerifier, VaultNonce,
+};
 
+#[derive(PartialEq, Eq)]
 pub struct CloseVault {
-    signature: FalconSignature,
+    // None when the vault's close authority signs the transaction instead
+    signature: Option<FalconSignature>,
     bump: u8,
+    // (min, max) vault balance the signature commits to, see build_close_message_with_balance
+    balance_bounds: Option<(u64, u64)>,
 }
 
 impl CloseVault {
+    // data: [signature(666), bump(1)], optionally followed by [min_balance(8), max_balance(8)]
+    // when the signature commits to the balance, or just [bump(1)] to close with the close authority
     pub fn deserialize(bytes: &[u8]) -> Result<Self, ProgramError> {
-        let expected_size = FALCON_512_SIGNATURE_SIZE + 1;
-        if bytes.len() != expected_size {
-            return Err(ProgramError::InvalidInstructionData);
-        }
-
-        let mut signature_bytes = [0u8; FALCON_512_SIGNATURE_SIZE];
-        signature_bytes.copy_from_slice(&bytes[0..FALCON_512_SIGNATURE_SIZE]);
-        let bump = bytes[FALCON_512_SIGNATURE_SIZE];
+        let signed_size = FALCON_512_SIGNATURE_SIZE + 1;
+        let balance_bounds = match bytes.len() {
+            1 => return Ok(Self { signature: None, bump: bytes[0], balance_bounds: None }),
+            n if n == signed_size => None,
+            n if n == signed_size + 16 => Some((
+                u64::from_le_bytes(*array_ref!(bytes, signed_size, 8)),
+                u64::from_le_bytes(*array_ref!(bytes, signed_size + 8, 8)),
+            )),
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
 
         Ok(Self {
-            signature: FalconSignature::from(signature_bytes),
-            bump,
+            signature: Some(
+                FalconSignature::try_from(&bytes[..FALCON_512_SIGNATURE_SIZE])
+                    .map_err(|_| ProgramError::InvalidInstructionData)?,
+            ),
+            bump: bytes[FALCON_512_SIGNATURE_SIZE],
+            balance_bounds,
         })
     }
 
+    // the data deserialize reads, without the discriminator. the balance bounds only
+    // go out with a signature, the authority layout has no room for them
+    #[cfg(any(test, feature = "client"))]
+    pub fn serialize(&self) -> Vec<u8> {
+        let Some(signature) = &self.signature else {
+            return vec![self.bump];
+        };
+        let mut data = signature.as_bytes().to_vec();
+        data.push(self.bump);
+        if let Some((min_balance, max_balance)) = self.balance_bounds {
+            data.extend_from_slice(&min_balance.to_le_bytes());
+            data.extend_from_slice(&max_balance.to_le_bytes());
+        }
+        data
+    }
+
+    #[inline(never)]
     pub fn process(&self, accounts: &[AccountInfo]) -> ProgramResult {
-        // asert we have exactly 2 accounts
-        let [vault, refund] = accounts else {
-            return Err(ProgramError::NotEnoughAccountKeys);
+        self.process_with(accounts, &FalconVerifier)
+    }
+
+    pub fn process_with<V: SignatureVerifier>(&self, accounts: &[AccountInfo], verifier: &V) -> ProgramResult {
+        // vault and refund, then the close authority when closing without a signature
+        let (vault, refund, close_authority) = match accounts {
+            [vault, refund] => (vault, refund, None),
+            [vault, refund, close_authority] => (vault, refund, Some(close_authority)),
+            _ => return Err(ProgramError::NotEnoughAccountKeys),
         };
 
         // check that vault is owned by our program
-        // AccountInfo::owner() is safe to call as it's just reading the account's owner field
-        if unsafe { vault.owner() } != &crate::ID {
-            return Err(ProgramError::IncorrectProgramId);
-        }
+        check_vault_owner(vault)?;
 
-        // read the public key from the vault account
-        let vault_data = vault.try_borrow_data()?;
-        if vault_data.len() != FALCON_512_PUBLIC_KEY_SIZE {
-            return Err(ProgramError::InvalidAccountData);
+        // crediting the vault and then closing it would burn the balance
+        if refund.key() == vault.key() {
+            return Err(ProgramError::InvalidArgument);
         }
-        
-        let mut public_key_bytes = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
-        public_key_bytes.copy_from_slice(&vault_data);
-        let public_key = FalconPublicKey::from(public_key_bytes);
-        drop(vault_data);
-
-        // create message to verify
-        // Message: "CLOSE_VAULT" + refund pubkey
-        let mut message = [0u8; 43];
-        message[0..11].copy_from_slice(b"CLOSE_VAULT");
-        message[11..43].copy_from_slice(refund.key());
-
-        // verify the Falcon signature
-        self.signature.verify(&public_key, &message)?;
-
-        // Verify PDA
-        let pubkey_hash = public_key.hash();
-        if solana_nostd_sha256::hashv(&[
-            pubkey_hash.as_ref(),
-            &[self.bump],
-            crate::ID.as_ref(),
-            b"ProgramDerivedAddress",
-        ])
-        .ne(vault.key())
-        {
-            return Err(ProgramError::MissingRequiredSignature);
+
+        // read the public key, close authority and nonce from the vault account
+        let (public_key, stored_authority, nonce) = {
+            let data = vault.try_borrow_data()?;
+            (load_public_key(&data)?, load_close_authority(&data)?, load_close_nonce(&data)?)
+        };
+
+        // Verify PDA, before the signature so a close that was always going to fail
+        // doesn't pay for verification
+        check_vault_address(vault, &public_key.hash(), stored_authority.as_ref(), self.bump)?;
+
+        match (&self.signature, close_authority) {
+            (Some(signature), _) => {
+                if let Some(bounds) = self.balance_bounds {
+                    check_balance_bounds(bounds, vault.lamports())?;
+                }
+                check_signed_close(signature, &public_key, refund.key(), nonce, vault.key(), self.balance_bounds, verifier)?
+            }
+            // no signature: the close authority stored at open time has to sign the transaction
+            (None, Some(close_authority)) => {
+                if stored_authority.as_ref() != Some(close_authority.key()) {
+                    return Err(VaultError::InvalidCloseAuthority.into());
+                }
+                if !close_authority.is_signer() {
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+                // the authority only recovers rent, whatever was deposited on top
+                // takes the Falcon key to move
+                if vault.lamports() > rent_exempt_minimum(vault.data_len())? {
+                    return Err(VaultError::VaultNotEmpty.into());
+                }
+            }
+            (None, None) => return Err(ProgramError::NotEnoughAccountKeys),
         }
 
         // close vault and refund all lamports to refund account
-        *refund.try_borrow_mut_lamports()? += vault.lamports();
-        vault.close()
+        close_into(vault, refund)
+    }
+}
+
+// hold the vault balance to the bounds a signed close commits to, before the signature
+// is verified: the balance moved since signing when a withdrawal front-ran the close,
+// or a deposit arrived that the signer never agreed to sweep
+fn check_balance_bounds((min_balance, max_balance): (u64, u64), vault_lamports: u64) -> ProgramResult {
+    if !(min_balance..=max_balance).contains(&vault_lamports) {
+        return Err(VaultError::VaultBalanceOutOfRange.into());
+    }
+    Ok(())
+}
+
+// message: "CLOSE_VAULT" + refund pubkey + nonce + vault address [+ min + max balance],
+// the layout of messages::build_close_message(_with_balance), signed under
+// VAULT_SIGNING_CONTEXT. the nonce is the vault's, see load_close_nonce
+fn check_signed_close<V: SignatureVerifier>(
+    signature: &FalconSignature,
+    public_key: &FalconPublicKey,
+    refund: &Pubkey,
+    nonce: VaultNonce,
+    vault: &Pubkey,
+    balance_bounds: Option<(u64, u64)>,
+    verifier: &V,
+) -> ProgramResult {
+    let nonce_bytes = nonce.to_le_bytes();
+    match balance_bounds {
+        None => verifier.verify_parts(
+            signature,
+            public_key,
+            VAULT_SIGNING_CONTEXT,
+            &[CLOSE_VAULT_TAG, refund, &nonce_bytes, vault],
+        ),
+        Some((min_balance, max_balance)) => verifier.verify_parts(
+            signature,
+            public_key,
+            VAULT_SIGNING_CONTEXT,
+            &[CLOSE_VAULT_TAG, refund, &nonce_bytes, vault, &min_balance.to_le_bytes(), &max_balance.to_le_bytes()],
+        ),
     }
-} 
\ No newline at end of file
+}
+
+// move every lamport of the vault to refund, then close the vault; refund is never the
+// vault itself, process_with rejects that up front
+// the debit and the credit happen together, after both borrows succeeded, so there is
+// no point where one balance changed and the other didn't. the vault is empty before
+// close() clears its data and owner, so even a failed close can't leave it spendable
+// with the refund already paid (and the runtime rolls back a failed instruction anyway)
+fn close_into(vault: &AccountInfo, refund: &AccountInfo) -> ProgramResult {
+    {
+        let mut vault_lamports = vault.try_borrow_mut_lamports()?;
+        let mut refund_lamports = refund.try_borrow_mut_lamports()?;
+        let (emptied, refunded) = checked_lamport_transfer(*vault_lamports, *refund_lamports, *vault_lamports)?;
+        *vault_lamports = emptied;
+        *refund_lamports = refunded;
+    }
+    vault.close()
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{MockVerifier, VerifyCall};
+    use crate::messages::{build_close_message, build_close_message_with_balance};
+    use proptest::prelude::*;
+
+    // with verification stubbed to accept, the message layout is all that's checked
+    #[test]
+    fn test_signed_close_message() {
+        let (signature, public_key) = (FalconSignature::new([0; 666]), FalconPublicKey::new([9; 897]));
+        let (refund, vault, nonce) = ([0x33u8; 32], [0x44u8; 32], VaultNonce::new(5));
+
+        let verifier = MockVerifier::accepting();
+        assert_eq!(check_signed_close(&signature, &public_key, &refund, nonce, &vault, None, &verifier), Ok(()));
+        let bounds = Some((100, 200));
+        assert_eq!(check_signed_close(&signature, &public_key, &refund, nonce, &vault, bounds, &verifier), Ok(()));
+
+        let message = build_close_message(&refund, 5, &vault).to_vec();
+        let with_balance = build_close_message_with_balance(&refund, 5, &vault, 100, 200).to_vec();
+        assert_eq!(
+            verifier.calls(),
+            [
+                VerifyCall::Message(VAULT_SIGNING_CONTEXT.to_vec(), message),
+                VerifyCall::Message(VAULT_SIGNING_CONTEXT.to_vec(), with_balance),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_balance_bounds() {
+        for (lamports, expected) in [
+            (100, Ok(())),
+            (200, Ok(())),
+            (99, Err(VaultError::VaultBalanceOutOfRange.into())),
+            (201, Err(VaultError::VaultBalanceOutOfRange.into())),
+        ] {
+            assert_eq!(check_balance_bounds((100, 200), lamports), expected);
+        }
+        assert_eq!(check_balance_bounds((0, u64::MAX), 0), Ok(()));
+    }
+
+    // a close signature is good for the one vault and nonce it names: not for the same
+    // key's vault with a close authority, nor for its own vault once the nonce moved
+    #[test]
+    fn test_close_signature_replayed_on_another_vault() {
+        let keypair = &crate::fixtures::fixture_keypairs()[0];
+        let public_key = FalconPublicKey::new(keypair.public_key);
+        let [plain, _, with_authority] = &keypair.closes[..] else { panic!("three fixture closes") };
+        assert_ne!(plain.vault, with_authority.vault);
+
+        let check = |close: &crate::fixtures::CloseFixture, nonce: u64, vault: &Pubkey| {
+            let signature = FalconSignature::new(close.signature);
+            check_signed_close(&signature, &public_key, &close.refund, VaultNonce::new(nonce), vault, None, &FalconVerifier)
+        };
+        assert_eq!(check(plain, plain.nonce, &plain.vault), Ok(()));
+        assert_eq!(check(with_authority, with_authority.nonce, &with_authority.vault), Ok(()));
+
+        let rejected = Err(VaultError::NormBoundExceeded.into());
+        assert_eq!(check(plain, plain.nonce, &with_authority.vault), rejected);
+        assert_eq!(check(with_authority, with_authority.nonce, &plain.vault), rejected);
+        assert_eq!(check(plain, plain.nonce + 1, &plain.vault), rejected);
+    }
+
+    proptest! {
+        #[test]
+        fn test_serialize_roundtrip(
+            signature in prop::option::of(prop::collection::vec(any::<u8>(), FALCON_512_SIGNATURE_SIZE)),
+            bump in any::<u8>(),
+            balance_bounds in prop::option::of(any::<(u64, u64)>()),
+            extra in any::<u8>(),
+        ) {
+            // closing with the authority has no signature to commit to bounds
+            let signature = signature.map(|bytes| FalconSignature::try_from(&bytes[..]).unwrap());
+            let balance_bounds = balance_bounds.filter(|_| signature.is_some());
+            let close = CloseVault { signature, bump, balance_bounds };
+            let data = close.serialize();
+            prop_assert!(CloseVault::deserialize(&data).ok() == Some(close));
+
+            // none of the three layouts is a byte away from another
+            prop_assert!(CloseVault::deserialize(&data[..data.len() - 1]).is_err());
+            let mut longer = data;
+            longer.push(extra);
+            prop_assert!(CloseVault::deserialize(&longer).is_err());
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use pinocchio::{
+    program_error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+};
+use crate::error::VaultError;
+
+// the cluster defaults behind the rent-exempt minimum: 3480 lamports per byte-year, two
+// years of rent, and 128 bytes of overhead charged to every account
+pub const DEFAULT_LAMPORTS_PER_BYTE_YEAR: u64 = 3_480;
+pub const DEFAULT_EXEMPTION_YEARS: u64 = 2;
+pub const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+
+// rent-exempt minimum for an account of `space` bytes under the default rent parameters,
+// 890_880 lamports for an empty account and 7_196_640 for a vault (906 bytes)
+pub const fn default_rent_exempt_minimum(space: usize) -> u64 {
+    (ACCOUNT_STORAGE_OVERHEAD + space as u64) * DEFAULT_LAMPORTS_PER_BYTE_YEAR * DEFAULT_EXEMPTION_YEARS
+}
+
+// rent-exempt minimum for an account of `space` bytes, from the Rent sysvar
+// the sysvar is authoritative; only a localnet build falls back to the defaults
+// above when it can't be read, see the localnet feature in Cargo.toml
+pub fn rent_exempt_minimum(space: usize) -> Result<u64, ProgramError> {
+    rent_exempt_minimum_or_default(Rent::get().map(|rent| rent.minimum_balance(space)), space)
+}
+
+#[cfg(not(feature = "localnet"))]
+fn rent_exempt_minimum_or_default(sysvar: Result<u64, ProgramError>, _space: usize) -> Result<u64, ProgramError> {
+    sysvar
+}
+
+#[cfg(feature = "localnet")]
+fn rent_exempt_minimum_or_default(sysvar: Result<u64, ProgramError>, space: usize) -> Result<u64, ProgramError> {
+    Ok(sysvar.unwrap_or(default_rent_exempt_minimum(space)))
+}
+
+// compute the balances after moving `amount` lamports between two accounts
+// returns (new_from_balance, new_to_balance) without touching either account
+pub fn checked_lamport_transfer(from_balance: u64, to_balance: u64, amount: u64) -> Result<(u64, u64), ProgramError> {
+    let new_from_balance = from_balance
+        .checked_sub(amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    let new_to_balance = to_balance
+        .checked_add(amount)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    Ok((new_from_balance, new_to_balance))
+}
+
+// a recipient with no lamports doesn't exist yet, and the runtime only lets a transfer
+// create it if it ends up rent-exempt. the vault can't create it with CreateAccount
+// (that needs the recipient's signature), so a transfer to a new account must carry at
+// least the rent-exempt minimum itself. existing recipients are left to the runtime
+pub fn check_recipient_rent(recipient_balance: u64, amount: u64, minimum_balance: u64) -> Result<(), ProgramError> {
+    if recipient_balance == 0 && amount < minimum_balance {
+        return Err(VaultError::RecipientNotRentExempt.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_transfer() {
+        assert_eq!(checked_lamport_transfer(1_000, 5, 400), Ok((600, 405)));
+        assert_eq!(checked_lamport_transfer(1_000, 5, 1_000), Ok((0, 1_005)));
+    }
+
+    #[test]
+    fn test_insufficient_funds() {
+        assert_eq!(
+            checked_lamport_transfer(100, 0, 101),
+            Err(ProgramError::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn test_recipient_near_u64_max() {
+        assert_eq!(checked_lamport_transfer(10, u64::MAX - 10, 10), Ok((0, u64::MAX)));
+        assert_eq!(
+            checked_lamport_transfer(10, u64::MAX - 9, 10),
+            Err(VaultError::ArithmeticOverflow.into())
+        );
+    }
+
+    #[test]
+    fn test_new_recipient_rent() {
+        assert_eq!(check_recipient_rent(0, 890_880, 890_880), Ok(()));
+        assert_eq!(
+            check_recipient_rent(0, 1, 890_880),
+            Err(VaultError::RecipientNotRentExempt.into())
+        );
+        // an existing account can take any amount
+        assert_eq!(check_recipient_rent(1_000_000, 1, 890_880), Ok(()));
+    }
+
+    // the sysvar answer wins whenever there is one, localnet or not
+    #[test]
+    fn test_rent_sysvar_used_by_default() {
+        let space = super::super::VAULT_DATA_SIZE_NONCE;
+        assert_eq!(default_rent_exempt_minimum(0), 890_880);
+        assert_eq!(default_rent_exempt_minimum(space), 7_196_640);
+        assert_eq!(rent_exempt_minimum_or_default(Ok(1_234), space), Ok(1_234));
+
+        #[cfg(not(feature = "localnet"))]
+        assert_eq!(
+            rent_exempt_minimum_or_default(Err(ProgramError::UnsupportedSysvar), space),
+            Err(ProgramError::UnsupportedSysvar)
+        );
+    }
+
+    #[cfg(feature = "localnet")]
+    #[test]
+    fn test_rent_fallback_on_localnet() {
+        for space in [0, super::super::VAULT_DATA_SIZE_NONCE, super::super::VAULT_DATA_SIZE_NONCE_CLOSE_AUTHORITY] {
+            assert_eq!(
+                rent_exempt_minimum_or_default(Err(ProgramError::UnsupportedSysvar), space),
+                Ok(default_rent_exempt_minimum(space))
+            );
+        }
+    }
+}
@@ -4,8 +4,40 @@ pub use vault_instructions::*;
 pub mod open_vault;
 pub use open_vault::*;
 
+pub mod batch_open_vault;
+pub use batch_open_vault::*;
+
 pub mod transfer_from_vault;
 pub use transfer_from_vault::*;
 
 pub mod close_vault;
-pub use close_vault::*; 
\ No newline at end of file
+pub use close_vault::*;
+
+pub mod verify_only;
+pub use verify_only::*;
+
+pub mod self_test;
+pub use self_test::*;
+
+#[cfg(feature = "bench")]
+pub mod benchmark_verify;
+#[cfg(feature = "bench")]
+pub use benchmark_verify::*;
+
+pub mod lamports;
+pub use lamports::*;
+
+pub mod vault_data;
+pub use vault_data::*;
+
+pub mod nonce;
+pub use nonce::*;
+
+pub mod verifier;
+pub use verifier::*;
+
+pub mod payload;
+pub use payload::*;
+
+pub mod accounts;
+pub use accounts::*;
@@ -0,0 +1,117 @@
+use pinocchio::program_error::ProgramError;
+use crate::error::VaultError;
+
+// bytes a stored transfer nonce takes, little-endian like the signed message
+pub const VAULT_NONCE_SIZE: usize = 8;
+
+// a vault opened in slot s starts at nonce s << OPENING_NONCE_SHIFT, see VaultNonce::opening
+pub const OPENING_NONCE_SHIFT: u32 = 16;
+
+// a vault's transfer nonce and the slot of vault data it lives in. each transfer signs
+// the current nonce and moves it on by one, so a signature verifies once; next() stops
+// at u64::MAX, since wrapping to zero would make every signature the vault ever
+// accepted valid again. the slot is the last VAULT_NONCE_SIZE bytes of a v3 or v4 vault,
+// see load_transfer_nonce and advance_transfer_nonce
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VaultNonce(u64);
+
+impl VaultNonce {
+    pub const fn new(value: u64) -> Self {
+        Self(value)
+    }
+
+    // the nonce a vault opened in `slot` starts at. every transfer write locks the vault,
+    // and a block spends at most 12M compute units on one writable account, far short of
+    // 2^16 Falcon verifications, so a vault never counts up to where a vault opened at
+    // its address in a later slot starts: what was signed for a closed vault doesn't
+    // verify for the one reopened after it. only a reopen within the slot the vault was
+    // opened in starts where the old one did
+    pub const fn opening(slot: u64) -> Self {
+        Self(slot.saturating_mul(1 << OPENING_NONCE_SHIFT))
+    }
+
+    pub const fn value(self) -> u64 {
+        self.0
+    }
+
+    // the nonce as it appears in the transfer message
+    pub const fn to_le_bytes(self) -> [u8; VAULT_NONCE_SIZE] {
+        self.0.to_le_bytes()
+    }
+
+    // the nonce stored in a slot of exactly VAULT_NONCE_SIZE bytes
+    pub fn read(slot: &[u8]) -> Result<Self, ProgramError> {
+        let bytes = <[u8; VAULT_NONCE_SIZE]>::try_from(slot).map_err(|_| ProgramError::InvalidAccountData)?;
+        Ok(Self(u64::from_le_bytes(bytes)))
+    }
+
+    pub fn write(self, slot: &mut [u8]) -> Result<(), ProgramError> {
+        let bytes = <&mut [u8; VAULT_NONCE_SIZE]>::try_from(slot).map_err(|_| ProgramError::InvalidAccountData)?;
+        *bytes = self.to_le_bytes();
+        Ok(())
+    }
+
+    // the nonce after this one. u64::MAX has none, and advance can't store past it,
+    // so u64::MAX - 1 is the last nonce a vault signs
+    pub fn next(self) -> Result<Self, VaultError> {
+        self.0.checked_add(1).map(Self).ok_or(VaultError::NonceExhausted)
+    }
+
+    // the nonce in the slot, leaving the next one stored in its place. nothing is
+    // written unless next() succeeds, so an exhausted slot keeps u64::MAX, and the
+    // runtime rolls the write back if the instruction fails later on
+    pub fn advance(slot: &mut [u8]) -> Result<Self, ProgramError> {
+        let current = Self::read(slot)?;
+        current.next()?.write(slot)?;
+        Ok(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_write_little_endian() {
+        let mut slot = [0u8; VAULT_NONCE_SIZE];
+        VaultNonce::new(0x0102030405060708).write(&mut slot).unwrap();
+        assert_eq!(slot, [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(VaultNonce::read(&slot), Ok(VaultNonce::new(0x0102030405060708)));
+
+        // the slot is exactly eight bytes, shorter or longer is someone else's data
+        assert_eq!(VaultNonce::read(&slot[..7]), Err(ProgramError::InvalidAccountData));
+        assert_eq!(VaultNonce::new(1).write(&mut [0u8; 9]), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_opening_nonces_leave_room_between_slots() {
+        assert_eq!(VaultNonce::opening(0), VaultNonce::new(0));
+        assert_eq!(VaultNonce::opening(3).value(), 3 << 16);
+        assert_eq!(VaultNonce::opening(4).value() - VaultNonce::opening(3).value(), 1 << OPENING_NONCE_SHIFT);
+        // slots far past any cluster's lifetime saturate instead of wrapping to used nonces
+        assert_eq!(VaultNonce::opening(u64::MAX), VaultNonce::new(u64::MAX));
+    }
+
+    #[test]
+    fn test_advance() {
+        let mut slot = [0u8; VAULT_NONCE_SIZE];
+        assert_eq!(VaultNonce::advance(&mut slot), Ok(VaultNonce::new(0)));
+        assert_eq!(VaultNonce::advance(&mut slot), Ok(VaultNonce::new(1)));
+        assert_eq!(VaultNonce::read(&slot), Ok(VaultNonce::new(2)));
+    }
+
+    // driven up to u64::MAX the nonce is exhausted, it never wraps back to a used value
+    #[test]
+    fn test_exhaustion_does_not_wrap() {
+        assert_eq!(VaultNonce::new(u64::MAX - 1).next(), Ok(VaultNonce::new(u64::MAX)));
+        assert_eq!(VaultNonce::new(u64::MAX).next(), Err(VaultError::NonceExhausted));
+
+        let mut slot = (u64::MAX - 2).to_le_bytes();
+        let used: Vec<u64> = core::iter::from_fn(|| VaultNonce::advance(&mut slot).ok())
+            .map(VaultNonce::value)
+            .collect();
+        assert_eq!(used, [u64::MAX - 2, u64::MAX - 1]);
+        assert_eq!(VaultNonce::advance(&mut slot), Err(VaultError::NonceExhausted.into()));
+        assert_eq!(VaultNonce::read(&slot), Ok(VaultNonce::new(u64::MAX)));
+    }
+}
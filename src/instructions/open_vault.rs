@@ -2,66 +2,145 @@ use pinocchio::{
     account_info::AccountInfo,
     instruction::{Seed, Signer},
     program_error::ProgramError,
-    sysvars::{rent::Rent, Sysvar},
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
     ProgramResult,
 };
 use pinocchio_system::instructions::CreateAccount;
 use crate::falcon::{FalconPublicKey, FALCON_512_PUBLIC_KEY_SIZE};
+use super::{
+    check_system_program, check_vault_address, close_authority_seed, rent_exempt_minimum, store_vault_data,
+    vault_data_size, VaultNonce,
+};
 
+#[derive(PartialEq, Eq)]
 pub struct OpenVault {
     public_key: FalconPublicKey,
     bump: u8,
+    close_authority: Option<Pubkey>,
 }
 
 impl OpenVault {
+    // data: [falcon_pubkey(897), bump(1)] with an optional close_authority(32)
+    // that may close the vault without a Falcon signature, see CloseVault
     pub fn deserialize(bytes: &[u8]) -> Result<Self, ProgramError> {
         let expected_size = FALCON_512_PUBLIC_KEY_SIZE + 1;
-        if bytes.len() != expected_size {
-            return Err(ProgramError::InvalidInstructionData);
-        }
+        let close_authority = match bytes.len() {
+            n if n == expected_size => None,
+            n if n == expected_size + 32 => {
+                let mut close_authority = [0u8; 32];
+                close_authority.copy_from_slice(&bytes[expected_size..]);
+                Some(close_authority)
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
 
-        let mut pubkey_bytes = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
-        pubkey_bytes.copy_from_slice(&bytes[0..FALCON_512_PUBLIC_KEY_SIZE]);
-        let bump = bytes[FALCON_512_PUBLIC_KEY_SIZE];
-        
         Ok(Self {
-            public_key: FalconPublicKey::from(pubkey_bytes),
-            bump,
+            public_key: FalconPublicKey::try_from(&bytes[..FALCON_512_PUBLIC_KEY_SIZE])
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+            bump: bytes[FALCON_512_PUBLIC_KEY_SIZE],
+            close_authority,
         })
     }
 
-    pub fn process(&self, accounts: &[AccountInfo], program_id: &pinocchio::pubkey::Pubkey) -> ProgramResult {
+    // the data deserialize reads, without the discriminator
+    #[cfg(any(test, feature = "client"))]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.public_key.as_bytes().to_vec();
+        data.push(self.bump);
+        if let Some(close_authority) = &self.close_authority {
+            data.extend_from_slice(close_authority);
+        }
+        data
+    }
+
+    #[inline(never)]
+    pub fn process(&self, accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
         // assert we have exactly 3 accounts
-        let [payer, vault, _system_program] = accounts else {
+        let [payer, vault, system_program] = accounts else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
+        check_system_program(system_program)?;
+
+        create_vault(payer, vault, &self.public_key, self.bump, self.close_authority.as_ref(), program_id)
+    }
+}
+
+// check a key and its vault address, then create the vault and store it
+// shared by OpenVault and BatchOpenVault; the system program is checked by the caller
+pub(crate) fn create_vault(
+    payer: &AccountInfo,
+    vault: &AccountInfo,
+    public_key: &FalconPublicKey,
+    bump: u8,
+    close_authority: Option<&Pubkey>,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    // reject malformed keys up front, a vault opened with one could never be spent from
+    public_key.validate()?;
+
+    // Hash the Falcon public key to create a 32-byte seed for the PDA
+    let pubkey_hash = public_key.hash();
+
+    // a payer and vault passed the other way round fail here, before the CPI
+    // and so does a close authority the vault address wasn't derived with
+    check_vault_address(vault, &pubkey_hash, close_authority, bump)?;
+
+    let bump_array = [bump];
+    
+    // Standard Solana PDA: [key hash, close authority, bump] using actual program_id
+    let seeds = [
+        Seed::from(&pubkey_hash),
+        Seed::from(close_authority_seed(close_authority)),
+        Seed::from(&bump_array),
+    ];
+    
+    // rent for the version byte, the public key and the close authority if any
+    let space = vault_data_size(close_authority);
+    let lamports = rent_exempt_minimum(space)?;
+    
+    let signers = [Signer::from(&seeds)];
+
+    // create vault with space for its layout
+    CreateAccount {
+        from: payer,
+        to: vault,
+        lamports,
+        space: space as u64,
+        owner: program_id,
+    }
+    .invoke_signed(&signers[..])?;
+    
+    // a vault reopened at the same address starts past every nonce the closed one
+    // could have signed, so none of its transfer or close signatures replay here
+    let first_nonce = VaultNonce::opening(Clock::get()?.slot);
+
+    // store the version, public key, close authority and first nonce in the vault account
+    store_vault_data(&mut vault.try_borrow_mut_data()?, public_key, close_authority, first_nonce)
+}
 
-        // Hash the Falcon public key to create a 32-byte seed for the PDA
-        let pubkey_hash = self.public_key.hash();
-        let bump_array = [self.bump];
-        
-        // Standard Solana PDA: [seed, bump] using actual program_id
-        let seeds = [Seed::from(&pubkey_hash), Seed::from(&bump_array)];
-        
-        // rent for storing the public key
-        let lamports = Rent::get()?.minimum_balance(FALCON_512_PUBLIC_KEY_SIZE);
-        
-        let signers = [Signer::from(&seeds)];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
 
-        // create vault with space for the public key
-        CreateAccount {
-            from: payer,
-            to: vault,
-            lamports,
-            space: FALCON_512_PUBLIC_KEY_SIZE as u64,
-            owner: program_id,
+    proptest! {
+        #[test]
+        fn test_serialize_roundtrip(
+            key in prop::collection::vec(any::<u8>(), FALCON_512_PUBLIC_KEY_SIZE),
+            bump in any::<u8>(),
+            close_authority in prop::option::of(any::<Pubkey>()),
+            extra in any::<u8>(),
+        ) {
+            let open = OpenVault { public_key: FalconPublicKey::try_from(&key[..]).unwrap(), bump, close_authority };
+            let data = open.serialize();
+            prop_assert!(OpenVault::deserialize(&data).ok() == Some(open));
+
+            // both layouts are a byte away from none
+            prop_assert!(OpenVault::deserialize(&data[..data.len() - 1]).is_err());
+            let mut longer = data;
+            longer.push(extra);
+            prop_assert!(OpenVault::deserialize(&longer).is_err());
         }
-        .invoke_signed(&signers[..])?;
-        
-        // store the public key in the vault account
-        vault.try_borrow_mut_data()?
-            .copy_from_slice(&self.public_key.bytes);
-        
-        Ok(())
     }
-} 
\ No newline at end of file
+}
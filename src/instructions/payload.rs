@@ -0,0 +1,97 @@
+use pinocchio::{program_error::ProgramError, ProgramResult};
+use crate::falcon::{FalconPublicKey, FalconSignature};
+use crate::messages::{PAYLOAD_MESSAGE_TAG, PAYLOAD_MODE_DIRECT, PAYLOAD_MODE_SHA256, VAULT_SIGNING_CONTEXT};
+
+// verify a signature authorizing an arbitrary payload, see messages::build_payload_message
+// made under VAULT_SIGNING_CONTEXT like every vault signature. in PAYLOAD_MODE_SHA256 the payload is hashed here with the SHA256 syscall and only the
+// digest goes through SHAKE256, so large payloads cost little more than small ones
+pub fn verify_payload_signature(
+    signature: &FalconSignature,
+    public_key: &FalconPublicKey,
+    mode: u8,
+    payload: &[u8],
+) -> ProgramResult {
+    match mode {
+        PAYLOAD_MODE_DIRECT => {
+            Ok(signature.verify_with_context(public_key, VAULT_SIGNING_CONTEXT, &[PAYLOAD_MESSAGE_TAG, &[mode], payload])?)
+        }
+        PAYLOAD_MODE_SHA256 => {
+            let digest = solana_nostd_sha256::hash(payload);
+            Ok(signature.verify_with_context(public_key, VAULT_SIGNING_CONTEXT, &[PAYLOAD_MESSAGE_TAG, &[mode], &digest])?)
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::VaultError;
+    use crate::falcon::verify::tests::{context_fixture, signed_fixture, FIXTURE_NONCE};
+    use crate::messages::build_payload_message;
+
+    fn signed_payload(mode: u8, payload: &[u8]) -> (FalconPublicKey, FalconSignature) {
+        let (public_key, signature) = context_fixture(VAULT_SIGNING_CONTEXT, &build_payload_message(mode, payload));
+        (FalconPublicKey::new(public_key), FalconSignature::new(signature))
+    }
+
+    #[test]
+    fn test_payload_modes_verify() {
+        let payload = vec![0x5Cu8; 4096];
+        for mode in [PAYLOAD_MODE_DIRECT, PAYLOAD_MODE_SHA256] {
+            let (public_key, signature) = signed_payload(mode, &payload);
+            assert_eq!(verify_payload_signature(&signature, &public_key, mode, &payload), Ok(()));
+
+            let mut other = payload.clone();
+            other[4000] ^= 1;
+            assert!(verify_payload_signature(&signature, &public_key, mode, &other).is_err());
+        }
+    }
+
+    #[test]
+    fn test_payload_modes_cannot_be_confused() {
+        let payload = b"execute this";
+        let digest = solana_nostd_sha256::hash(payload);
+
+        // a direct signature over the digest bytes is not a prehashed signature over the payload
+        let (public_key, signature) = signed_payload(PAYLOAD_MODE_DIRECT, &digest);
+        assert_eq!(verify_payload_signature(&signature, &public_key, PAYLOAD_MODE_DIRECT, &digest), Ok(()));
+        assert_eq!(
+            verify_payload_signature(&signature, &public_key, PAYLOAD_MODE_SHA256, payload),
+            Err(VaultError::NormBoundExceeded.into())
+        );
+
+        // and a prehashed signature doesn't verify as a direct one over the payload or the digest
+        let (public_key, signature) = signed_payload(PAYLOAD_MODE_SHA256, payload);
+        assert_eq!(verify_payload_signature(&signature, &public_key, PAYLOAD_MODE_SHA256, payload), Ok(()));
+        for direct in [&payload[..], &digest[..]] {
+            assert_eq!(
+                verify_payload_signature(&signature, &public_key, PAYLOAD_MODE_DIRECT, direct),
+                Err(VaultError::NormBoundExceeded.into())
+            );
+        }
+    }
+
+    // the same message signed without the vault context, e.g. by a program that verifies
+    // plain Falcon, is no payload signature
+    #[test]
+    fn test_payload_needs_vault_context() {
+        let payload = b"execute this";
+        for mode in [PAYLOAD_MODE_DIRECT, PAYLOAD_MODE_SHA256] {
+            let (public_key, signature) = signed_fixture(&build_payload_message(mode, payload), &FIXTURE_NONCE);
+            assert_eq!(
+                verify_payload_signature(&FalconSignature::new(signature), &FalconPublicKey::new(public_key), mode, payload),
+                Err(VaultError::NormBoundExceeded.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_payload_mode() {
+        let (public_key, signature) = signed_payload(PAYLOAD_MODE_DIRECT, b"payload");
+        assert_eq!(
+            verify_payload_signature(&signature, &public_key, 2, b"payload"),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+}
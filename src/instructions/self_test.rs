@@ -0,0 +1,34 @@
+use pinocchio::{account_info::AccountInfo, cpi::set_return_data, program_error::ProgramError, ProgramResult};
+use crate::falcon::run_self_test;
+
+// return data written by SelfTest when every check passes; otherwise the
+// number of the failing falcon::SelfTestCheck
+pub const SELF_TEST_PASSED: u8 = 0;
+
+// runs falcon::self_test's known-answer checks on the validator, no accounts or data.
+// like VerifyOnly the instruction itself succeeds and reports through return data
+pub struct SelfTest;
+
+impl SelfTest {
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, ProgramError> {
+        if !bytes.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(Self)
+    }
+
+    #[inline(never)]
+    pub fn process(&self, accounts: &[AccountInfo]) -> ProgramResult {
+        if !accounts.is_empty() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let result = match run_self_test() {
+            Ok(()) => SELF_TEST_PASSED,
+            Err(check) => check as u8,
+        };
+        set_return_data(&[result]);
+
+        Ok(())
+    }
+}
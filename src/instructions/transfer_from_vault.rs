@@ -1,21 +1,56 @@
-use pinocchio::{account_info::AccountInfo, program_error::ProgramError, ProgramResult};
-use crate::falcon::{FalconSignature, FalconPublicKey, FALCON_512_SIGNATURE_SIZE, FALCON_512_PUBLIC_KEY_SIZE};
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    ProgramResult,
+};
+use crate::error::VaultError;
+use crate::falcon::{prehash_message, FalconPublicKey, FalconSignature, FALCON_512_SIGNATURE_SIZE};
+use crate::messages::VAULT_SIGNING_CONTEXT;
+use super::{
+    advance_transfer_nonce, check_recipient_rent, check_system_program, check_vault_address, check_vault_owner,
+    checked_lamport_transfer, load_close_authority, load_public_key, load_transfer_nonce, rent_exempt_minimum,
+    FalconVerifier, SignatureVerifier, VaultNonce,
+};
 
+// optional trailing byte of the instruction data selecting how the message was signed
+pub const TRANSFER_MESSAGE_RAW: u8 = 0;
+pub const TRANSFER_MESSAGE_PREHASHED: u8 = 1;
+
+#[derive(PartialEq, Eq)]
 pub struct TransferFromVault {
     signature: FalconSignature,
     amount: u64,
     bump: u8,
+    prehashed: bool,
 }
 
 impl TransferFromVault {
+    // data: [signature(666), amount(8), bump(1)] with an optional digest flag(1)
+    // no flag is the same as TRANSFER_MESSAGE_RAW
     pub fn deserialize(bytes: &[u8]) -> Result<Self, ProgramError> {
         let expected_size = FALCON_512_SIGNATURE_SIZE + 8 + 1;
-        if bytes.len() != expected_size {
+        match bytes.len() {
+            n if n == expected_size => Self::deserialize_with_flag(bytes, None),
+            n if n == expected_size + 1 => Self::deserialize_with_flag(&bytes[..expected_size], Some(bytes[expected_size])),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+
+    // the fixed part of the data and the digest flag read apart, for VerifyOnly which
+    // puts its recipient between the two
+    pub(crate) fn deserialize_with_flag(bytes: &[u8], digest_flag: Option<u8>) -> Result<Self, ProgramError> {
+        if bytes.len() != FALCON_512_SIGNATURE_SIZE + 8 + 1 {
             return Err(ProgramError::InvalidInstructionData);
         }
+        let prehashed = match digest_flag {
+            None | Some(TRANSFER_MESSAGE_RAW) => false,
+            Some(TRANSFER_MESSAGE_PREHASHED) => true,
+            Some(_) => return Err(ProgramError::InvalidInstructionData),
+        };
 
-        let mut signature_bytes = [0u8; FALCON_512_SIGNATURE_SIZE];
-        signature_bytes.copy_from_slice(&bytes[0..FALCON_512_SIGNATURE_SIZE]);
+        let signature = FalconSignature::try_from(&bytes[..FALCON_512_SIGNATURE_SIZE])
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
         
         let mut amount_bytes = [0u8; 8];
         amount_bytes.copy_from_slice(&bytes[FALCON_512_SIGNATURE_SIZE..FALCON_512_SIGNATURE_SIZE + 8]);
@@ -23,69 +58,225 @@ impl TransferFromVault {
         let bump = bytes[FALCON_512_SIGNATURE_SIZE + 8];
 
         Ok(Self {
-            signature: FalconSignature::from(signature_bytes),
+            signature,
             amount: u64::from_le_bytes(amount_bytes),
             bump,
+            prehashed,
         })
     }
 
+    // the data deserialize reads, without the discriminator; the digest flag only
+    // when prehashed, a raw transfer leaves it off like client::transfer_ix
+    #[cfg(any(test, feature = "client"))]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = self.signature.as_bytes().to_vec();
+        data.extend_from_slice(&self.amount.to_le_bytes());
+        data.push(self.bump);
+        if self.prehashed {
+            data.push(TRANSFER_MESSAGE_PREHASHED);
+        }
+        data
+    }
+
+    #[inline(never)]
     pub fn process(&self, accounts: &[AccountInfo]) -> ProgramResult {
+        self.process_with(accounts, &FalconVerifier)
+    }
+
+    pub fn process_with<V: SignatureVerifier>(&self, accounts: &[AccountInfo], verifier: &V) -> ProgramResult {
         // assert we have exactly 3 accounts
-        let [vault, recipient, _system_program] = accounts else {
+        let [vault, recipient, system_program] = accounts else {
             return Err(ProgramError::NotEnoughAccountKeys);
         };
+        check_system_program(system_program)?;
 
-        // check that vault is owned by our programm
-        // AccountInfo::owner() is safe to call as it's just reading the account's owner field
-        if unsafe { vault.owner() } != &crate::ID {
-            return Err(ProgramError::IncorrectProgramId);
+        // paying the vault into itself would credit the amount without debiting it
+        if recipient.key() == vault.key() {
+            return Err(ProgramError::InvalidArgument);
         }
 
-        // read the public key from the vault account
-        let vault_data = vault.try_borrow_data()?;
-        if vault_data.len() != FALCON_512_PUBLIC_KEY_SIZE {
-            return Err(ProgramError::InvalidAccountData);
-        }
-        
-        let mut public_key_bytes = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
-        public_key_bytes.copy_from_slice(&vault_data);
-        let public_key = FalconPublicKey::from(public_key_bytes);
-        drop(vault_data);
-
-        // Create the message to verify
-        // message includes: amount (8 bytes) + recipient pubkey (32 bytes) + current slot (8 bytes)
-        let mut message = [0u8; 48];
-        message[0..8].copy_from_slice(&self.amount.to_le_bytes());
-        message[8..40].copy_from_slice(recipient.key());
-        // on mainnet, we would include the current slot or nonce for replay protection
-        // for now... we'll use a placeholder
-        message[40..48].copy_from_slice(&[0u8; 8]);
+        // every check that costs a few hundred CU runs before the ~150k CU verification,
+        // so a transfer that was always going to fail doesn't pay for it
+        let (public_key, nonce) = self.check_vault(vault)?;
 
-        // verify the Falcon signature
-        self.signature.verify(&public_key, &message)?;
-
-        // verify PDA (similar to Winternitz vault, thanks Dean!)
-        let pubkey_hash = public_key.hash();
-        if solana_nostd_sha256::hashv(&[
-            pubkey_hash.as_ref(),
-            &[self.bump],
-            crate::ID.as_ref(),
-            b"ProgramDerivedAddress",
-        ])
-        .ne(vault.key())
-        {
-            return Err(ProgramError::MissingRequiredSignature);
-        }
+        // a recipient that doesn't exist yet is created by this transfer, see check_recipient_rent
+        check_recipient_rent(recipient.lamports(), self.amount, rent_exempt_minimum(0)?)?;
 
-        // check vault has sufficient balance
+        // check vault has sufficient balance and the recipient can't overflow
+        let (vault_lamports, recipient_lamports) =
+            checked_lamport_transfer(vault.lamports(), recipient.lamports(), self.amount)?;
+
+        self.check_signature(&public_key, recipient.key(), nonce, vault.key(), verifier)?;
+
+        // the signature is spent, the next transfer has to sign the nonce after this one
+        advance_transfer_nonce(&mut vault.try_borrow_mut_data()?)?;
+
+        // trasfer lamports from vault to recipient
+        *vault.try_borrow_mut_lamports()? = vault_lamports;
+        *recipient.try_borrow_mut_lamports()? = recipient_lamports;
+
+        Ok(())
+    }
+
+    // every check a transfer needs short of moving lamports, for VerifyOnly, so a dry run
+    // accepts exactly what a transfer would. the recipient isn't an account there, so
+    // only the vault side of the balance check can be made. the nonce isn't advanced,
+    // a dry run doesn't spend the signature
+    pub(crate) fn authorize<V: SignatureVerifier>(
+        &self,
+        vault: &AccountInfo,
+        recipient: &Pubkey,
+        verifier: &V,
+    ) -> ProgramResult {
+        let (public_key, nonce) = self.check_vault(vault)?;
         if vault.lamports() < self.amount {
             return Err(ProgramError::InsufficientFunds);
         }
+        self.check_signature(&public_key, recipient, nonce, vault.key(), verifier)
+    }
 
-        // trasfer lamports from vault to recipient
-        *vault.try_borrow_mut_lamports()? -= self.amount;
-        *recipient.try_borrow_mut_lamports()? += self.amount;
+    // the amount, then the vault's owner, key and address; returns the key to verify with
+    // and the nonce the signature has to be over
+    fn check_vault(&self, vault: &AccountInfo) -> Result<(FalconPublicKey, VaultNonce), ProgramError> {
+        // a zero transfer moves nothing, reject it before paying for verification
+        if self.amount == 0 {
+            return Err(VaultError::ZeroAmount.into());
+        }
 
-        Ok(())
+        // check that vault is owned by our programm
+        check_vault_owner(vault)?;
+
+        // read the public key and close authority from the vault account
+        let data = vault.try_borrow_data()?;
+        let public_key = load_public_key(&data)?;
+
+        // verify PDA
+        check_vault_address(vault, &public_key.hash(), load_close_authority(&data)?.as_ref(), self.bump)?;
+
+        // an exhausted nonce can't be advanced past, fail before paying for verification
+        let nonce = load_transfer_nonce(&data)?;
+        nonce.next()?;
+
+        Ok((public_key, nonce))
+    }
+
+    // the signed message is amount (8 bytes) + recipient pubkey (32 bytes) + nonce (8 bytes)
+    // + vault address (32 bytes), the same layout as messages::build_transfer_message,
+    // passed as parts to avoid a copy, and signed under VAULT_SIGNING_CONTEXT
+    fn check_signature<V: SignatureVerifier>(
+        &self,
+        public_key: &FalconPublicKey,
+        recipient: &Pubkey,
+        nonce: VaultNonce,
+        vault: &Pubkey,
+        verifier: &V,
+    ) -> ProgramResult {
+        // the nonce is the vault's, not the caller's: a signature over any other nonce,
+        // an already spent one included, doesn't verify. nor does one made for another
+        // vault of the same key
+        let amount_bytes = self.amount.to_le_bytes();
+        let nonce_bytes = nonce.to_le_bytes();
+
+        // verify the Falcon signature
+        // a pre-hashed signature is over prehash_message of the same parts
+        let message_parts: [&[u8]; 4] = [&amount_bytes, recipient, &nonce_bytes, vault];
+        if self.prehashed {
+            let digest = prehash_message(&message_parts);
+            verifier.verify_prehashed(&self.signature, public_key, VAULT_SIGNING_CONTEXT, &digest)
+        } else {
+            verifier.verify_parts(&self.signature, public_key, VAULT_SIGNING_CONTEXT, &message_parts)
+        }
+    }
+
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::{MockVerifier, VerifyCall};
+    use crate::messages::build_transfer_message;
+    use proptest::prelude::*;
+
+    fn transfer(amount: u64, flag: Option<u8>) -> TransferFromVault {
+        let mut data = vec![0u8; FALCON_512_SIGNATURE_SIZE];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(255);
+        data.extend(flag);
+        TransferFromVault::deserialize(&data).unwrap()
+    }
+
+    #[test]
+    fn test_amount_endianness() {
+        let amount = 0x0102030405060708u64;
+        let recipient = [0x11u8; 32];
+
+        // the signed message carries the amount little-endian
+        let message = build_transfer_message(amount, &recipient, 0, &[0x33; 32]);
+        assert_eq!(message[0..8], [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(message[40..48], [0u8; 8]);
+
+        // and the instruction data carries it the same way
+        let mut data = [0u8; FALCON_512_SIGNATURE_SIZE + 8 + 1];
+        data[FALCON_512_SIGNATURE_SIZE..FALCON_512_SIGNATURE_SIZE + 8].copy_from_slice(&message[0..8]);
+        data[FALCON_512_SIGNATURE_SIZE + 8] = 255;
+
+        let transfer = TransferFromVault::deserialize(&data).unwrap();
+        assert_eq!(transfer.amount(), amount);
+        assert_eq!(transfer.bump, 255);
+        assert!(!transfer.prehashed);
+    }
+
+    // with verification stubbed out: the verifier is asked about exactly the message
+    // build_transfer_message produces, or its prehash, and its answer is what the check returns
+    #[test]
+    fn test_check_signature_message() {
+        let public_key = FalconPublicKey::new([9; 897]);
+        let (recipient, vault) = ([0x22u8; 32], [0x44u8; 32]);
+        let nonce = VaultNonce::new(41);
+        let message = build_transfer_message(5_000, &recipient, 41, &vault);
+
+        let verifier = MockVerifier::accepting();
+        assert_eq!(transfer(5_000, None).check_signature(&public_key, &recipient, nonce, &vault, &verifier), Ok(()));
+        assert_eq!(verifier.calls(), [VerifyCall::Message(VAULT_SIGNING_CONTEXT.to_vec(), message.to_vec())]);
+
+        let verifier = MockVerifier::accepting();
+        let prehashed = transfer(5_000, Some(TRANSFER_MESSAGE_PREHASHED));
+        assert_eq!(prehashed.check_signature(&public_key, &recipient, nonce, &vault, &verifier), Ok(()));
+        let digest = prehash_message(&[&message]);
+        assert_eq!(verifier.calls(), [VerifyCall::Prehashed(VAULT_SIGNING_CONTEXT.to_vec(), digest)]);
+
+        let verifier = MockVerifier::rejecting(VaultError::NormBoundExceeded);
+        assert_eq!(
+            transfer(5_000, None).check_signature(&public_key, &recipient, nonce, &vault, &verifier),
+            Err(VaultError::NormBoundExceeded.into())
+        );
     }
-} 
\ No newline at end of file
+
+    proptest! {
+        #[test]
+        fn test_serialize_roundtrip(
+            signature in prop::collection::vec(any::<u8>(), FALCON_512_SIGNATURE_SIZE),
+            amount in any::<u64>(),
+            bump in any::<u8>(),
+            prehashed in any::<bool>(),
+            extra in any::<u8>(),
+        ) {
+            let signature = FalconSignature::try_from(&signature[..]).unwrap();
+            let transfer = TransferFromVault { signature, amount, bump, prehashed };
+            let data = transfer.serialize();
+            prop_assert!(TransferFromVault::deserialize(&data).ok() == Some(transfer));
+
+            // cutting the flag off a prehashed transfer leaves a raw one, any other cut fails
+            prop_assert_eq!(TransferFromVault::deserialize(&data[..data.len() - 1]).is_ok(), prehashed);
+
+            // a byte past a raw transfer is read as the digest flag, so only the two
+            // flag values get through; past a flagged one nothing does
+            let mut longer = data;
+            longer.push(extra);
+            let is_flag = extra == TRANSFER_MESSAGE_RAW || extra == TRANSFER_MESSAGE_PREHASHED;
+            prop_assert_eq!(TransferFromVault::deserialize(&longer).is_ok(), !prehashed && is_flag);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use pinocchio::{program_error::ProgramError, pubkey::Pubkey};
+use crate::error::VaultError;
+use crate::falcon::{FalconPublicKey, FALCON_512_PUBLIC_KEY_SIZE};
+use crate::messages::LEGACY_CLOSE_NONCE;
+use super::{VaultNonce, VAULT_NONCE_SIZE};
+
+// layout version in the first byte of the vault data, bumped whenever the layout changes
+// so a program never reads an account written for a layout it doesn't know
+pub const VAULT_VERSION: u8 = 1;
+
+// version of a vault that also names a close authority, see CloseVault
+pub const VAULT_VERSION_CLOSE_AUTHORITY: u8 = 2;
+
+// versions 1 and 2 with a transfer nonce at the end, see VaultNonce. OpenVault only
+// writes these; a v1 or v2 vault has no nonce, so it can only be closed, never
+// transferred out of, see load_transfer_nonce
+pub const VAULT_VERSION_NONCE: u8 = 3;
+pub const VAULT_VERSION_NONCE_CLOSE_AUTHORITY: u8 = 4;
+
+// v1 layout: [version(1), falcon_pubkey(897)]
+pub const VAULT_DATA_SIZE: usize = 1 + FALCON_512_PUBLIC_KEY_SIZE;
+
+// v2 layout: [version(1), falcon_pubkey(897), close_authority(32)]
+pub const VAULT_DATA_SIZE_CLOSE_AUTHORITY: usize = VAULT_DATA_SIZE + 32;
+
+// v3 layout: [version(1), falcon_pubkey(897), nonce(8)]
+pub const VAULT_DATA_SIZE_NONCE: usize = VAULT_DATA_SIZE + VAULT_NONCE_SIZE;
+
+// v4 layout: [version(1), falcon_pubkey(897), close_authority(32), nonce(8)]
+pub const VAULT_DATA_SIZE_NONCE_CLOSE_AUTHORITY: usize = VAULT_DATA_SIZE_CLOSE_AUTHORITY + VAULT_NONCE_SIZE;
+
+// account size for a vault opened with or without a close authority
+pub const fn vault_data_size(close_authority: Option<&Pubkey>) -> usize {
+    match close_authority {
+        Some(_) => VAULT_DATA_SIZE_NONCE_CLOSE_AUTHORITY,
+        None => VAULT_DATA_SIZE_NONCE,
+    }
+}
+
+// the close authority is a seed of the vault address next to the key hash, so a vault
+// naming one lives at its own address and nobody can take the key's vault by opening
+// it first with themselves as its authority. a vault without one seeds nothing extra,
+// an empty seed adds no bytes to the hash, and keeps the PDA of the key hash alone
+pub fn close_authority_seed(close_authority: Option<&Pubkey>) -> &[u8] {
+    match close_authority {
+        Some(authority) => authority,
+        None => &[],
+    }
+}
+
+// write a vault: the version byte, the key, the close authority if there is one and
+// first_nonce, VaultNonce::opening for the slot it's opened in. anything after that is left
+// untouched, so the layout can grow without changing this; an account too small for it
+// is an error instead of a panic
+pub fn store_vault_data(
+    data: &mut [u8],
+    public_key: &FalconPublicKey,
+    close_authority: Option<&Pubkey>,
+    first_nonce: VaultNonce,
+) -> Result<(), ProgramError> {
+    let Some((version, rest)) = data
+        .get_mut(..vault_data_size(close_authority))
+        .and_then(|data| data.split_first_mut())
+    else {
+        return Err(ProgramError::AccountDataTooSmall);
+    };
+    let (key_bytes, rest) = rest.split_at_mut(FALCON_512_PUBLIC_KEY_SIZE);
+    key_bytes.copy_from_slice(public_key.as_bytes());
+    let (authority_bytes, nonce_slot) = rest.split_at_mut(rest.len() - VAULT_NONCE_SIZE);
+    first_nonce.write(nonce_slot)?;
+
+    *version = match close_authority {
+        Some(authority) => {
+            authority_bytes.copy_from_slice(authority);
+            VAULT_VERSION_NONCE_CLOSE_AUTHORITY
+        }
+        None => VAULT_VERSION_NONCE,
+    };
+    Ok(())
+}
+
+// the public key of a vault, after checking the version byte
+// an unknown version is rejected before anything else is read
+pub fn load_public_key(data: &[u8]) -> Result<FalconPublicKey, ProgramError> {
+    let parts = split_vault_data(data)?;
+    FalconPublicKey::try_from(parts.key_bytes).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+// the close authority of a vault, None for a vault opened without one
+pub fn load_close_authority(data: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+    Ok(split_vault_data(data)?.close_authority.copied())
+}
+
+// the nonce the next transfer out of a vault signs. a v1 or v2 vault has no slot for
+// one, and a transfer that could not spend its signature could be replayed until the
+// vault is empty, so those are UnsupportedVaultVersion; CloseVault still takes them
+pub fn load_transfer_nonce(data: &[u8]) -> Result<VaultNonce, ProgramError> {
+    let nonce_slot = split_vault_data(data)?.nonce_slot.ok_or(VaultError::UnsupportedVaultVersion)?;
+    VaultNonce::read(nonce_slot)
+}
+
+// the nonce a close of the vault signs: the stored one, which the close doesn't advance
+// since it deletes the vault, or LEGACY_CLOSE_NONCE for a v1 or v2 vault
+pub fn load_close_nonce(data: &[u8]) -> Result<VaultNonce, ProgramError> {
+    match split_vault_data(data)?.nonce_slot {
+        Some(nonce_slot) => VaultNonce::read(nonce_slot),
+        None => Ok(VaultNonce::new(LEGACY_CLOSE_NONCE)),
+    }
+}
+
+// spend the stored nonce of a vault after its transfer verified, see VaultNonce::advance
+pub fn advance_transfer_nonce(data: &mut [u8]) -> Result<(), ProgramError> {
+    let (_, has_nonce) = vault_layout(data.first().copied())?;
+    if !has_nonce {
+        return Err(VaultError::UnsupportedVaultVersion.into());
+    }
+    let nonce_slot = data.last_chunk_mut::<VAULT_NONCE_SIZE>().ok_or(ProgramError::InvalidAccountData)?;
+    VaultNonce::advance(nonce_slot).map(drop)
+}
+
+// whether a version's layout has a close authority and a nonce after the key
+fn vault_layout(version: Option<u8>) -> Result<(bool, bool), ProgramError> {
+    match version.ok_or(ProgramError::InvalidAccountData)? {
+        VAULT_VERSION => Ok((false, false)),
+        VAULT_VERSION_CLOSE_AUTHORITY => Ok((true, false)),
+        VAULT_VERSION_NONCE => Ok((false, true)),
+        VAULT_VERSION_NONCE_CLOSE_AUTHORITY => Ok((true, true)),
+        _ => Err(VaultError::UnsupportedVaultVersion.into()),
+    }
+}
+
+// the key bytes, close authority and nonce slot of a vault of any known version
+struct VaultParts<'a> {
+    key_bytes: &'a [u8],
+    close_authority: Option<&'a Pubkey>,
+    nonce_slot: Option<&'a [u8]>,
+}
+
+fn split_vault_data(data: &[u8]) -> Result<VaultParts<'_>, ProgramError> {
+    let (has_authority, has_nonce) = vault_layout(data.first().copied())?;
+    let mut rest = &data[1..];
+
+    let mut nonce_slot = None;
+    if has_nonce {
+        let (front, slot) = rest.split_last_chunk::<VAULT_NONCE_SIZE>().ok_or(ProgramError::InvalidAccountData)?;
+        (rest, nonce_slot) = (front, Some(&slot[..]));
+    }
+    let mut close_authority = None;
+    if has_authority {
+        let (front, authority) = rest.split_last_chunk::<32>().ok_or(ProgramError::InvalidAccountData)?;
+        (rest, close_authority) = (front, Some(authority));
+    }
+    Ok(VaultParts { key_bytes: rest, close_authority, nonce_slot })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> FalconPublicKey {
+        let mut bytes = [0u8; FALCON_512_PUBLIC_KEY_SIZE];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (i % 251) as u8 + 1;
+        }
+        FalconPublicKey::new(bytes)
+    }
+
+    #[test]
+    fn test_store_vault_data_exact_size() {
+        let key = test_key();
+        let mut data = [0xEEu8; VAULT_DATA_SIZE_NONCE];
+        let first_nonce = VaultNonce::opening(3);
+        assert_eq!(store_vault_data(&mut data, &key, None, first_nonce), Ok(()));
+        assert_eq!(data[0], VAULT_VERSION_NONCE);
+        assert_eq!(data[1..VAULT_DATA_SIZE], key.bytes);
+        assert_eq!(data[VAULT_DATA_SIZE..], first_nonce.to_le_bytes());
+        assert!(load_public_key(&data).unwrap() == key);
+        assert_eq!(load_close_authority(&data), Ok(None));
+        assert_eq!(load_transfer_nonce(&data), Ok(first_nonce));
+        assert_eq!(load_close_nonce(&data), Ok(first_nonce));
+    }
+
+    #[test]
+    fn test_store_vault_data_close_authority() {
+        let key = test_key();
+        let authority = [0xCA; 32];
+        let mut data = [0xEEu8; VAULT_DATA_SIZE_NONCE_CLOSE_AUTHORITY];
+        assert_eq!(store_vault_data(&mut data, &key, Some(&authority), VaultNonce::new(0)), Ok(()));
+        assert_eq!(data[0], VAULT_VERSION_NONCE_CLOSE_AUTHORITY);
+        assert_eq!(data[1..VAULT_DATA_SIZE], key.bytes);
+        assert_eq!(data[VAULT_DATA_SIZE..VAULT_DATA_SIZE_CLOSE_AUTHORITY], authority);
+        assert_eq!(data[VAULT_DATA_SIZE_CLOSE_AUTHORITY..], [0u8; VAULT_NONCE_SIZE]);
+        assert!(load_public_key(&data).unwrap() == key);
+        assert_eq!(load_close_authority(&data), Ok(Some(authority)));
+        assert_eq!(load_transfer_nonce(&data), Ok(VaultNonce::new(0)));
+
+        // an account sized for no authority has no room for one
+        let mut small = [0u8; VAULT_DATA_SIZE_NONCE];
+        assert_eq!(
+            store_vault_data(&mut small, &key, Some(&authority), VaultNonce::new(0)),
+            Err(ProgramError::AccountDataTooSmall)
+        );
+        assert_eq!(load_public_key(&data[..VAULT_DATA_SIZE_NONCE]).err(), Some(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_store_vault_data_larger_account() {
+        let key = test_key();
+        let mut data = [0xEEu8; VAULT_DATA_SIZE_NONCE + 64];
+        assert_eq!(store_vault_data(&mut data, &key, None, VaultNonce::new(0)), Ok(()));
+        assert_eq!(data[1..VAULT_DATA_SIZE], key.bytes);
+        assert_eq!(data[VAULT_DATA_SIZE..VAULT_DATA_SIZE_NONCE], [0u8; VAULT_NONCE_SIZE]);
+        assert!(data[VAULT_DATA_SIZE_NONCE..].iter().all(|&b| b == 0xEE));
+    }
+
+    #[test]
+    fn test_store_vault_data_account_too_small() {
+        let mut data = [0u8; VAULT_DATA_SIZE_NONCE - 1];
+        assert_eq!(store_vault_data(&mut data, &test_key(), None, VaultNonce::new(0)), Err(ProgramError::AccountDataTooSmall));
+        assert!(data.iter().all(|&b| b == 0));
+    }
+
+    // v1 and v2 vaults opened before the nonce still load, so they can be closed, with
+    // LEGACY_CLOSE_NONCE, but have no nonce to transfer with
+    #[test]
+    fn test_load_legacy_layouts() {
+        let key = test_key();
+        let authority = [0xCA; 32];
+        let mut v2 = [0u8; VAULT_DATA_SIZE_CLOSE_AUTHORITY];
+        v2[0] = VAULT_VERSION_CLOSE_AUTHORITY;
+        v2[1..VAULT_DATA_SIZE].copy_from_slice(&key.bytes);
+        v2[VAULT_DATA_SIZE..].copy_from_slice(&authority);
+        let mut v1 = v2;
+        v1[0] = VAULT_VERSION;
+        let v1 = &mut v1[..VAULT_DATA_SIZE];
+
+        assert!(load_public_key(v1).unwrap() == key);
+        assert_eq!(load_close_authority(v1), Ok(None));
+        assert!(load_public_key(&v2).unwrap() == key);
+        assert_eq!(load_close_authority(&v2), Ok(Some(authority)));
+
+        for data in [v1, &mut v2[..]] {
+            let before = data.to_vec();
+            assert_eq!(load_close_nonce(data), Ok(VaultNonce::new(LEGACY_CLOSE_NONCE)));
+            assert_eq!(load_transfer_nonce(data), Err(VaultError::UnsupportedVaultVersion.into()));
+            assert_eq!(advance_transfer_nonce(data), Err(VaultError::UnsupportedVaultVersion.into()));
+            assert_eq!(data, &before[..]);
+        }
+    }
+
+    #[test]
+    fn test_advance_transfer_nonce() {
+        for close_authority in [None, Some(&[0xCA; 32])] {
+            let mut data = vec![0u8; vault_data_size(close_authority)];
+            store_vault_data(&mut data, &test_key(), close_authority, VaultNonce::new(0)).unwrap();
+            let before = data.clone();
+
+            assert_eq!(advance_transfer_nonce(&mut data), Ok(()));
+            assert_eq!(advance_transfer_nonce(&mut data), Ok(()));
+            assert_eq!(load_transfer_nonce(&data), Ok(VaultNonce::new(2)));
+            // only the nonce moved
+            let nonce_at = data.len() - VAULT_NONCE_SIZE;
+            assert_eq!(data[..nonce_at], before[..nonce_at]);
+
+            // an exhausted nonce stays exhausted
+            data[nonce_at..].copy_from_slice(&u64::MAX.to_le_bytes());
+            assert_eq!(advance_transfer_nonce(&mut data), Err(VaultError::NonceExhausted.into()));
+            assert_eq!(load_transfer_nonce(&data), Ok(VaultNonce::new(u64::MAX)));
+        }
+    }
+
+    #[test]
+    fn test_load_rejects_unknown_versions() {
+        let mut data = [0u8; VAULT_DATA_SIZE_NONCE];
+        store_vault_data(&mut data, &test_key(), None, VaultNonce::new(0)).unwrap();
+
+        for version in [0, 5, 0xFF] {
+            data[0] = version;
+            assert_eq!(load_public_key(&data).err(), Some(VaultError::UnsupportedVaultVersion.into()));
+            assert_eq!(load_close_authority(&data).err(), Some(VaultError::UnsupportedVaultVersion.into()));
+            assert_eq!(load_transfer_nonce(&data).err(), Some(VaultError::UnsupportedVaultVersion.into()));
+            assert_eq!(load_close_nonce(&data).err(), Some(VaultError::UnsupportedVaultVersion.into()));
+            assert_eq!(advance_transfer_nonce(&mut data).err(), Some(VaultError::UnsupportedVaultVersion.into()));
+        }
+
+        // a pre-versioning vault holds only the key, starting with its 0x09 header
+        let mut legacy = test_key().bytes;
+        legacy[0] = 0x09;
+        assert_eq!(load_public_key(&legacy).err(), Some(VaultError::UnsupportedVaultVersion.into()));
+    }
+
+    #[test]
+    fn test_load_rejects_wrong_length() {
+        let mut data = [0u8; VAULT_DATA_SIZE_NONCE + 1];
+        store_vault_data(&mut data, &test_key(), None, VaultNonce::new(0)).unwrap();
+        assert_eq!(load_public_key(&data).err(), Some(ProgramError::InvalidAccountData));
+        assert_eq!(load_public_key(&data[..VAULT_DATA_SIZE_NONCE - 1]).err(), Some(ProgramError::InvalidAccountData));
+        assert_eq!(load_public_key(&[]).err(), Some(ProgramError::InvalidAccountData));
+        assert_eq!(load_transfer_nonce(&[VAULT_VERSION_NONCE; 7]).err(), Some(ProgramError::InvalidAccountData));
+        assert_eq!(advance_transfer_nonce(&mut []).err(), Some(ProgramError::InvalidAccountData));
+    }
+}
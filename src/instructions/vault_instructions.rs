@@ -4,6 +4,12 @@ pub enum VaultInstructions {
     OpenVault,
     TransferFromVault,
     CloseVault,
+    VerifyOnly,
+    BatchOpenVault,
+    SelfTest,
+    // far from the real instructions so adding those never shifts it
+    #[cfg(feature = "bench")]
+    BenchmarkVerify,
 }
 
 impl TryFrom<&u8> for VaultInstructions {
@@ -14,6 +20,11 @@ impl TryFrom<&u8> for VaultInstructions {
             0 => Ok(Self::OpenVault),
             1 => Ok(Self::TransferFromVault),
             2 => Ok(Self::CloseVault),
+            3 => Ok(Self::VerifyOnly),
+            4 => Ok(Self::BatchOpenVault),
+            5 => Ok(Self::SelfTest),
+            #[cfg(feature = "bench")]
+            0xFF => Ok(Self::BenchmarkVerify),
             _ => Err(ProgramError::InvalidInstructionData),
         }
     }
@@ -0,0 +1,134 @@
+use pinocchio::ProgramResult;
+use crate::falcon::{FalconPublicKey, FalconSignature, FALCON_PREHASH_DIGEST_SIZE};
+
+// how the instruction handlers check a Falcon signature. the program always uses
+// FalconVerifier; the handlers take the verifier as a generic parameter so their own
+// logic (message layout, balance bounds, which error wins) can be unit tested with
+// MockVerifier, apart from the cost and fixtures real verification needs
+pub trait SignatureVerifier {
+    // see FalconSignature::verify_with_context
+    fn verify_parts(
+        &self,
+        signature: &FalconSignature,
+        public_key: &FalconPublicKey,
+        context: &[u8],
+        message_parts: &[&[u8]],
+    ) -> ProgramResult;
+
+    // see FalconSignature::verify_prehashed_with_context
+    fn verify_prehashed(
+        &self,
+        signature: &FalconSignature,
+        public_key: &FalconPublicKey,
+        context: &[u8],
+        digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+    ) -> ProgramResult;
+}
+
+// real Falcon-512 verification
+pub struct FalconVerifier;
+
+impl SignatureVerifier for FalconVerifier {
+    #[inline(always)]
+    fn verify_parts(
+        &self,
+        signature: &FalconSignature,
+        public_key: &FalconPublicKey,
+        context: &[u8],
+        message_parts: &[&[u8]],
+    ) -> ProgramResult {
+        Ok(signature.verify_with_context(public_key, context, message_parts)?)
+    }
+
+    #[inline(always)]
+    fn verify_prehashed(
+        &self,
+        signature: &FalconSignature,
+        public_key: &FalconPublicKey,
+        context: &[u8],
+        digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+    ) -> ProgramResult {
+        Ok(signature.verify_prehashed_with_context(public_key, context, digest)?)
+    }
+}
+
+// what a handler asked a MockVerifier to check
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum VerifyCall {
+    // the context, then the message parts concatenated
+    Message(Vec<u8>, Vec<u8>),
+    // the context, then the digest
+    Prehashed(Vec<u8>, [u8; FALCON_PREHASH_DIGEST_SIZE]),
+}
+
+// answers every check with a fixed result and remembers what it was asked
+#[cfg(test)]
+pub(crate) struct MockVerifier {
+    result: ProgramResult,
+    calls: core::cell::RefCell<Vec<VerifyCall>>,
+}
+
+#[cfg(test)]
+impl MockVerifier {
+    pub(crate) fn accepting() -> Self {
+        Self { result: Ok(()), calls: Default::default() }
+    }
+
+    pub(crate) fn rejecting(error: impl Into<pinocchio::program_error::ProgramError>) -> Self {
+        Self { result: Err(error.into()), calls: Default::default() }
+    }
+
+    pub(crate) fn calls(&self) -> Vec<VerifyCall> {
+        self.calls.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+impl SignatureVerifier for MockVerifier {
+    fn verify_parts(
+        &self,
+        _: &FalconSignature,
+        _: &FalconPublicKey,
+        context: &[u8],
+        message_parts: &[&[u8]],
+    ) -> ProgramResult {
+        self.calls.borrow_mut().push(VerifyCall::Message(context.to_vec(), message_parts.concat()));
+        self.result.clone()
+    }
+
+    fn verify_prehashed(
+        &self,
+        _: &FalconSignature,
+        _: &FalconPublicKey,
+        context: &[u8],
+        digest: &[u8; FALCON_PREHASH_DIGEST_SIZE],
+    ) -> ProgramResult {
+        self.calls.borrow_mut().push(VerifyCall::Prehashed(context.to_vec(), *digest));
+        self.result.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::falcon::verify::tests::{context_fixture, prehashed_fixture};
+
+    // FalconVerifier is the real thing, not a pass-through that accepts anything, and
+    // checks the context it is given
+    #[test]
+    fn test_falcon_verifier_verifies() {
+        let (public_key, signature) = context_fixture(b"vault", b"verifier");
+        let (public_key, signature) = (FalconPublicKey::new(public_key), FalconSignature::new(signature));
+
+        assert_eq!(FalconVerifier.verify_parts(&signature, &public_key, b"vault", &[b"veri", b"fier"]), Ok(()));
+        assert!(FalconVerifier.verify_parts(&signature, &public_key, b"vault", &[b"other"]).is_err());
+        assert!(FalconVerifier.verify_parts(&signature, &public_key, b"", &[b"verifier"]).is_err());
+
+        let digest = [7; FALCON_PREHASH_DIGEST_SIZE];
+        let (public_key, signature) = prehashed_fixture(b"vault", &digest);
+        let (public_key, signature) = (FalconPublicKey::new(public_key), FalconSignature::new(signature));
+        assert_eq!(FalconVerifier.verify_prehashed(&signature, &public_key, b"vault", &digest), Ok(()));
+        assert!(FalconVerifier.verify_prehashed(&signature, &public_key, b"", &digest).is_err());
+    }
+}
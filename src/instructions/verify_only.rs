@@ -0,0 +1,123 @@
+use pinocchio::{account_info::AccountInfo, cpi::set_return_data, program_error::ProgramError, pubkey::Pubkey, ProgramResult};
+use crate::falcon::FALCON_512_SIGNATURE_SIZE;
+use super::{FalconVerifier, TransferFromVault};
+
+// return data written by VerifyOnly
+pub const VERIFY_ONLY_ACCEPTED: u8 = 1;
+pub const VERIFY_ONLY_REJECTED: u8 = 0;
+
+// [VERIFY_ONLY_REJECTED, error(8)]: a rejection carries the error the transfer would
+// fail with, little-endian, encoded as the runtime reports it (custom codes as they are)
+pub const VERIFY_ONLY_REJECTED_SIZE: usize = 1 + 8;
+
+// dry run of TransferFromVault: verifies the signature against the vault's key
+// and reports the outcome via return data without moving any lamports
+pub struct VerifyOnly {
+    transfer: TransferFromVault,
+    recipient: Pubkey,
+}
+
+impl VerifyOnly {
+    // data: TransferFromVault's [signature(666), amount(8), bump(1)], then recipient(32)
+    // and the same optional digest flag(1) TransferFromVault takes
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, ProgramError> {
+        let transfer_size = FALCON_512_SIGNATURE_SIZE + 8 + 1;
+        let expected_size = transfer_size + 32;
+        let digest_flag = match bytes.len() {
+            n if n == expected_size => None,
+            n if n == expected_size + 1 => Some(bytes[expected_size]),
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
+
+        let mut recipient = [0u8; 32];
+        recipient.copy_from_slice(&bytes[transfer_size..expected_size]);
+
+        Ok(Self {
+            transfer: TransferFromVault::deserialize_with_flag(&bytes[..transfer_size], digest_flag)?,
+            recipient,
+        })
+    }
+
+    #[inline(never)]
+    pub fn process(&self, accounts: &[AccountInfo]) -> ProgramResult {
+        // assert we have exactly 1 account
+        let [vault] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        let result = self.transfer.authorize(vault, &self.recipient, &FalconVerifier);
+
+        let mut return_data = [0u8; VERIFY_ONLY_REJECTED_SIZE];
+        set_return_data(encode_outcome(result, &mut return_data));
+
+        Ok(())
+    }
+}
+
+// the return data for the outcome of a dry run: [VERIFY_ONLY_ACCEPTED], or
+// [VERIFY_ONLY_REJECTED, error(8)]
+fn encode_outcome(result: ProgramResult, buffer: &mut [u8; VERIFY_ONLY_REJECTED_SIZE]) -> &[u8] {
+    match result {
+        Ok(()) => {
+            buffer[0] = VERIFY_ONLY_ACCEPTED;
+            &buffer[..1]
+        }
+        Err(error) => {
+            buffer[0] = VERIFY_ONLY_REJECTED;
+            buffer[1..].copy_from_slice(&u64::from(error).to_le_bytes());
+            &buffer[..]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::VaultError;
+    use crate::instructions::{TRANSFER_MESSAGE_PREHASHED, TRANSFER_MESSAGE_RAW};
+
+    fn data(digest_flag: Option<u8>) -> Vec<u8> {
+        let mut data = vec![0x59u8; FALCON_512_SIGNATURE_SIZE];
+        data.extend_from_slice(&42u64.to_le_bytes());
+        data.push(255);
+        data.extend_from_slice(&[0x77; 32]);
+        data.extend(digest_flag);
+        data
+    }
+
+    // the digest flag goes after the recipient and reads like TransferFromVault's
+    #[test]
+    fn test_deserialize_digest_flag() {
+        let transfer = |digest_flag: Option<u8>| {
+            let bytes = data(digest_flag);
+            let mut transfer = bytes[..FALCON_512_SIGNATURE_SIZE + 9].to_vec();
+            transfer.extend(digest_flag);
+            TransferFromVault::deserialize(&transfer).unwrap()
+        };
+
+        for digest_flag in [None, Some(TRANSFER_MESSAGE_RAW), Some(TRANSFER_MESSAGE_PREHASHED)] {
+            let verify = VerifyOnly::deserialize(&data(digest_flag)).unwrap();
+            assert!(verify.transfer == transfer(digest_flag));
+            assert_eq!(verify.recipient, [0x77; 32]);
+        }
+        assert!(VerifyOnly::deserialize(&data(Some(2))).is_err());
+
+        let mut too_long = data(Some(TRANSFER_MESSAGE_RAW));
+        too_long.push(0);
+        assert!(VerifyOnly::deserialize(&too_long).is_err());
+        assert!(VerifyOnly::deserialize(&data(None)[1..]).is_err());
+    }
+
+    #[test]
+    fn test_outcome_carries_error_code() {
+        let mut buffer = [0u8; VERIFY_ONLY_REJECTED_SIZE];
+        assert_eq!(encode_outcome(Ok(()), &mut buffer), [VERIFY_ONLY_ACCEPTED]);
+
+        let zero_amount = encode_outcome(Err(VaultError::ZeroAmount.into()), &mut buffer);
+        assert_eq!(zero_amount[0], VERIFY_ONLY_REJECTED);
+        assert_eq!(zero_amount[1..], (VaultError::ZeroAmount as u64).to_le_bytes());
+
+        let missing_signature = encode_outcome(Err(ProgramError::MissingRequiredSignature), &mut buffer);
+        assert_eq!(missing_signature[1..], u64::from(ProgramError::MissingRequiredSignature).to_le_bytes());
+    }
+}
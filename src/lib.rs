@@ -1,20 +1,73 @@
 #![allow(unexpected_cfgs)]
+// nostd-check builds the on-chain code path without std, clippy then also rejects any float math
+#![cfg_attr(all(feature = "nostd-check", not(test)), no_std)]
+#![cfg_attr(feature = "nostd-check", deny(clippy::float_arithmetic))]
 
+// skip_norm_check makes every parseable signature verify, it must never reach a deployed program
+#[cfg(all(feature = "skip_norm_check", target_os = "solana"))]
+compile_error!("the skip_norm_check feature disables signature verification and must not be built for Solana");
+
+#[cfg(feature = "program")]
 pub mod instructions;
+#[cfg(feature = "program")]
 use instructions::*;
 
 pub mod falcon;
 
-#[cfg(test)]
+pub mod messages;
+
+pub mod error;
+
+// hex and base58 into caller buffers, for logs and return data
+#[cfg(feature = "program")]
+pub mod encoding;
+
+#[cfg(any(all(test, feature = "program"), feature = "client"))]
+pub mod client;
+
+// the interface description behind idl/solana_falcon_vault.json
+#[cfg(feature = "client")]
+pub mod idl;
+
+#[cfg(all(test, feature = "program"))]
 pub mod tests;
 
+// fails a test that touches the heap where the program has no allocator
+#[cfg(test)]
+mod alloc_guard;
+
+// signed fixtures from tests/fixtures, shared by the unit and mollusk tests,
+// the CU measurements, the host benchmarks (which build with signing) and the
+// node tests of the wasm build
+#[cfg(any(test, feature = "bench-tests", feature = "signing", feature = "wasm"))]
+pub mod fixtures;
+
+#[cfg(feature = "bench-tests")]
+pub mod measure;
+
+// the checks behind the cargo-fuzz targets in fuzz/, replaying fuzz/corpus in tests
+#[cfg(any(all(test, feature = "program"), feature = "fuzzing"))]
+pub mod fuzz;
+
+// falcon-vault-cli's commands, keystore and account decoding
+#[cfg(feature = "cli")]
+pub mod cli;
+
+#[cfg(feature = "program")]
 use pinocchio::{
-    account_info::AccountInfo, entrypoint, program_error::ProgramError, pubkey::Pubkey,
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey,
     ProgramResult,
 };
+// entrypoint! and program_entrypoint! expand to unqualified calls of themselves,
+// so they have to be imported rather than called by path
+#[cfg(all(feature = "program", not(feature = "nostd-check")))]
+use pinocchio::entrypoint;
+#[cfg(feature = "nostd-check")]
+use pinocchio::program_entrypoint;
 
 // Program ID... update this with your deployed program ID
 // generated using: solana-keygen new --outfile program-keypair.json
+#[cfg(feature = "program")]
 pub const ID: Pubkey = [
     0x39, 0x65, 0xE5, 0x2C, 0x78, 0x96, 0xF7, 0x4E, 
     0x95, 0x25, 0x8F, 0x52, 0xB6, 0xFB, 0x0D, 0x47,
@@ -22,10 +75,21 @@ pub const ID: Pubkey = [
     0x8C, 0x36, 0x4F, 0xB2, 0x9A, 0x7E, 0x6D, 0x41,
 ];
 
+#[cfg(all(feature = "program", not(feature = "nostd-check")))]
 entrypoint!(process_instruction);
 
+#[cfg(feature = "nostd-check")]
+program_entrypoint!(process_instruction);
+#[cfg(feature = "nostd-check")]
+pinocchio::default_allocator!();
+#[cfg(feature = "nostd-check")]
+pinocchio::nostd_panic_handler!();
+
 // Main program entry point
+// every instruction's process is #[inline(never)]: inlined here their locals all
+// landed in the entrypoint's frame, 4.5 KB against the 4 KB SBF stack frame
 
+#[cfg(feature = "program")]
 fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -45,5 +109,18 @@ fn process_instruction(
         VaultInstructions::CloseVault => {
             CloseVault::deserialize(data)?.process(accounts)
         },
+        VaultInstructions::VerifyOnly => {
+            VerifyOnly::deserialize(data)?.process(accounts)
+        },
+        VaultInstructions::BatchOpenVault => {
+            BatchOpenVault::deserialize(data)?.process(accounts, program_id)
+        },
+        VaultInstructions::SelfTest => {
+            SelfTest::deserialize(data)?.process(accounts)
+        },
+        #[cfg(feature = "bench")]
+        VaultInstructions::BenchmarkVerify => {
+            BenchmarkVerify::deserialize(data)?.process(accounts)
+        },
     }
 } 
\ No newline at end of file
@@ -0,0 +1,133 @@
+// compute units the program's instructions actually consume, run through mollusk
+// against the build in target/deploy and set next to the estimates in falcon::performance.
+// host only, see the bench-tests feature; `cargo run --example measure_cu` prints the table
+
+use mollusk_svm::{program::keyed_account_for_system_program, Mollusk};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::client::{derive_vault_address, open_vault_ix, transfer_ix, verify_only_ix};
+use crate::falcon::{FalconPublicKey, ESTIMATED_OPEN_VAULT_COMPUTE_UNITS, TOTAL_ESTIMATED_COMPUTE_UNITS};
+use crate::fixtures::fixture_keypairs;
+use crate::instructions::{store_vault_data, vault_data_size, VaultNonce, VERIFY_ONLY_ACCEPTED};
+
+// the program as built by cargo build-sbf, relative to the crate root
+pub const PROGRAM_PATH: &str = "target/deploy/solana_falcon_vault";
+
+// default for FALCON_CU_TOLERANCE_PERCENT
+pub const DEFAULT_TOLERANCE_PERCENT: u64 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CuMeasurement {
+    pub instruction: &'static str,
+    pub measured: u64,
+    pub estimated: u64,
+}
+
+impl CuMeasurement {
+    // signed distance from the estimate, in percent of it
+    pub fn drift_percent(&self) -> i64 {
+        (self.measured as i64 - self.estimated as i64) * 100 / self.estimated as i64
+    }
+
+    pub fn within(&self, tolerance_percent: u64) -> bool {
+        self.measured.abs_diff(self.estimated) * 100 <= self.estimated * tolerance_percent
+    }
+}
+
+// how far a measurement may drift from its estimate before the bench test fails,
+// FALCON_CU_TOLERANCE_PERCENT in the environment or DEFAULT_TOLERANCE_PERCENT
+pub fn tolerance_percent() -> u64 {
+    std::env::var("FALCON_CU_TOLERANCE_PERCENT")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_TOLERANCE_PERCENT)
+}
+
+// OpenVault, a TransferFromVault with the first fixture signature and the same
+// transfer as a VerifyOnly dry run; panics if any of them doesn't succeed, since
+// the CU of a rejected input says nothing about the estimates
+pub fn measure_compute_units() -> Vec<CuMeasurement> {
+    let program_id = Pubkey::new_from_array(crate::ID);
+    let mollusk = Mollusk::new(&program_id, PROGRAM_PATH);
+
+    let keypair = &fixture_keypairs()[0];
+    let transfer = &keypair.transfers[0];
+    let recipient = Pubkey::new_from_array(transfer.recipient);
+    let public_key = FalconPublicKey::from(keypair.public_key);
+    let (vault_pda, bump) = derive_vault_address(&program_id, &public_key);
+    let system_program = keyed_account_for_system_program();
+
+    // the vault as OpenVault leaves it in slot 0, at the nonce the first fixture transfer signs
+    let mut vault_account = Account::new(1_000_000_000, vault_data_size(None), &program_id);
+    store_vault_data(&mut vault_account.data, &public_key, None, VaultNonce::new(0)).expect("vault data");
+
+    let payer = Pubkey::new_unique();
+    let open = mollusk.process_instruction(
+        &open_vault_ix(&program_id, &payer, &vault_pda, &public_key, bump, None),
+        &[
+            (payer, Account::new(1_000_000_000, 0, &system_program.0)),
+            (vault_pda, Account::default()),
+            system_program.clone(),
+        ],
+    );
+    assert!(!open.program_result.is_err(), "OpenVault failed: {:?}", open.program_result);
+
+    let transfer_result = mollusk.process_instruction(
+        &transfer_ix(&program_id, &vault_pda, &recipient, transfer.amount, &transfer.signature, bump),
+        &[
+            (vault_pda, vault_account.clone()),
+            (recipient, Account::new(1_000_000, 0, &system_program.0)),
+            system_program,
+        ],
+    );
+    assert!(!transfer_result.program_result.is_err(), "TransferFromVault failed: {:?}", transfer_result.program_result);
+
+    let verify = mollusk.process_instruction(
+        &verify_only_ix(&program_id, &vault_pda, &recipient, transfer.amount, &transfer.signature, bump),
+        &[(vault_pda, vault_account)],
+    );
+    assert_eq!(verify.return_data, [VERIFY_ONLY_ACCEPTED], "VerifyOnly rejected the fixture transfer");
+
+    vec![
+        CuMeasurement {
+            instruction: "OpenVault",
+            measured: open.compute_units_consumed,
+            estimated: ESTIMATED_OPEN_VAULT_COMPUTE_UNITS,
+        },
+        CuMeasurement {
+            instruction: "TransferFromVault",
+            measured: transfer_result.compute_units_consumed,
+            estimated: TOTAL_ESTIMATED_COMPUTE_UNITS,
+        },
+        CuMeasurement {
+            instruction: "VerifyOnly",
+            measured: verify.compute_units_consumed,
+            estimated: TOTAL_ESTIMATED_COMPUTE_UNITS,
+        },
+    ]
+}
+
+// the measurements as a JSON array, one object per instruction
+pub fn measurements_json(measurements: &[CuMeasurement]) -> String {
+    let entries: Vec<String> = measurements
+        .iter()
+        .map(|m| {
+            format!(
+                "  {{\"instruction\": \"{}\", \"measured\": {}, \"estimated\": {}, \"drift_percent\": {}}}",
+                m.instruction,
+                m.measured,
+                m.estimated,
+                m.drift_percent()
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", entries.join(",\n"))
+}
+
+// a table for updating the constants by hand
+pub fn print_measurements(measurements: &[CuMeasurement]) {
+    println!("{:<20} {:>10} {:>10} {:>8}", "instruction", "measured", "estimated", "drift");
+    for m in measurements {
+        println!("{:<20} {:>10} {:>10} {:>7}%", m.instruction, m.measured, m.estimated, m.drift_percent());
+    }
+}
@@ -0,0 +1,156 @@
+// signed message layouts shared by the on-chain processors and off-chain clients
+// both sides must build byte-identical messages or verification will fail
+// pubkeys are plain [u8; 32] (pinocchio's Pubkey), wallets build them without the program
+
+// the context every vault signature is made under, absorbed by hash_to_point ahead of
+// the nonce (see verify_falcon_signature_with_context). a key that also signs for other
+// programs can't have those signatures replayed here, whatever bytes they cover
+pub const VAULT_SIGNING_CONTEXT: &[u8] = b"solana-falcon-512-vault";
+
+// transfer message: amount (8 bytes) + recipient pubkey (32 bytes) + nonce (8 bytes)
+// + vault address (32 bytes)
+pub const TRANSFER_MESSAGE_SIZE: usize = 80;
+
+// close message: "CLOSE_VAULT" (11 bytes) + refund pubkey (32 bytes) + nonce (8 bytes)
+// + vault address (32 bytes)
+pub const CLOSE_MESSAGE_SIZE: usize = 83;
+
+// domain tag prefixed to every close message
+pub const CLOSE_VAULT_TAG: &[u8; 11] = b"CLOSE_VAULT";
+
+// close message committing to the vault balance: the close message + min_balance (8 bytes)
+// + max_balance (8 bytes), little-endian. the program won't close a vault holding less
+// than min or more than max, so a balance that changed after signing can't be swept
+pub const CLOSE_WITH_BALANCE_MESSAGE_SIZE: usize = CLOSE_MESSAGE_SIZE + 16;
+
+// payload message: "FALCON_PAYLOAD" (14 bytes) + mode (1 byte) + the payload or its digest
+// for instructions that authorize arbitrary bytes, signed under VAULT_SIGNING_CONTEXT.
+// the mode byte is signed too, so a signature made in one mode never verifies in the other
+pub const PAYLOAD_MESSAGE_TAG: &[u8; 14] = b"FALCON_PAYLOAD";
+
+// the payload itself follows the mode byte
+pub const PAYLOAD_MODE_DIRECT: u8 = 0;
+
+// SHA256(payload) follows the mode byte, so the program only runs SHAKE256 over
+// 47 bytes however large the payload is
+pub const PAYLOAD_MODE_SHA256: u8 = 1;
+
+// the nonce a close of a v1 or v2 vault signs, those layouts store none. every vault
+// opened since starts at VaultNonce::opening of its slot instead, see load_close_nonce
+pub const LEGACY_CLOSE_NONCE: u64 = 0;
+
+// build the message signed to authorize a transfer out of the vault at `vault`
+// amount and nonce are little-endian encoded. the vault address is signed so the
+// vaults one key can hold (one per close authority) each need their own signature
+pub fn build_transfer_message(
+    amount: u64,
+    recipient: &[u8; 32],
+    nonce: u64,
+    vault: &[u8; 32],
+) -> [u8; TRANSFER_MESSAGE_SIZE] {
+    let mut message = [0u8; TRANSFER_MESSAGE_SIZE];
+    message[0..8].copy_from_slice(&amount.to_le_bytes());
+    message[8..40].copy_from_slice(recipient);
+    message[40..48].copy_from_slice(&nonce.to_le_bytes());
+    message[48..80].copy_from_slice(vault);
+    message
+}
+
+// build the message signed to authorize closing the vault at `vault`, whose nonce is
+// `nonce` (little-endian), like build_transfer_message. the vault address keeps the
+// signature to the one vault of the key it was made for, and the nonce to that vault's
+// current state: a transfer since, or a vault reopened at the address, wants another
+pub fn build_close_message(refund: &[u8; 32], nonce: u64, vault: &[u8; 32]) -> [u8; CLOSE_MESSAGE_SIZE] {
+    let mut message = [0u8; CLOSE_MESSAGE_SIZE];
+    message[0..11].copy_from_slice(CLOSE_VAULT_TAG);
+    message[11..43].copy_from_slice(refund);
+    message[43..51].copy_from_slice(&nonce.to_le_bytes());
+    message[51..83].copy_from_slice(vault);
+    message
+}
+
+// build the message signed to authorize closing the vault only while its balance
+// is within [min_balance, max_balance]; 0 and u64::MAX leave a side open
+pub fn build_close_message_with_balance(
+    refund: &[u8; 32],
+    nonce: u64,
+    vault: &[u8; 32],
+    min_balance: u64,
+    max_balance: u64,
+) -> [u8; CLOSE_WITH_BALANCE_MESSAGE_SIZE] {
+    let mut message = [0u8; CLOSE_WITH_BALANCE_MESSAGE_SIZE];
+    message[..CLOSE_MESSAGE_SIZE].copy_from_slice(&build_close_message(refund, nonce, vault));
+    message[83..91].copy_from_slice(&min_balance.to_le_bytes());
+    message[91..99].copy_from_slice(&max_balance.to_le_bytes());
+    message
+}
+
+// build the message signed to authorize `payload` in the given mode
+// client side only, the program verifies the same bytes as parts without copying
+#[cfg(any(test, feature = "client"))]
+pub fn build_payload_message(mode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut message = PAYLOAD_MESSAGE_TAG.to_vec();
+    message.push(mode);
+    if mode == PAYLOAD_MODE_SHA256 {
+        message.extend_from_slice(&solana_nostd_sha256::hash(payload));
+    } else {
+        message.extend_from_slice(payload);
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_message_layout() {
+        let (recipient, vault) = ([0xABu8; 32], [0xEFu8; 32]);
+        let message = build_transfer_message(0x0102030405060708, &recipient, 0x1112131415161718, &vault);
+
+        assert_eq!(message.len(), 80);
+        assert_eq!(message[0..8], [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(message[8..40], recipient);
+        assert_eq!(message[40..48], [0x18, 0x17, 0x16, 0x15, 0x14, 0x13, 0x12, 0x11]);
+        assert_eq!(message[48..80], vault);
+    }
+
+    #[test]
+    fn test_close_message_layout() {
+        let (refund, vault) = ([0xCDu8; 32], [0xEFu8; 32]);
+        let message = build_close_message(&refund, 0x1112131415161718, &vault);
+
+        assert_eq!(message.len(), 83);
+        assert_eq!(&message[0..11], b"CLOSE_VAULT");
+        assert_eq!(message[11..43], refund);
+        assert_eq!(message[43..51], [0x18, 0x17, 0x16, 0x15, 0x14, 0x13, 0x12, 0x11]);
+        assert_eq!(message[51..83], vault);
+    }
+
+    #[test]
+    fn test_close_message_with_balance_layout() {
+        let (refund, vault) = ([0xCDu8; 32], [0xEFu8; 32]);
+        let message = build_close_message_with_balance(&refund, 7, &vault, 0x0102030405060708, u64::MAX);
+
+        assert_eq!(message.len(), 99);
+        assert_eq!(message[..83], build_close_message(&refund, 7, &vault));
+        assert_eq!(message[83..91], [0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(message[91..99], [0xFF; 8]);
+    }
+
+    #[test]
+    fn test_payload_message_layout() {
+        let payload = [0x42u8; 300];
+
+        let direct = build_payload_message(PAYLOAD_MODE_DIRECT, &payload);
+        assert_eq!(direct.len(), 15 + 300);
+        assert_eq!(&direct[0..14], b"FALCON_PAYLOAD");
+        assert_eq!(direct[14], PAYLOAD_MODE_DIRECT);
+        assert_eq!(direct[15..], payload);
+
+        let prehashed = build_payload_message(PAYLOAD_MODE_SHA256, &payload);
+        assert_eq!(prehashed.len(), 15 + 32);
+        assert_eq!(prehashed[14], PAYLOAD_MODE_SHA256);
+        assert_eq!(prehashed[15..], solana_nostd_sha256::hash(&payload));
+    }
+}
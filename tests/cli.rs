@@ -0,0 +1,307 @@
+// falcon-vault-cli: argument parsing, decoding of the vault account dump in
+// tests/fixtures/vault_account.json, and the keystore. nothing here talks to an RPC
+//
+//   cargo test --features cli --test cli
+
+use std::path::PathBuf;
+
+use clap::{CommandFactory, Parser};
+use solana_falcon_vault::cli::*;
+use solana_falcon_vault::client::derive_vault_address_with_authority;
+use solana_falcon_vault::falcon::FalconPublicKey;
+use solana_falcon_vault::fixtures::fixture_keypairs;
+use solana_falcon_vault::instructions::{VAULT_DATA_SIZE, VAULT_DATA_SIZE_CLOSE_AUTHORITY, VAULT_DATA_SIZE_NONCE_CLOSE_AUTHORITY};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+// `solana account <ADDRESS> --output json` of a v2 vault holding fixture key 0
+const VAULT_ACCOUNT: &str = include_str!("fixtures/vault_account.json");
+
+// argon2's minimum, so the keystore tests don't spend 19 MiB and seconds per call
+const CHEAP_KDF: KdfParams = KdfParams { m_cost: 8, t_cost: 1, p_cost: 1 };
+
+fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+    Cli::try_parse_from(std::iter::once("falcon-vault-cli").chain(args.iter().copied()))
+}
+
+// the message of an expected failure
+fn error<T>(result: CliResult<T>) -> String {
+    match result {
+        Err(error) => error.to_string(),
+        Ok(_) => panic!("expected an error"),
+    }
+}
+
+fn program_id() -> Pubkey {
+    Pubkey::new_from_array(solana_falcon_vault::ID)
+}
+
+fn fixture_key() -> FalconPublicKey {
+    FalconPublicKey::new(fixture_keypairs()[0].public_key)
+}
+
+#[test]
+fn test_command_definition() {
+    Cli::command().debug_assert();
+}
+
+#[test]
+fn test_parse_defaults_and_global_flags() {
+    let cli = parse(&["balance", "--keypair", "vault.json"]).unwrap();
+    assert_eq!(cli.url, DEFAULT_RPC_URL);
+    assert_eq!(cli.program_id, program_id());
+    assert_eq!(cli.fee_payer, None);
+    assert_eq!(
+        cli.command,
+        Command::Balance {
+            vault: VaultArg { vault: None, keypair: Some(PathBuf::from("vault.json")), close_authority: None }
+        }
+    );
+
+    // global flags go before or after the subcommand
+    let program = Pubkey::new_unique();
+    let cli = parse(&[
+        "-u",
+        "https://api.devnet.solana.com",
+        "balance",
+        "--vault",
+        &program.to_string(),
+        "--program-id",
+        &program.to_string(),
+        "--fee-payer",
+        "payer.json",
+    ])
+    .unwrap();
+    assert_eq!(cli.url, "https://api.devnet.solana.com");
+    assert_eq!(cli.program_id, program);
+    assert_eq!(cli.fee_payer, Some(PathBuf::from("payer.json")));
+}
+
+#[test]
+fn test_parse_commands() {
+    let to = Pubkey::new_unique();
+    let cli = parse(&["transfer", "-k", "vault.json", "--to", &to.to_string(), "--lamports", "5000"]).unwrap();
+    assert_eq!(
+        cli.command,
+        Command::Transfer {
+            keypair: "vault.json".into(),
+            to,
+            lamports: 5000,
+            compute_unit_limit: None,
+            close_authority: None
+        }
+    );
+
+    let cli = parse(&["open", "-k", "vault.json", "--close-authority", &to.to_string()]).unwrap();
+    assert_eq!(cli.command, Command::Open { keypair: "vault.json".into(), close_authority: Some(to) });
+
+    let cli = parse(&["close", "-k", "vault.json", "--refund", &to.to_string()]).unwrap();
+    assert_eq!(cli.command, Command::Close { keypair: "vault.json".into(), refund: to, close_authority: None });
+
+    // a vault opened with a close authority is found through it
+    let cli = parse(&["close", "-k", "vault.json", "--refund", &to.to_string(), "--close-authority", &to.to_string()]);
+    assert_eq!(
+        cli.unwrap().command,
+        Command::Close { keypair: "vault.json".into(), refund: to, close_authority: Some(to) }
+    );
+    let cli = parse(&["balance", "-k", "vault.json", "--close-authority", &to.to_string()]).unwrap();
+    assert_eq!(
+        cli.command,
+        Command::Balance {
+            vault: VaultArg { vault: None, keypair: Some("vault.json".into()), close_authority: Some(to) }
+        }
+    );
+
+    let cli = parse(&["keygen", "-k", "vault.json"]).unwrap();
+    assert_eq!(cli.command, Command::Keygen { keypair: "vault.json".into(), force: false });
+
+    let cli = parse(&["inspect", "--account-file", "dump.json"]).unwrap();
+    assert_eq!(
+        cli.command,
+        Command::Inspect {
+            vault: VaultArg { vault: None, keypair: None, close_authority: None },
+            account_file: Some("dump.json".into())
+        }
+    );
+}
+
+#[test]
+fn test_parse_rejects_bad_arguments() {
+    let to = Pubkey::new_unique().to_string();
+    let rejected = [
+        vec!["transfer", "-k", "vault.json", "--to", &to],
+        vec!["transfer", "-k", "vault.json", "--to", "not-a-pubkey", "--lamports", "1"],
+        vec!["transfer", "-k", "vault.json", "--to", &to, "--lamports", "-1"],
+        vec!["transfer", "-k", "vault.json", "--to", &to, "--lamports", "1 SOL"],
+        vec!["close", "-k", "vault.json"],
+        vec!["keygen"],
+        vec!["balance", "--vault", &to, "--keypair", "vault.json"],
+        vec!["balance", "--vault", &to, "--close-authority", &to],
+        vec!["balance", "--close-authority", &to],
+        vec!["inspect", "--vault", &to, "--account-file", "dump.json"],
+        vec!["--program-id", "short", "balance", "--vault", &to],
+        vec!["withdraw"],
+        vec![],
+    ];
+    for args in rejected {
+        assert!(parse(&args).is_err(), "{:?}", args);
+    }
+}
+
+#[test]
+fn test_vault_address() {
+    let vault = Pubkey::new_unique();
+    let arg = VaultArg { vault: Some(vault), keypair: None, close_authority: None };
+    assert_eq!(vault_address(&program_id(), &arg).unwrap(), vault);
+    assert!(vault_address(&program_id(), &VaultArg { vault: None, keypair: None, close_authority: None }).is_err());
+}
+
+#[test]
+fn test_decode_fixture_account() {
+    let (address, account) = parse_account_dump(VAULT_ACCOUNT).unwrap();
+    assert_eq!(account.owner, program_id());
+    assert_eq!(account.data.len(), VAULT_DATA_SIZE_NONCE_CLOSE_AUTHORITY);
+
+    let info = VaultInfo::decode(&program_id(), &address, &account).unwrap();
+    assert_eq!(info.address, address);
+    assert_eq!(info.lamports, 1_007_419_360);
+    assert_eq!(info.version, 4);
+    assert!(info.public_key == fixture_key());
+    assert_eq!(info.key_hash(), fixture_key().hash());
+    assert_eq!(info.close_authority, Some(Pubkey::new_from_array([0xCA; 32])));
+    assert_eq!(info.nonce, Some(3));
+    assert_eq!(info.bump, Some(252));
+
+    let text = info.to_string();
+    assert!(text.contains(&format!("Vault:           {}", address)));
+    assert!(text.contains("Balance:         1007419360 lamports (1.007419360 SOL)"));
+    assert!(text.contains("Layout version:  4"));
+    assert!(text.contains("Key hash:        47f1c29fd499a1c23cc82808840e053e0c51b9e3f32795326b8fe9f6ebe3e90e"));
+    assert!(text.contains("vault PDA of the key and close authority, bump 252"));
+    assert!(text.contains("Close authority: Eechuq54TEzmBHXX7Ltbmpu6K5Vf3qgdsRdYmn41vJ9o"));
+    assert!(text.contains("Transfer nonce:  3"));
+}
+
+#[test]
+fn test_decode_rejects_other_accounts() {
+    let (address, account) = parse_account_dump(VAULT_ACCOUNT).unwrap();
+    let decode = |account: &Account| VaultInfo::decode(&program_id(), &address, account);
+
+    // v1 and v2 vaults are the same key without the nonce, and the authority for v1
+    let mut v2 = account.clone();
+    v2.data.truncate(VAULT_DATA_SIZE_CLOSE_AUTHORITY);
+    v2.data[0] = 2;
+    let info = decode(&v2).unwrap();
+    assert_eq!((info.version, info.nonce), (2, None));
+    assert!(info.to_string().contains("Transfer nonce:  none (transfers rejected, close the vault)"));
+    let mut v1 = v2;
+    v1.data.truncate(VAULT_DATA_SIZE);
+    v1.data[0] = 1;
+    let info = decode(&v1).unwrap();
+    assert_eq!((info.version, info.close_authority, info.nonce), (1, None, None));
+    assert!(info.to_string().contains("Close authority: none"));
+
+    // the key of another vault decodes, but isn't this address's
+    let info = VaultInfo::decode(&program_id(), &Pubkey::new_unique(), &account).unwrap();
+    assert_eq!(info.bump, None);
+    assert!(info.to_string().contains("NOT the vault PDA"));
+
+    let mut foreign = account.clone();
+    foreign.owner = Pubkey::new_unique();
+    assert!(error(decode(&foreign)).contains("not the vault program"));
+
+    let mut unknown = account.clone();
+    unknown.data[0] = 5;
+    assert!(error(decode(&unknown)).contains("unknown vault layout version 5"));
+
+    let mut truncated = account.clone();
+    truncated.data.truncate(VAULT_DATA_SIZE_NONCE_CLOSE_AUTHORITY - 1);
+    assert!(decode(&truncated).is_err());
+
+    let mut empty = account;
+    empty.data.clear();
+    assert!(decode(&empty).is_err());
+}
+
+#[test]
+fn test_parse_account_dump_errors() {
+    assert!(parse_account_dump("{}").is_err());
+    assert!(parse_account_dump(&VAULT_ACCOUNT.replace("\"base64\"", "\"base58\"")).is_err());
+    assert!(parse_account_dump(&VAULT_ACCOUNT.replace("\"BA", "\"!A")).is_err());
+    assert!(read_account_file(&PathBuf::from("tests/fixtures/missing.json")).is_err());
+}
+
+#[test]
+fn test_keystore_round_trip() {
+    let seed = [7u8; SEED_SIZE];
+    let keystore = Keystore::encrypt(&seed, &fixture_key(), "correct horse", CHEAP_KDF).unwrap();
+
+    // through the file format and back
+    let json = serde_json::to_string(&keystore).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["version"], 1);
+    assert_eq!(value["kdf"]["name"], "argon2id");
+    assert_eq!(value["cipher"]["name"], "aes-256-gcm");
+    assert_eq!(value["public_key"], fixture_key().to_string());
+    assert!(!json.contains(&serde_json::to_string(&seed).unwrap()));
+
+    let keystore: Keystore = serde_json::from_str(&json).unwrap();
+    assert_eq!(*keystore.decrypt_seed("correct horse").unwrap(), seed);
+    assert!(keystore.decrypt_seed("wrong horse").is_err());
+
+    // the public key is bound to the ciphertext
+    let mut swapped = keystore.clone();
+    swapped.public_key = FalconPublicKey::new(fixture_keypairs()[1].public_key);
+    assert!(swapped.decrypt_seed("correct horse").is_err());
+
+    let mut future = keystore;
+    future.version = 2;
+    assert!(error(future.decrypt_seed("correct horse")).contains("version 2"));
+}
+
+#[test]
+fn test_keystore_file() {
+    let path = std::env::temp_dir().join(format!("falcon-vault-cli-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let keystore = Keystore::encrypt(&[1; SEED_SIZE], &fixture_key(), "pass", CHEAP_KDF).unwrap();
+
+    keystore.write(&path, false).unwrap();
+    assert!(keystore.write(&path, false).is_err());
+    keystore.write(&path, true).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    let read = Keystore::read(&path).unwrap();
+    assert!(read.public_key == fixture_key());
+    assert_eq!(*read.decrypt_seed("pass").unwrap(), [1; SEED_SIZE]);
+    let vault = VaultArg { vault: None, keypair: Some(path.clone()), close_authority: None };
+    assert_eq!(
+        vault_address(&program_id(), &vault).unwrap(),
+        solana_falcon_vault::client::derive_vault_address(&program_id(), &fixture_key()).0
+    );
+    // the close authority is a seed of the address
+    let authority = Pubkey::new_unique();
+    let vault = VaultArg { close_authority: Some(authority), ..vault };
+    assert_eq!(
+        vault_address(&program_id(), &vault).unwrap(),
+        derive_vault_address_with_authority(&program_id(), &fixture_key(), Some(&authority)).0
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+// the seed regenerates the key pair the keystore was written for, and unlock checks it
+#[test]
+fn test_keystore_unlock() {
+    let seed = [42u8; SEED_SIZE];
+    let public_key = keypair_from_seed(&seed).public_key;
+    let keystore = Keystore::encrypt(&seed, &public_key, "pass", CHEAP_KDF).unwrap();
+    let keypair = keystore.unlock("pass").unwrap();
+    assert!(keypair.public_key == public_key);
+    assert!(keypair.private_key.sign(b"cli").verify(&public_key, b"cli").is_ok());
+
+    // a keystore naming a key its seed doesn't produce
+    let mismatched = Keystore::encrypt(&seed, &fixture_key(), "pass", CHEAP_KDF).unwrap();
+    assert!(error(mismatched.unlock("pass")).contains("doesn't generate"));
+}
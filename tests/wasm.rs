@@ -0,0 +1,59 @@
+// the wasm build of falcon::wasm::verify against the signed fixtures, run in node:
+//
+//   wasm-pack test --node --no-default-features --features wasm --test wasm
+//
+// and, on the host, a check that the crate still builds for the wasm target:
+//
+//   rustup target add wasm32-unknown-unknown
+//   cargo test --features wasm --test wasm -- --ignored
+
+use solana_falcon_vault::error::VaultError;
+use solana_falcon_vault::falcon::wasm::verify;
+use solana_falcon_vault::fixtures::fixture_keypairs;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+#[test]
+fn test_fixtures_verify() {
+    for keypair in fixture_keypairs() {
+        for transfer in &keypair.transfers {
+            assert_eq!(verify(&keypair.public_key, &transfer.signature, &transfer.message()), Ok(()));
+        }
+        for close in &keypair.closes {
+            assert_eq!(verify(&keypair.public_key, &close.signature, &close.message()), Ok(()));
+        }
+    }
+}
+
+// a tampered transfer is rejected with the code the program would fail it with
+#[test]
+fn test_tampered_transfer_rejected() {
+    let keypair = &fixture_keypairs()[0];
+    let transfer = &keypair.transfers[0];
+
+    let mut message = transfer.message();
+    message[0] ^= 1;
+    let rejected = verify(&keypair.public_key, &transfer.signature, &message).unwrap_err();
+    assert_eq!(rejected.code(), Some(VaultError::NormBoundExceeded as u32));
+
+    let mut signature = transfer.signature;
+    signature[0] ^= 1;
+    let rejected = verify(&keypair.public_key, &signature, &transfer.message()).unwrap_err();
+    assert_eq!(rejected.code(), Some(VaultError::BadSignatureHeader as u32));
+}
+
+// the program feature off and nothing but falcon, messages and error left, which is
+// what the browser build links
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+#[ignore]
+fn test_builds_for_wasm32() {
+    let status = std::process::Command::new(env!("CARGO"))
+        .args(["check", "--lib", "--no-default-features", "--features", "wasm"])
+        .args(["--target", "wasm32-unknown-unknown"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}